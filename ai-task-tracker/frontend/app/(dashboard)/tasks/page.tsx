@@ -17,17 +17,31 @@ interface Task {
   due_date?: string;
 }
 
+interface TaskGroup {
+  key: string | null;
+  tasks: Task[];
+}
+
+// The built-in "My Tasks" view: your tasks by status, soonest due first
+const MY_TASKS_VIEW_ID = '00000000-0000-0000-0000-000000000001';
+
+const STATUS_LABELS: Record<string, string> = {
+  todo: 'To do',
+  in_progress: 'In progress',
+  done: 'Done',
+};
+
 export default function MyTasksPage() {
   const { user } = useAuth();
-  const [tasks, setTasks] = useState<Task[]>([]);
+  const [groups, setGroups] = useState<TaskGroup[]>([]);
   const [loading, setLoading] = useState(true);
 
   useEffect(() => {
     const fetchTasks = async () => {
       if (!user?.id) return;
       try {
-        const response = await api.get(`/api/tasks?assignee_id=${user.id}`);
-        setTasks(response.data);
+        const response = await api.get(`/api/views/${MY_TASKS_VIEW_ID}/tasks`);
+        setGroups(response.data.groups);
       } catch (error) {
         console.error('Failed to fetch tasks', error);
       } finally {
@@ -48,30 +62,36 @@ export default function MyTasksPage() {
         <h1 className="text-3xl font-bold tracking-tight">My Tasks</h1>
       </div>
 
-      <div className="grid gap-4">
-        {tasks.length === 0 ? (
-          <div className="text-center py-10 text-muted-foreground">You have no tasks assigned.</div>
-        ) : (
-          tasks.map((task) => (
-            <Link key={task.id} href={`/tasks/${task.id}`}>
-              <Card className="hover:bg-slate-50 dark:hover:bg-slate-900 transition-colors">
-                <CardContent className="flex items-center justify-between p-4">
-                  <div className="flex flex-col gap-1">
-                    <span className="font-medium">{task.title}</span>
-                    <div className="flex items-center gap-2 text-xs text-muted-foreground">
-                      <Badge variant="outline" className="capitalize">{task.status}</Badge>
-                      <Badge variant="outline" className="capitalize">{task.priority}</Badge>
-                      {task.due_date && (
-                        <span>Due: {new Date(task.due_date).toLocaleDateString()}</span>
-                      )}
-                    </div>
-                  </div>
-                </CardContent>
-              </Card>
-            </Link>
-          ))
-        )}
-      </div>
+      {groups.every((group) => group.tasks.length === 0) ? (
+        <div className="text-center py-10 text-muted-foreground">You have no tasks assigned.</div>
+      ) : (
+        groups.map((group) => (
+          <div key={group.key ?? 'all'} className="flex flex-col gap-3">
+            {group.key && (
+              <h2 className="text-lg font-semibold">{STATUS_LABELS[group.key] ?? group.key}</h2>
+            )}
+            <div className="grid gap-4">
+              {group.tasks.map((task) => (
+                <Link key={task.id} href={`/tasks/${task.id}`}>
+                  <Card className="hover:bg-slate-50 dark:hover:bg-slate-900 transition-colors">
+                    <CardContent className="flex items-center justify-between p-4">
+                      <div className="flex flex-col gap-1">
+                        <span className="font-medium">{task.title}</span>
+                        <div className="flex items-center gap-2 text-xs text-muted-foreground">
+                          <Badge variant="outline" className="capitalize">{task.priority}</Badge>
+                          {task.due_date && (
+                            <span>Due: {new Date(task.due_date).toLocaleDateString()}</span>
+                          )}
+                        </div>
+                      </div>
+                    </CardContent>
+                  </Card>
+                </Link>
+              ))}
+            </div>
+          </div>
+        ))
+      )}
     </div>
   );
 }
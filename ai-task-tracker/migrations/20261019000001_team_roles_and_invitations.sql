@@ -0,0 +1,32 @@
+-- Add per-team roles to team_members
+ALTER TABLE team_members
+    ADD COLUMN role VARCHAR(20) NOT NULL DEFAULT 'member'
+    CHECK (role IN ('owner', 'maintainer', 'member', 'viewer'));
+
+-- Existing team managers become owners of their team
+UPDATE team_members tm
+SET role = 'owner'
+FROM teams t
+WHERE t.id = tm.team_id AND t.manager_id = tm.user_id;
+
+-- Create team_invitations table for people who are not registered yet
+CREATE TABLE IF NOT EXISTS team_invitations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    team_id UUID NOT NULL REFERENCES teams(id) ON DELETE CASCADE,
+    email VARCHAR(255) NOT NULL,
+    role VARCHAR(20) NOT NULL DEFAULT 'member'
+        CHECK (role IN ('owner', 'maintainer', 'member', 'viewer')),
+    status VARCHAR(20) NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'accepted', 'declined')),
+    invited_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    invitee_id UUID REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    responded_at TIMESTAMPTZ
+);
+
+-- Only one open invitation per email and team
+CREATE UNIQUE INDEX idx_team_invitations_pending
+    ON team_invitations(team_id, LOWER(email))
+    WHERE status = 'pending';
+CREATE INDEX idx_team_invitations_email ON team_invitations(LOWER(email));
+CREATE INDEX idx_team_invitations_invitee ON team_invitations(invitee_id);
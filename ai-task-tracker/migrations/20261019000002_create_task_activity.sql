@@ -0,0 +1,21 @@
+-- Create task_activity audit table (status transitions and other task events)
+CREATE TABLE IF NOT EXISTS task_activity (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    task_id UUID NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+    actor_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    action VARCHAR(50) NOT NULL,
+    old_value TEXT,
+    new_value TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Create indexes for per-task timelines and analytics
+CREATE INDEX idx_task_activity_task ON task_activity(task_id, created_at);
+CREATE INDEX idx_task_activity_action ON task_activity(action, new_value, created_at);
+
+-- Backfill creation events, and a best-effort completion event for finished tasks
+INSERT INTO task_activity (task_id, action, new_value, created_at)
+SELECT id, 'created', status, created_at FROM tasks;
+
+INSERT INTO task_activity (task_id, action, old_value, new_value, created_at)
+SELECT id, 'status_changed', NULL, status, updated_at FROM tasks WHERE status <> 'todo';
@@ -0,0 +1,26 @@
+-- Create labels table; a label belongs to exactly one team or one project
+CREATE TABLE IF NOT EXISTS labels (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    team_id UUID REFERENCES teams(id) ON DELETE CASCADE,
+    project_id UUID REFERENCES projects(id) ON DELETE CASCADE,
+    name VARCHAR(50) NOT NULL,
+    color CHAR(7) NOT NULL DEFAULT '#6b7280' CHECK (color ~ '^#[0-9a-fA-F]{6}$'),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK ((team_id IS NULL) <> (project_id IS NULL))
+);
+
+-- Label names are unique (case-insensitively) within their team or project
+CREATE UNIQUE INDEX idx_labels_team_name ON labels(team_id, LOWER(name)) WHERE team_id IS NOT NULL;
+CREATE UNIQUE INDEX idx_labels_project_name ON labels(project_id, LOWER(name)) WHERE project_id IS NOT NULL;
+
+-- Create task_labels join table
+CREATE TABLE IF NOT EXISTS task_labels (
+    task_id UUID NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+    label_id UUID NOT NULL REFERENCES labels(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (task_id, label_id)
+);
+
+-- Create index for filtering tasks by label
+CREATE INDEX idx_task_labels_label ON task_labels(label_id);
@@ -0,0 +1,44 @@
+-- Create sprints table (time-boxed sprints and date-bound milestones)
+CREATE TABLE IF NOT EXISTS sprints (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    project_id UUID NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+    name VARCHAR(255) NOT NULL,
+    goal TEXT,
+    kind VARCHAR(20) NOT NULL DEFAULT 'sprint' CHECK (kind IN ('sprint', 'milestone')),
+    status VARCHAR(20) NOT NULL DEFAULT 'planned' CHECK (status IN ('planned', 'active', 'closed')),
+    start_date DATE NOT NULL,
+    end_date DATE NOT NULL,
+    started_at TIMESTAMPTZ,
+    closed_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (end_date >= start_date)
+);
+
+-- Only one sprint per project can be running at a time; milestones may overlap
+CREATE UNIQUE INDEX idx_sprints_one_active ON sprints(project_id)
+    WHERE status = 'active' AND kind = 'sprint';
+CREATE INDEX idx_sprints_project ON sprints(project_id, start_date);
+
+-- Tasks belong to at most one sprint
+ALTER TABLE tasks ADD COLUMN sprint_id UUID REFERENCES sprints(id) ON DELETE SET NULL;
+CREATE INDEX idx_tasks_sprint ON tasks(sprint_id);
+
+-- Every task that was ever in a sprint, and how it left, for committed vs. completed reporting
+CREATE TABLE IF NOT EXISTS sprint_tasks (
+    sprint_id UUID NOT NULL REFERENCES sprints(id) ON DELETE CASCADE,
+    task_id UUID NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+    added_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    added_after_start BOOLEAN NOT NULL DEFAULT FALSE,
+    outcome VARCHAR(20) CHECK (outcome IN ('completed', 'spilled', 'removed')),
+    resolved_at TIMESTAMPTZ,
+    PRIMARY KEY (sprint_id, task_id)
+);
+
+-- Planned capacity per member for a sprint
+CREATE TABLE IF NOT EXISTS sprint_capacities (
+    sprint_id UUID NOT NULL REFERENCES sprints(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    capacity_minutes INTEGER NOT NULL CHECK (capacity_minutes >= 0),
+    PRIMARY KEY (sprint_id, user_id)
+);
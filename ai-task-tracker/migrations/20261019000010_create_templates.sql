@@ -0,0 +1,31 @@
+-- Create project_templates table; templates without a team are available to everyone
+CREATE TABLE IF NOT EXISTS project_templates (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(150) NOT NULL,
+    description TEXT,
+    team_id UUID REFERENCES teams(id) ON DELETE CASCADE,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Create template_tasks table; position is the task's place in a depth-first walk of the tree,
+-- so every parent comes before its children
+CREATE TABLE IF NOT EXISTS template_tasks (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    template_id UUID NOT NULL REFERENCES project_templates(id) ON DELETE CASCADE,
+    parent_id UUID REFERENCES template_tasks(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    title VARCHAR(255) NOT NULL,
+    description TEXT,
+    priority VARCHAR(20) NOT NULL DEFAULT 'medium',
+    estimate_minutes INTEGER CHECK (estimate_minutes >= 0),
+    due_offset_days INTEGER,
+    assignee_role VARCHAR(20) CHECK (assignee_role IN ('owner', 'maintainer', 'member', 'viewer')),
+    subtasks TEXT[] NOT NULL DEFAULT '{}',
+    UNIQUE (template_id, position)
+);
+
+-- Create indexes
+CREATE INDEX idx_project_templates_team ON project_templates(team_id);
+CREATE INDEX idx_template_tasks_parent ON template_tasks(parent_id);
@@ -0,0 +1,21 @@
+-- Create jobs table, the queue background workers claim work from.
+-- A failed job goes back to 'pending' with a later run_at until max_attempts is
+-- used up, then stays 'dead' for an admin to look at or retry.
+CREATE TABLE IF NOT EXISTS jobs (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    kind VARCHAR(100) NOT NULL,
+    payload JSONB NOT NULL DEFAULT '{}',
+    status VARCHAR(20) NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'running', 'completed', 'dead')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    max_attempts INTEGER NOT NULL CHECK (max_attempts >= 1),
+    run_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    -- When a worker claimed it; a running job whose worker died is picked up again once this is old
+    locked_at TIMESTAMPTZ,
+    last_error TEXT,
+    completed_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_jobs_due ON jobs(run_at) WHERE status = 'pending';
+CREATE INDEX idx_jobs_status ON jobs(status, updated_at DESC);
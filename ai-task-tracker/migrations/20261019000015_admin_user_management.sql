@@ -0,0 +1,17 @@
+-- Deactivated users cannot sign in or use their sessions and tokens until reactivated
+ALTER TABLE users ADD COLUMN deactivated_at TIMESTAMPTZ;
+-- Set by an admin; the user can do nothing but change their password until they do
+ALTER TABLE users ADD COLUMN password_reset_required BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Create impersonations table, the audit trail of admins acting as other users for support
+CREATE TABLE IF NOT EXISTS impersonations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    admin_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    reason TEXT NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_impersonations_created ON impersonations(created_at DESC);
+CREATE INDEX idx_impersonations_user ON impersonations(user_id);
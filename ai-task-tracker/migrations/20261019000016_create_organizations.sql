@@ -0,0 +1,100 @@
+-- Create organizations table; each one is a tenant whose data the others never see
+CREATE TABLE IF NOT EXISTS organizations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(100) NOT NULL,
+    slug VARCHAR(50) UNIQUE NOT NULL CHECK (slug ~ '^[a-z0-9]+(-[a-z0-9]+)*$'),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Create organization_members table; a user can belong to several organizations
+CREATE TABLE IF NOT EXISTS organization_members (
+    organization_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    joined_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (organization_id, user_id)
+);
+
+CREATE INDEX idx_organization_members_user ON organization_members(user_id, joined_at);
+
+-- Everything that exists so far, and everyone who signs up on their own, belongs to the default organization
+INSERT INTO organizations (name, slug) VALUES ('Default', 'default');
+
+INSERT INTO organization_members (organization_id, user_id, joined_at)
+SELECT o.id, u.id, u.created_at FROM organizations o CROSS JOIN users u;
+
+ALTER TABLE teams ADD COLUMN organization_id UUID REFERENCES organizations(id) ON DELETE CASCADE;
+ALTER TABLE projects ADD COLUMN organization_id UUID REFERENCES organizations(id) ON DELETE CASCADE;
+ALTER TABLE tasks ADD COLUMN organization_id UUID REFERENCES organizations(id) ON DELETE CASCADE;
+ALTER TABLE project_templates ADD COLUMN organization_id UUID REFERENCES organizations(id) ON DELETE CASCADE;
+ALTER TABLE api_tokens ADD COLUMN organization_id UUID REFERENCES organizations(id) ON DELETE CASCADE;
+ALTER TABLE team_invitations ADD COLUMN organization_id UUID REFERENCES organizations(id) ON DELETE CASCADE;
+
+UPDATE teams SET organization_id = (SELECT id FROM organizations);
+UPDATE projects SET organization_id = (SELECT id FROM organizations);
+UPDATE tasks SET organization_id = (SELECT id FROM organizations);
+UPDATE project_templates SET organization_id = (SELECT id FROM organizations);
+UPDATE api_tokens SET organization_id = (SELECT id FROM organizations);
+UPDATE team_invitations SET organization_id = (SELECT id FROM organizations);
+
+ALTER TABLE teams ALTER COLUMN organization_id SET NOT NULL;
+ALTER TABLE projects ALTER COLUMN organization_id SET NOT NULL;
+ALTER TABLE tasks ALTER COLUMN organization_id SET NOT NULL;
+ALTER TABLE project_templates ALTER COLUMN organization_id SET NOT NULL;
+ALTER TABLE api_tokens ALTER COLUMN organization_id SET NOT NULL;
+ALTER TABLE team_invitations ALTER COLUMN organization_id SET NOT NULL;
+
+-- Rows can only point at rows of the same organization. Deletes are still
+-- handled by the existing single-column foreign keys.
+ALTER TABLE teams ADD CONSTRAINT teams_id_organization_key UNIQUE (id, organization_id);
+ALTER TABLE projects ADD CONSTRAINT projects_id_organization_key UNIQUE (id, organization_id);
+ALTER TABLE tasks ADD CONSTRAINT tasks_id_organization_key UNIQUE (id, organization_id);
+
+ALTER TABLE teams ADD CONSTRAINT teams_parent_same_organization
+    FOREIGN KEY (parent_team_id, organization_id) REFERENCES teams(id, organization_id);
+ALTER TABLE projects ADD CONSTRAINT projects_team_same_organization
+    FOREIGN KEY (team_id, organization_id) REFERENCES teams(id, organization_id);
+ALTER TABLE tasks ADD CONSTRAINT tasks_project_same_organization
+    FOREIGN KEY (project_id, organization_id) REFERENCES projects(id, organization_id);
+ALTER TABLE tasks ADD CONSTRAINT tasks_parent_same_organization
+    FOREIGN KEY (parent_task_id, organization_id) REFERENCES tasks(id, organization_id);
+ALTER TABLE project_templates ADD CONSTRAINT project_templates_team_same_organization
+    FOREIGN KEY (team_id, organization_id) REFERENCES teams(id, organization_id);
+-- Accepting an invitation joins the team's organization, which the invitee may not see yet
+ALTER TABLE team_invitations ADD CONSTRAINT team_invitations_team_same_organization
+    FOREIGN KEY (team_id, organization_id) REFERENCES teams(id, organization_id);
+
+-- Create indexes
+CREATE INDEX idx_teams_organization ON teams(organization_id);
+CREATE INDEX idx_projects_organization ON projects(organization_id);
+CREATE INDEX idx_tasks_organization ON tasks(organization_id);
+CREATE INDEX idx_project_templates_organization ON project_templates(organization_id);
+
+-- Row-level security as a second line of defense behind the queries' own
+-- organization filters. The application sets app.organization_id on each
+-- connection it hands to a request; connections used outside a request (jobs,
+-- migrations, sign-in) leave it empty and are not restricted. Superusers
+-- bypass these policies, so the application should connect as a regular role.
+CREATE FUNCTION current_organization_id() RETURNS UUID AS $$
+    SELECT NULLIF(current_setting('app.organization_id', true), '')::uuid
+$$ LANGUAGE SQL STABLE;
+
+ALTER TABLE teams ENABLE ROW LEVEL SECURITY;
+ALTER TABLE teams FORCE ROW LEVEL SECURITY;
+CREATE POLICY organization_isolation ON teams
+    USING (current_organization_id() IS NULL OR organization_id = current_organization_id());
+
+ALTER TABLE projects ENABLE ROW LEVEL SECURITY;
+ALTER TABLE projects FORCE ROW LEVEL SECURITY;
+CREATE POLICY organization_isolation ON projects
+    USING (current_organization_id() IS NULL OR organization_id = current_organization_id());
+
+ALTER TABLE tasks ENABLE ROW LEVEL SECURITY;
+ALTER TABLE tasks FORCE ROW LEVEL SECURITY;
+CREATE POLICY organization_isolation ON tasks
+    USING (current_organization_id() IS NULL OR organization_id = current_organization_id());
+
+ALTER TABLE project_templates ENABLE ROW LEVEL SECURITY;
+ALTER TABLE project_templates FORCE ROW LEVEL SECURITY;
+CREATE POLICY organization_isolation ON project_templates
+    USING (current_organization_id() IS NULL OR organization_id = current_organization_id());
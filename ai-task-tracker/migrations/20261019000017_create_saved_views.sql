@@ -0,0 +1,41 @@
+-- Create saved_views table; a view is a task filter plus its sort and grouping, kept for one
+-- user or shared with a team. Built-in views belong to no organization and cannot be changed.
+CREATE TABLE IF NOT EXISTS saved_views (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    organization_id UUID REFERENCES organizations(id) ON DELETE CASCADE,
+    name VARCHAR(100) NOT NULL,
+    user_id UUID REFERENCES users(id) ON DELETE CASCADE,
+    team_id UUID REFERENCES teams(id) ON DELETE CASCADE,
+    filter JSONB NOT NULL DEFAULT '{}',
+    sort_by VARCHAR(20) NOT NULL DEFAULT 'created_at'
+        CHECK (sort_by IN ('created_at', 'updated_at', 'due_date', 'priority', 'title')),
+    sort_order VARCHAR(4) NOT NULL DEFAULT 'desc' CHECK (sort_order IN ('asc', 'desc')),
+    group_by VARCHAR(20) CHECK (group_by IN ('status', 'priority', 'assignee', 'project')),
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (
+        (organization_id IS NULL AND user_id IS NULL AND team_id IS NULL)
+        OR (organization_id IS NOT NULL AND (user_id IS NULL) <> (team_id IS NULL))
+    ),
+    FOREIGN KEY (team_id, organization_id) REFERENCES teams(id, organization_id)
+);
+
+-- Create indexes
+CREATE INDEX idx_saved_views_user ON saved_views(organization_id, user_id);
+CREATE INDEX idx_saved_views_team ON saved_views(team_id);
+
+-- The "My Tasks" page; its id is fixed so clients can open it directly
+INSERT INTO saved_views (id, name, filter, sort_by, sort_order, group_by)
+VALUES ('00000000-0000-0000-0000-000000000001', 'My Tasks', '{"assigned_to_me": true}', 'due_date', 'asc', 'status');
+
+-- Everyone sees the built-in views, but requests cannot write them
+ALTER TABLE saved_views ENABLE ROW LEVEL SECURITY;
+ALTER TABLE saved_views FORCE ROW LEVEL SECURITY;
+CREATE POLICY organization_isolation ON saved_views
+    USING (
+        current_organization_id() IS NULL
+        OR organization_id IS NULL
+        OR organization_id = current_organization_id()
+    )
+    WITH CHECK (current_organization_id() IS NULL OR organization_id = current_organization_id());
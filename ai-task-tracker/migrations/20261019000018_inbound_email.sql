@@ -0,0 +1,11 @@
+-- Inbound email is read from an IMAP mailbox. Message-ID deduplicates messages
+-- that are fetched twice, and a reply is threaded onto the task its thread
+-- started, found through the task's source_email_id.
+ALTER TABLE email_logs ADD COLUMN message_id TEXT;
+ALTER TABLE email_logs ADD COLUMN in_reply_to TEXT;
+ALTER TABLE email_logs ADD COLUMN task_id UUID REFERENCES tasks(id) ON DELETE SET NULL;
+
+-- Create indexes
+CREATE UNIQUE INDEX idx_email_logs_message_id ON email_logs(message_id);
+CREATE INDEX idx_email_logs_task ON email_logs(task_id);
+CREATE INDEX idx_tasks_source_email_id ON tasks(source_email_id) WHERE source_email_id IS NOT NULL;
@@ -0,0 +1,10 @@
+-- Impersonations belong to the organization the admin was working in, and
+-- only that organization's admins see them in the audit trail
+ALTER TABLE impersonations ADD COLUMN organization_id UUID REFERENCES organizations(id) ON DELETE CASCADE;
+
+UPDATE impersonations SET organization_id = (SELECT id FROM organizations WHERE slug = 'default');
+
+ALTER TABLE impersonations ALTER COLUMN organization_id SET NOT NULL;
+
+DROP INDEX idx_impersonations_created;
+CREATE INDEX idx_impersonations_organization ON impersonations(organization_id, created_at DESC);
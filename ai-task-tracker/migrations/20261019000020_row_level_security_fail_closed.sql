@@ -0,0 +1,58 @@
+-- Row-level security fails closed: a connection sees an organization's rows
+-- only when app.organization_id names it. Work done for every organization
+-- (background jobs, migrations, the calendar feed) says so explicitly by
+-- setting app.all_organizations; a connection with neither sees nothing.
+CREATE FUNCTION sees_all_organizations() RETURNS BOOLEAN AS $$
+    SELECT COALESCE(current_setting('app.all_organizations', true), '') = 'on'
+$$ LANGUAGE SQL STABLE;
+
+ALTER POLICY organization_isolation ON teams
+    USING (sees_all_organizations() OR organization_id = current_organization_id());
+ALTER POLICY organization_isolation ON projects
+    USING (sees_all_organizations() OR organization_id = current_organization_id());
+ALTER POLICY organization_isolation ON tasks
+    USING (sees_all_organizations() OR organization_id = current_organization_id());
+ALTER POLICY organization_isolation ON project_templates
+    USING (sees_all_organizations() OR organization_id = current_organization_id());
+
+-- Built-in views stay visible to everyone and writable by no request
+ALTER POLICY organization_isolation ON saved_views
+    USING (sees_all_organizations() OR organization_id IS NULL OR organization_id = current_organization_id())
+    WITH CHECK (sees_all_organizations() OR organization_id = current_organization_id());
+
+-- Rows without an organization of their own follow the row they belong to.
+-- The parent's policy applies inside these subqueries, so they fail closed,
+-- and honour app.all_organizations, the same way.
+ALTER TABLE comments ENABLE ROW LEVEL SECURITY;
+ALTER TABLE comments FORCE ROW LEVEL SECURITY;
+CREATE POLICY organization_isolation ON comments
+    USING (EXISTS (SELECT 1 FROM tasks WHERE tasks.id = comments.task_id));
+
+ALTER TABLE attachments ENABLE ROW LEVEL SECURITY;
+ALTER TABLE attachments FORCE ROW LEVEL SECURITY;
+CREATE POLICY organization_isolation ON attachments
+    USING (EXISTS (SELECT 1 FROM tasks WHERE tasks.id = attachments.task_id));
+
+ALTER TABLE time_entries ENABLE ROW LEVEL SECURITY;
+ALTER TABLE time_entries FORCE ROW LEVEL SECURITY;
+CREATE POLICY organization_isolation ON time_entries
+    USING (EXISTS (SELECT 1 FROM tasks WHERE tasks.id = time_entries.task_id));
+
+ALTER TABLE sprints ENABLE ROW LEVEL SECURITY;
+ALTER TABLE sprints FORCE ROW LEVEL SECURITY;
+CREATE POLICY organization_isolation ON sprints
+    USING (EXISTS (SELECT 1 FROM projects WHERE projects.id = sprints.project_id));
+
+ALTER TABLE labels ENABLE ROW LEVEL SECURITY;
+ALTER TABLE labels FORCE ROW LEVEL SECURITY;
+CREATE POLICY organization_isolation ON labels
+    USING (
+        EXISTS (SELECT 1 FROM teams WHERE teams.id = labels.team_id)
+        OR EXISTS (SELECT 1 FROM projects WHERE projects.id = labels.project_id)
+    );
+
+-- Emails not threaded onto a task are only seen by the poller
+ALTER TABLE email_logs ENABLE ROW LEVEL SECURITY;
+ALTER TABLE email_logs FORCE ROW LEVEL SECURITY;
+CREATE POLICY organization_isolation ON email_logs
+    USING (sees_all_organizations() OR EXISTS (SELECT 1 FROM tasks WHERE tasks.id = email_logs.task_id));
@@ -0,0 +1,4 @@
+-- Each claim of a job gets its own token. A worker only records the outcome,
+-- or refreshes locked_at, while its token still holds the lock, so a worker
+-- whose job was taken over as stale cannot overwrite the new run.
+ALTER TABLE jobs ADD COLUMN locked_by UUID;
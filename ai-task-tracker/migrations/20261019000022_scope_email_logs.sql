@@ -0,0 +1,14 @@
+-- An email belongs to the organization of the task it is threaded onto or
+-- created; emails on no task belong to none, and only the poller sees them.
+ALTER TABLE email_logs ADD COLUMN organization_id UUID REFERENCES organizations(id) ON DELETE CASCADE;
+
+UPDATE email_logs
+SET organization_id = tasks.organization_id
+FROM tasks
+WHERE tasks.id = COALESCE(email_logs.task_id, email_logs.created_task_id)
+   OR tasks.source_email_id = email_logs.message_id;
+
+CREATE INDEX idx_email_logs_organization ON email_logs(organization_id);
+
+ALTER POLICY organization_isolation ON email_logs
+    USING (sees_all_organizations() OR organization_id = current_organization_id());
@@ -0,0 +1,19 @@
+-- Create organization_invitations table; an existing user only joins another
+-- organization by accepting one. The invitee has to see invitations from
+-- organizations they are not in yet, so the table has no row-level security.
+CREATE TABLE IF NOT EXISTS organization_invitations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    organization_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'accepted', 'declined')),
+    invited_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    responded_at TIMESTAMPTZ
+);
+
+-- Only one open invitation per user and organization
+CREATE UNIQUE INDEX idx_organization_invitations_pending
+    ON organization_invitations(organization_id, user_id)
+    WHERE status = 'pending';
+CREATE INDEX idx_organization_invitations_user ON organization_invitations(user_id, created_at DESC);
@@ -0,0 +1,4 @@
+-- Platform admins look after what every organization shares: the job queue
+-- and the two-factor policy. An organization's admin is not one; the flag is
+-- only ever set directly in the database.
+ALTER TABLE users ADD COLUMN is_platform_admin BOOLEAN NOT NULL DEFAULT FALSE;
@@ -1,32 +1,752 @@
-use std::env;
+//! Settings are read from three layers, each overriding the one before:
+//!
+//! 1. a TOML file: `--config <path>`, else `CONFIG_FILE`, else `config.toml` if it exists
+//! 2. environment variables (`PORT`, `DATABASE_URL`, ...)
+//! 3. command-line flags named after the file keys: `--server.port 9000` or `--server.port=9000`
+//!
+//! Secrets can instead be read from a file, named by `<ENV_VAR>_FILE`, `<key>_file` in the
+//! config file, or `--<key>_file`. Every problem found is reported at once by [`Config::load`].
+
+use std::{collections::HashMap, env, path::Path, str::FromStr, time::Duration};
+
+const DEFAULT_CONFIG_FILE: &str = "config.toml";
 
 #[derive(Debug, Clone)]
 pub struct Config {
     pub host: String,
     pub port: u16,
-    pub database_url: String,
-    pub jwt_secret: String,
-    pub jwt_expiration_hours: i64,
+    pub database: DatabaseConfig,
+    pub jwt: JwtConfig,
+    /// bcrypt work factor for new password hashes
+    pub bcrypt_cost: u32,
+    /// Origins allowed to call the API from a browser; `*` allows any
+    pub cors_allowed_origins: Vec<String>,
+    pub log: LogConfig,
+    pub features: FeatureConfig,
+    pub storage: StorageConfig,
+    /// Days a deleted team, project or task stays in the trash before it is purged
+    pub trash_retention_days: i64,
+    pub trash_purge_interval_minutes: u64,
+    pub jobs: JobsConfig,
+    /// Single sign-on, enabled when `OIDC_ISSUER_URL` is set
+    pub oidc: Option<OidcConfig>,
+    /// Inbound email, polled from this mailbox when `IMAP_HOST` is set
+    pub imap: Option<ImapConfig>,
 }
 
-impl Config {
-    pub fn from_env() -> Result<Self, env::VarError> {
-        Ok(Config {
-            host: env::var("HOST").unwrap_or_else(|_| "127.0.0.1".to_string()),
-            port: env::var("PORT")
-                .unwrap_or_else(|_| "8080".to_string())
-                .parse()
-                .expect("PORT must be a number"),
-            database_url: env::var("DATABASE_URL")?,
-            jwt_secret: env::var("JWT_SECRET")?,
-            jwt_expiration_hours: env::var("JWT_EXPIRATION_HOURS")
-                .unwrap_or_else(|_| "24".to_string())
-                .parse()
-                .expect("JWT_EXPIRATION_HOURS must be a number"),
+#[derive(Debug, Clone)]
+pub struct DatabaseConfig {
+    pub url: String,
+    pub max_connections: u32,
+    pub min_connections: u32,
+    /// How long a request waits for a free connection
+    pub acquire_timeout: Duration,
+    /// Idle connections above `min_connections` are closed after this long; `None` keeps them
+    pub idle_timeout: Option<Duration>,
+}
+
+#[derive(Debug, Clone)]
+pub struct JwtConfig {
+    pub secret: String,
+    pub expiration_hours: i64,
+    /// When set, session tokens carry it as `iss` and tokens without it are rejected
+    pub issuer: Option<String>,
+    /// When set, session tokens carry it as `aud` and tokens without it are rejected
+    pub audience: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct LogConfig {
+    pub format: LogFormat,
+    /// Filter used when `RUST_LOG` is not set, e.g. `info` or `info,actix_web=debug`
+    pub level: String,
+    /// OTLP/HTTP collector that spans are exported to, e.g. `http://localhost:4318`
+    pub otlp_endpoint: Option<String>,
+    /// Service name spans are exported under
+    pub service_name: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    Text,
+    /// One JSON object per line
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => Err("must be `text` or `json`".to_string()),
+        }
+    }
+}
+
+/// Parts of the application that can be switched off.
+#[derive(Debug, Clone)]
+pub struct FeatureConfig {
+    /// Self-service sign-up through `POST /api/auth/register`
+    pub registration: bool,
+    /// The OpenAPI document and docs page
+    pub api_docs: bool,
+    /// The background job that empties the trash
+    pub trash_purge: bool,
+}
+
+/// Background job workers.
+#[derive(Debug, Clone)]
+pub struct JobsConfig {
+    /// Jobs run at the same time by this server; 0 leaves the queue to other servers
+    pub workers: usize,
+    /// How often an idle worker checks the queue
+    pub poll_interval: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct StorageConfig {
+    /// `local` or `s3`
+    pub backend: String,
+    pub local_path: String,
+    pub s3_endpoint: Option<String>,
+    pub s3_bucket: Option<String>,
+    pub s3_region: String,
+    pub s3_access_key_id: Option<String>,
+    pub s3_secret_access_key: Option<String>,
+    pub max_attachment_bytes: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct OidcConfig {
+    pub issuer_url: String,
+    pub client_id: String,
+    /// Unset for public clients, which rely on PKCE alone
+    pub client_secret: Option<String>,
+    pub redirect_url: String,
+    pub scopes: String,
+    /// Lower-case email domains allowed to sign in; empty allows any
+    pub allowed_email_domains: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ImapConfig {
+    pub host: String,
+    pub port: u16,
+    /// Connect over TLS (IMAPS); only turn off for a server on the same host
+    pub tls: bool,
+    pub username: String,
+    pub password: String,
+    pub mailbox: String,
+    pub poll_interval: Duration,
+    /// Larger messages are skipped rather than read into memory
+    pub max_message_bytes: usize,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("cannot read config file {path}: {source}")]
+    ReadFile { path: String, source: std::io::Error },
+    #[error("config file {path} is not valid TOML: {source}")]
+    ParseFile { path: String, source: toml::de::Error },
+    #[error("invalid command line: {0}")]
+    Arguments(String),
+    #[error("invalid configuration:\n  - {}", .0.join("\n  - "))]
+    Invalid(Vec<String>),
+}
+
+/// A setting, by its config file key and environment variable.
+struct Setting {
+    key: &'static str,
+    env: &'static str,
+    /// May be read from a file instead
+    secret: bool,
+}
+
+const fn setting(key: &'static str, env: &'static str) -> Setting {
+    Setting { key, env, secret: false }
+}
+
+const fn secret(key: &'static str, env: &'static str) -> Setting {
+    Setting { key, env, secret: true }
+}
+
+const SETTINGS: &[Setting] = &[
+    setting("server.host", "HOST"),
+    setting("server.port", "PORT"),
+    secret("database.url", "DATABASE_URL"),
+    setting("database.max_connections", "DATABASE_MAX_CONNECTIONS"),
+    setting("database.min_connections", "DATABASE_MIN_CONNECTIONS"),
+    setting("database.acquire_timeout_seconds", "DATABASE_ACQUIRE_TIMEOUT_SECONDS"),
+    setting("database.idle_timeout_seconds", "DATABASE_IDLE_TIMEOUT_SECONDS"),
+    secret("jwt.secret", "JWT_SECRET"),
+    setting("jwt.expiration_hours", "JWT_EXPIRATION_HOURS"),
+    setting("jwt.issuer", "JWT_ISSUER"),
+    setting("jwt.audience", "JWT_AUDIENCE"),
+    setting("auth.bcrypt_cost", "BCRYPT_COST"),
+    setting("cors.allowed_origins", "CORS_ALLOWED_ORIGINS"),
+    setting("log.format", "LOG_FORMAT"),
+    setting("log.level", "LOG_LEVEL"),
+    setting("log.otlp_endpoint", "OTEL_EXPORTER_OTLP_ENDPOINT"),
+    setting("log.service_name", "OTEL_SERVICE_NAME"),
+    setting("features.registration", "FEATURE_REGISTRATION"),
+    setting("features.api_docs", "FEATURE_API_DOCS"),
+    setting("features.trash_purge", "FEATURE_TRASH_PURGE"),
+    setting("storage.backend", "STORAGE_BACKEND"),
+    setting("storage.local_path", "STORAGE_LOCAL_PATH"),
+    setting("storage.s3_endpoint", "S3_ENDPOINT"),
+    setting("storage.s3_bucket", "S3_BUCKET"),
+    setting("storage.s3_region", "S3_REGION"),
+    setting("storage.s3_access_key_id", "S3_ACCESS_KEY_ID"),
+    secret("storage.s3_secret_access_key", "S3_SECRET_ACCESS_KEY"),
+    setting("storage.max_attachment_bytes", "MAX_ATTACHMENT_BYTES"),
+    setting("trash.retention_days", "TRASH_RETENTION_DAYS"),
+    setting("trash.purge_interval_minutes", "TRASH_PURGE_INTERVAL_MINUTES"),
+    setting("jobs.workers", "JOB_WORKERS"),
+    setting("jobs.poll_interval_seconds", "JOB_POLL_INTERVAL_SECONDS"),
+    setting("oidc.issuer_url", "OIDC_ISSUER_URL"),
+    setting("oidc.client_id", "OIDC_CLIENT_ID"),
+    secret("oidc.client_secret", "OIDC_CLIENT_SECRET"),
+    setting("oidc.redirect_url", "OIDC_REDIRECT_URL"),
+    setting("oidc.scopes", "OIDC_SCOPES"),
+    setting("oidc.allowed_email_domains", "OIDC_ALLOWED_EMAIL_DOMAINS"),
+    setting("imap.host", "IMAP_HOST"),
+    setting("imap.port", "IMAP_PORT"),
+    setting("imap.tls", "IMAP_TLS"),
+    setting("imap.username", "IMAP_USERNAME"),
+    secret("imap.password", "IMAP_PASSWORD"),
+    setting("imap.mailbox", "IMAP_MAILBOX"),
+    setting("imap.poll_interval_seconds", "IMAP_POLL_INTERVAL_SECONDS"),
+    setting("imap.max_message_bytes", "IMAP_MAX_MESSAGE_BYTES"),
+];
+
+fn find_setting(key: &str) -> Option<&'static Setting> {
+    SETTINGS.iter().find(|s| s.key == key)
+}
+
+/// A value and where it came from, for error messages.
+struct Value {
+    raw: String,
+    origin: String,
+}
+
+/// The merged layers: later writes win.
+#[derive(Default)]
+struct Layers {
+    values: HashMap<&'static str, Value>,
+    errors: Vec<String>,
+}
+
+impl Layers {
+    /// Records `key` (or its `_file` form for secrets) from the named origin.
+    fn set(&mut self, key: &str, raw: String, origin: String) {
+        if let Some(setting) = find_setting(key) {
+            self.values.insert(setting.key, Value { raw, origin });
+            return;
+        }
+
+        match key.strip_suffix("_file").and_then(find_setting) {
+            Some(setting) if setting.secret => self.set_from_file(setting, &raw, &origin),
+            _ => self.errors.push(format!("unknown setting `{}` ({})", key, origin)),
+        }
+    }
+
+    fn set_from_file(&mut self, setting: &'static Setting, path: &str, origin: &str) {
+        match std::fs::read_to_string(path) {
+            Ok(contents) => {
+                let value = Value {
+                    raw: contents.trim_end_matches(['\r', '\n']).to_string(),
+                    origin: format!("file {} named by {}", path, origin),
+                };
+                self.values.insert(setting.key, value);
+            }
+            Err(e) => self.errors.push(format!("{}: cannot read {} ({}): {}", setting.key, path, origin, e)),
+        }
+    }
+
+    fn add_file(&mut self, path: &str) -> Result<(), ConfigError> {
+        let contents = std::fs::read_to_string(path).map_err(|source| ConfigError::ReadFile {
+            path: path.to_string(),
+            source,
+        })?;
+        let table: toml::Table = contents.parse().map_err(|source| ConfigError::ParseFile {
+            path: path.to_string(),
+            source,
+        })?;
+        self.add_table("", &table, path);
+        Ok(())
+    }
+
+    fn add_table(&mut self, prefix: &str, table: &toml::Table, path: &str) {
+        for (name, value) in table {
+            let key = format!("{}{}", prefix, name);
+            let raw = match value {
+                toml::Value::Table(nested) => {
+                    self.add_table(&format!("{}.", key), nested, path);
+                    continue;
+                }
+                toml::Value::String(s) => s.clone(),
+                toml::Value::Array(items) => items
+                    .iter()
+                    .map(|item| match item {
+                        toml::Value::String(s) => s.clone(),
+                        other => other.to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(","),
+                other => other.to_string(),
+            };
+            self.set(&key, raw, path.to_string());
+        }
+    }
+
+    fn add_env(&mut self, env: &impl Fn(&str) -> Option<String>) {
+        for setting in SETTINGS {
+            if let Some(raw) = env(setting.env) {
+                self.set(setting.key, raw, format!("environment variable {}", setting.env));
+            }
+            let file_var = format!("{}_FILE", setting.env);
+            if setting.secret {
+                if let Some(path) = env(&file_var) {
+                    self.set_from_file(setting, &path, &format!("environment variable {}", file_var));
+                }
+            }
+        }
+    }
+
+    fn get(&self, key: &str) -> Option<&Value> {
+        self.values.get(key)
+    }
+}
+
+/// The command line: an optional config file path and `--key value` overrides.
+#[derive(Default)]
+struct Arguments {
+    config_file: Option<String>,
+    overrides: Vec<(String, String)>,
+}
+
+fn parse_arguments(args: &[String]) -> Result<Arguments, ConfigError> {
+    let mut parsed = Arguments::default();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let Some(flag) = arg.strip_prefix("--") else {
+            return Err(ConfigError::Arguments(format!("unexpected argument `{}`", arg)));
+        };
+        let (name, value) = match flag.split_once('=') {
+            Some((name, value)) => (name, value.to_string()),
+            None => match args.next() {
+                Some(value) => (flag, value.clone()),
+                None => return Err(ConfigError::Arguments(format!("`--{}` needs a value", flag))),
+            },
+        };
+
+        if name == "config" {
+            parsed.config_file = Some(value);
+        } else {
+            parsed.overrides.push((name.to_string(), value));
+        }
+    }
+
+    Ok(parsed)
+}
+
+/// Reads typed settings out of the merged layers, collecting every problem.
+struct Resolver {
+    layers: Layers,
+}
+
+impl Resolver {
+    fn string(&self, key: &str) -> Option<String> {
+        self.layers.get(key).map(|v| v.raw.trim().to_string()).filter(|s| !s.is_empty())
+    }
+
+    fn string_or(&self, key: &str, default: &str) -> String {
+        self.string(key).unwrap_or_else(|| default.to_string())
+    }
+
+    fn required(&mut self, key: &str) -> String {
+        self.string(key).unwrap_or_else(|| {
+            let env = find_setting(key).map(|s| s.env).unwrap_or_default();
+            self.error(format!("{} is required; set {} or `{}` in the config file", key, env, key));
+            String::new()
         })
     }
 
+    fn parse<T: FromStr>(&mut self, key: &str, default: T) -> T {
+        let Some(raw) = self.string(key) else {
+            return default;
+        };
+        raw.parse().unwrap_or_else(|_| {
+            self.invalid(key, "is not a valid number");
+            default
+        })
+    }
+
+    fn flag(&mut self, key: &str, default: bool) -> bool {
+        match self.string(key).map(|raw| raw.to_lowercase()).as_deref() {
+            None => default,
+            Some("true" | "1" | "yes" | "on") => true,
+            Some("false" | "0" | "no" | "off") => false,
+            Some(_) => {
+                self.invalid(key, "must be true or false");
+                default
+            }
+        }
+    }
+
+    fn list(&self, key: &str) -> Vec<String> {
+        self.string(key)
+            .unwrap_or_default()
+            .split(',')
+            .map(|item| item.trim().to_string())
+            .filter(|item| !item.is_empty())
+            .collect()
+    }
+
+    /// Records that the value set for `key` is wrong, quoting it unless it is a secret.
+    fn invalid(&mut self, key: &str, problem: &str) {
+        let secret = find_setting(key).is_some_and(|s| s.secret);
+        let message = match self.layers.get(key) {
+            Some(value) if secret => format!("{} {} (from {})", key, problem, value.origin),
+            Some(value) => format!("{} = `{}` {} (from {})", key, value.raw, problem, value.origin),
+            None => format!("{} {}", key, problem),
+        };
+        self.error(message);
+    }
+
+    fn error(&mut self, message: String) {
+        self.layers.errors.push(message);
+    }
+}
+
+impl Config {
+    /// Loads the configuration from the config file, the environment and the
+    /// process's command line.
+    pub fn load() -> Result<Self, ConfigError> {
+        let args: Vec<String> = env::args().skip(1).collect();
+        Self::load_from(&args, |name| env::var(name).ok())
+    }
+
+    /// Loads the configuration from the given command-line arguments and
+    /// environment lookup.
+    pub fn load_from(args: &[String], env: impl Fn(&str) -> Option<String>) -> Result<Self, ConfigError> {
+        let args = parse_arguments(args)?;
+        let mut layers = Layers::default();
+
+        match args.config_file.or_else(|| env("CONFIG_FILE")) {
+            Some(path) => layers.add_file(&path)?,
+            None if Path::new(DEFAULT_CONFIG_FILE).exists() => layers.add_file(DEFAULT_CONFIG_FILE)?,
+            None => {}
+        }
+        layers.add_env(&env);
+        for (key, value) in args.overrides {
+            let origin = format!("--{}", key);
+            layers.set(&key, value, origin);
+        }
+
+        let mut resolver = Resolver { layers };
+        let config = Self::resolve(&mut resolver);
+
+        let errors = resolver.layers.errors;
+        if errors.is_empty() {
+            Ok(config)
+        } else {
+            Err(ConfigError::Invalid(errors))
+        }
+    }
+
+    fn resolve(r: &mut Resolver) -> Self {
+        let port = match r.string("server.port") {
+            Some(raw) => raw.parse().unwrap_or_else(|_| {
+                r.invalid("server.port", "is not a valid port number");
+                0
+            }),
+            None => 8080,
+        };
+
+        let database = DatabaseConfig {
+            url: r.required("database.url"),
+            max_connections: r.parse("database.max_connections", 5),
+            min_connections: r.parse("database.min_connections", 0),
+            acquire_timeout: Duration::from_secs(r.parse("database.acquire_timeout_seconds", 30)),
+            idle_timeout: match r.parse("database.idle_timeout_seconds", 600) {
+                0 => None,
+                seconds => Some(Duration::from_secs(seconds)),
+            },
+        };
+        if !database.url.is_empty()
+            && !database.url.starts_with("postgres://")
+            && !database.url.starts_with("postgresql://")
+        {
+            r.invalid("database.url", "must be a postgres:// or postgresql:// URL");
+        }
+        if database.max_connections == 0 {
+            r.invalid("database.max_connections", "must be at least 1");
+        }
+        if database.min_connections > database.max_connections {
+            r.invalid("database.min_connections", "cannot be more than database.max_connections");
+        }
+        if database.acquire_timeout.is_zero() {
+            r.invalid("database.acquire_timeout_seconds", "must be at least 1");
+        }
+
+        let jwt = JwtConfig {
+            secret: r.required("jwt.secret"),
+            expiration_hours: r.parse("jwt.expiration_hours", 24),
+            issuer: r.string("jwt.issuer"),
+            audience: r.string("jwt.audience"),
+        };
+        if jwt.expiration_hours < 1 {
+            r.invalid("jwt.expiration_hours", "must be at least 1");
+        }
+
+        let bcrypt_cost = r.parse("auth.bcrypt_cost", bcrypt::DEFAULT_COST);
+        if !(4..=31).contains(&bcrypt_cost) {
+            r.invalid("auth.bcrypt_cost", "must be between 4 and 31");
+        }
+
+        let mut cors_allowed_origins = r.list("cors.allowed_origins");
+        if cors_allowed_origins.is_empty() {
+            cors_allowed_origins.push("*".to_string());
+        }
+        let any_origin = cors_allowed_origins.iter().any(|o| o == "*");
+        if any_origin && cors_allowed_origins.len() > 1 {
+            r.invalid("cors.allowed_origins", "cannot combine `*` with specific origins");
+        }
+        let bad_origin = cors_allowed_origins.iter().any(|origin| {
+            origin != "*"
+                && (!(origin.starts_with("http://") || origin.starts_with("https://")) || origin.ends_with('/'))
+        });
+        if bad_origin {
+            r.invalid("cors.allowed_origins", "must be origins like https://tracker.example.com, without a path");
+        }
+
+        let log = LogConfig {
+            format: match r.string("log.format").map(|raw| raw.parse::<LogFormat>()) {
+                None => LogFormat::Text,
+                Some(Ok(format)) => format,
+                Some(Err(problem)) => {
+                    r.invalid("log.format", &problem);
+                    LogFormat::Text
+                }
+            },
+            level: r.string_or("log.level", "info"),
+            otlp_endpoint: r.string("log.otlp_endpoint"),
+            service_name: r.string_or("log.service_name", "ai-task-tracker"),
+        };
+        if tracing_subscriber::EnvFilter::try_new(&log.level).is_err() {
+            r.invalid("log.level", "is not a valid filter such as `info` or `info,sqlx=warn`");
+        }
+        if log
+            .otlp_endpoint
+            .as_deref()
+            .is_some_and(|endpoint| !endpoint.starts_with("http://") && !endpoint.starts_with("https://"))
+        {
+            r.invalid("log.otlp_endpoint", "must be an http:// or https:// URL");
+        }
+
+        let features = FeatureConfig {
+            registration: r.flag("features.registration", true),
+            api_docs: r.flag("features.api_docs", true),
+            trash_purge: r.flag("features.trash_purge", true),
+        };
+
+        let storage = StorageConfig {
+            backend: r.string_or("storage.backend", "local"),
+            local_path: r.string_or("storage.local_path", "./data/attachments"),
+            s3_endpoint: r.string("storage.s3_endpoint"),
+            s3_bucket: r.string("storage.s3_bucket"),
+            s3_region: r.string_or("storage.s3_region", "us-east-1"),
+            s3_access_key_id: r.string("storage.s3_access_key_id"),
+            s3_secret_access_key: r.string("storage.s3_secret_access_key"),
+            max_attachment_bytes: r.parse("storage.max_attachment_bytes", 10 * 1024 * 1024),
+        };
+        match storage.backend.as_str() {
+            "local" => {}
+            "s3" => {
+                for key in ["storage.s3_endpoint", "storage.s3_bucket"] {
+                    if r.string(key).is_none() {
+                        r.invalid(key, "is required when storage.backend is `s3`");
+                    }
+                }
+            }
+            _ => r.invalid("storage.backend", "must be `local` or `s3`"),
+        }
+        if storage.max_attachment_bytes == 0 {
+            r.invalid("storage.max_attachment_bytes", "must be at least 1");
+        }
+
+        let trash_retention_days = r.parse("trash.retention_days", 30);
+        if trash_retention_days < 1 {
+            r.invalid("trash.retention_days", "must be at least 1");
+        }
+        let trash_purge_interval_minutes = r.parse("trash.purge_interval_minutes", 60);
+        if trash_purge_interval_minutes == 0 {
+            r.invalid("trash.purge_interval_minutes", "must be at least 1");
+        }
+
+        let jobs = JobsConfig {
+            workers: r.parse("jobs.workers", 2),
+            poll_interval: Duration::from_secs(r.parse("jobs.poll_interval_seconds", 5)),
+        };
+        if jobs.poll_interval.is_zero() {
+            r.invalid("jobs.poll_interval_seconds", "must be at least 1");
+        }
+
+        let oidc = r.string("oidc.issuer_url").map(|issuer_url| OidcConfig {
+            issuer_url,
+            client_id: r.required("oidc.client_id"),
+            client_secret: r.string("oidc.client_secret"),
+            redirect_url: r.required("oidc.redirect_url"),
+            scopes: r.string_or("oidc.scopes", "openid email profile"),
+            allowed_email_domains: r
+                .list("oidc.allowed_email_domains")
+                .iter()
+                .map(|d| d.trim_start_matches('@').to_lowercase())
+                .collect(),
+        });
+
+        let imap = r.string("imap.host").map(|host| {
+            let tls = r.flag("imap.tls", true);
+            ImapConfig {
+                host,
+                port: r.parse("imap.port", if tls { 993 } else { 143 }),
+                tls,
+                username: r.required("imap.username"),
+                password: r.required("imap.password"),
+                mailbox: r.string_or("imap.mailbox", "INBOX"),
+                poll_interval: Duration::from_secs(r.parse("imap.poll_interval_seconds", 60)),
+                max_message_bytes: r.parse("imap.max_message_bytes", 25 * 1024 * 1024),
+            }
+        });
+        if imap.as_ref().is_some_and(|imap| imap.poll_interval.is_zero()) {
+            r.invalid("imap.poll_interval_seconds", "must be at least 1");
+        }
+        if imap.as_ref().is_some_and(|imap| imap.max_message_bytes == 0) {
+            r.invalid("imap.max_message_bytes", "must be at least 1");
+        }
+
+        Config {
+            host: r.string_or("server.host", "127.0.0.1"),
+            port,
+            database,
+            jwt,
+            bcrypt_cost,
+            cors_allowed_origins,
+            log,
+            features,
+            storage,
+            trash_retention_days,
+            trash_purge_interval_minutes,
+            jobs,
+            oidc,
+            imap,
+        }
+    }
+
     pub fn server_address(&self) -> String {
         format!("{}:{}", self.host, self.port)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(args: &[&str], env: &[(&str, &str)]) -> Result<Config, ConfigError> {
+        // An empty config file, so a config.toml in the working directory is not picked up
+        let dir = tempfile::tempdir().unwrap();
+        let empty = dir.path().join("empty.toml");
+        std::fs::write(&empty, "").unwrap();
+
+        let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+        let mut env: HashMap<String, String> = env.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        env.entry("CONFIG_FILE".to_string()).or_insert_with(|| empty.to_string_lossy().into_owned());
+        Config::load_from(&args, |name| env.get(name).cloned())
+    }
+
+    const REQUIRED: &[(&str, &str)] = &[("DATABASE_URL", "postgres://localhost/tracker"), ("JWT_SECRET", "s3cret")];
+
+    fn errors(result: Result<Config, ConfigError>) -> Vec<String> {
+        match result {
+            Err(ConfigError::Invalid(errors)) => errors,
+            Err(e) => panic!("unexpected error: {}", e),
+            Ok(_) => panic!("configuration was accepted"),
+        }
+    }
+
+    #[test]
+    fn later_layers_override_earlier_ones() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tracker.toml");
+        std::fs::write(
+            &path,
+            "[server]\nport = 7000\nhost = \"0.0.0.0\"\n[cors]\nallowed_origins = [\"https://a.example\"]\n",
+        )
+        .unwrap();
+        let path = path.to_str().unwrap();
+
+        let mut env = REQUIRED.to_vec();
+        env.push(("PORT", "7001"));
+        let config = load(&["--config", path, "--server.port=7002"], &env).unwrap();
+
+        assert_eq!(config.port, 7002);
+        assert_eq!(config.host, "0.0.0.0");
+        assert_eq!(config.cors_allowed_origins, vec!["https://a.example"]);
+        assert_eq!(config.database.max_connections, 5);
+    }
+
+    #[test]
+    fn secrets_are_read_from_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("jwt");
+        std::fs::write(&path, "from-a-file\n").unwrap();
+
+        let env = [("DATABASE_URL", "postgres://localhost/tracker"), ("JWT_SECRET_FILE", path.to_str().unwrap())];
+        let config = load(&[], &env).unwrap();
+
+        assert_eq!(config.jwt.secret, "from-a-file");
+    }
+
+    #[test]
+    fn every_problem_is_reported() {
+        let env = [("PORT", "eighty"), ("BCRYPT_COST", "2"), ("LOG_FORMAT", "xml")];
+        let errors = errors(load(&["--server.colour=blue"], &env));
+
+        assert_eq!(errors.len(), 6, "{:?}", errors);
+        assert!(errors.iter().any(|e| e.contains("`eighty`") && e.contains("environment variable PORT")));
+        assert!(errors.iter().any(|e| e.starts_with("database.url is required")));
+        assert!(errors.iter().any(|e| e.starts_with("jwt.secret is required")));
+        assert!(errors.iter().any(|e| e.contains("auth.bcrypt_cost")));
+        assert!(errors.iter().any(|e| e.contains("log.format")));
+        assert!(errors.iter().any(|e| e.contains("unknown setting `server.colour`")));
+    }
+
+    #[test]
+    fn imap_polling_needs_credentials() {
+        let mut env = REQUIRED.to_vec();
+        env.push(("IMAP_HOST", "imap.example.com"));
+        let errors = errors(load(&[], &env));
+        assert_eq!(errors.len(), 2, "{:?}", errors);
+
+        env.extend([("IMAP_USERNAME", "tasks@example.com"), ("IMAP_PASSWORD", "s3cret")]);
+        let imap = load(&[], &env).unwrap().imap.unwrap();
+        assert_eq!((imap.port, imap.tls, imap.mailbox.as_str()), (993, true, "INBOX"));
+        assert_eq!(imap.max_message_bytes, 25 * 1024 * 1024);
+
+        env.push(("IMAP_MAX_MESSAGE_BYTES", "0"));
+        assert!(load(&[], &env).is_err());
+    }
+
+    #[test]
+    fn secret_values_are_not_echoed() {
+        let env = [("DATABASE_URL", "mysql://user:hunter2@db/tracker"), ("JWT_SECRET", "s3cret")];
+        let errors = errors(load(&[], &env));
+
+        assert_eq!(errors.len(), 1);
+        assert!(!errors[0].contains("hunter2"), "{}", errors[0]);
+    }
+}
@@ -0,0 +1,249 @@
+use chrono::NaiveDate;
+use sqlx::PgPool;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::models::{
+    AssigneeWorkload, BurndownPoint, CycleTimeBucket, OverdueBucket, ThroughputBucket,
+};
+
+#[instrument(level = "debug", skip_all)]
+pub async fn open_workload_by_assignee(
+    pool: &PgPool,
+    organization_id: Uuid,
+    project_id: Option<Uuid>,
+    team_id: Option<Uuid>,
+) -> Result<Vec<AssigneeWorkload>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, AssigneeWorkload>(
+        r#"
+        SELECT
+            t.assignee_id,
+            u.full_name,
+            u.email,
+            COUNT(*) FILTER (WHERE t.priority = 'low') AS low,
+            COUNT(*) FILTER (WHERE t.priority = 'medium') AS medium,
+            COUNT(*) FILTER (WHERE t.priority = 'high') AS high,
+            COUNT(*) FILTER (WHERE t.priority = 'urgent') AS urgent,
+            COUNT(*) AS total
+        FROM tasks t
+        JOIN projects p ON p.id = t.project_id
+        LEFT JOIN users u ON u.id = t.assignee_id
+        WHERE t.status <> 'done' AND t.deleted_at IS NULL
+          AND ($1::uuid IS NULL OR t.project_id = $1)
+          AND ($2::uuid IS NULL OR p.team_id = $2)
+          AND t.organization_id = $3
+        GROUP BY t.assignee_id, u.full_name, u.email
+        ORDER BY total DESC
+        "#,
+    )
+    .bind(project_id)
+    .bind(team_id)
+    .bind(organization_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Tasks whose latest transition into `done` falls in each ISO week of the range.
+#[instrument(level = "debug", skip_all)]
+pub async fn weekly_throughput(
+    pool: &PgPool,
+    organization_id: Uuid,
+    project_id: Option<Uuid>,
+    team_id: Option<Uuid>,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<ThroughputBucket>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, ThroughputBucket>(
+        r#"
+        WITH weeks AS (
+            SELECT generate_series(
+                date_trunc('week', $3::date),
+                date_trunc('week', $4::date),
+                INTERVAL '1 week'
+            )::date AS week_start
+        ),
+        completions AS (
+            SELECT a.task_id, MAX(a.created_at) AS done_at
+            FROM task_activity a
+            JOIN tasks t ON t.id = a.task_id
+            JOIN projects p ON p.id = t.project_id
+            WHERE a.action = 'status_changed' AND a.new_value = 'done'
+              AND t.status = 'done' AND t.deleted_at IS NULL
+              AND ($1::uuid IS NULL OR t.project_id = $1)
+              AND ($2::uuid IS NULL OR p.team_id = $2)
+              AND t.organization_id = $5
+            GROUP BY a.task_id
+        )
+        SELECT w.week_start, COUNT(c.task_id) AS completed
+        FROM weeks w
+        LEFT JOIN completions c
+            ON date_trunc('week', c.done_at)::date = w.week_start
+           AND c.done_at::date BETWEEN $3 AND $4
+        GROUP BY w.week_start
+        ORDER BY w.week_start
+        "#,
+    )
+    .bind(project_id)
+    .bind(team_id)
+    .bind(from)
+    .bind(to)
+    .bind(organization_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Time from the first move into `in_progress` to the final move into `done`, bucketed by completion week.
+#[instrument(level = "debug", skip_all)]
+pub async fn weekly_cycle_time(
+    pool: &PgPool,
+    organization_id: Uuid,
+    project_id: Option<Uuid>,
+    team_id: Option<Uuid>,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<CycleTimeBucket>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, CycleTimeBucket>(
+        r#"
+        WITH weeks AS (
+            SELECT generate_series(
+                date_trunc('week', $3::date),
+                date_trunc('week', $4::date),
+                INTERVAL '1 week'
+            )::date AS week_start
+        ),
+        cycles AS (
+            SELECT
+                a.task_id,
+                MAX(a.created_at) FILTER (WHERE a.new_value = 'done') AS done_at,
+                MIN(a.created_at) FILTER (WHERE a.new_value = 'in_progress') AS started_at
+            FROM task_activity a
+            JOIN tasks t ON t.id = a.task_id
+            JOIN projects p ON p.id = t.project_id
+            WHERE a.action = 'status_changed'
+              AND t.status = 'done' AND t.deleted_at IS NULL
+              AND ($1::uuid IS NULL OR t.project_id = $1)
+              AND ($2::uuid IS NULL OR p.team_id = $2)
+              AND t.organization_id = $5
+            GROUP BY a.task_id
+        ),
+        durations AS (
+            SELECT
+                date_trunc('week', done_at)::date AS week_start,
+                EXTRACT(EPOCH FROM (done_at - started_at))::float8 / 3600.0 AS hours
+            FROM cycles
+            WHERE started_at IS NOT NULL
+              AND done_at >= started_at
+              AND done_at::date BETWEEN $3 AND $4
+        )
+        SELECT
+            w.week_start,
+            COUNT(d.hours) AS completed,
+            AVG(d.hours) AS avg_hours,
+            percentile_cont(0.5) WITHIN GROUP (ORDER BY d.hours) AS median_hours
+        FROM weeks w
+        LEFT JOIN durations d ON d.week_start = w.week_start
+        GROUP BY w.week_start
+        ORDER BY w.week_start
+        "#,
+    )
+    .bind(project_id)
+    .bind(team_id)
+    .bind(from)
+    .bind(to)
+    .bind(organization_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Daily scope and completion for a project, reconstructed from task creation and status events.
+#[instrument(level = "debug", skip_all)]
+pub async fn project_burndown(
+    pool: &PgPool,
+    organization_id: Uuid,
+    project_id: Uuid,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<BurndownPoint>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, BurndownPoint>(
+        r#"
+        WITH days AS (
+            SELECT generate_series($2::date, $3::date, INTERVAL '1 day')::date AS day
+        ),
+        snapshot AS (
+            SELECT
+                d.day,
+                t.id AS task_id,
+                (
+                    SELECT a.new_value
+                    FROM task_activity a
+                    WHERE a.task_id = t.id
+                      AND a.action IN ('created', 'status_changed')
+                      AND a.created_at < d.day + 1
+                    ORDER BY a.created_at DESC
+                    LIMIT 1
+                ) AS status
+            FROM days d
+            JOIN tasks t ON t.project_id = $1 AND t.organization_id = $4
+                        AND t.created_at < d.day + 1 AND t.deleted_at IS NULL
+        )
+        SELECT
+            d.day AS date,
+            COUNT(s.task_id) AS total,
+            COUNT(s.task_id) FILTER (WHERE s.status = 'done') AS completed,
+            COUNT(s.task_id) FILTER (WHERE s.status IS DISTINCT FROM 'done') AS remaining
+        FROM days d
+        LEFT JOIN snapshot s ON s.day = d.day
+        GROUP BY d.day
+        ORDER BY d.day
+        "#,
+    )
+    .bind(project_id)
+    .bind(from)
+    .bind(to)
+    .bind(organization_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn overdue_by_assignee(
+    pool: &PgPool,
+    organization_id: Uuid,
+    project_id: Option<Uuid>,
+    team_id: Option<Uuid>,
+) -> Result<Vec<OverdueBucket>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, OverdueBucket>(
+        r#"
+        SELECT
+            t.assignee_id,
+            u.full_name,
+            COUNT(*) FILTER (WHERE t.due_date < NOW()) AS overdue,
+            COUNT(*) FILTER (WHERE t.due_date >= NOW() AND t.due_date < NOW() + INTERVAL '7 days') AS due_this_week
+        FROM tasks t
+        JOIN projects p ON p.id = t.project_id
+        LEFT JOIN users u ON u.id = t.assignee_id
+        WHERE t.status <> 'done' AND t.deleted_at IS NULL
+          AND t.due_date IS NOT NULL
+          AND ($1::uuid IS NULL OR t.project_id = $1)
+          AND ($2::uuid IS NULL OR p.team_id = $2)
+          AND t.organization_id = $3
+        GROUP BY t.assignee_id, u.full_name
+        ORDER BY overdue DESC
+        "#,
+    )
+    .bind(project_id)
+    .bind(team_id)
+    .bind(organization_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
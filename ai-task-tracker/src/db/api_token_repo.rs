@@ -0,0 +1,116 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::models::{ApiToken, ApiTokenIdentity};
+
+pub struct NewApiToken<'a> {
+    pub user_id: Uuid,
+    /// The only organization the token can reach
+    pub organization_id: Uuid,
+    pub name: &'a str,
+    pub token_prefix: &'a str,
+    pub token_hash: &'a str,
+    pub scopes: &'a [String],
+    pub expires_at: Option<DateTime<Utc>>,
+    pub created_by: Option<Uuid>,
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn create_api_token(pool: &PgPool, token: &NewApiToken<'_>) -> Result<ApiToken, sqlx::Error> {
+    let api_token = sqlx::query_as::<_, ApiToken>(
+        r#"
+        INSERT INTO api_tokens (user_id, name, token_prefix, token_hash, scopes, expires_at, created_by,
+                                organization_id)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        RETURNING *
+        "#,
+    )
+    .bind(token.user_id)
+    .bind(token.name)
+    .bind(token.token_prefix)
+    .bind(token.token_hash)
+    .bind(token.scopes)
+    .bind(token.expires_at)
+    .bind(token.created_by)
+    .bind(token.organization_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(api_token)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn find_api_token(pool: &PgPool, id: Uuid) -> Result<Option<ApiToken>, sqlx::Error> {
+    let api_token = sqlx::query_as::<_, ApiToken>(
+        r#"
+        SELECT * FROM api_tokens WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(api_token)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn list_api_tokens(pool: &PgPool, user_id: Uuid) -> Result<Vec<ApiToken>, sqlx::Error> {
+    let api_tokens = sqlx::query_as::<_, ApiToken>(
+        r#"
+        SELECT * FROM api_tokens WHERE user_id = $1 ORDER BY created_at DESC
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(api_tokens)
+}
+
+/// Returns `false` if the token does not exist or was already revoked.
+#[instrument(level = "debug", skip_all)]
+pub async fn revoke_api_token(pool: &PgPool, id: Uuid) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        UPDATE api_tokens SET revoked_at = NOW() WHERE id = $1 AND revoked_at IS NULL
+        "#,
+    )
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Looks up an unrevoked, unexpired token of an active user by its hash and
+/// records the use. Tokens of users who have since left the token's
+/// organization are not accepted.
+#[instrument(level = "debug", skip_all)]
+pub async fn authenticate_api_token(
+    pool: &PgPool,
+    token_hash: &str,
+) -> Result<Option<ApiTokenIdentity>, sqlx::Error> {
+    let identity = sqlx::query_as::<_, ApiTokenIdentity>(
+        r#"
+        WITH used AS (
+            UPDATE api_tokens SET last_used_at = NOW()
+            WHERE token_hash = $1
+              AND revoked_at IS NULL
+              AND (expires_at IS NULL OR expires_at > NOW())
+              AND user_id IN (SELECT id FROM users WHERE deactivated_at IS NULL)
+              AND (user_id, organization_id) IN (SELECT user_id, organization_id FROM organization_members)
+            RETURNING user_id, organization_id, scopes, expires_at
+        )
+        SELECT u.id AS user_id, used.organization_id, u.email, u.role, used.scopes, used.expires_at
+        FROM used
+        JOIN users u ON u.id = used.user_id
+        "#,
+    )
+    .bind(token_hash)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(identity)
+}
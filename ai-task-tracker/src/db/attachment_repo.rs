@@ -0,0 +1,88 @@
+use sqlx::PgPool;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::models::{Attachment, NewAttachment};
+
+#[instrument(level = "debug", skip_all)]
+pub async fn create_attachment(pool: &PgPool, new: &NewAttachment) -> Result<Attachment, sqlx::Error> {
+    let attachment = sqlx::query_as::<_, Attachment>(
+        r#"
+        INSERT INTO attachments (id, task_id, uploaded_by, file_name, content_type, size_bytes,
+                                 storage_key, checksum_sha256)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        RETURNING *
+        "#,
+    )
+    .bind(new.id)
+    .bind(new.task_id)
+    .bind(new.uploaded_by)
+    .bind(&new.file_name)
+    .bind(&new.content_type)
+    .bind(new.size_bytes)
+    .bind(&new.storage_key)
+    .bind(&new.checksum_sha256)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(attachment)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn find_attachment(
+    pool: &PgPool,
+    organization_id: Uuid,
+    task_id: Uuid,
+    id: Uuid,
+) -> Result<Option<Attachment>, sqlx::Error> {
+    let attachment = sqlx::query_as::<_, Attachment>(
+        r#"
+        SELECT a.* FROM attachments a
+        JOIN tasks t ON t.id = a.task_id
+        WHERE a.id = $1 AND a.task_id = $2 AND t.organization_id = $3 AND t.deleted_at IS NULL
+        "#,
+    )
+    .bind(id)
+    .bind(task_id)
+    .bind(organization_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(attachment)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn list_attachments(
+    pool: &PgPool,
+    organization_id: Uuid,
+    task_id: Uuid,
+) -> Result<Vec<Attachment>, sqlx::Error> {
+    let attachments = sqlx::query_as::<_, Attachment>(
+        r#"
+        SELECT a.* FROM attachments a
+        JOIN tasks t ON t.id = a.task_id
+        WHERE a.task_id = $1 AND t.organization_id = $2 AND t.deleted_at IS NULL
+        ORDER BY a.created_at ASC
+        "#,
+    )
+    .bind(task_id)
+    .bind(organization_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(attachments)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn delete_attachment(pool: &PgPool, id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        DELETE FROM attachments WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
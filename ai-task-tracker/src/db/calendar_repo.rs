@@ -0,0 +1,88 @@
+use sqlx::PgPool;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::models::{CalendarTask, CalendarToken};
+
+#[instrument(level = "debug", skip_all)]
+pub async fn find_calendar_token_by_user(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> Result<Option<CalendarToken>, sqlx::Error> {
+    let token = sqlx::query_as::<_, CalendarToken>(
+        r#"
+        SELECT * FROM calendar_tokens WHERE user_id = $1
+        "#,
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(token)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn find_calendar_token(pool: &PgPool, token: &str) -> Result<Option<CalendarToken>, sqlx::Error> {
+    let token = sqlx::query_as::<_, CalendarToken>(
+        r#"
+        SELECT * FROM calendar_tokens WHERE token = $1
+        "#,
+    )
+    .bind(token)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(token)
+}
+
+/// Creates or replaces the user's feed token; the previous URL stops working.
+#[instrument(level = "debug", skip_all)]
+pub async fn upsert_calendar_token(
+    pool: &PgPool,
+    user_id: Uuid,
+    token: &str,
+) -> Result<CalendarToken, sqlx::Error> {
+    let token = sqlx::query_as::<_, CalendarToken>(
+        r#"
+        INSERT INTO calendar_tokens (user_id, token)
+        VALUES ($1, $2)
+        ON CONFLICT (user_id) DO UPDATE SET token = EXCLUDED.token, created_at = NOW()
+        RETURNING *
+        "#,
+    )
+    .bind(user_id)
+    .bind(token)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(token)
+}
+
+/// Due-dated tasks assigned to the user in the organizations they still belong to.
+#[instrument(level = "debug", skip_all)]
+pub async fn list_calendar_tasks(
+    pool: &PgPool,
+    assignee_id: Uuid,
+    project_id: Option<Uuid>,
+) -> Result<Vec<CalendarTask>, sqlx::Error> {
+    let tasks = sqlx::query_as::<_, CalendarTask>(
+        r#"
+        SELECT t.id, p.name AS project_name, t.title, t.description, t.status, t.priority,
+               t.progress_percent, t.due_date, t.created_at, t.updated_at
+        FROM tasks t
+        JOIN projects p ON p.id = t.project_id
+        WHERE t.assignee_id = $1
+          AND t.due_date IS NOT NULL
+          AND t.deleted_at IS NULL
+          AND ($2::uuid IS NULL OR t.project_id = $2)
+          AND t.organization_id IN (SELECT organization_id FROM organization_members WHERE user_id = $1)
+        ORDER BY t.due_date ASC
+        "#,
+    )
+    .bind(assignee_id)
+    .bind(project_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(tasks)
+}
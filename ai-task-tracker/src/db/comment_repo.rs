@@ -0,0 +1,216 @@
+use sqlx::{PgPool, Postgres, Transaction};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    db::create_notification,
+    models::{Comment, CommentWithAuthor, NewComment, NewNotification},
+};
+
+async fn replace_mentions(
+    tx: &mut Transaction<'_, Postgres>,
+    comment_id: Uuid,
+    mentions: &[Uuid],
+) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM comment_mentions WHERE comment_id = $1")
+        .bind(comment_id)
+        .execute(&mut **tx)
+        .await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO comment_mentions (comment_id, user_id)
+        SELECT $1, UNNEST($2::uuid[])
+        ON CONFLICT DO NOTHING
+        "#,
+    )
+    .bind(comment_id)
+    .bind(mentions)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+/// Stores the comment, its mentions and the notifications it triggers in one transaction.
+#[instrument(level = "debug", skip_all)]
+pub async fn create_comment(
+    pool: &PgPool,
+    new: &NewComment,
+    mentions: &[Uuid],
+    notifications: &[NewNotification],
+) -> Result<Comment, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let comment = sqlx::query_as::<_, Comment>(
+        r#"
+        INSERT INTO comments (task_id, parent_comment_id, author_id, body, body_html)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING *
+        "#,
+    )
+    .bind(new.task_id)
+    .bind(new.parent_comment_id)
+    .bind(new.author_id)
+    .bind(&new.body)
+    .bind(&new.body_html)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    replace_mentions(&mut tx, comment.id, mentions).await?;
+    for notification in notifications {
+        create_notification(
+            &mut *tx,
+            notification,
+            Some(new.author_id),
+            Some(comment.task_id),
+            Some(comment.id),
+        )
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(comment)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn find_comment(
+    pool: &PgPool,
+    organization_id: Uuid,
+    task_id: Uuid,
+    id: Uuid,
+) -> Result<Option<Comment>, sqlx::Error> {
+    let comment = sqlx::query_as::<_, Comment>(
+        r#"
+        SELECT c.* FROM comments c
+        JOIN tasks t ON t.id = c.task_id
+        WHERE c.id = $1 AND c.task_id = $2 AND t.organization_id = $3 AND t.deleted_at IS NULL
+        "#,
+    )
+    .bind(id)
+    .bind(task_id)
+    .bind(organization_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(comment)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn list_comment_mentions(pool: &PgPool, comment_id: Uuid) -> Result<Vec<Uuid>, sqlx::Error> {
+    let mentions: Vec<(Uuid,)> = sqlx::query_as(
+        r#"
+        SELECT user_id FROM comment_mentions WHERE comment_id = $1
+        "#,
+    )
+    .bind(comment_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(mentions.into_iter().map(|(id,)| id).collect())
+}
+
+/// Replaces the body and mentions of a comment; `notifications` should only
+/// cover users who were not mentioned before the edit.
+#[instrument(level = "debug", skip_all)]
+pub async fn update_comment(
+    pool: &PgPool,
+    comment: &Comment,
+    body: &str,
+    body_html: &str,
+    mentions: &[Uuid],
+    notifications: &[NewNotification],
+) -> Result<Comment, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let updated = sqlx::query_as::<_, Comment>(
+        r#"
+        UPDATE comments
+        SET body = $2, body_html = $3, edited_at = NOW()
+        WHERE id = $1
+        RETURNING *
+        "#,
+    )
+    .bind(comment.id)
+    .bind(body)
+    .bind(body_html)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    replace_mentions(&mut tx, comment.id, mentions).await?;
+    for notification in notifications {
+        create_notification(
+            &mut *tx,
+            notification,
+            comment.author_id,
+            Some(comment.task_id),
+            Some(comment.id),
+        )
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(updated)
+}
+
+/// Blanks a comment but keeps the row so its replies stay threaded.
+#[instrument(level = "debug", skip_all)]
+pub async fn soft_delete_comment(pool: &PgPool, id: Uuid) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        r#"
+        UPDATE comments SET body = '', body_html = '', deleted_at = NOW() WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query("DELETE FROM comment_mentions WHERE comment_id = $1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn list_comments(
+    pool: &PgPool,
+    organization_id: Uuid,
+    task_id: Uuid,
+) -> Result<Vec<CommentWithAuthor>, sqlx::Error> {
+    let comments = sqlx::query_as::<_, CommentWithAuthor>(
+        r#"
+        SELECT
+            c.id,
+            c.task_id,
+            c.parent_comment_id,
+            c.author_id,
+            u.full_name AS author_name,
+            u.email AS author_email,
+            c.body,
+            c.body_html,
+            COALESCE(
+                (SELECT array_agg(cm.user_id) FROM comment_mentions cm WHERE cm.comment_id = c.id),
+                '{}'
+            ) AS mentions,
+            c.created_at,
+            c.edited_at,
+            c.deleted_at
+        FROM comments c
+        JOIN tasks t ON t.id = c.task_id
+        LEFT JOIN users u ON c.author_id = u.id
+        WHERE c.task_id = $1 AND t.organization_id = $2 AND t.deleted_at IS NULL
+        ORDER BY c.created_at ASC
+        "#,
+    )
+    .bind(task_id)
+    .bind(organization_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(comments)
+}
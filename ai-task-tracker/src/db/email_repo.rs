@@ -0,0 +1,95 @@
+use sqlx::PgPool;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::models::{EmailLog, InboundEmail};
+
+/// The task a thread belongs to, with its organization: the one whose
+/// `source_email_id` is one of the thread's Message-IDs, or that an earlier
+/// message of the thread was threaded onto or created. Headers are the
+/// sender's word, so a task is only found if `sender` is the address of an
+/// active member of its organization.
+#[instrument(level = "debug", skip_all)]
+pub async fn find_thread_task(
+    pool: &PgPool,
+    thread_ids: &[String],
+    sender: &str,
+) -> Result<Option<(Uuid, Uuid)>, sqlx::Error> {
+    if thread_ids.is_empty() {
+        return Ok(None);
+    }
+
+    let task = sqlx::query_as::<_, (Uuid, Uuid)>(
+        r#"
+        SELECT id, organization_id FROM tasks
+        WHERE deleted_at IS NULL
+          AND (source_email_id = ANY($1)
+               OR id IN (SELECT COALESCE(task_id, created_task_id) FROM email_logs WHERE message_id = ANY($1)))
+          AND organization_id IN (
+              SELECT m.organization_id FROM organization_members m
+              JOIN users u ON u.id = m.user_id
+              WHERE LOWER(u.email) = LOWER($2) AND u.deactivated_at IS NULL
+          )
+        ORDER BY created_at
+        LIMIT 1
+        "#,
+    )
+    .bind(thread_ids)
+    .bind(sender)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(task)
+}
+
+/// Stores an inbound email, threaded onto `task_id` in `organization_id` if it
+/// is a reply on one. Returns `None` if an email with the same Message-ID is
+/// already stored.
+#[instrument(level = "debug", skip_all)]
+pub async fn insert_email_log(
+    pool: &PgPool,
+    email: &InboundEmail,
+    task_id: Option<Uuid>,
+    organization_id: Option<Uuid>,
+) -> Result<Option<EmailLog>, sqlx::Error> {
+    let email_log = sqlx::query_as::<_, EmailLog>(
+        r#"
+        INSERT INTO email_logs
+            (sender, subject, body_text, received_at, processed, message_id, in_reply_to, task_id, organization_id)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        ON CONFLICT (message_id) DO NOTHING
+        RETURNING *
+        "#,
+    )
+    .bind(&email.sender)
+    .bind(&email.subject)
+    .bind(&email.body_text)
+    .bind(email.received_at)
+    .bind(task_id.is_some())
+    .bind(&email.message_id)
+    .bind(&email.in_reply_to)
+    .bind(task_id)
+    .bind(organization_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(email_log)
+}
+
+/// The email that created a task and the replies threaded onto it, oldest first.
+#[instrument(level = "debug", skip_all)]
+pub async fn list_task_emails(pool: &PgPool, task_id: Uuid) -> Result<Vec<EmailLog>, sqlx::Error> {
+    let emails = sqlx::query_as::<_, EmailLog>(
+        r#"
+        SELECT * FROM email_logs
+        WHERE task_id = $1 OR created_task_id = $1
+           OR message_id = (SELECT source_email_id FROM tasks WHERE id = $1)
+        ORDER BY received_at, created_at
+        "#,
+    )
+    .bind(task_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(emails)
+}
@@ -0,0 +1,101 @@
+use std::collections::HashMap;
+
+use sqlx::PgPool;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::db::record_task_activity;
+use crate::models::ImportedTask;
+
+/// Inserts validated import rows in a single transaction.
+/// Returns the number of tasks and subtasks created.
+#[instrument(level = "debug", skip_all)]
+pub async fn import_project_tasks(
+    pool: &PgPool,
+    organization_id: Uuid,
+    tasks: &[ImportedTask],
+    actor_id: Option<Uuid>,
+) -> Result<(usize, usize), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let mut ids: HashMap<&str, Uuid> = HashMap::new();
+    let mut created_ids = Vec::with_capacity(tasks.len());
+    let mut subtask_count = 0;
+
+    for imported in tasks {
+        let req = &imported.task;
+        let status = imported
+            .status
+            .as_ref()
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| "todo".to_string());
+        let priority = req
+            .priority
+            .as_ref()
+            .map(|p| p.to_string())
+            .unwrap_or_else(|| "medium".to_string());
+
+        let id: Uuid = sqlx::query_scalar(
+            r#"
+            INSERT INTO tasks (project_id, parent_task_id, title, description, status,
+                               progress_percent, priority, assignee_id, due_date, estimate_minutes,
+                               organization_id)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            RETURNING id
+            "#,
+        )
+        .bind(req.project_id)
+        .bind(req.parent_task_id)
+        .bind(&req.title)
+        .bind(&req.description)
+        .bind(&status)
+        .bind(imported.progress_percent.unwrap_or(0))
+        .bind(priority)
+        .bind(req.assignee_id)
+        .bind(req.due_date)
+        .bind(req.estimate_minutes)
+        .bind(organization_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        record_task_activity(&mut *tx, id, actor_id, "created", None, Some("todo")).await?;
+        if status != "todo" {
+            record_task_activity(&mut *tx, id, actor_id, "status_changed", Some("todo"), Some(&status))
+                .await?;
+        }
+
+        if let Some(reference) = &imported.reference {
+            ids.insert(reference.as_str(), id);
+        }
+        created_ids.push(id);
+
+        for subtask in &imported.subtasks {
+            sqlx::query(
+                r#"
+                INSERT INTO subtasks (parent_task_id, title, is_completed)
+                VALUES ($1, $2, $3)
+                "#,
+            )
+            .bind(id)
+            .bind(&subtask.title)
+            .bind(subtask.is_completed)
+            .execute(&mut *tx)
+            .await?;
+            subtask_count += 1;
+        }
+    }
+
+    // Link parents once every task in the file exists, so row order does not matter
+    for (imported, id) in tasks.iter().zip(&created_ids) {
+        if let Some(parent_id) = imported.parent_ref.as_deref().and_then(|r| ids.get(r)) {
+            sqlx::query("UPDATE tasks SET parent_task_id = $2 WHERE id = $1")
+                .bind(id)
+                .bind(parent_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+    }
+
+    tx.commit().await?;
+
+    Ok((created_ids.len(), subtask_count))
+}
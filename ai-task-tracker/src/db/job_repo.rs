@@ -0,0 +1,234 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::models::Job;
+
+#[instrument(level = "debug", skip_all)]
+pub async fn enqueue_job(
+    pool: &PgPool,
+    kind: &str,
+    payload: &serde_json::Value,
+    run_at: DateTime<Utc>,
+    max_attempts: i32,
+) -> Result<Job, sqlx::Error> {
+    let job = sqlx::query_as::<_, Job>(
+        r#"
+        INSERT INTO jobs (kind, payload, run_at, max_attempts)
+        VALUES ($1, $2, $3, $4)
+        RETURNING *
+        "#,
+    )
+    .bind(kind)
+    .bind(payload)
+    .bind(run_at)
+    .bind(max_attempts)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(job)
+}
+
+/// Like [`enqueue_job`], unless a job of `kind` is already pending, in which
+/// case nothing is queued and `None` is returned. Servers starting together
+/// take turns on a lock per kind, so they queue one job between them.
+#[instrument(level = "debug", skip_all)]
+pub async fn enqueue_job_unless_pending(
+    pool: &PgPool,
+    kind: &str,
+    payload: &serde_json::Value,
+    run_at: DateTime<Utc>,
+    max_attempts: i32,
+) -> Result<Option<Job>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtext('jobs:' || $1))")
+        .bind(kind)
+        .execute(&mut *tx)
+        .await?;
+
+    let job = sqlx::query_as::<_, Job>(
+        r#"
+        INSERT INTO jobs (kind, payload, run_at, max_attempts)
+        SELECT $1, $2, $3, $4
+        WHERE NOT EXISTS (SELECT 1 FROM jobs WHERE kind = $1 AND status = 'pending')
+        RETURNING *
+        "#,
+    )
+    .bind(kind)
+    .bind(payload)
+    .bind(run_at)
+    .bind(max_attempts)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(job)
+}
+
+/// Marks the next due job as running under `lock` and returns it. Due jobs are
+/// pending ones whose `run_at` has passed, and running ones locked before
+/// `stale_before`, whose worker is presumed dead. `SKIP LOCKED` lets several
+/// workers claim at once without waiting on each other or taking the same job.
+#[instrument(level = "debug", skip_all)]
+pub async fn claim_next_job(
+    pool: &PgPool,
+    stale_before: DateTime<Utc>,
+    lock: Uuid,
+) -> Result<Option<Job>, sqlx::Error> {
+    let job = sqlx::query_as::<_, Job>(
+        r#"
+        UPDATE jobs
+        SET status = 'running', attempts = attempts + 1, locked_by = $2, locked_at = NOW(), updated_at = NOW()
+        WHERE id = (
+            SELECT id FROM jobs
+            WHERE (status = 'pending' AND run_at <= NOW())
+               OR (status = 'running' AND locked_at < $1)
+            ORDER BY run_at
+            LIMIT 1
+            FOR UPDATE SKIP LOCKED
+        )
+        RETURNING *
+        "#,
+    )
+    .bind(stale_before)
+    .bind(lock)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(job)
+}
+
+/// Moves the lock's `locked_at` forward so a long-running job is not taken
+/// for stale. Returns `false` if `lock` no longer holds the job.
+#[instrument(level = "debug", skip_all)]
+pub async fn heartbeat_job(pool: &PgPool, id: Uuid, lock: Uuid) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        UPDATE jobs
+        SET locked_at = NOW()
+        WHERE id = $1 AND locked_by = $2 AND status = 'running'
+        "#,
+    )
+    .bind(id)
+    .bind(lock)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Returns `false`, recording nothing, if `lock` no longer holds the job.
+#[instrument(level = "debug", skip_all)]
+pub async fn complete_job(pool: &PgPool, id: Uuid, lock: Uuid) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        UPDATE jobs
+        SET status = 'completed', locked_by = NULL, locked_at = NULL, last_error = NULL,
+            completed_at = NOW(), updated_at = NOW()
+        WHERE id = $1 AND locked_by = $2 AND status = 'running'
+        "#,
+    )
+    .bind(id)
+    .bind(lock)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Like [`complete_job`], but removes the finished job instead of keeping it
+/// as completed. Returns `false`, removing nothing, if `lock` no longer holds
+/// the job.
+#[instrument(level = "debug", skip_all)]
+pub async fn discard_completed_job(pool: &PgPool, id: Uuid, lock: Uuid) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM jobs WHERE id = $1 AND locked_by = $2 AND status = 'running'")
+        .bind(id)
+        .bind(lock)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Records a failed attempt: the job runs again at `retry_at`, or is dead if
+/// there is none. Returns `false`, recording nothing, if `lock` no longer
+/// holds the job.
+#[instrument(level = "debug", skip_all)]
+pub async fn fail_job(
+    pool: &PgPool,
+    id: Uuid,
+    lock: Uuid,
+    error: &str,
+    retry_at: Option<DateTime<Utc>>,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        UPDATE jobs
+        SET status = CASE WHEN $4::timestamptz IS NULL THEN 'dead' ELSE 'pending' END,
+            run_at = COALESCE($4, run_at),
+            locked_by = NULL,
+            locked_at = NULL,
+            last_error = $3,
+            updated_at = NOW()
+        WHERE id = $1 AND locked_by = $2 AND status = 'running'
+        "#,
+    )
+    .bind(id)
+    .bind(lock)
+    .bind(error)
+    .bind(retry_at)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn find_job(pool: &PgPool, id: Uuid) -> Result<Option<Job>, sqlx::Error> {
+    let job = sqlx::query_as::<_, Job>("SELECT * FROM jobs WHERE id = $1")
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(job)
+}
+
+/// Most recently updated first.
+#[instrument(level = "debug", skip_all)]
+pub async fn list_jobs(pool: &PgPool, status: &str, kind: Option<&str>, limit: i64) -> Result<Vec<Job>, sqlx::Error> {
+    let jobs = sqlx::query_as::<_, Job>(
+        r#"
+        SELECT * FROM jobs
+        WHERE status = $1 AND ($2::varchar IS NULL OR kind = $2)
+        ORDER BY updated_at DESC
+        LIMIT $3
+        "#,
+    )
+    .bind(status)
+    .bind(kind)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(jobs)
+}
+
+/// Puts a dead job back in the queue with a fresh set of attempts. Returns
+/// `None` if there is no dead job with this id.
+#[instrument(level = "debug", skip_all)]
+pub async fn retry_dead_job(pool: &PgPool, id: Uuid) -> Result<Option<Job>, sqlx::Error> {
+    let job = sqlx::query_as::<_, Job>(
+        r#"
+        UPDATE jobs
+        SET status = 'pending', attempts = 0, run_at = NOW(), updated_at = NOW()
+        WHERE id = $1 AND status = 'dead'
+        RETURNING *
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(job)
+}
@@ -0,0 +1,246 @@
+use sqlx::PgPool;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    db::record_task_activity,
+    models::{CreateLabelRequest, Label, UpdateLabelRequest},
+};
+
+/// Labels belong to a team or a project, and through it to an organization;
+/// `org` is the placeholder the organization id is bound to.
+fn in_organization(org: &str) -> String {
+    format!(
+        "(l.team_id IN (SELECT id FROM teams WHERE organization_id = {org}) \
+          OR l.project_id IN (SELECT id FROM projects WHERE organization_id = {org}))"
+    )
+}
+
+/// Returns `None` if the team or project is not in the organization.
+#[instrument(level = "debug", skip_all)]
+pub async fn create_label(
+    pool: &PgPool,
+    organization_id: Uuid,
+    req: &CreateLabelRequest,
+    color: &str,
+) -> Result<Option<Label>, sqlx::Error> {
+    let label = sqlx::query_as::<_, Label>(
+        r#"
+        INSERT INTO labels (team_id, project_id, name, color)
+        SELECT $1, $2, $3, $4
+        WHERE EXISTS (SELECT 1 FROM teams WHERE id = $1 AND organization_id = $5)
+           OR EXISTS (SELECT 1 FROM projects WHERE id = $2 AND organization_id = $5)
+        RETURNING *
+        "#,
+    )
+    .bind(req.team_id)
+    .bind(req.project_id)
+    .bind(req.name.trim())
+    .bind(color)
+    .bind(organization_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(label)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn find_label_by_id(pool: &PgPool, organization_id: Uuid, id: Uuid) -> Result<Option<Label>, sqlx::Error> {
+    let query = format!("SELECT l.* FROM labels l WHERE l.id = $1 AND {}", in_organization("$2"));
+    let label = sqlx::query_as::<_, Label>(&query)
+        .bind(id)
+        .bind(organization_id)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(label)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn list_team_labels(pool: &PgPool, organization_id: Uuid, team_id: Uuid) -> Result<Vec<Label>, sqlx::Error> {
+    let labels = sqlx::query_as::<_, Label>(
+        r#"
+        SELECT l.* FROM labels l
+        JOIN teams tm ON tm.id = l.team_id
+        WHERE l.team_id = $1 AND tm.organization_id = $2
+        ORDER BY LOWER(l.name)
+        "#,
+    )
+    .bind(team_id)
+    .bind(organization_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(labels)
+}
+
+/// Labels usable on a project's tasks: its own plus those of its team.
+#[instrument(level = "debug", skip_all)]
+pub async fn list_project_labels(
+    pool: &PgPool,
+    organization_id: Uuid,
+    project_id: Uuid,
+) -> Result<Vec<Label>, sqlx::Error> {
+    let labels = sqlx::query_as::<_, Label>(
+        r#"
+        SELECT l.*
+        FROM labels l
+        JOIN projects p ON p.id = $1 AND p.organization_id = $2
+        WHERE l.project_id = $1 OR (l.team_id IS NOT NULL AND l.team_id = p.team_id)
+        ORDER BY LOWER(l.name)
+        "#,
+    )
+    .bind(project_id)
+    .bind(organization_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(labels)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn update_label(
+    pool: &PgPool,
+    organization_id: Uuid,
+    id: Uuid,
+    req: &UpdateLabelRequest,
+) -> Result<Option<Label>, sqlx::Error> {
+    let query = format!(
+        r#"
+        UPDATE labels l
+        SET name = COALESCE($2, name),
+            color = COALESCE($3, color),
+            updated_at = NOW()
+        WHERE l.id = $1 AND {}
+        RETURNING *
+        "#,
+        in_organization("$4")
+    );
+    let label = sqlx::query_as::<_, Label>(&query)
+        .bind(id)
+        .bind(req.name.as_deref().map(str::trim))
+        .bind(&req.color)
+        .bind(organization_id)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(label)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn delete_label(pool: &PgPool, organization_id: Uuid, id: Uuid) -> Result<bool, sqlx::Error> {
+    let query = format!("DELETE FROM labels l WHERE l.id = $1 AND {}", in_organization("$2"));
+    let result = sqlx::query(&query)
+        .bind(id)
+        .bind(organization_id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn list_task_labels(pool: &PgPool, organization_id: Uuid, task_id: Uuid) -> Result<Vec<Label>, sqlx::Error> {
+    let labels = sqlx::query_as::<_, Label>(
+        r#"
+        SELECT l.*
+        FROM labels l
+        JOIN task_labels tl ON tl.label_id = l.id
+        JOIN tasks t ON t.id = tl.task_id
+        WHERE tl.task_id = $1 AND t.organization_id = $2 AND t.deleted_at IS NULL
+        ORDER BY LOWER(l.name)
+        "#,
+    )
+    .bind(task_id)
+    .bind(organization_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(labels)
+}
+
+/// Finds a label only if it belongs to the task's project or to that project's team.
+#[instrument(level = "debug", skip_all)]
+pub async fn find_label_for_task(
+    pool: &PgPool,
+    organization_id: Uuid,
+    task_id: Uuid,
+    label_id: Uuid,
+) -> Result<Option<Label>, sqlx::Error> {
+    let label = sqlx::query_as::<_, Label>(
+        r#"
+        SELECT l.*
+        FROM labels l
+        JOIN tasks t ON t.id = $1 AND t.organization_id = $3
+        JOIN projects p ON p.id = t.project_id
+        WHERE l.id = $2 AND (l.project_id = p.id OR l.team_id = p.team_id)
+        "#,
+    )
+    .bind(task_id)
+    .bind(label_id)
+    .bind(organization_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(label)
+}
+
+/// Returns false if the task already had the label.
+#[instrument(level = "debug", skip_all)]
+pub async fn add_task_label(
+    pool: &PgPool,
+    task_id: Uuid,
+    label: &Label,
+    actor_id: Option<Uuid>,
+) -> Result<bool, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let result = sqlx::query(
+        r#"
+        INSERT INTO task_labels (task_id, label_id)
+        VALUES ($1, $2)
+        ON CONFLICT DO NOTHING
+        "#,
+    )
+    .bind(task_id)
+    .bind(label.id)
+    .execute(&mut *tx)
+    .await?;
+
+    let added = result.rows_affected() > 0;
+    if added {
+        record_task_activity(&mut *tx, task_id, actor_id, "label_added", None, Some(&label.name)).await?;
+    }
+    tx.commit().await?;
+
+    Ok(added)
+}
+
+/// Returns false if the task didn't have the label.
+#[instrument(level = "debug", skip_all)]
+pub async fn remove_task_label(
+    pool: &PgPool,
+    task_id: Uuid,
+    label: &Label,
+    actor_id: Option<Uuid>,
+) -> Result<bool, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let result = sqlx::query(
+        r#"
+        DELETE FROM task_labels WHERE task_id = $1 AND label_id = $2
+        "#,
+    )
+    .bind(task_id)
+    .bind(label.id)
+    .execute(&mut *tx)
+    .await?;
+
+    let removed = result.rows_affected() > 0;
+    if removed {
+        record_task_activity(&mut *tx, task_id, actor_id, "label_removed", Some(&label.name), None).await?;
+    }
+    tx.commit().await?;
+
+    Ok(removed)
+}
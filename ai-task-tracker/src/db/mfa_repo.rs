@@ -0,0 +1,246 @@
+use sqlx::PgPool;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::models::{MfaStatus, TotpState};
+
+/// Failed codes in a row before two-factor verification is locked.
+const MFA_MAX_FAILED_ATTEMPTS: i32 = 5;
+const MFA_LOCKOUT_MINUTES: i32 = 15;
+
+#[instrument(level = "debug", skip_all)]
+pub async fn get_mfa_status(pool: &PgPool, user_id: Uuid) -> Result<Option<MfaStatus>, sqlx::Error> {
+    let status = sqlx::query_as::<_, MfaStatus>(
+        r#"
+        SELECT u.totp_enabled_at IS NOT NULL AS enabled,
+               EXISTS (SELECT 1 FROM mfa_required_roles r WHERE r.role = u.role) AS required,
+               (SELECT COUNT(*) FROM mfa_recovery_codes c
+                WHERE c.user_id = u.id AND c.used_at IS NULL) AS recovery_codes_remaining
+        FROM users u
+        WHERE u.id = $1
+        "#,
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(status)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn find_totp_state(pool: &PgPool, user_id: Uuid) -> Result<Option<TotpState>, sqlx::Error> {
+    let state = sqlx::query_as::<_, TotpState>(
+        r#"
+        SELECT totp_secret, totp_enabled_at, mfa_locked_until
+        FROM users WHERE id = $1
+        "#,
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(state)
+}
+
+/// Starts (or restarts) enrollment with a new secret. Returns `false` if
+/// two-factor authentication is already enabled.
+#[instrument(level = "debug", skip_all)]
+pub async fn set_pending_totp_secret(pool: &PgPool, user_id: Uuid, secret: &str) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        UPDATE users SET totp_secret = $2
+        WHERE id = $1 AND totp_enabled_at IS NULL
+        "#,
+    )
+    .bind(user_id)
+    .bind(secret)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+async fn insert_recovery_codes(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: Uuid,
+    code_hashes: &[String],
+) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM mfa_recovery_codes WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut **tx)
+        .await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO mfa_recovery_codes (user_id, code_hash)
+        SELECT $1, UNNEST($2::text[])
+        "#,
+    )
+    .bind(user_id)
+    .bind(code_hashes)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+/// Turns on two-factor authentication with the pending secret, after a code
+/// for time step `step` was confirmed, replacing any recovery codes.
+#[instrument(level = "debug", skip_all)]
+pub async fn enable_totp(pool: &PgPool, user_id: Uuid, step: i64, code_hashes: &[String]) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        r#"
+        UPDATE users
+        SET totp_enabled_at = NOW(), totp_last_step = $2, mfa_failed_attempts = 0, mfa_locked_until = NULL
+        WHERE id = $1
+        "#,
+    )
+    .bind(user_id)
+    .bind(step)
+    .execute(&mut *tx)
+    .await?;
+
+    insert_recovery_codes(&mut tx, user_id, code_hashes).await?;
+    tx.commit().await?;
+
+    Ok(())
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn disable_totp(pool: &PgPool, user_id: Uuid) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        r#"
+        UPDATE users
+        SET totp_secret = NULL, totp_enabled_at = NULL, totp_last_step = NULL
+        WHERE id = $1
+        "#,
+    )
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query("DELETE FROM mfa_recovery_codes WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn replace_recovery_codes(pool: &PgPool, user_id: Uuid, code_hashes: &[String]) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    insert_recovery_codes(&mut tx, user_id, code_hashes).await?;
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Accepts a TOTP time step unless it (or a later one) was used before,
+/// clearing failed attempts.
+#[instrument(level = "debug", skip_all)]
+pub async fn record_totp_step(pool: &PgPool, user_id: Uuid, step: i64) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        UPDATE users
+        SET totp_last_step = $2, mfa_failed_attempts = 0
+        WHERE id = $1 AND (totp_last_step IS NULL OR totp_last_step < $2)
+        "#,
+    )
+    .bind(user_id)
+    .bind(step)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Marks an unused recovery code as used, clearing failed attempts.
+/// Returns `false` if there is no such code.
+#[instrument(level = "debug", skip_all)]
+pub async fn use_recovery_code(pool: &PgPool, user_id: Uuid, code_hash: &str) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        UPDATE mfa_recovery_codes SET used_at = NOW()
+        WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL
+        "#,
+    )
+    .bind(user_id)
+    .bind(code_hash)
+    .execute(pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Ok(false);
+    }
+
+    sqlx::query("UPDATE users SET mfa_failed_attempts = 0 WHERE id = $1")
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+
+    Ok(true)
+}
+
+/// Counts a wrong code, locking verification for a while after too many.
+#[instrument(level = "debug", skip_all)]
+pub async fn record_mfa_failure(pool: &PgPool, user_id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE users
+        SET mfa_failed_attempts = CASE WHEN mfa_failed_attempts + 1 >= $2 THEN 0 ELSE mfa_failed_attempts + 1 END,
+            mfa_locked_until = CASE WHEN mfa_failed_attempts + 1 >= $2
+                                    THEN NOW() + make_interval(mins => $3)
+                                    ELSE mfa_locked_until END
+        WHERE id = $1
+        "#,
+    )
+    .bind(user_id)
+    .bind(MFA_MAX_FAILED_ATTEMPTS)
+    .bind(MFA_LOCKOUT_MINUTES)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn list_mfa_required_roles(pool: &PgPool) -> Result<Vec<String>, sqlx::Error> {
+    let roles = sqlx::query_scalar::<_, String>("SELECT role FROM mfa_required_roles ORDER BY role")
+        .fetch_all(pool)
+        .await?;
+
+    Ok(roles)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn set_mfa_required_roles(pool: &PgPool, roles: &[String], updated_by: Uuid) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("DELETE FROM mfa_required_roles WHERE NOT (role = ANY($1))")
+        .bind(roles)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO mfa_required_roles (role, updated_by)
+        SELECT UNNEST($1::text[]), $2
+        ON CONFLICT (role) DO NOTHING
+        "#,
+    )
+    .bind(roles)
+    .bind(updated_by)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
@@ -1,22 +1,151 @@
-use sqlx::{postgres::PgPoolOptions, PgPool};
+use std::future::Future;
+
+use sqlx::{postgres::PgPoolOptions, Executor, PgPool};
+use uuid::Uuid;
+
+use crate::config::DatabaseConfig;
 
 pub mod user_repo;
 pub mod team_repo;
+pub mod team_invitation_repo;
 pub mod project_repo;
 pub mod task_repo;
 pub mod subtask_repo;
 pub mod task_history_repo;
+pub mod task_activity_repo;
+pub mod analytics_repo;
+pub mod import_repo;
+pub mod calendar_repo;
+pub mod attachment_repo;
+pub mod comment_repo;
+pub mod notification_repo;
+pub mod label_repo;
+pub mod time_entry_repo;
+pub mod sprint_repo;
+pub mod trash_repo;
+pub mod template_repo;
+pub mod api_token_repo;
+pub mod oidc_repo;
+pub mod mfa_repo;
+pub mod job_repo;
+pub mod organization_repo;
+pub mod saved_view_repo;
+pub mod email_repo;
+pub mod repository;
 
 pub use user_repo::*;
 pub use team_repo::*;
+pub use team_invitation_repo::*;
 pub use project_repo::*;
 pub use task_repo::*;
 pub use subtask_repo::*;
 pub use task_history_repo::*;
+pub use task_activity_repo::*;
+pub use analytics_repo::*;
+pub use import_repo::*;
+pub use calendar_repo::*;
+pub use attachment_repo::*;
+pub use comment_repo::*;
+pub use notification_repo::*;
+pub use label_repo::*;
+pub use time_entry_repo::*;
+pub use sprint_repo::*;
+pub use trash_repo::*;
+pub use template_repo::*;
+pub use api_token_repo::*;
+pub use oidc_repo::*;
+pub use mfa_repo::*;
+pub use job_repo::*;
+pub use organization_repo::*;
+pub use saved_view_repo::*;
+pub use email_repo::*;
+pub use repository::*;
+
+/// Applies any migrations the database has not run yet.
+pub async fn run_migrations(pool: &PgPool) -> Result<(), sqlx::migrate::MigrateError> {
+    across_organizations(sqlx::migrate!("./migrations").run(pool)).await
+}
+
+/// Versions of the migrations this build ships that the database has not run.
+/// Fails if the database cannot be reached.
+pub async fn pending_migrations(pool: &PgPool) -> Result<Vec<i64>, sqlx::Error> {
+    let applied: Vec<i64> = sqlx::query_scalar("SELECT version FROM _sqlx_migrations WHERE success")
+        .fetch_all(pool)
+        .await
+        .or_else(|e| match &e {
+            // Nothing has been migrated yet
+            sqlx::Error::Database(db) if db.code().as_deref() == Some("42P01") => Ok(Vec::new()),
+            _ => Err(e),
+        })?;
+
+    Ok(sqlx::migrate!("./migrations")
+        .iter()
+        .filter(|m| !m.migration_type.is_down_migration() && !applied.contains(&m.version))
+        .map(|m| m.version)
+        .collect())
+}
+
+/// Which rows the row-level security policies let a connection see.
+#[derive(Debug, Clone, Copy)]
+enum RowAccess {
+    Organization(Uuid),
+    AllOrganizations,
+}
 
-pub async fn create_pool(database_url: &str) -> Result<PgPool, sqlx::Error> {
+tokio::task_local! {
+    /// The rows the current request or background task may see.
+    static ROW_ACCESS: RowAccess;
+}
+
+/// Runs `fut` with connections from [`pool_options`] pools restricted to the
+/// rows of `organization_id` by the row-level security policies.
+pub async fn scoped_to_organization<F: Future>(organization_id: Uuid, fut: F) -> F::Output {
+    ROW_ACCESS.scope(RowAccess::Organization(organization_id), fut).await
+}
+
+/// Runs `fut` with connections that see every organization's rows, for work
+/// not done on behalf of one: background jobs, migrations and the calendar
+/// feed. Connections outside both this and [`scoped_to_organization`] see no
+/// organization's rows at all.
+pub async fn across_organizations<F: Future>(fut: F) -> F::Output {
+    ROW_ACCESS.scope(RowAccess::AllOrganizations, fut).await
+}
+
+/// Sets the connection's `app.organization_id` and `app.all_organizations`,
+/// which the row-level security policies read, for the current task.
+async fn set_connection_access(conn: &mut sqlx::PgConnection) -> Result<(), sqlx::Error> {
+    let (organization_id, all_organizations) = match ROW_ACCESS.try_with(|access| *access) {
+        Ok(RowAccess::Organization(id)) => (id.to_string(), ""),
+        Ok(RowAccess::AllOrganizations) => (String::new(), "on"),
+        Err(_) => (String::new(), ""),
+    };
+    let query = sqlx::query(
+        "SELECT set_config('app.organization_id', $1, false), set_config('app.all_organizations', $2, false)",
+    );
+    conn.execute(query.bind(organization_id).bind(all_organizations)).await?;
+    Ok(())
+}
+
+/// Pool options that set each connection's row access as it is handed out.
+/// Both hooks run in the task acquiring the connection, so they see the scope
+/// [`scoped_to_organization`] or [`across_organizations`] set for it.
+pub fn pool_options() -> PgPoolOptions {
     PgPoolOptions::new()
-        .max_connections(5)
-        .connect(database_url)
+        .after_connect(|conn, _| Box::pin(set_connection_access(conn)))
+        .before_acquire(|conn, _| {
+            Box::pin(async move {
+                set_connection_access(conn).await?;
+                Ok(true)
+            })
+        })
+}
+
+pub async fn create_pool(config: &DatabaseConfig) -> Result<PgPool, sqlx::Error> {
+    pool_options()
+        .max_connections(config.max_connections)
+        .min_connections(config.min_connections)
+        .acquire_timeout(config.acquire_timeout)
+        .idle_timeout(config.idle_timeout)
+        .connect(&config.url)
         .await
 }
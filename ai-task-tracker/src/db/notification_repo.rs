@@ -0,0 +1,106 @@
+use sqlx::{PgExecutor, PgPool};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::models::{NewNotification, Notification};
+
+#[instrument(level = "debug", skip_all)]
+pub async fn create_notification<'e, E: PgExecutor<'e>>(
+    executor: E,
+    notification: &NewNotification,
+    actor_id: Option<Uuid>,
+    task_id: Option<Uuid>,
+    comment_id: Option<Uuid>,
+) -> Result<Notification, sqlx::Error> {
+    let notification = sqlx::query_as::<_, Notification>(
+        r#"
+        INSERT INTO notifications (user_id, kind, actor_id, task_id, comment_id, message)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING *
+        "#,
+    )
+    .bind(notification.user_id)
+    .bind(notification.kind.to_string())
+    .bind(actor_id)
+    .bind(task_id)
+    .bind(comment_id)
+    .bind(&notification.message)
+    .fetch_one(executor)
+    .await?;
+
+    Ok(notification)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn list_notifications(
+    pool: &PgPool,
+    user_id: Uuid,
+    unread_only: bool,
+    limit: i64,
+) -> Result<Vec<Notification>, sqlx::Error> {
+    let notifications = sqlx::query_as::<_, Notification>(
+        r#"
+        SELECT * FROM notifications
+        WHERE user_id = $1 AND ($2 = FALSE OR read_at IS NULL)
+        ORDER BY created_at DESC
+        LIMIT $3
+        "#,
+    )
+    .bind(user_id)
+    .bind(unread_only)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(notifications)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn count_unread_notifications(pool: &PgPool, user_id: Uuid) -> Result<i64, sqlx::Error> {
+    let count: (i64,) = sqlx::query_as(
+        r#"
+        SELECT COUNT(*) FROM notifications WHERE user_id = $1 AND read_at IS NULL
+        "#,
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(count.0)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn mark_notification_read(
+    pool: &PgPool,
+    user_id: Uuid,
+    id: Uuid,
+) -> Result<Option<Notification>, sqlx::Error> {
+    let notification = sqlx::query_as::<_, Notification>(
+        r#"
+        UPDATE notifications
+        SET read_at = COALESCE(read_at, NOW())
+        WHERE id = $1 AND user_id = $2
+        RETURNING *
+        "#,
+    )
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(notification)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn mark_all_notifications_read(pool: &PgPool, user_id: Uuid) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        UPDATE notifications SET read_at = NOW() WHERE user_id = $1 AND read_at IS NULL
+        "#,
+    )
+    .bind(user_id)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
@@ -0,0 +1,146 @@
+use sqlx::PgPool;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::db::add_organization_member;
+use crate::models::{OidcLoginState, User};
+
+/// Minutes a user has to complete a sign-in at the identity provider.
+const LOGIN_STATE_TTL_MINUTES: i32 = 10;
+
+/// Stores a pending authorization request, clearing out abandoned ones.
+#[instrument(level = "debug", skip_all)]
+pub async fn create_oidc_login_state(pool: &PgPool, login_state: &OidcLoginState) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM oidc_login_states WHERE created_at < NOW() - make_interval(mins => $1)")
+        .bind(LOGIN_STATE_TTL_MINUTES)
+        .execute(pool)
+        .await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO oidc_login_states (state, nonce, code_verifier)
+        VALUES ($1, $2, $3)
+        "#,
+    )
+    .bind(&login_state.state)
+    .bind(&login_state.nonce)
+    .bind(&login_state.code_verifier)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Removes and returns a pending authorization request, so each state can be
+/// used once. Returns `None` for unknown or expired states.
+#[instrument(level = "debug", skip_all)]
+pub async fn take_oidc_login_state(pool: &PgPool, state: &str) -> Result<Option<OidcLoginState>, sqlx::Error> {
+    let login_state = sqlx::query_as::<_, OidcLoginState>(
+        r#"
+        DELETE FROM oidc_login_states
+        WHERE state = $1 AND created_at >= NOW() - make_interval(mins => $2)
+        RETURNING state, nonce, code_verifier
+        "#,
+    )
+    .bind(state)
+    .bind(LOGIN_STATE_TTL_MINUTES)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(login_state)
+}
+
+/// The user linked to an identity provider account, recording the sign-in.
+#[instrument(level = "debug", skip_all)]
+pub async fn find_user_by_identity(
+    pool: &PgPool,
+    issuer: &str,
+    subject: &str,
+    email: &str,
+) -> Result<Option<User>, sqlx::Error> {
+    let user = sqlx::query_as::<_, User>(
+        r#"
+        WITH identity AS (
+            UPDATE user_identities SET last_login_at = NOW(), email = $3
+            WHERE issuer = $1 AND subject = $2
+            RETURNING user_id
+        )
+        SELECT u.* FROM users u JOIN identity ON identity.user_id = u.id
+        "#,
+    )
+    .bind(issuer)
+    .bind(subject)
+    .bind(email)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(user)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn link_user_identity(
+    pool: &PgPool,
+    user_id: Uuid,
+    issuer: &str,
+    subject: &str,
+    email: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO user_identities (user_id, issuer, subject, email)
+        VALUES ($1, $2, $3, $4)
+        "#,
+    )
+    .bind(user_id)
+    .bind(issuer)
+    .bind(subject)
+    .bind(email)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Creates a passwordless user for a first single sign-on, linked to the
+/// identity provider account and a member of `organization_id`.
+#[instrument(level = "debug", skip_all)]
+pub async fn provision_oidc_user(
+    pool: &PgPool,
+    email: &str,
+    full_name: &str,
+    issuer: &str,
+    subject: &str,
+    organization_id: Uuid,
+) -> Result<User, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let user = sqlx::query_as::<_, User>(
+        r#"
+        INSERT INTO users (email, password_hash, full_name, role)
+        VALUES ($1, NULL, $2, 'member')
+        RETURNING *
+        "#,
+    )
+    .bind(email)
+    .bind(full_name)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO user_identities (user_id, issuer, subject, email)
+        VALUES ($1, $2, $3, $4)
+        "#,
+    )
+    .bind(user.id)
+    .bind(issuer)
+    .bind(subject)
+    .bind(email)
+    .execute(&mut *tx)
+    .await?;
+
+    add_organization_member(&mut *tx, organization_id, user.id).await?;
+    tx.commit().await?;
+
+    Ok(user)
+}
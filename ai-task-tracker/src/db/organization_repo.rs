@@ -0,0 +1,274 @@
+use sqlx::{PgExecutor, PgPool};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::models::{CreateOrganizationRequest, Organization, OrganizationInvitation, OrganizationMember};
+
+/// Creates an organization with `creator_id` as its first member.
+#[instrument(level = "debug", skip_all)]
+pub async fn create_organization(
+    pool: &PgPool,
+    req: &CreateOrganizationRequest,
+    creator_id: Uuid,
+) -> Result<Organization, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let organization = sqlx::query_as::<_, Organization>(
+        r#"
+        INSERT INTO organizations (name, slug)
+        VALUES ($1, $2)
+        RETURNING *
+        "#,
+    )
+    .bind(&req.name)
+    .bind(&req.slug)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    sqlx::query("INSERT INTO organization_members (organization_id, user_id) VALUES ($1, $2)")
+        .bind(organization.id)
+        .bind(creator_id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(organization)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn find_organization_by_id(pool: &PgPool, id: Uuid) -> Result<Option<Organization>, sqlx::Error> {
+    let organization = sqlx::query_as::<_, Organization>("SELECT * FROM organizations WHERE id = $1")
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(organization)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn find_organization_by_slug(pool: &PgPool, slug: &str) -> Result<Option<Organization>, sqlx::Error> {
+    let organization = sqlx::query_as::<_, Organization>("SELECT * FROM organizations WHERE slug = $1")
+        .bind(slug)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(organization)
+}
+
+/// The organizations the user belongs to, in the order they joined them.
+#[instrument(level = "debug", skip_all)]
+pub async fn list_user_organizations(pool: &PgPool, user_id: Uuid) -> Result<Vec<Organization>, sqlx::Error> {
+    let organizations = sqlx::query_as::<_, Organization>(
+        r#"
+        SELECT o.* FROM organizations o
+        JOIN organization_members om ON om.organization_id = o.id
+        WHERE om.user_id = $1
+        ORDER BY om.joined_at, o.name
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(organizations)
+}
+
+/// Ids of the organizations the user belongs to, in the order they joined them.
+#[instrument(level = "debug", skip_all)]
+pub async fn list_user_organization_ids(pool: &PgPool, user_id: Uuid) -> Result<Vec<Uuid>, sqlx::Error> {
+    let ids = sqlx::query_scalar(
+        r#"
+        SELECT organization_id FROM organization_members
+        WHERE user_id = $1
+        ORDER BY joined_at, organization_id
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(ids)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn is_organization_member(pool: &PgPool, organization_id: Uuid, user_id: Uuid) -> Result<bool, sqlx::Error> {
+    let member = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM organization_members WHERE organization_id = $1 AND user_id = $2)",
+    )
+    .bind(organization_id)
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(member)
+}
+
+/// Adds the user to the organization; does nothing if they already belong to it.
+#[instrument(level = "debug", skip_all)]
+pub async fn add_organization_member<'e, E: PgExecutor<'e>>(
+    executor: E,
+    organization_id: Uuid,
+    user_id: Uuid,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO organization_members (organization_id, user_id)
+        VALUES ($1, $2)
+        ON CONFLICT DO NOTHING
+        "#,
+    )
+    .bind(organization_id)
+    .bind(user_id)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn list_organization_members(
+    pool: &PgPool,
+    organization_id: Uuid,
+) -> Result<Vec<OrganizationMember>, sqlx::Error> {
+    let members = sqlx::query_as::<_, OrganizationMember>(
+        "SELECT * FROM organization_members WHERE organization_id = $1 ORDER BY joined_at",
+    )
+    .bind(organization_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(members)
+}
+
+/// Removes the user from the organization and from its teams. Returns `false`
+/// if they were not a member.
+#[instrument(level = "debug", skip_all)]
+pub async fn remove_organization_member(
+    pool: &PgPool,
+    organization_id: Uuid,
+    user_id: Uuid,
+) -> Result<bool, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let removed = sqlx::query("DELETE FROM organization_members WHERE organization_id = $1 AND user_id = $2")
+        .bind(organization_id)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+    sqlx::query(
+        r#"
+        DELETE FROM team_members
+        WHERE user_id = $2 AND team_id IN (SELECT id FROM teams WHERE organization_id = $1)
+        "#,
+    )
+    .bind(organization_id)
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(removed > 0)
+}
+
+/// Invites an existing user to the organization. Fails with a unique violation
+/// if an invitation for them is already pending.
+#[instrument(level = "debug", skip_all)]
+pub async fn create_organization_invitation(
+    pool: &PgPool,
+    organization_id: Uuid,
+    user_id: Uuid,
+    invited_by: Uuid,
+) -> Result<OrganizationInvitation, sqlx::Error> {
+    let invitation = sqlx::query_as::<_, OrganizationInvitation>(
+        r#"
+        INSERT INTO organization_invitations (organization_id, user_id, invited_by)
+        VALUES ($1, $2, $3)
+        RETURNING *
+        "#,
+    )
+    .bind(organization_id)
+    .bind(user_id)
+    .bind(invited_by)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(invitation)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn find_organization_invitation_by_id(
+    pool: &PgPool,
+    id: Uuid,
+) -> Result<Option<OrganizationInvitation>, sqlx::Error> {
+    let invitation = sqlx::query_as::<_, OrganizationInvitation>("SELECT * FROM organization_invitations WHERE id = $1")
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(invitation)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn list_pending_organization_invitations(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> Result<Vec<OrganizationInvitation>, sqlx::Error> {
+    let invitations = sqlx::query_as::<_, OrganizationInvitation>(
+        r#"
+        SELECT * FROM organization_invitations
+        WHERE user_id = $1 AND status = 'pending'
+        ORDER BY created_at DESC
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(invitations)
+}
+
+/// Marks the invitation accepted and adds the user to the organization, in
+/// one transaction.
+#[instrument(level = "debug", skip_all)]
+pub async fn accept_organization_invitation(pool: &PgPool, id: Uuid) -> Result<OrganizationInvitation, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let invitation = sqlx::query_as::<_, OrganizationInvitation>(
+        r#"
+        UPDATE organization_invitations
+        SET status = 'accepted', responded_at = NOW()
+        WHERE id = $1 AND status = 'pending'
+        RETURNING *
+        "#,
+    )
+    .bind(id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    add_organization_member(&mut *tx, invitation.organization_id, invitation.user_id).await?;
+
+    tx.commit().await?;
+
+    Ok(invitation)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn decline_organization_invitation(pool: &PgPool, id: Uuid) -> Result<OrganizationInvitation, sqlx::Error> {
+    let invitation = sqlx::query_as::<_, OrganizationInvitation>(
+        r#"
+        UPDATE organization_invitations
+        SET status = 'declined', responded_at = NOW()
+        WHERE id = $1 AND status = 'pending'
+        RETURNING *
+        "#,
+    )
+    .bind(id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(invitation)
+}
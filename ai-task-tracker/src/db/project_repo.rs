@@ -1,16 +1,20 @@
 use crate::models::{CreateProjectRequest, Project, UpdateProjectRequest};
+use chrono::{DateTime, Utc};
 use sqlx::PgPool;
+use tracing::instrument;
 use uuid::Uuid;
 
+#[instrument(level = "debug", skip_all)]
 pub async fn create_project(
     pool: &PgPool,
+    organization_id: Uuid,
     req: &CreateProjectRequest,
     created_by: Uuid,
 ) -> Result<Project, sqlx::Error> {
     let project = sqlx::query_as::<_, Project>(
         r#"
-        INSERT INTO projects (name, description, team_id, created_by)
-        VALUES ($1, $2, $3, $4)
+        INSERT INTO projects (name, description, team_id, created_by, organization_id)
+        VALUES ($1, $2, $3, $4, $5)
         RETURNING *
         "#,
     )
@@ -18,41 +22,57 @@ pub async fn create_project(
     .bind(&req.description)
     .bind(req.team_id)
     .bind(created_by)
+    .bind(organization_id)
     .fetch_one(pool)
     .await?;
 
     Ok(project)
 }
 
-pub async fn find_project_by_id(pool: &PgPool, id: Uuid) -> Result<Option<Project>, sqlx::Error> {
+#[instrument(level = "debug", skip_all)]
+pub async fn find_project_by_id(
+    pool: &PgPool,
+    organization_id: Uuid,
+    id: Uuid,
+) -> Result<Option<Project>, sqlx::Error> {
     let project = sqlx::query_as::<_, Project>(
         r#"
-        SELECT * FROM projects WHERE id = $1
+        SELECT * FROM projects WHERE id = $1 AND organization_id = $2 AND deleted_at IS NULL
         "#,
     )
     .bind(id)
+    .bind(organization_id)
     .fetch_optional(pool)
     .await?;
 
     Ok(project)
 }
 
-pub async fn list_projects(pool: &PgPool, team_id: Option<Uuid>) -> Result<Vec<Project>, sqlx::Error> {
+#[instrument(level = "debug", skip_all)]
+pub async fn list_projects(
+    pool: &PgPool,
+    organization_id: Uuid,
+    team_id: Option<Uuid>,
+) -> Result<Vec<Project>, sqlx::Error> {
     let projects = if let Some(team_id) = team_id {
         sqlx::query_as::<_, Project>(
             r#"
-            SELECT * FROM projects WHERE team_id = $1 ORDER BY created_at DESC
+            SELECT * FROM projects
+            WHERE team_id = $1 AND organization_id = $2 AND deleted_at IS NULL
+            ORDER BY created_at DESC
             "#,
         )
         .bind(team_id)
+        .bind(organization_id)
         .fetch_all(pool)
         .await?
     } else {
         sqlx::query_as::<_, Project>(
             r#"
-            SELECT * FROM projects ORDER BY created_at DESC
+            SELECT * FROM projects WHERE organization_id = $1 AND deleted_at IS NULL ORDER BY created_at DESC
             "#,
         )
+        .bind(organization_id)
         .fetch_all(pool)
         .await?
     };
@@ -60,8 +80,10 @@ pub async fn list_projects(pool: &PgPool, team_id: Option<Uuid>) -> Result<Vec<P
     Ok(projects)
 }
 
+#[instrument(level = "debug", skip_all)]
 pub async fn update_project(
     pool: &PgPool,
+    organization_id: Uuid,
     id: Uuid,
     req: &UpdateProjectRequest,
 ) -> Result<Project, sqlx::Error> {
@@ -72,7 +94,7 @@ pub async fn update_project(
             description = COALESCE($3, description),
             team_id = COALESCE($4, team_id),
             updated_at = NOW()
-        WHERE id = $1
+        WHERE id = $1 AND organization_id = $5 AND deleted_at IS NULL
         RETURNING *
         "#,
     )
@@ -80,21 +102,101 @@ pub async fn update_project(
     .bind(&req.name)
     .bind(&req.description)
     .bind(req.team_id)
+    .bind(organization_id)
     .fetch_one(pool)
     .await?;
 
     Ok(project)
 }
 
-pub async fn delete_project(pool: &PgPool, id: Uuid) -> Result<(), sqlx::Error> {
+/// Moves a project and its tasks to the trash. Returns `false` if the project
+/// does not exist or is already deleted.
+#[instrument(level = "debug", skip_all)]
+pub async fn soft_delete_project(
+    pool: &PgPool,
+    organization_id: Uuid,
+    id: Uuid,
+    actor_id: Option<Uuid>,
+) -> Result<bool, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let deleted = sqlx::query(
+        r#"
+        UPDATE projects SET deleted_at = NOW(), deleted_by = $2
+        WHERE id = $1 AND organization_id = $3 AND deleted_at IS NULL
+        "#,
+    )
+    .bind(id)
+    .bind(actor_id)
+    .bind(organization_id)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    if deleted == 0 {
+        return Ok(false);
+    }
+
+    sqlx::query(
+        r#"
+        UPDATE tasks SET deleted_at = NOW(), deleted_by = $2
+        WHERE project_id = $1 AND deleted_at IS NULL
+        "#,
+    )
+    .bind(id)
+    .bind(actor_id)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(true)
+}
+
+/// Restores a project from the trash along with the tasks deleted with it.
+#[instrument(level = "debug", skip_all)]
+pub async fn restore_project(
+    pool: &PgPool,
+    organization_id: Uuid,
+    id: Uuid,
+) -> Result<Option<Project>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let deleted_at: Option<DateTime<Utc>> = sqlx::query_scalar(
+        "SELECT deleted_at FROM projects WHERE id = $1 AND organization_id = $2 AND deleted_at IS NOT NULL FOR UPDATE",
+    )
+    .bind(id)
+    .bind(organization_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let Some(deleted_at) = deleted_at else {
+        return Ok(None);
+    };
+
     sqlx::query(
         r#"
-        DELETE FROM projects WHERE id = $1
+        UPDATE tasks SET deleted_at = NULL, deleted_by = NULL
+        WHERE project_id = $1 AND deleted_at = $2
         "#,
     )
     .bind(id)
-    .execute(pool)
+    .bind(deleted_at)
+    .execute(&mut *tx)
     .await?;
 
-    Ok(())
+    let project = sqlx::query_as::<_, Project>(
+        r#"
+        UPDATE projects SET deleted_at = NULL, deleted_by = NULL, updated_at = NOW()
+        WHERE id = $1
+        RETURNING *
+        "#,
+    )
+    .bind(id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(Some(project))
 }
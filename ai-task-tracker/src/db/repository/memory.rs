@@ -0,0 +1,1054 @@
+use std::{
+    borrow::Cow,
+    cmp::{Ordering, Reverse},
+    sync::{Mutex, MutexGuard},
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::error::{DatabaseError, ErrorKind};
+use uuid::Uuid;
+
+use crate::db::{ProjectRepository, TaskRepository, TeamRepository, UserRepository};
+use crate::models::{
+    CreateProjectRequest, CreateSubtaskRequest, CreateTaskHistoryRequest, CreateTaskRequest, CreateTeamRequest,
+    CreateUserRequest, EmailLog, OrganizationMember, Project, SortOrder, Subtask, Task, TaskActivity, TaskFilter,
+    TaskHistory, TaskHistoryWithUser, TaskSortField, Team, TeamInvitation, TeamMember, TeamMemberWithUser, TeamRole,
+    TrashItem, TrashKind, UpdateProjectRequest, UpdateSubtaskRequest, UpdateTaskRequest, UpdateTeamRequest, User,
+};
+
+/// Stands in for the constraint errors Postgres would raise, so handlers map
+/// them the same way.
+#[derive(Debug, thiserror::Error)]
+#[error("{message}")]
+struct ConstraintViolation {
+    /// The SQLSTATE Postgres reports for the violation
+    code: &'static str,
+    message: &'static str,
+}
+
+const UNIQUE_VIOLATION: &str = "23505";
+const FOREIGN_KEY_VIOLATION: &str = "23503";
+const CHECK_VIOLATION: &str = "23514";
+
+impl DatabaseError for ConstraintViolation {
+    fn message(&self) -> &str {
+        self.message
+    }
+
+    fn code(&self) -> Option<Cow<'_, str>> {
+        Some(Cow::Borrowed(self.code))
+    }
+
+    fn as_error(&self) -> &(dyn std::error::Error + Send + Sync + 'static) {
+        self
+    }
+
+    fn as_error_mut(&mut self) -> &mut (dyn std::error::Error + Send + Sync + 'static) {
+        self
+    }
+
+    fn into_error(self: Box<Self>) -> Box<dyn std::error::Error + Send + Sync + 'static> {
+        self
+    }
+
+    fn kind(&self) -> ErrorKind {
+        match self.code {
+            UNIQUE_VIOLATION => ErrorKind::UniqueViolation,
+            FOREIGN_KEY_VIOLATION => ErrorKind::ForeignKeyViolation,
+            CHECK_VIOLATION => ErrorKind::CheckViolation,
+            _ => ErrorKind::Other,
+        }
+    }
+}
+
+fn violation(code: &'static str, message: &'static str) -> sqlx::Error {
+    sqlx::Error::Database(Box::new(ConstraintViolation { code, message }))
+}
+
+/// A row that can be moved to the trash. Rows deleted together share a
+/// timestamp, which is how a restore finds them again. `deleted_by` only
+/// means something while the row is in the trash.
+struct Trashable<T> {
+    value: T,
+    deleted_at: Option<DateTime<Utc>>,
+    deleted_by: Option<Uuid>,
+}
+
+impl<T> Trashable<T> {
+    fn new(value: T) -> Self {
+        Self {
+            value,
+            deleted_at: None,
+            deleted_by: None,
+        }
+    }
+
+    fn trash(&mut self, at: DateTime<Utc>, by: Option<Uuid>) {
+        self.deleted_at = Some(at);
+        self.deleted_by = by;
+    }
+
+    fn live(&self) -> Option<&T> {
+        self.deleted_at.is_none().then_some(&self.value)
+    }
+}
+
+/// Rows owned by an organization.
+trait Owned {
+    fn id(&self) -> Uuid;
+    fn organization_id(&self) -> Uuid;
+}
+
+macro_rules! impl_owned {
+    ($($row:ty),*) => {$(
+        impl Owned for $row {
+            fn id(&self) -> Uuid {
+                self.id
+            }
+
+            fn organization_id(&self) -> Uuid {
+                self.organization_id
+            }
+        }
+    )*};
+}
+
+impl_owned!(Task, Project, Team);
+
+impl<T: Owned> Trashable<T> {
+    fn owned_by(&self, organization_id: Uuid) -> bool {
+        self.value.organization_id() == organization_id
+    }
+}
+
+fn priority_rank(priority: &str) -> u8 {
+    match priority {
+        "low" => 0,
+        "medium" => 1,
+        "high" => 2,
+        _ => 3,
+    }
+}
+
+/// Orders tasks the way `db::list_tasks` does. They must come in newest first,
+/// which the stable sort keeps for ties.
+fn sort_tasks(tasks: &mut [Task], sort_by: TaskSortField, order: SortOrder) {
+    tasks.sort_by(|a, b| {
+        let ordering = match sort_by {
+            TaskSortField::CreatedAt => a.created_at.cmp(&b.created_at),
+            TaskSortField::UpdatedAt => a.updated_at.cmp(&b.updated_at),
+            TaskSortField::DueDate => match (a.due_date, b.due_date) {
+                (Some(a), Some(b)) => a.cmp(&b),
+                // Tasks without a due date come last in either direction
+                (Some(_), None) => return Ordering::Less,
+                (None, Some(_)) => return Ordering::Greater,
+                (None, None) => return Ordering::Equal,
+            },
+            TaskSortField::Priority => priority_rank(&a.priority).cmp(&priority_rank(&b.priority)),
+            TaskSortField::Title => a.title.to_lowercase().cmp(&b.title.to_lowercase()),
+        };
+        match order {
+            SortOrder::Asc => ordering,
+            SortOrder::Desc => ordering.reverse(),
+        }
+    });
+}
+
+/// Whether the organization has a row with this id, in the trash or not, as a
+/// foreign key to it would check.
+fn exists_in<T: Owned>(rows: &[Trashable<T>], organization_id: Uuid, id: Uuid) -> bool {
+    rows.iter().any(|row| row.value.id() == id && row.owned_by(organization_id))
+}
+
+/// The organization's row with this id, unless it is in the trash.
+fn find_live<T: Owned + Clone>(rows: &[Trashable<T>], organization_id: Uuid, id: Uuid) -> Option<T> {
+    rows.iter()
+        .find(|row| row.value.id() == id && row.owned_by(organization_id))
+        .and_then(Trashable::live)
+        .cloned()
+}
+
+/// Rows are kept in insertion order; listings return the newest first, as
+/// the queries' `ORDER BY created_at DESC` does.
+#[derive(Default)]
+struct Store {
+    users: Vec<User>,
+    organization_members: Vec<OrganizationMember>,
+    teams: Vec<Trashable<Team>>,
+    team_members: Vec<TeamMember>,
+    team_invitations: Vec<TeamInvitation>,
+    projects: Vec<Trashable<Project>>,
+    tasks: Vec<Trashable<Task>>,
+    subtasks: Vec<Subtask>,
+    task_history: Vec<TaskHistory>,
+    task_activity: Vec<TaskActivity>,
+}
+
+impl Store {
+    fn live_task_mut(&mut self, organization_id: Uuid, id: Uuid) -> Result<&mut Task, sqlx::Error> {
+        self.tasks
+            .iter_mut()
+            .find(|t| t.value.id == id && t.owned_by(organization_id) && t.deleted_at.is_none())
+            .map(|t| &mut t.value)
+            .ok_or(sqlx::Error::RowNotFound)
+    }
+
+    /// Whether the task exists and is not in the trash, in any organization.
+    fn task_is_live(&self, id: Uuid) -> bool {
+        self.tasks.iter().any(|t| t.value.id == id && t.deleted_at.is_none())
+    }
+
+    fn record_activity(
+        &mut self,
+        task_id: Uuid,
+        actor_id: Option<Uuid>,
+        action: &str,
+        old_value: Option<&str>,
+        new_value: Option<&str>,
+    ) {
+        self.task_activity.push(TaskActivity {
+            id: Uuid::new_v4(),
+            task_id,
+            actor_id,
+            action: action.to_string(),
+            old_value: old_value.map(str::to_string),
+            new_value: new_value.map(str::to_string),
+            created_at: Utc::now(),
+        });
+    }
+
+    /// Ids of the task and its descendants, following only child tasks that
+    /// `include` accepts.
+    fn task_tree(&self, id: Uuid, include: impl Fn(&Trashable<Task>) -> bool) -> Vec<Uuid> {
+        let mut tree = vec![id];
+        let mut next = 0;
+        while next < tree.len() {
+            let parent = tree[next];
+            tree.extend(
+                self.tasks
+                    .iter()
+                    .filter(|t| t.value.parent_task_id == Some(parent) && include(t))
+                    .map(|t| t.value.id),
+            );
+            next += 1;
+        }
+        tree
+    }
+}
+
+/// Repositories kept in process memory, for tests that should not need
+/// Postgres. Labels and emails are stored elsewhere, so a label filter matches
+/// no tasks and a task has no emails.
+#[derive(Default)]
+pub struct InMemoryRepository {
+    store: Mutex<Store>,
+}
+
+impl InMemoryRepository {
+    fn store(&self) -> MutexGuard<'_, Store> {
+        self.store.lock().expect("in-memory repository lock poisoned")
+    }
+}
+
+#[async_trait]
+impl TaskRepository for InMemoryRepository {
+    async fn create_task(
+        &self,
+        organization_id: Uuid,
+        req: &CreateTaskRequest,
+        actor_id: Option<Uuid>,
+    ) -> Result<Task, sqlx::Error> {
+        let mut store = self.store();
+        if !exists_in(&store.projects, organization_id, req.project_id) {
+            return Err(violation(FOREIGN_KEY_VIOLATION, "project does not exist"));
+        }
+
+        let now = Utc::now();
+        let task = Task {
+            id: Uuid::new_v4(),
+            organization_id,
+            project_id: req.project_id,
+            parent_task_id: req.parent_task_id,
+            title: req.title.clone(),
+            description: req.description.clone(),
+            status: "todo".to_string(),
+            progress_percent: 0,
+            priority: req.priority.as_ref().map(|p| p.to_string()).unwrap_or_else(|| "medium".to_string()),
+            assignee_id: req.assignee_id,
+            due_date: req.due_date,
+            source_email_id: None,
+            estimate_minutes: req.estimate_minutes,
+            sprint_id: None,
+            created_at: now,
+            updated_at: now,
+        };
+        store.tasks.push(Trashable::new(task.clone()));
+        store.record_activity(task.id, actor_id, "created", None, Some(&task.status));
+        Ok(task)
+    }
+
+    async fn find_task_by_id(&self, organization_id: Uuid, id: Uuid) -> Result<Option<Task>, sqlx::Error> {
+        Ok(find_live(&self.store().tasks, organization_id, id))
+    }
+
+    async fn list_tasks(&self, organization_id: Uuid, filter: &TaskFilter) -> Result<Vec<Task>, sqlx::Error> {
+        if !filter.label_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut tasks: Vec<Task> = self
+            .store()
+            .tasks
+            .iter()
+            .rev()
+            .filter(|t| t.owned_by(organization_id))
+            .filter_map(Trashable::live)
+            .filter(|t| filter.project_id.is_none_or(|id| t.project_id == id))
+            .filter(|t| filter.assignee_id.is_none_or(|id| t.assignee_id == Some(id)))
+            .filter(|t| filter.status.as_ref().is_none_or(|s| &t.status == s))
+            .filter(|t| filter.priority.as_ref().is_none_or(|p| &t.priority == p))
+            .filter(|t| filter.parent_task_id.is_none_or(|id| t.parent_task_id == Some(id)))
+            .filter(|t| filter.sprint_id.is_none_or(|id| t.sprint_id == Some(id)))
+            .filter(|t| filter.due_before.is_none_or(|due| t.due_date.is_some_and(|d| d <= due)))
+            .cloned()
+            .collect();
+        sort_tasks(&mut tasks, filter.sort_by, filter.sort_order);
+        Ok(tasks)
+    }
+
+    async fn update_task(
+        &self,
+        organization_id: Uuid,
+        id: Uuid,
+        req: &UpdateTaskRequest,
+        actor_id: Option<Uuid>,
+    ) -> Result<Task, sqlx::Error> {
+        let mut store = self.store();
+        let task = store.live_task_mut(organization_id, id)?;
+        let old_status = task.status.clone();
+
+        if let Some(title) = &req.title {
+            task.title = title.clone();
+        }
+        if let Some(description) = &req.description {
+            task.description = Some(description.clone());
+        }
+        if let Some(status) = &req.status {
+            task.status = status.to_string();
+        }
+        if let Some(priority) = &req.priority {
+            task.priority = priority.to_string();
+        }
+        if let Some(assignee_id) = req.assignee_id {
+            task.assignee_id = Some(assignee_id);
+        }
+        if let Some(due_date) = req.due_date {
+            task.due_date = Some(due_date);
+        }
+        if let Some(progress) = req.progress_percent {
+            task.progress_percent = progress;
+        }
+        if let Some(estimate) = req.estimate_minutes {
+            task.estimate_minutes = Some(estimate);
+        }
+        task.updated_at = Utc::now();
+
+        let task = task.clone();
+        if task.status != old_status {
+            store.record_activity(task.id, actor_id, "status_changed", Some(&old_status), Some(&task.status));
+        }
+        Ok(task)
+    }
+
+    async fn update_task_progress(&self, organization_id: Uuid, id: Uuid, progress: i32) -> Result<Task, sqlx::Error> {
+        let mut store = self.store();
+        let task = store.live_task_mut(organization_id, id)?;
+        task.progress_percent = progress;
+        task.updated_at = Utc::now();
+        Ok(task.clone())
+    }
+
+    async fn soft_delete_task(
+        &self,
+        organization_id: Uuid,
+        id: Uuid,
+        actor_id: Option<Uuid>,
+    ) -> Result<bool, sqlx::Error> {
+        let mut store = self.store();
+        if store.live_task_mut(organization_id, id).is_err() {
+            return Ok(false);
+        }
+
+        let tree = store.task_tree(id, |t| t.deleted_at.is_none());
+        let now = Utc::now();
+        for task in store.tasks.iter_mut().filter(|t| tree.contains(&t.value.id)) {
+            task.trash(now, actor_id);
+        }
+        store.record_activity(id, actor_id, "deleted", None, None);
+        Ok(true)
+    }
+
+    async fn restore_task(
+        &self,
+        organization_id: Uuid,
+        id: Uuid,
+        actor_id: Option<Uuid>,
+    ) -> Result<Option<Task>, sqlx::Error> {
+        let mut store = self.store();
+        let deleted_at = store
+            .tasks
+            .iter()
+            .find(|t| t.value.id == id && t.owned_by(organization_id))
+            .and_then(|t| t.deleted_at);
+
+        if let Some(deleted_at) = deleted_at {
+            let tree = store.task_tree(id, |t| t.deleted_at == Some(deleted_at));
+            let now = Utc::now();
+            for task in store.tasks.iter_mut().filter(|t| tree.contains(&t.value.id)) {
+                task.deleted_at = None;
+                task.value.updated_at = now;
+            }
+        }
+
+        let task = find_live(&store.tasks, organization_id, id);
+        if task.is_some() {
+            store.record_activity(id, actor_id, "restored", None, None);
+        }
+        Ok(task)
+    }
+
+    async fn create_subtask(&self, task_id: Uuid, req: &CreateSubtaskRequest) -> Result<Subtask, sqlx::Error> {
+        let mut store = self.store();
+        if !store.tasks.iter().any(|t| t.value.id == task_id) {
+            return Err(violation(FOREIGN_KEY_VIOLATION, "task does not exist"));
+        }
+
+        let now = Utc::now();
+        let subtask = Subtask {
+            id: Uuid::new_v4(),
+            parent_task_id: task_id,
+            title: req.title.clone(),
+            is_completed: false,
+            created_at: now,
+            updated_at: now,
+        };
+        store.subtasks.push(subtask.clone());
+        Ok(subtask)
+    }
+
+    async fn find_subtask_by_id(&self, organization_id: Uuid, id: Uuid) -> Result<Option<Subtask>, sqlx::Error> {
+        let store = self.store();
+        let subtask = store.subtasks.iter().find(|s| s.id == id);
+        Ok(subtask.filter(|s| find_live(&store.tasks, organization_id, s.parent_task_id).is_some()).cloned())
+    }
+
+    async fn list_subtasks(&self, task_id: Uuid) -> Result<Vec<Subtask>, sqlx::Error> {
+        let store = self.store();
+        if !store.task_is_live(task_id) {
+            return Ok(Vec::new());
+        }
+        Ok(store.subtasks.iter().filter(|s| s.parent_task_id == task_id).cloned().collect())
+    }
+
+    async fn update_subtask(&self, id: Uuid, req: &UpdateSubtaskRequest) -> Result<Subtask, sqlx::Error> {
+        let mut store = self.store();
+        let subtask = store.subtasks.iter_mut().find(|s| s.id == id).ok_or(sqlx::Error::RowNotFound)?;
+
+        if let Some(title) = &req.title {
+            subtask.title = title.clone();
+        }
+        if let Some(is_completed) = req.is_completed {
+            subtask.is_completed = is_completed;
+        }
+        subtask.updated_at = Utc::now();
+
+        Ok(subtask.clone())
+    }
+
+    async fn delete_subtask(&self, id: Uuid) -> Result<(), sqlx::Error> {
+        self.store().subtasks.retain(|s| s.id != id);
+        Ok(())
+    }
+
+    async fn create_task_history(
+        &self,
+        task_id: Uuid,
+        user_id: Uuid,
+        req: &CreateTaskHistoryRequest,
+    ) -> Result<TaskHistory, sqlx::Error> {
+        let mut store = self.store();
+        if !store.tasks.iter().any(|t| t.value.id == task_id) {
+            return Err(violation(FOREIGN_KEY_VIOLATION, "task does not exist"));
+        }
+        if !store.users.iter().any(|u| u.id == user_id) {
+            return Err(violation(FOREIGN_KEY_VIOLATION, "user does not exist"));
+        }
+
+        let history = TaskHistory {
+            id: Uuid::new_v4(),
+            task_id,
+            user_id,
+            comment: req.comment.clone(),
+            completion_percentage: req.completion_percentage,
+            created_at: Utc::now(),
+        };
+        store.task_history.push(history.clone());
+        Ok(history)
+    }
+
+    async fn list_task_history(&self, task_id: Uuid) -> Result<Vec<TaskHistoryWithUser>, sqlx::Error> {
+        let store = self.store();
+        if !store.task_is_live(task_id) {
+            return Ok(Vec::new());
+        }
+
+        let history = store
+            .task_history
+            .iter()
+            .rev()
+            .filter(|h| h.task_id == task_id)
+            .filter_map(|h| {
+                let user = store.users.iter().find(|u| u.id == h.user_id)?;
+                Some(TaskHistoryWithUser {
+                    id: h.id,
+                    task_id: h.task_id,
+                    user_id: h.user_id,
+                    comment: h.comment.clone(),
+                    completion_percentage: h.completion_percentage,
+                    created_at: h.created_at,
+                    user_name: user.full_name.clone(),
+                    user_email: user.email.clone(),
+                })
+            })
+            .collect();
+        Ok(history)
+    }
+
+    async fn list_task_activity(&self, task_id: Uuid) -> Result<Vec<TaskActivity>, sqlx::Error> {
+        let store = self.store();
+        if !store.task_is_live(task_id) {
+            return Ok(Vec::new());
+        }
+        Ok(store.task_activity.iter().rev().filter(|a| a.task_id == task_id).cloned().collect())
+    }
+
+    async fn list_task_emails(&self, _task_id: Uuid) -> Result<Vec<EmailLog>, sqlx::Error> {
+        Ok(Vec::new())
+    }
+}
+
+#[async_trait]
+impl ProjectRepository for InMemoryRepository {
+    async fn create_project(
+        &self,
+        organization_id: Uuid,
+        req: &CreateProjectRequest,
+        created_by: Uuid,
+    ) -> Result<Project, sqlx::Error> {
+        let mut store = self.store();
+        if req.team_id.is_some_and(|id| !exists_in(&store.teams, organization_id, id)) {
+            return Err(violation(FOREIGN_KEY_VIOLATION, "team does not exist"));
+        }
+
+        let now = Utc::now();
+        let project = Project {
+            id: Uuid::new_v4(),
+            organization_id,
+            name: req.name.clone(),
+            description: req.description.clone(),
+            team_id: req.team_id,
+            created_by,
+            created_at: now,
+            updated_at: now,
+        };
+        store.projects.push(Trashable::new(project.clone()));
+        Ok(project)
+    }
+
+    async fn find_project_by_id(&self, organization_id: Uuid, id: Uuid) -> Result<Option<Project>, sqlx::Error> {
+        Ok(find_live(&self.store().projects, organization_id, id))
+    }
+
+    async fn list_projects(&self, organization_id: Uuid, team_id: Option<Uuid>) -> Result<Vec<Project>, sqlx::Error> {
+        let projects = self
+            .store()
+            .projects
+            .iter()
+            .rev()
+            .filter(|p| p.owned_by(organization_id))
+            .filter_map(Trashable::live)
+            .filter(|p| team_id.is_none_or(|id| p.team_id == Some(id)))
+            .cloned()
+            .collect();
+        Ok(projects)
+    }
+
+    async fn update_project(
+        &self,
+        organization_id: Uuid,
+        id: Uuid,
+        req: &UpdateProjectRequest,
+    ) -> Result<Project, sqlx::Error> {
+        let mut store = self.store();
+        if req.team_id.is_some_and(|id| !exists_in(&store.teams, organization_id, id)) {
+            return Err(violation(FOREIGN_KEY_VIOLATION, "team does not exist"));
+        }
+        let project = store
+            .projects
+            .iter_mut()
+            .find(|p| p.value.id == id && p.owned_by(organization_id) && p.deleted_at.is_none())
+            .map(|p| &mut p.value)
+            .ok_or(sqlx::Error::RowNotFound)?;
+
+        if let Some(name) = &req.name {
+            project.name = name.clone();
+        }
+        if let Some(description) = &req.description {
+            project.description = Some(description.clone());
+        }
+        if let Some(team_id) = req.team_id {
+            project.team_id = Some(team_id);
+        }
+        project.updated_at = Utc::now();
+
+        Ok(project.clone())
+    }
+
+    async fn soft_delete_project(
+        &self,
+        organization_id: Uuid,
+        id: Uuid,
+        actor_id: Option<Uuid>,
+    ) -> Result<bool, sqlx::Error> {
+        let mut store = self.store();
+        let now = Utc::now();
+
+        let Some(project) = store
+            .projects
+            .iter_mut()
+            .find(|p| p.value.id == id && p.owned_by(organization_id) && p.deleted_at.is_none())
+        else {
+            return Ok(false);
+        };
+        project.trash(now, actor_id);
+
+        for task in store.tasks.iter_mut().filter(|t| t.value.project_id == id && t.deleted_at.is_none()) {
+            task.trash(now, actor_id);
+        }
+        Ok(true)
+    }
+
+    async fn restore_project(&self, organization_id: Uuid, id: Uuid) -> Result<Option<Project>, sqlx::Error> {
+        let mut store = self.store();
+        let now = Utc::now();
+
+        let Some(project) = store
+            .projects
+            .iter_mut()
+            .find(|p| p.value.id == id && p.owned_by(organization_id) && p.deleted_at.is_some())
+        else {
+            return Ok(None);
+        };
+        let deleted_at = project.deleted_at.take();
+        project.value.updated_at = now;
+        let project = project.value.clone();
+
+        for task in store.tasks.iter_mut().filter(|t| t.value.project_id == id && t.deleted_at == deleted_at) {
+            task.deleted_at = None;
+        }
+        Ok(Some(project))
+    }
+}
+
+#[async_trait]
+impl TeamRepository for InMemoryRepository {
+    async fn create_team(&self, organization_id: Uuid, req: &CreateTeamRequest) -> Result<Team, sqlx::Error> {
+        let mut store = self.store();
+        if req.parent_team_id.is_some_and(|id| !exists_in(&store.teams, organization_id, id)) {
+            return Err(violation(FOREIGN_KEY_VIOLATION, "parent team does not exist"));
+        }
+
+        let now = Utc::now();
+        let team = Team {
+            id: Uuid::new_v4(),
+            organization_id,
+            name: req.name.clone(),
+            parent_team_id: req.parent_team_id,
+            manager_id: req.manager_id,
+            created_at: now,
+            updated_at: now,
+        };
+        store.teams.push(Trashable::new(team.clone()));
+        Ok(team)
+    }
+
+    async fn find_team_by_id(&self, organization_id: Uuid, id: Uuid) -> Result<Option<Team>, sqlx::Error> {
+        Ok(find_live(&self.store().teams, organization_id, id))
+    }
+
+    async fn list_teams(&self, organization_id: Uuid) -> Result<Vec<Team>, sqlx::Error> {
+        let store = self.store();
+        let teams = store.teams.iter().rev().filter(|t| t.owned_by(organization_id));
+        Ok(teams.filter_map(Trashable::live).cloned().collect())
+    }
+
+    async fn update_team(&self, organization_id: Uuid, id: Uuid, req: &UpdateTeamRequest) -> Result<Team, sqlx::Error> {
+        let mut store = self.store();
+        if req.parent_team_id.is_some_and(|id| !exists_in(&store.teams, organization_id, id)) {
+            return Err(violation(FOREIGN_KEY_VIOLATION, "parent team does not exist"));
+        }
+        let team = store
+            .teams
+            .iter_mut()
+            .find(|t| t.value.id == id && t.owned_by(organization_id) && t.deleted_at.is_none())
+            .map(|t| &mut t.value)
+            .ok_or(sqlx::Error::RowNotFound)?;
+
+        if let Some(name) = &req.name {
+            team.name = name.clone();
+        }
+        if let Some(parent_team_id) = req.parent_team_id {
+            team.parent_team_id = Some(parent_team_id);
+        }
+        if let Some(manager_id) = req.manager_id {
+            team.manager_id = Some(manager_id);
+        }
+        team.updated_at = Utc::now();
+
+        Ok(team.clone())
+    }
+
+    async fn soft_delete_team(
+        &self,
+        organization_id: Uuid,
+        id: Uuid,
+        actor_id: Option<Uuid>,
+    ) -> Result<bool, sqlx::Error> {
+        let mut store = self.store();
+        let now = Utc::now();
+
+        let Some(team) = store
+            .teams
+            .iter_mut()
+            .find(|t| t.value.id == id && t.owned_by(organization_id) && t.deleted_at.is_none())
+        else {
+            return Ok(false);
+        };
+        team.trash(now, actor_id);
+
+        let mut project_ids = Vec::new();
+        for project in store.projects.iter_mut() {
+            if project.value.team_id == Some(id) && project.deleted_at.is_none() {
+                project.trash(now, actor_id);
+                project_ids.push(project.value.id);
+            }
+        }
+        for task in store.tasks.iter_mut() {
+            if project_ids.contains(&task.value.project_id) && task.deleted_at.is_none() {
+                task.trash(now, actor_id);
+            }
+        }
+        Ok(true)
+    }
+
+    async fn restore_team(&self, organization_id: Uuid, id: Uuid) -> Result<Option<Team>, sqlx::Error> {
+        let mut store = self.store();
+        let now = Utc::now();
+
+        let Some(team) = store
+            .teams
+            .iter_mut()
+            .find(|t| t.value.id == id && t.owned_by(organization_id) && t.deleted_at.is_some())
+        else {
+            return Ok(None);
+        };
+        let deleted_at = team.deleted_at.take();
+        team.value.updated_at = now;
+        let team = team.value.clone();
+
+        let mut project_ids = Vec::new();
+        for project in store.projects.iter_mut() {
+            if project.value.team_id == Some(id) && project.deleted_at == deleted_at {
+                project.deleted_at = None;
+                project_ids.push(project.value.id);
+            }
+        }
+        for task in store.tasks.iter_mut() {
+            if project_ids.contains(&task.value.project_id) && task.deleted_at == deleted_at {
+                task.deleted_at = None;
+            }
+        }
+        Ok(Some(team))
+    }
+
+    async fn add_team_member(&self, team_id: Uuid, user_id: Uuid, role: &TeamRole) -> Result<TeamMember, sqlx::Error> {
+        let mut store = self.store();
+        if !store.teams.iter().any(|t| t.value.id == team_id) {
+            return Err(violation(FOREIGN_KEY_VIOLATION, "team does not exist"));
+        }
+        if !store.users.iter().any(|u| u.id == user_id) {
+            return Err(violation(FOREIGN_KEY_VIOLATION, "user does not exist"));
+        }
+        if store.team_members.iter().any(|m| m.team_id == team_id && m.user_id == user_id) {
+            return Err(violation(UNIQUE_VIOLATION, "user is already a member of the team"));
+        }
+
+        let member = TeamMember {
+            team_id,
+            user_id,
+            joined_at: Utc::now(),
+            role: role.to_string(),
+        };
+        store.team_members.push(member.clone());
+        Ok(member)
+    }
+
+    async fn find_team_member(&self, team_id: Uuid, user_id: Uuid) -> Result<Option<TeamMember>, sqlx::Error> {
+        let store = self.store();
+        Ok(store.team_members.iter().find(|m| m.team_id == team_id && m.user_id == user_id).cloned())
+    }
+
+    async fn update_team_member_role(
+        &self,
+        team_id: Uuid,
+        user_id: Uuid,
+        role: &TeamRole,
+    ) -> Result<Option<TeamMember>, sqlx::Error> {
+        let mut store = self.store();
+        let member = store.team_members.iter_mut().find(|m| m.team_id == team_id && m.user_id == user_id);
+        Ok(member.map(|m| {
+            m.role = role.to_string();
+            m.clone()
+        }))
+    }
+
+    async fn remove_team_member(&self, team_id: Uuid, user_id: Uuid) -> Result<(), sqlx::Error> {
+        self.store().team_members.retain(|m| !(m.team_id == team_id && m.user_id == user_id));
+        Ok(())
+    }
+
+    async fn list_team_members(&self, team_id: Uuid) -> Result<Vec<TeamMemberWithUser>, sqlx::Error> {
+        let store = self.store();
+        let members = store
+            .team_members
+            .iter()
+            .filter(|m| m.team_id == team_id)
+            .filter_map(|m| {
+                let user = store.users.iter().find(|u| u.id == m.user_id)?;
+                Some(TeamMemberWithUser {
+                    team_id: m.team_id,
+                    user_id: m.user_id,
+                    joined_at: m.joined_at,
+                    full_name: user.full_name.clone(),
+                    email: user.email.clone(),
+                    role: user.role.clone(),
+                    team_role: m.role.clone(),
+                })
+            })
+            .collect();
+        Ok(members)
+    }
+
+    async fn create_team_invitation(
+        &self,
+        organization_id: Uuid,
+        team_id: Uuid,
+        email: &str,
+        role: &TeamRole,
+        invited_by: Uuid,
+    ) -> Result<TeamInvitation, sqlx::Error> {
+        let mut store = self.store();
+        if !store.teams.iter().any(|t| t.value.id == team_id) {
+            return Err(violation(FOREIGN_KEY_VIOLATION, "team does not exist"));
+        }
+        let pending = |i: &&TeamInvitation| i.team_id == team_id && i.status == "pending";
+        if store.team_invitations.iter().filter(pending).any(|i| i.email.eq_ignore_ascii_case(email)) {
+            return Err(violation(UNIQUE_VIOLATION, "an invitation for this email is already pending"));
+        }
+
+        let invitation = TeamInvitation {
+            id: Uuid::new_v4(),
+            team_id,
+            organization_id,
+            email: email.to_string(),
+            role: role.to_string(),
+            status: "pending".to_string(),
+            invited_by: Some(invited_by),
+            invitee_id: None,
+            created_at: Utc::now(),
+            responded_at: None,
+        };
+        store.team_invitations.push(invitation.clone());
+        Ok(invitation)
+    }
+
+    async fn find_pending_team_invitation(
+        &self,
+        team_id: Uuid,
+        email: &str,
+    ) -> Result<Option<TeamInvitation>, sqlx::Error> {
+        let store = self.store();
+        let invitation = store
+            .team_invitations
+            .iter()
+            .find(|i| i.team_id == team_id && i.status == "pending" && i.email.eq_ignore_ascii_case(email));
+        Ok(invitation.cloned())
+    }
+
+    async fn list_team_invitations(&self, team_id: Uuid) -> Result<Vec<TeamInvitation>, sqlx::Error> {
+        let store = self.store();
+        let invitations = store.team_invitations.iter().rev().filter(|i| i.team_id == team_id && i.status == "pending");
+        Ok(invitations.cloned().collect())
+    }
+
+    async fn delete_team_invitation(&self, team_id: Uuid, id: Uuid) -> Result<(), sqlx::Error> {
+        self.store().team_invitations.retain(|i| !(i.id == id && i.team_id == team_id));
+        Ok(())
+    }
+
+    async fn list_team_trash(&self, organization_id: Uuid, team_id: Uuid) -> Result<Vec<TrashItem>, sqlx::Error> {
+        let store = self.store();
+        let Some(team) = store.teams.iter().find(|t| t.value.id == team_id && t.owned_by(organization_id)) else {
+            return Ok(Vec::new());
+        };
+        let item = |kind: TrashKind, id, name: &str, project_id, deleted_at, deleted_by: Option<Uuid>, parent_deleted| {
+            TrashItem {
+                kind: kind.to_string(),
+                id,
+                name: name.to_string(),
+                team_id: Some(team_id),
+                project_id,
+                deleted_at,
+                deleted_by,
+                deleted_by_name: store.users.iter().find(|u| Some(u.id) == deleted_by).map(|u| u.full_name.clone()),
+                parent_deleted,
+            }
+        };
+
+        let mut items = Vec::new();
+        if let Some(deleted_at) = team.deleted_at {
+            items.push(item(TrashKind::Team, team_id, &team.value.name, None, deleted_at, team.deleted_by, false));
+        }
+        let projects: Vec<_> = store
+            .projects
+            .iter()
+            .filter(|p| p.value.team_id == Some(team_id) && p.owned_by(organization_id))
+            .collect();
+        for project in &projects {
+            // Projects deleted along with the team are restored with it
+            let Some(deleted_at) = project.deleted_at.filter(|at| Some(*at) != team.deleted_at) else {
+                continue;
+            };
+            let (id, name) = (project.value.id, &project.value.name);
+            let parent_deleted = team.deleted_at.is_some();
+            items.push(item(TrashKind::Project, id, name, Some(id), deleted_at, project.deleted_by, parent_deleted));
+        }
+        for task in store.tasks.iter().filter(|t| t.owned_by(organization_id)) {
+            let Some(project) = projects.iter().find(|p| p.value.id == task.value.project_id) else {
+                continue;
+            };
+            let parent = task.value.parent_task_id.and_then(|id| store.tasks.iter().find(|t| t.value.id == id));
+            let parent_deleted_at = parent.and_then(|t| t.deleted_at);
+            // Likewise tasks deleted along with their project or parent task
+            let Some(deleted_at) = task
+                .deleted_at
+                .filter(|at| Some(*at) != project.deleted_at && Some(*at) != parent_deleted_at)
+            else {
+                continue;
+            };
+            let (id, title, project_id) = (task.value.id, &task.value.title, Some(task.value.project_id));
+            let parent_deleted = project.deleted_at.is_some() || parent_deleted_at.is_some();
+            items.push(item(TrashKind::Task, id, title, project_id, deleted_at, task.deleted_by, parent_deleted));
+        }
+
+        items.sort_by_key(|item| Reverse(item.deleted_at));
+        Ok(items)
+    }
+}
+
+
+#[async_trait]
+impl UserRepository for InMemoryRepository {
+    async fn create_user(
+        &self,
+        req: &CreateUserRequest,
+        password_hash: &str,
+        organization_id: Uuid,
+    ) -> Result<User, sqlx::Error> {
+        let mut store = self.store();
+        if store.users.iter().any(|u| u.email == req.email) {
+            return Err(violation(UNIQUE_VIOLATION, "email is already registered"));
+        }
+
+        let now = Utc::now();
+        let user = User {
+            id: Uuid::new_v4(),
+            email: req.email.clone(),
+            password_hash: Some(password_hash.to_string()),
+            full_name: req.full_name.clone(),
+            role: "member".to_string(),
+            is_service_account: false,
+            deactivated_at: None,
+            password_reset_required: false,
+            created_at: now,
+            updated_at: now,
+        };
+        store.users.push(user.clone());
+        store.organization_members.push(OrganizationMember {
+            organization_id,
+            user_id: user.id,
+            joined_at: now,
+        });
+        Ok(user)
+    }
+
+    async fn find_user_by_id(&self, id: Uuid) -> Result<Option<User>, sqlx::Error> {
+        Ok(self.store().users.iter().find(|u| u.id == id).cloned())
+    }
+
+    async fn find_user_by_email(&self, email: &str) -> Result<Option<User>, sqlx::Error> {
+        Ok(self.store().users.iter().find(|u| u.email == email).cloned())
+    }
+
+    async fn list_users(&self, organization_id: Uuid) -> Result<Vec<User>, sqlx::Error> {
+        let store = self.store();
+        let member_ids: Vec<Uuid> = store
+            .organization_members
+            .iter()
+            .filter(|m| m.organization_id == organization_id)
+            .map(|m| m.user_id)
+            .collect();
+        Ok(store.users.iter().rev().filter(|u| member_ids.contains(&u.id)).cloned().collect())
+    }
+
+    async fn update_user(
+        &self,
+        user_id: Uuid,
+        full_name: Option<String>,
+        password_hash: Option<String>,
+    ) -> Result<User, sqlx::Error> {
+        let mut store = self.store();
+        let user = store.users.iter_mut().find(|u| u.id == user_id).ok_or(sqlx::Error::RowNotFound)?;
+
+        if user.is_service_account && password_hash.is_some() {
+            return Err(violation(CHECK_VIOLATION, "service accounts cannot have a password"));
+        }
+        if let Some(full_name) = full_name {
+            user.full_name = full_name;
+        }
+        if let Some(password_hash) = password_hash {
+            user.password_hash = Some(password_hash);
+            user.password_reset_required = false;
+        }
+        user.updated_at = Utc::now();
+
+        Ok(user.clone())
+    }
+
+    async fn organization_ids(&self, user_id: Uuid) -> Result<Vec<Uuid>, sqlx::Error> {
+        let store = self.store();
+        let memberships = store.organization_members.iter().filter(|m| m.user_id == user_id);
+        Ok(memberships.map(|m| m.organization_id).collect())
+    }
+}
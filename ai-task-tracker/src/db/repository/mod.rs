@@ -0,0 +1,210 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::{
+    CreateProjectRequest, CreateSubtaskRequest, CreateTaskHistoryRequest, CreateTaskRequest, CreateTeamRequest,
+    CreateUserRequest, EmailLog, Project, Subtask, Task, TaskActivity, TaskFilter, TaskHistory, TaskHistoryWithUser,
+    Team, TeamInvitation, TeamMember, TeamMemberWithUser, TeamRole, TrashItem, UpdateProjectRequest,
+    UpdateSubtaskRequest, UpdateTaskRequest, UpdateTeamRequest, User,
+};
+
+pub mod memory;
+pub mod postgres;
+
+pub use memory::*;
+pub use postgres::*;
+
+/// Tasks and what hangs off them (subtasks, history, activity and emails), with
+/// the same semantics as the free functions in `task_repo` and its neighbours.
+/// Each task call only sees the tasks of the organization it is given; callers
+/// look up the task before working with what hangs off it.
+#[async_trait]
+pub trait TaskRepository: Send + Sync {
+    async fn create_task(
+        &self,
+        organization_id: Uuid,
+        req: &CreateTaskRequest,
+        actor_id: Option<Uuid>,
+    ) -> Result<Task, sqlx::Error>;
+    async fn find_task_by_id(&self, organization_id: Uuid, id: Uuid) -> Result<Option<Task>, sqlx::Error>;
+    async fn list_tasks(&self, organization_id: Uuid, filter: &TaskFilter) -> Result<Vec<Task>, sqlx::Error>;
+    /// Fails with `RowNotFound` if the task does not exist.
+    async fn update_task(
+        &self,
+        organization_id: Uuid,
+        id: Uuid,
+        req: &UpdateTaskRequest,
+        actor_id: Option<Uuid>,
+    ) -> Result<Task, sqlx::Error>;
+    async fn update_task_progress(&self, organization_id: Uuid, id: Uuid, progress: i32) -> Result<Task, sqlx::Error>;
+    async fn soft_delete_task(
+        &self,
+        organization_id: Uuid,
+        id: Uuid,
+        actor_id: Option<Uuid>,
+    ) -> Result<bool, sqlx::Error>;
+    async fn restore_task(
+        &self,
+        organization_id: Uuid,
+        id: Uuid,
+        actor_id: Option<Uuid>,
+    ) -> Result<Option<Task>, sqlx::Error>;
+    async fn create_subtask(&self, task_id: Uuid, req: &CreateSubtaskRequest) -> Result<Subtask, sqlx::Error>;
+    /// The subtask, unless its task is in another organization or the trash.
+    async fn find_subtask_by_id(&self, organization_id: Uuid, id: Uuid) -> Result<Option<Subtask>, sqlx::Error>;
+    /// Oldest first.
+    async fn list_subtasks(&self, task_id: Uuid) -> Result<Vec<Subtask>, sqlx::Error>;
+    /// Fails with `RowNotFound` if the subtask does not exist.
+    async fn update_subtask(&self, id: Uuid, req: &UpdateSubtaskRequest) -> Result<Subtask, sqlx::Error>;
+    async fn delete_subtask(&self, id: Uuid) -> Result<(), sqlx::Error>;
+    async fn create_task_history(
+        &self,
+        task_id: Uuid,
+        user_id: Uuid,
+        req: &CreateTaskHistoryRequest,
+    ) -> Result<TaskHistory, sqlx::Error>;
+    /// Newest first, with each author's name and email.
+    async fn list_task_history(&self, task_id: Uuid) -> Result<Vec<TaskHistoryWithUser>, sqlx::Error>;
+    /// Newest first.
+    async fn list_task_activity(&self, task_id: Uuid) -> Result<Vec<TaskActivity>, sqlx::Error>;
+    /// The email the task was created from and the replies threaded onto it, oldest first.
+    async fn list_task_emails(&self, task_id: Uuid) -> Result<Vec<EmailLog>, sqlx::Error>;
+}
+
+/// Projects, with the same semantics as the free functions in `project_repo`.
+/// Each call only sees the projects of the organization it is given.
+#[async_trait]
+pub trait ProjectRepository: Send + Sync {
+    async fn create_project(
+        &self,
+        organization_id: Uuid,
+        req: &CreateProjectRequest,
+        created_by: Uuid,
+    ) -> Result<Project, sqlx::Error>;
+    async fn find_project_by_id(&self, organization_id: Uuid, id: Uuid) -> Result<Option<Project>, sqlx::Error>;
+    async fn list_projects(&self, organization_id: Uuid, team_id: Option<Uuid>) -> Result<Vec<Project>, sqlx::Error>;
+    /// Fails with `RowNotFound` if the project does not exist.
+    async fn update_project(
+        &self,
+        organization_id: Uuid,
+        id: Uuid,
+        req: &UpdateProjectRequest,
+    ) -> Result<Project, sqlx::Error>;
+    async fn soft_delete_project(
+        &self,
+        organization_id: Uuid,
+        id: Uuid,
+        actor_id: Option<Uuid>,
+    ) -> Result<bool, sqlx::Error>;
+    async fn restore_project(&self, organization_id: Uuid, id: Uuid) -> Result<Option<Project>, sqlx::Error>;
+}
+
+/// Teams with their members, invitations and trash, with the same semantics as
+/// the free functions in `team_repo`, `team_invitation_repo` and `trash_repo`.
+/// Each call only sees the teams of the organization it is given; callers look
+/// up the team before working with its members and invitations.
+#[async_trait]
+pub trait TeamRepository: Send + Sync {
+    async fn create_team(&self, organization_id: Uuid, req: &CreateTeamRequest) -> Result<Team, sqlx::Error>;
+    async fn find_team_by_id(&self, organization_id: Uuid, id: Uuid) -> Result<Option<Team>, sqlx::Error>;
+    async fn list_teams(&self, organization_id: Uuid) -> Result<Vec<Team>, sqlx::Error>;
+    /// Fails with `RowNotFound` if the team does not exist.
+    async fn update_team(&self, organization_id: Uuid, id: Uuid, req: &UpdateTeamRequest) -> Result<Team, sqlx::Error>;
+    async fn soft_delete_team(
+        &self,
+        organization_id: Uuid,
+        id: Uuid,
+        actor_id: Option<Uuid>,
+    ) -> Result<bool, sqlx::Error>;
+    async fn restore_team(&self, organization_id: Uuid, id: Uuid) -> Result<Option<Team>, sqlx::Error>;
+    async fn add_team_member(&self, team_id: Uuid, user_id: Uuid, role: &TeamRole) -> Result<TeamMember, sqlx::Error>;
+    async fn find_team_member(&self, team_id: Uuid, user_id: Uuid) -> Result<Option<TeamMember>, sqlx::Error>;
+    async fn update_team_member_role(
+        &self,
+        team_id: Uuid,
+        user_id: Uuid,
+        role: &TeamRole,
+    ) -> Result<Option<TeamMember>, sqlx::Error>;
+    async fn remove_team_member(&self, team_id: Uuid, user_id: Uuid) -> Result<(), sqlx::Error>;
+    async fn list_team_members(&self, team_id: Uuid) -> Result<Vec<TeamMemberWithUser>, sqlx::Error>;
+    async fn create_team_invitation(
+        &self,
+        organization_id: Uuid,
+        team_id: Uuid,
+        email: &str,
+        role: &TeamRole,
+        invited_by: Uuid,
+    ) -> Result<TeamInvitation, sqlx::Error>;
+    /// Matches the email case-insensitively.
+    async fn find_pending_team_invitation(
+        &self,
+        team_id: Uuid,
+        email: &str,
+    ) -> Result<Option<TeamInvitation>, sqlx::Error>;
+    /// Pending invitations, newest first.
+    async fn list_team_invitations(&self, team_id: Uuid) -> Result<Vec<TeamInvitation>, sqlx::Error>;
+    async fn delete_team_invitation(&self, team_id: Uuid, id: Uuid) -> Result<(), sqlx::Error>;
+    /// Items deleted in the team, newest first, leaving out those that went to
+    /// the trash only because their team, project or parent task did.
+    async fn list_team_trash(&self, organization_id: Uuid, team_id: Uuid) -> Result<Vec<TrashItem>, sqlx::Error>;
+}
+
+/// Users, with the same semantics as the free functions in `user_repo`.
+#[async_trait]
+pub trait UserRepository: Send + Sync {
+    /// Creates the user as a member of `organization_id`.
+    async fn create_user(
+        &self,
+        req: &CreateUserRequest,
+        password_hash: &str,
+        organization_id: Uuid,
+    ) -> Result<User, sqlx::Error>;
+    async fn find_user_by_id(&self, id: Uuid) -> Result<Option<User>, sqlx::Error>;
+    async fn find_user_by_email(&self, email: &str) -> Result<Option<User>, sqlx::Error>;
+    /// The members of the organization, newest first.
+    async fn list_users(&self, organization_id: Uuid) -> Result<Vec<User>, sqlx::Error>;
+    /// Fails with `RowNotFound` if the user does not exist.
+    async fn update_user(
+        &self,
+        user_id: Uuid,
+        full_name: Option<String>,
+        password_hash: Option<String>,
+    ) -> Result<User, sqlx::Error>;
+    /// The organizations the user belongs to, in the order they joined them.
+    async fn organization_ids(&self, user_id: Uuid) -> Result<Vec<Uuid>, sqlx::Error>;
+}
+
+/// The repositories handlers receive as `web::Data<dyn ...>`.
+#[derive(Clone)]
+pub struct Repositories {
+    pub tasks: Arc<dyn TaskRepository>,
+    pub projects: Arc<dyn ProjectRepository>,
+    pub teams: Arc<dyn TeamRepository>,
+    pub users: Arc<dyn UserRepository>,
+}
+
+impl Repositories {
+    pub fn postgres(pool: PgPool) -> Self {
+        Self::backed_by(Arc::new(PgRepository::new(pool)))
+    }
+
+    /// Fresh, empty in-memory repositories that share one store.
+    pub fn in_memory() -> Self {
+        Self::backed_by(Arc::new(InMemoryRepository::default()))
+    }
+
+    fn backed_by<R>(repo: Arc<R>) -> Self
+    where
+        R: TaskRepository + ProjectRepository + TeamRepository + UserRepository + 'static,
+    {
+        Repositories {
+            tasks: repo.clone(),
+            projects: repo.clone(),
+            teams: repo.clone(),
+            users: repo,
+        }
+    }
+}
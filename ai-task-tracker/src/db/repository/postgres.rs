@@ -0,0 +1,283 @@
+use async_trait::async_trait;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::db::{self, ProjectRepository, TaskRepository, TeamRepository, UserRepository};
+use crate::models::{
+    CreateProjectRequest, CreateSubtaskRequest, CreateTaskHistoryRequest, CreateTaskRequest, CreateTeamRequest,
+    CreateUserRequest, EmailLog, Project, Subtask, Task, TaskActivity, TaskFilter, TaskHistory, TaskHistoryWithUser,
+    Team, TeamInvitation, TeamMember, TeamMemberWithUser, TeamRole, TrashItem, UpdateProjectRequest,
+    UpdateSubtaskRequest, UpdateTaskRequest, UpdateTeamRequest, User,
+};
+
+/// The production repositories, backed by the connection pool. Each method delegates
+/// to the `db` function of the same name, whose `debug` span times the query.
+pub struct PgRepository {
+    pool: PgPool,
+}
+
+impl PgRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl TaskRepository for PgRepository {
+    async fn create_task(
+        &self,
+        organization_id: Uuid,
+        req: &CreateTaskRequest,
+        actor_id: Option<Uuid>,
+    ) -> Result<Task, sqlx::Error> {
+        db::create_task(&self.pool, organization_id, req, actor_id).await
+    }
+
+    async fn find_task_by_id(&self, organization_id: Uuid, id: Uuid) -> Result<Option<Task>, sqlx::Error> {
+        db::find_task_by_id(&self.pool, organization_id, id).await
+    }
+
+    async fn list_tasks(&self, organization_id: Uuid, filter: &TaskFilter) -> Result<Vec<Task>, sqlx::Error> {
+        db::list_tasks(&self.pool, organization_id, filter).await
+    }
+
+    async fn update_task(
+        &self,
+        organization_id: Uuid,
+        id: Uuid,
+        req: &UpdateTaskRequest,
+        actor_id: Option<Uuid>,
+    ) -> Result<Task, sqlx::Error> {
+        db::update_task(&self.pool, organization_id, id, req, actor_id).await
+    }
+
+    async fn update_task_progress(&self, organization_id: Uuid, id: Uuid, progress: i32) -> Result<Task, sqlx::Error> {
+        db::update_task_progress(&self.pool, organization_id, id, progress).await
+    }
+
+    async fn soft_delete_task(
+        &self,
+        organization_id: Uuid,
+        id: Uuid,
+        actor_id: Option<Uuid>,
+    ) -> Result<bool, sqlx::Error> {
+        db::soft_delete_task(&self.pool, organization_id, id, actor_id).await
+    }
+
+    async fn restore_task(
+        &self,
+        organization_id: Uuid,
+        id: Uuid,
+        actor_id: Option<Uuid>,
+    ) -> Result<Option<Task>, sqlx::Error> {
+        db::restore_task(&self.pool, organization_id, id, actor_id).await
+    }
+
+    async fn create_subtask(&self, task_id: Uuid, req: &CreateSubtaskRequest) -> Result<Subtask, sqlx::Error> {
+        db::create_subtask(&self.pool, task_id, req).await
+    }
+
+    async fn find_subtask_by_id(&self, organization_id: Uuid, id: Uuid) -> Result<Option<Subtask>, sqlx::Error> {
+        db::find_subtask_by_id(&self.pool, organization_id, id).await
+    }
+
+    async fn list_subtasks(&self, task_id: Uuid) -> Result<Vec<Subtask>, sqlx::Error> {
+        db::list_subtasks(&self.pool, task_id).await
+    }
+
+    async fn update_subtask(&self, id: Uuid, req: &UpdateSubtaskRequest) -> Result<Subtask, sqlx::Error> {
+        db::update_subtask(&self.pool, id, req).await
+    }
+
+    async fn delete_subtask(&self, id: Uuid) -> Result<(), sqlx::Error> {
+        db::delete_subtask(&self.pool, id).await
+    }
+
+    async fn create_task_history(
+        &self,
+        task_id: Uuid,
+        user_id: Uuid,
+        req: &CreateTaskHistoryRequest,
+    ) -> Result<TaskHistory, sqlx::Error> {
+        db::create_task_history(&self.pool, task_id, user_id, req).await
+    }
+
+    async fn list_task_history(&self, task_id: Uuid) -> Result<Vec<TaskHistoryWithUser>, sqlx::Error> {
+        db::list_task_history(&self.pool, task_id).await
+    }
+
+    async fn list_task_activity(&self, task_id: Uuid) -> Result<Vec<TaskActivity>, sqlx::Error> {
+        db::list_task_activity(&self.pool, task_id).await
+    }
+
+    async fn list_task_emails(&self, task_id: Uuid) -> Result<Vec<EmailLog>, sqlx::Error> {
+        db::list_task_emails(&self.pool, task_id).await
+    }
+}
+
+#[async_trait]
+impl ProjectRepository for PgRepository {
+    async fn create_project(
+        &self,
+        organization_id: Uuid,
+        req: &CreateProjectRequest,
+        created_by: Uuid,
+    ) -> Result<Project, sqlx::Error> {
+        db::create_project(&self.pool, organization_id, req, created_by).await
+    }
+
+    async fn find_project_by_id(&self, organization_id: Uuid, id: Uuid) -> Result<Option<Project>, sqlx::Error> {
+        db::find_project_by_id(&self.pool, organization_id, id).await
+    }
+
+    async fn list_projects(&self, organization_id: Uuid, team_id: Option<Uuid>) -> Result<Vec<Project>, sqlx::Error> {
+        db::list_projects(&self.pool, organization_id, team_id).await
+    }
+
+    async fn update_project(
+        &self,
+        organization_id: Uuid,
+        id: Uuid,
+        req: &UpdateProjectRequest,
+    ) -> Result<Project, sqlx::Error> {
+        db::update_project(&self.pool, organization_id, id, req).await
+    }
+
+    async fn soft_delete_project(
+        &self,
+        organization_id: Uuid,
+        id: Uuid,
+        actor_id: Option<Uuid>,
+    ) -> Result<bool, sqlx::Error> {
+        db::soft_delete_project(&self.pool, organization_id, id, actor_id).await
+    }
+
+    async fn restore_project(&self, organization_id: Uuid, id: Uuid) -> Result<Option<Project>, sqlx::Error> {
+        db::restore_project(&self.pool, organization_id, id).await
+    }
+}
+
+#[async_trait]
+impl TeamRepository for PgRepository {
+    async fn create_team(&self, organization_id: Uuid, req: &CreateTeamRequest) -> Result<Team, sqlx::Error> {
+        db::create_team(&self.pool, organization_id, req).await
+    }
+
+    async fn find_team_by_id(&self, organization_id: Uuid, id: Uuid) -> Result<Option<Team>, sqlx::Error> {
+        db::find_team_by_id(&self.pool, organization_id, id).await
+    }
+
+    async fn list_teams(&self, organization_id: Uuid) -> Result<Vec<Team>, sqlx::Error> {
+        db::list_teams(&self.pool, organization_id).await
+    }
+
+    async fn update_team(&self, organization_id: Uuid, id: Uuid, req: &UpdateTeamRequest) -> Result<Team, sqlx::Error> {
+        db::update_team(&self.pool, organization_id, id, req).await
+    }
+
+    async fn soft_delete_team(
+        &self,
+        organization_id: Uuid,
+        id: Uuid,
+        actor_id: Option<Uuid>,
+    ) -> Result<bool, sqlx::Error> {
+        db::soft_delete_team(&self.pool, organization_id, id, actor_id).await
+    }
+
+    async fn restore_team(&self, organization_id: Uuid, id: Uuid) -> Result<Option<Team>, sqlx::Error> {
+        db::restore_team(&self.pool, organization_id, id).await
+    }
+
+    async fn add_team_member(&self, team_id: Uuid, user_id: Uuid, role: &TeamRole) -> Result<TeamMember, sqlx::Error> {
+        db::add_team_member(&self.pool, team_id, user_id, role).await
+    }
+
+    async fn find_team_member(&self, team_id: Uuid, user_id: Uuid) -> Result<Option<TeamMember>, sqlx::Error> {
+        db::find_team_member(&self.pool, team_id, user_id).await
+    }
+
+    async fn update_team_member_role(
+        &self,
+        team_id: Uuid,
+        user_id: Uuid,
+        role: &TeamRole,
+    ) -> Result<Option<TeamMember>, sqlx::Error> {
+        db::update_team_member_role(&self.pool, team_id, user_id, role).await
+    }
+
+    async fn remove_team_member(&self, team_id: Uuid, user_id: Uuid) -> Result<(), sqlx::Error> {
+        db::remove_team_member(&self.pool, team_id, user_id).await
+    }
+
+    async fn list_team_members(&self, team_id: Uuid) -> Result<Vec<TeamMemberWithUser>, sqlx::Error> {
+        db::list_team_members(&self.pool, team_id).await
+    }
+
+    async fn create_team_invitation(
+        &self,
+        organization_id: Uuid,
+        team_id: Uuid,
+        email: &str,
+        role: &TeamRole,
+        invited_by: Uuid,
+    ) -> Result<TeamInvitation, sqlx::Error> {
+        db::create_team_invitation(&self.pool, organization_id, team_id, email, role, invited_by).await
+    }
+
+    async fn find_pending_team_invitation(
+        &self,
+        team_id: Uuid,
+        email: &str,
+    ) -> Result<Option<TeamInvitation>, sqlx::Error> {
+        db::find_pending_team_invitation(&self.pool, team_id, email).await
+    }
+
+    async fn list_team_invitations(&self, team_id: Uuid) -> Result<Vec<TeamInvitation>, sqlx::Error> {
+        db::list_team_invitations(&self.pool, team_id).await
+    }
+
+    async fn delete_team_invitation(&self, team_id: Uuid, id: Uuid) -> Result<(), sqlx::Error> {
+        db::delete_team_invitation(&self.pool, team_id, id).await
+    }
+
+    async fn list_team_trash(&self, organization_id: Uuid, team_id: Uuid) -> Result<Vec<TrashItem>, sqlx::Error> {
+        db::list_team_trash(&self.pool, organization_id, team_id).await
+    }
+}
+
+#[async_trait]
+impl UserRepository for PgRepository {
+    async fn create_user(
+        &self,
+        req: &CreateUserRequest,
+        password_hash: &str,
+        organization_id: Uuid,
+    ) -> Result<User, sqlx::Error> {
+        db::create_user(&self.pool, req, password_hash, organization_id).await
+    }
+
+    async fn find_user_by_id(&self, id: Uuid) -> Result<Option<User>, sqlx::Error> {
+        db::find_user_by_id(&self.pool, id).await
+    }
+
+    async fn find_user_by_email(&self, email: &str) -> Result<Option<User>, sqlx::Error> {
+        db::find_user_by_email(&self.pool, email).await
+    }
+
+    async fn list_users(&self, organization_id: Uuid) -> Result<Vec<User>, sqlx::Error> {
+        db::list_users(&self.pool, organization_id).await
+    }
+
+    async fn update_user(
+        &self,
+        user_id: Uuid,
+        full_name: Option<String>,
+        password_hash: Option<String>,
+    ) -> Result<User, sqlx::Error> {
+        db::update_user(&self.pool, user_id, full_name, password_hash).await
+    }
+
+    async fn organization_ids(&self, user_id: Uuid) -> Result<Vec<Uuid>, sqlx::Error> {
+        db::list_user_organization_ids(&self.pool, user_id).await
+    }
+}
@@ -0,0 +1,123 @@
+use sqlx::{types::Json, PgPool};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::models::{CreateSavedViewRequest, SavedView, UpdateSavedViewRequest};
+
+/// Views `$2` may run in organization `$1`: the built-in ones, their own, and
+/// those of the teams they belong to.
+const VISIBLE_TO_USER: &str = r#"
+    (organization_id IS NULL
+     OR (organization_id = $1
+         AND (user_id = $2 OR team_id IN (SELECT team_id FROM team_members WHERE user_id = $2))))
+"#;
+
+/// Creates a view for `user_id`, or for `req.team_id` if set.
+#[instrument(level = "debug", skip_all)]
+pub async fn create_saved_view(
+    pool: &PgPool,
+    organization_id: Uuid,
+    req: &CreateSavedViewRequest,
+    user_id: Uuid,
+) -> Result<SavedView, sqlx::Error> {
+    let view = sqlx::query_as::<_, SavedView>(
+        r#"
+        INSERT INTO saved_views (organization_id, name, user_id, team_id, filter, sort_by, sort_order,
+                                 group_by, created_by)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        RETURNING *
+        "#,
+    )
+    .bind(organization_id)
+    .bind(&req.name)
+    .bind(if req.team_id.is_none() { Some(user_id) } else { None })
+    .bind(req.team_id)
+    .bind(Json(&req.filter))
+    .bind(req.sort_by.unwrap_or_default().to_string())
+    .bind(req.sort_order.unwrap_or_default().to_string())
+    .bind(req.group_by.map(|g| g.to_string()))
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(view)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn find_saved_view(
+    pool: &PgPool,
+    organization_id: Uuid,
+    user_id: Uuid,
+    id: Uuid,
+) -> Result<Option<SavedView>, sqlx::Error> {
+    let query = format!("SELECT * FROM saved_views WHERE id = $3 AND {}", VISIBLE_TO_USER);
+    let view = sqlx::query_as::<_, SavedView>(&query)
+        .bind(organization_id)
+        .bind(user_id)
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(view)
+}
+
+/// The views the user may run: built-in ones first, then by name.
+#[instrument(level = "debug", skip_all)]
+pub async fn list_saved_views(
+    pool: &PgPool,
+    organization_id: Uuid,
+    user_id: Uuid,
+) -> Result<Vec<SavedView>, sqlx::Error> {
+    let query = format!(
+        "SELECT * FROM saved_views WHERE {} ORDER BY organization_id IS NOT NULL, LOWER(name), created_at",
+        VISIBLE_TO_USER
+    );
+    let views = sqlx::query_as::<_, SavedView>(&query)
+        .bind(organization_id)
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?;
+
+    Ok(views)
+}
+
+/// Replaces the definition of a view in the organization; built-in views are
+/// never matched.
+#[instrument(level = "debug", skip_all)]
+pub async fn update_saved_view(
+    pool: &PgPool,
+    organization_id: Uuid,
+    id: Uuid,
+    req: &UpdateSavedViewRequest,
+) -> Result<Option<SavedView>, sqlx::Error> {
+    let view = sqlx::query_as::<_, SavedView>(
+        r#"
+        UPDATE saved_views
+        SET name = $3, filter = $4, sort_by = $5, sort_order = $6, group_by = $7, updated_at = NOW()
+        WHERE id = $1 AND organization_id = $2
+        RETURNING *
+        "#,
+    )
+    .bind(id)
+    .bind(organization_id)
+    .bind(&req.name)
+    .bind(Json(&req.filter))
+    .bind(req.sort_by.unwrap_or_default().to_string())
+    .bind(req.sort_order.unwrap_or_default().to_string())
+    .bind(req.group_by.map(|g| g.to_string()))
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(view)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn delete_saved_view(pool: &PgPool, organization_id: Uuid, id: Uuid) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM saved_views WHERE id = $1 AND organization_id = $2")
+        .bind(id)
+        .bind(organization_id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
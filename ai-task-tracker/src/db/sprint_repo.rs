@@ -0,0 +1,513 @@
+use sqlx::{PgPool, Postgres, Transaction};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    db::record_task_activity,
+    models::{
+        CreateSprintRequest, Sprint, SprintCapacity, SprintMemberLoad, SprintStatus,
+        SprintTaskOutcome, UpdateSprintRequest,
+    },
+};
+
+#[instrument(level = "debug", skip_all)]
+pub async fn create_sprint(pool: &PgPool, req: &CreateSprintRequest) -> Result<Sprint, sqlx::Error> {
+    let kind = req.kind.as_ref().map(|k| k.to_string()).unwrap_or_else(|| "sprint".to_string());
+
+    let sprint = sqlx::query_as::<_, Sprint>(
+        r#"
+        INSERT INTO sprints (project_id, name, goal, kind, start_date, end_date)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING *
+        "#,
+    )
+    .bind(req.project_id)
+    .bind(&req.name)
+    .bind(&req.goal)
+    .bind(kind)
+    .bind(req.start_date)
+    .bind(req.end_date)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(sprint)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn find_sprint_by_id(pool: &PgPool, organization_id: Uuid, id: Uuid) -> Result<Option<Sprint>, sqlx::Error> {
+    let sprint = sqlx::query_as::<_, Sprint>(
+        r#"
+        SELECT s.* FROM sprints s
+        JOIN projects p ON p.id = s.project_id
+        WHERE s.id = $1 AND p.organization_id = $2
+        "#,
+    )
+    .bind(id)
+    .bind(organization_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(sprint)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn list_sprints(
+    pool: &PgPool,
+    organization_id: Uuid,
+    project_id: Option<Uuid>,
+    status: Option<&SprintStatus>,
+) -> Result<Vec<Sprint>, sqlx::Error> {
+    let sprints = sqlx::query_as::<_, Sprint>(
+        r#"
+        SELECT s.* FROM sprints s
+        JOIN projects p ON p.id = s.project_id
+        WHERE p.organization_id = $3
+          AND ($1::uuid IS NULL OR s.project_id = $1)
+          AND ($2::text IS NULL OR s.status = $2)
+        ORDER BY s.start_date, s.created_at
+        "#,
+    )
+    .bind(project_id)
+    .bind(status.map(|s| s.to_string()))
+    .bind(organization_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(sprints)
+}
+
+/// The earliest planned sprint of the same kind in the project, other than `sprint`.
+#[instrument(level = "debug", skip_all)]
+pub async fn find_next_sprint(pool: &PgPool, sprint: &Sprint) -> Result<Option<Sprint>, sqlx::Error> {
+    let next = sqlx::query_as::<_, Sprint>(
+        r#"
+        SELECT * FROM sprints
+        WHERE project_id = $1 AND kind = $2 AND status = 'planned' AND id <> $3
+        ORDER BY start_date, created_at
+        LIMIT 1
+        "#,
+    )
+    .bind(sprint.project_id)
+    .bind(&sprint.kind)
+    .bind(sprint.id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(next)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn update_sprint(pool: &PgPool, id: Uuid, req: &UpdateSprintRequest) -> Result<Sprint, sqlx::Error> {
+    let sprint = sqlx::query_as::<_, Sprint>(
+        r#"
+        UPDATE sprints
+        SET name = COALESCE($2, name),
+            goal = COALESCE($3, goal),
+            start_date = COALESCE($4, start_date),
+            end_date = COALESCE($5, end_date),
+            updated_at = NOW()
+        WHERE id = $1
+        RETURNING *
+        "#,
+    )
+    .bind(id)
+    .bind(&req.name)
+    .bind(&req.goal)
+    .bind(req.start_date)
+    .bind(req.end_date)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(sprint)
+}
+
+/// Tasks in the sprint fall back to the backlog.
+#[instrument(level = "debug", skip_all)]
+pub async fn delete_sprint(pool: &PgPool, id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        DELETE FROM sprints WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Fails with a unique violation if the project already has an active sprint.
+#[instrument(level = "debug", skip_all)]
+pub async fn start_sprint(pool: &PgPool, id: Uuid) -> Result<Sprint, sqlx::Error> {
+    let sprint = sqlx::query_as::<_, Sprint>(
+        r#"
+        UPDATE sprints
+        SET status = 'active', started_at = NOW(), updated_at = NOW()
+        WHERE id = $1
+        RETURNING *
+        "#,
+    )
+    .bind(id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(sprint)
+}
+
+/// Takes `task_id` out of whatever sprint it is in. Before a sprint starts the
+/// task was never committed, so its membership is forgotten; afterwards it is
+/// kept as `removed` for the report.
+async fn leave_current_sprint(
+    tx: &mut Transaction<'_, Postgres>,
+    task_id: Uuid,
+) -> Result<Option<Uuid>, sqlx::Error> {
+    let current: Option<Uuid> =
+        sqlx::query_scalar("SELECT sprint_id FROM tasks WHERE id = $1 AND deleted_at IS NULL FOR UPDATE")
+            .bind(task_id)
+            .fetch_optional(&mut **tx)
+            .await?
+            .flatten();
+
+    if let Some(sprint_id) = current {
+        sqlx::query(
+            r#"
+            DELETE FROM sprint_tasks st
+            USING sprints s
+            WHERE st.sprint_id = s.id AND st.sprint_id = $1 AND st.task_id = $2 AND s.status = 'planned'
+            "#,
+        )
+        .bind(sprint_id)
+        .bind(task_id)
+        .execute(&mut **tx)
+        .await?;
+
+        sqlx::query(
+            r#"
+            UPDATE sprint_tasks
+            SET outcome = 'removed', resolved_at = NOW()
+            WHERE sprint_id = $1 AND task_id = $2 AND outcome IS NULL
+            "#,
+        )
+        .bind(sprint_id)
+        .bind(task_id)
+        .execute(&mut **tx)
+        .await?;
+    }
+
+    Ok(current)
+}
+
+async fn join_sprint(
+    tx: &mut Transaction<'_, Postgres>,
+    sprint: &Sprint,
+    task_id: Uuid,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE tasks SET sprint_id = $2, updated_at = NOW() WHERE id = $1")
+        .bind(task_id)
+        .bind(sprint.id)
+        .execute(&mut **tx)
+        .await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO sprint_tasks (sprint_id, task_id, added_after_start)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (sprint_id, task_id)
+        DO UPDATE SET added_at = NOW(), added_after_start = EXCLUDED.added_after_start,
+                      outcome = NULL, resolved_at = NULL
+        "#,
+    )
+    .bind(sprint.id)
+    .bind(task_id)
+    .bind(sprint.status() == SprintStatus::Active)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+/// Moves tasks of the sprint's project into the sprint and returns the ids that
+/// were moved; tasks from other projects or already in the sprint are skipped.
+#[instrument(level = "debug", skip_all)]
+pub async fn add_tasks_to_sprint(
+    pool: &PgPool,
+    sprint: &Sprint,
+    task_ids: &[Uuid],
+    actor_id: Option<Uuid>,
+) -> Result<Vec<Uuid>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let eligible: Vec<Uuid> = sqlx::query_scalar(
+        r#"
+        SELECT id FROM tasks
+        WHERE id = ANY($1) AND project_id = $2 AND sprint_id IS DISTINCT FROM $3 AND deleted_at IS NULL
+        ORDER BY id
+        "#,
+    )
+    .bind(task_ids)
+    .bind(sprint.project_id)
+    .bind(sprint.id)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    for task_id in &eligible {
+        let previous = leave_current_sprint(&mut tx, *task_id).await?;
+        join_sprint(&mut tx, sprint, *task_id).await?;
+        record_task_activity(
+            &mut *tx,
+            *task_id,
+            actor_id,
+            "sprint_changed",
+            previous.map(|id| id.to_string()).as_deref(),
+            Some(&sprint.id.to_string()),
+        )
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(eligible)
+}
+
+/// Returns false if the task wasn't in the sprint.
+#[instrument(level = "debug", skip_all)]
+pub async fn remove_task_from_sprint(
+    pool: &PgPool,
+    sprint: &Sprint,
+    task_id: Uuid,
+    actor_id: Option<Uuid>,
+) -> Result<bool, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let in_sprint: Option<bool> =
+        sqlx::query_scalar("SELECT sprint_id = $2 FROM tasks WHERE id = $1 AND deleted_at IS NULL")
+            .bind(task_id)
+            .bind(sprint.id)
+            .fetch_optional(&mut *tx)
+            .await?;
+    if in_sprint != Some(true) {
+        return Ok(false);
+    }
+
+    leave_current_sprint(&mut tx, task_id).await?;
+    sqlx::query("UPDATE tasks SET sprint_id = NULL, updated_at = NOW() WHERE id = $1")
+        .bind(task_id)
+        .execute(&mut *tx)
+        .await?;
+    record_task_activity(
+        &mut *tx,
+        task_id,
+        actor_id,
+        "sprint_changed",
+        Some(&sprint.id.to_string()),
+        None,
+    )
+    .await?;
+
+    tx.commit().await?;
+    Ok(true)
+}
+
+/// Closes the sprint: finished tasks are marked completed, the rest spill over
+/// into `carry_over_to` (or the backlog). Returns the ids of the moved tasks.
+#[instrument(level = "debug", skip_all)]
+pub async fn close_sprint(
+    pool: &PgPool,
+    sprint: &Sprint,
+    carry_over_to: Option<&Sprint>,
+    actor_id: Option<Uuid>,
+) -> Result<Vec<Uuid>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        r#"
+        UPDATE sprint_tasks st
+        SET outcome = CASE WHEN t.status = 'done' THEN 'completed' ELSE 'spilled' END,
+            resolved_at = NOW()
+        FROM tasks t
+        WHERE st.task_id = t.id AND st.sprint_id = $1 AND st.outcome IS NULL
+        "#,
+    )
+    .bind(sprint.id)
+    .execute(&mut *tx)
+    .await?;
+
+    let unfinished: Vec<Uuid> = sqlx::query_scalar(
+        r#"
+        SELECT id FROM tasks
+        WHERE sprint_id = $1 AND status <> 'done' AND deleted_at IS NULL
+        ORDER BY id
+        FOR UPDATE
+        "#,
+    )
+    .bind(sprint.id)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    for task_id in &unfinished {
+        match carry_over_to {
+            Some(target) => join_sprint(&mut tx, target, *task_id).await?,
+            None => {
+                sqlx::query("UPDATE tasks SET sprint_id = NULL, updated_at = NOW() WHERE id = $1")
+                    .bind(task_id)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+        }
+        record_task_activity(
+            &mut *tx,
+            *task_id,
+            actor_id,
+            "sprint_changed",
+            Some(&sprint.id.to_string()),
+            carry_over_to.map(|s| s.id.to_string()).as_deref(),
+        )
+        .await?;
+    }
+
+    sqlx::query(
+        r#"
+        UPDATE sprints SET status = 'closed', closed_at = NOW(), updated_at = NOW() WHERE id = $1
+        "#,
+    )
+    .bind(sprint.id)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(unfinished)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn set_sprint_capacity(
+    pool: &PgPool,
+    sprint_id: Uuid,
+    user_id: Uuid,
+    capacity_minutes: i32,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO sprint_capacities (sprint_id, user_id, capacity_minutes)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (sprint_id, user_id) DO UPDATE SET capacity_minutes = EXCLUDED.capacity_minutes
+        "#,
+    )
+    .bind(sprint_id)
+    .bind(user_id)
+    .bind(capacity_minutes)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn delete_sprint_capacity(pool: &PgPool, sprint_id: Uuid, user_id: Uuid) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        DELETE FROM sprint_capacities WHERE sprint_id = $1 AND user_id = $2
+        "#,
+    )
+    .bind(sprint_id)
+    .bind(user_id)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn list_sprint_capacities(pool: &PgPool, sprint_id: Uuid) -> Result<Vec<SprintCapacity>, sqlx::Error> {
+    let capacities = sqlx::query_as::<_, SprintCapacity>(
+        r#"
+        SELECT sc.user_id, u.full_name AS user_name, u.email AS user_email, sc.capacity_minutes
+        FROM sprint_capacities sc
+        JOIN users u ON u.id = sc.user_id
+        WHERE sc.sprint_id = $1
+        ORDER BY u.full_name
+        "#,
+    )
+    .bind(sprint_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(capacities)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn list_sprint_task_outcomes(
+    pool: &PgPool,
+    sprint_id: Uuid,
+) -> Result<Vec<SprintTaskOutcome>, sqlx::Error> {
+    let tasks = sqlx::query_as::<_, SprintTaskOutcome>(
+        r#"
+        SELECT
+            t.id AS task_id,
+            t.title,
+            t.status,
+            t.assignee_id,
+            t.estimate_minutes,
+            st.added_after_start,
+            COALESCE(st.outcome, 'open') AS outcome
+        FROM sprint_tasks st
+        JOIN tasks t ON t.id = st.task_id
+        WHERE st.sprint_id = $1 AND t.deleted_at IS NULL
+        ORDER BY st.added_at, t.created_at
+        "#,
+    )
+    .bind(sprint_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(tasks)
+}
+
+/// Capacity against assigned estimates and time logged during the sprint, for
+/// everyone with a capacity or an assigned task.
+#[instrument(level = "debug", skip_all)]
+pub async fn sprint_member_loads(pool: &PgPool, sprint: &Sprint) -> Result<Vec<SprintMemberLoad>, sqlx::Error> {
+    let loads = sqlx::query_as::<_, SprintMemberLoad>(
+        r#"
+        WITH members AS (
+            SELECT user_id FROM sprint_capacities WHERE sprint_id = $1
+            UNION
+            SELECT t.assignee_id FROM sprint_tasks st
+            JOIN tasks t ON t.id = st.task_id
+            WHERE st.sprint_id = $1 AND t.assignee_id IS NOT NULL AND t.deleted_at IS NULL
+              AND (st.outcome IS NULL OR st.outcome <> 'removed')
+        )
+        SELECT
+            u.id AS user_id,
+            u.full_name AS user_name,
+            u.email AS user_email,
+            sc.capacity_minutes,
+            COALESCE(SUM(t.estimate_minutes), 0)::BIGINT AS assigned_minutes,
+            COALESCE(SUM(t.estimate_minutes) FILTER (
+                WHERE st.outcome = 'completed' OR (st.outcome IS NULL AND t.status = 'done')
+            ), 0)::BIGINT AS completed_minutes,
+            COALESCE((
+                SELECT ROUND(SUM(EXTRACT(EPOCH FROM (te.ended_at - te.started_at))) / 60)
+                FROM time_entries te
+                JOIN sprint_tasks ste ON ste.task_id = te.task_id AND ste.sprint_id = $1
+                WHERE te.user_id = u.id AND te.ended_at IS NOT NULL
+                  AND te.started_at >= $2::date::timestamp AT TIME ZONE 'UTC'
+                  AND te.started_at < ($3::date + 1)::timestamp AT TIME ZONE 'UTC'
+            ), 0)::BIGINT AS logged_minutes
+        FROM members m
+        JOIN users u ON u.id = m.user_id
+        LEFT JOIN sprint_capacities sc ON sc.sprint_id = $1 AND sc.user_id = u.id
+        LEFT JOIN sprint_tasks st ON st.sprint_id = $1
+            AND (st.outcome IS NULL OR st.outcome <> 'removed')
+            AND st.task_id IN (SELECT id FROM tasks WHERE assignee_id = u.id AND deleted_at IS NULL)
+        LEFT JOIN tasks t ON t.id = st.task_id
+        GROUP BY u.id, sc.capacity_minutes
+        ORDER BY u.full_name
+        "#,
+    )
+    .bind(sprint.id)
+    .bind(sprint.start_date)
+    .bind(sprint.end_date)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(loads)
+}
@@ -1,7 +1,9 @@
 use crate::models::{CreateSubtaskRequest, Subtask, UpdateSubtaskRequest};
 use sqlx::PgPool;
+use tracing::instrument;
 use uuid::Uuid;
 
+#[instrument(level = "debug", skip_all)]
 pub async fn create_subtask(
     pool: &PgPool,
     parent_task_id: Uuid,
@@ -22,23 +24,35 @@ pub async fn create_subtask(
     Ok(subtask)
 }
 
-pub async fn find_subtask_by_id(pool: &PgPool, id: Uuid) -> Result<Option<Subtask>, sqlx::Error> {
+#[instrument(level = "debug", skip_all)]
+pub async fn find_subtask_by_id(
+    pool: &PgPool,
+    organization_id: Uuid,
+    id: Uuid,
+) -> Result<Option<Subtask>, sqlx::Error> {
     let subtask = sqlx::query_as::<_, Subtask>(
         r#"
-        SELECT * FROM subtasks WHERE id = $1
+        SELECT s.* FROM subtasks s
+        JOIN tasks t ON t.id = s.parent_task_id
+        WHERE s.id = $1 AND t.organization_id = $2 AND t.deleted_at IS NULL
         "#,
     )
     .bind(id)
+    .bind(organization_id)
     .fetch_optional(pool)
     .await?;
 
     Ok(subtask)
 }
 
+#[instrument(level = "debug", skip_all)]
 pub async fn list_subtasks(pool: &PgPool, parent_task_id: Uuid) -> Result<Vec<Subtask>, sqlx::Error> {
     let subtasks = sqlx::query_as::<_, Subtask>(
         r#"
-        SELECT * FROM subtasks WHERE parent_task_id = $1 ORDER BY created_at ASC
+        SELECT s.* FROM subtasks s
+        JOIN tasks t ON t.id = s.parent_task_id
+        WHERE s.parent_task_id = $1 AND t.deleted_at IS NULL
+        ORDER BY s.created_at ASC
         "#,
     )
     .bind(parent_task_id)
@@ -48,6 +62,27 @@ pub async fn list_subtasks(pool: &PgPool, parent_task_id: Uuid) -> Result<Vec<Su
     Ok(subtasks)
 }
 
+#[instrument(level = "debug", skip_all)]
+pub async fn list_subtasks_for_project(
+    pool: &PgPool,
+    project_id: Uuid,
+) -> Result<Vec<Subtask>, sqlx::Error> {
+    let subtasks = sqlx::query_as::<_, Subtask>(
+        r#"
+        SELECT s.* FROM subtasks s
+        JOIN tasks t ON t.id = s.parent_task_id
+        WHERE t.project_id = $1 AND t.deleted_at IS NULL
+        ORDER BY s.created_at ASC
+        "#,
+    )
+    .bind(project_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(subtasks)
+}
+
+#[instrument(level = "debug", skip_all)]
 pub async fn update_subtask(
     pool: &PgPool,
     id: Uuid,
@@ -72,6 +107,7 @@ pub async fn update_subtask(
     Ok(subtask)
 }
 
+#[instrument(level = "debug", skip_all)]
 pub async fn delete_subtask(pool: &PgPool, id: Uuid) -> Result<(), sqlx::Error> {
     sqlx::query(
         r#"
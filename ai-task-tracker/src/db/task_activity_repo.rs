@@ -0,0 +1,49 @@
+use sqlx::{PgExecutor, PgPool};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::models::TaskActivity;
+
+#[instrument(level = "debug", skip_all)]
+pub async fn record_task_activity<'e, E: PgExecutor<'e>>(
+    executor: E,
+    task_id: Uuid,
+    actor_id: Option<Uuid>,
+    action: &str,
+    old_value: Option<&str>,
+    new_value: Option<&str>,
+) -> Result<TaskActivity, sqlx::Error> {
+    let activity = sqlx::query_as::<_, TaskActivity>(
+        r#"
+        INSERT INTO task_activity (task_id, actor_id, action, old_value, new_value)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING *
+        "#,
+    )
+    .bind(task_id)
+    .bind(actor_id)
+    .bind(action)
+    .bind(old_value)
+    .bind(new_value)
+    .fetch_one(executor)
+    .await?;
+
+    Ok(activity)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn list_task_activity(pool: &PgPool, task_id: Uuid) -> Result<Vec<TaskActivity>, sqlx::Error> {
+    let activity = sqlx::query_as::<_, TaskActivity>(
+        r#"
+        SELECT a.* FROM task_activity a
+        JOIN tasks t ON t.id = a.task_id
+        WHERE a.task_id = $1 AND t.deleted_at IS NULL
+        ORDER BY a.created_at DESC
+        "#,
+    )
+    .bind(task_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(activity)
+}
@@ -1,8 +1,10 @@
 use sqlx::PgPool;
+use tracing::instrument;
 use uuid::Uuid;
 
 use crate::models::{CreateTaskHistoryRequest, TaskHistory, TaskHistoryWithUser};
 
+#[instrument(level = "debug", skip_all)]
 pub async fn create_task_history(
     pool: &PgPool,
     task_id: Uuid,
@@ -26,6 +28,7 @@ pub async fn create_task_history(
     Ok(history)
 }
 
+#[instrument(level = "debug", skip_all)]
 pub async fn list_task_history(
     pool: &PgPool,
     task_id: Uuid,
@@ -43,7 +46,8 @@ pub async fn list_task_history(
             u.email as user_email
         FROM task_history th
         JOIN users u ON th.user_id = u.id
-        WHERE th.task_id = $1
+        JOIN tasks t ON th.task_id = t.id
+        WHERE th.task_id = $1 AND t.deleted_at IS NULL
         ORDER BY th.created_at DESC
         "#,
     )
@@ -53,3 +57,33 @@ pub async fn list_task_history(
 
     Ok(history)
 }
+
+#[instrument(level = "debug", skip_all)]
+pub async fn list_task_history_for_project(
+    pool: &PgPool,
+    project_id: Uuid,
+) -> Result<Vec<TaskHistoryWithUser>, sqlx::Error> {
+    let history = sqlx::query_as::<_, TaskHistoryWithUser>(
+        r#"
+        SELECT
+            th.id,
+            th.task_id,
+            th.user_id,
+            th.comment,
+            th.completion_percentage,
+            th.created_at,
+            u.full_name as user_name,
+            u.email as user_email
+        FROM task_history th
+        JOIN users u ON th.user_id = u.id
+        JOIN tasks t ON th.task_id = t.id
+        WHERE t.project_id = $1 AND t.deleted_at IS NULL
+        ORDER BY th.created_at ASC
+        "#,
+    )
+    .bind(project_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(history)
+}
@@ -1,14 +1,24 @@
-use crate::models::{CreateTaskRequest, Task, UpdateTaskRequest};
+use crate::db::record_task_activity;
+use crate::models::{CreateTaskRequest, LabelMatch, SortOrder, Task, TaskFilter, TaskSortField, UpdateTaskRequest};
 use sqlx::PgPool;
+use tracing::instrument;
 use uuid::Uuid;
 
-pub async fn create_task(pool: &PgPool, req: &CreateTaskRequest) -> Result<Task, sqlx::Error> {
+#[instrument(level = "debug", skip_all)]
+pub async fn create_task(
+    pool: &PgPool,
+    organization_id: Uuid,
+    req: &CreateTaskRequest,
+    actor_id: Option<Uuid>,
+) -> Result<Task, sqlx::Error> {
     let priority = req.priority.as_ref().map(|p| p.to_string()).unwrap_or_else(|| "medium".to_string());
-    
+    let mut tx = pool.begin().await?;
+
     let task = sqlx::query_as::<_, Task>(
         r#"
-        INSERT INTO tasks (project_id, parent_task_id, title, description, priority, assignee_id, due_date)
-        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        INSERT INTO tasks (project_id, parent_task_id, title, description, priority, assignee_id, due_date,
+                           estimate_minutes, organization_id)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
         RETURNING *
         "#,
     )
@@ -19,81 +29,149 @@ pub async fn create_task(pool: &PgPool, req: &CreateTaskRequest) -> Result<Task,
     .bind(priority)
     .bind(req.assignee_id)
     .bind(req.due_date)
-    .fetch_one(pool)
+    .bind(req.estimate_minutes)
+    .bind(organization_id)
+    .fetch_one(&mut *tx)
     .await?;
 
+    record_task_activity(&mut *tx, task.id, actor_id, "created", None, Some(&task.status)).await?;
+    tx.commit().await?;
+
     Ok(task)
 }
 
-pub async fn find_task_by_id(pool: &PgPool, id: Uuid) -> Result<Option<Task>, sqlx::Error> {
+#[instrument(level = "debug", skip_all)]
+pub async fn find_task_by_id(pool: &PgPool, organization_id: Uuid, id: Uuid) -> Result<Option<Task>, sqlx::Error> {
     let task = sqlx::query_as::<_, Task>(
         r#"
-        SELECT * FROM tasks WHERE id = $1
+        SELECT * FROM tasks WHERE id = $1 AND organization_id = $2 AND deleted_at IS NULL
         "#,
     )
     .bind(id)
+    .bind(organization_id)
     .fetch_optional(pool)
     .await?;
 
     Ok(task)
 }
 
-pub async fn list_tasks(
-    pool: &PgPool,
-    project_id: Option<Uuid>,
-    assignee_id: Option<Uuid>,
-    status: Option<String>,
-    parent_task_id: Option<Uuid>,
-) -> Result<Vec<Task>, sqlx::Error> {
-    let mut query = String::from("SELECT * FROM tasks WHERE 1=1");
-    let mut param_idx = 1;
+#[instrument(level = "debug", skip_all)]
+pub async fn list_tasks(pool: &PgPool, organization_id: Uuid, filter: &TaskFilter) -> Result<Vec<Task>, sqlx::Error> {
+    let mut query = String::from("SELECT * FROM tasks WHERE organization_id = $1 AND deleted_at IS NULL");
+    let mut param_idx = 2;
     
-    if project_id.is_some() {
+    if filter.project_id.is_some() {
         query.push_str(&format!(" AND project_id = ${}", param_idx));
         param_idx += 1;
     }
-    if assignee_id.is_some() {
+    if filter.assignee_id.is_some() {
         query.push_str(&format!(" AND assignee_id = ${}", param_idx));
         param_idx += 1;
     }
-    if status.is_some() {
+    if filter.status.is_some() {
         query.push_str(&format!(" AND status = ${}", param_idx));
         param_idx += 1;
     }
-    if parent_task_id.is_some() {
+    if filter.priority.is_some() {
+        query.push_str(&format!(" AND priority = ${}", param_idx));
+        param_idx += 1;
+    }
+    if filter.parent_task_id.is_some() {
         query.push_str(&format!(" AND parent_task_id = ${}", param_idx));
         param_idx += 1;
     }
+    if filter.sprint_id.is_some() {
+        query.push_str(&format!(" AND sprint_id = ${}", param_idx));
+        param_idx += 1;
+    }
+    if filter.due_before.is_some() {
+        query.push_str(&format!(" AND due_date <= ${}", param_idx));
+        param_idx += 1;
+    }
+    if !filter.label_ids.is_empty() {
+        match filter.label_match {
+            LabelMatch::Any => query.push_str(&format!(
+                " AND EXISTS (SELECT 1 FROM task_labels tl WHERE tl.task_id = tasks.id AND tl.label_id = ANY(${}))",
+                param_idx
+            )),
+            LabelMatch::All => query.push_str(&format!(
+                " AND id IN (SELECT task_id FROM task_labels WHERE label_id = ANY(${0}) \
+                 GROUP BY task_id HAVING COUNT(*) = CARDINALITY(${0}))",
+                param_idx
+            )),
+        }
+    }
     
-    query.push_str(" ORDER BY created_at DESC");
+    let sort_column = match filter.sort_by {
+        TaskSortField::CreatedAt => "created_at",
+        TaskSortField::UpdatedAt => "updated_at",
+        TaskSortField::DueDate => "due_date",
+        TaskSortField::Priority => {
+            "CASE priority WHEN 'low' THEN 0 WHEN 'medium' THEN 1 WHEN 'high' THEN 2 ELSE 3 END"
+        }
+        TaskSortField::Title => "LOWER(title)",
+    };
+    let direction = match filter.sort_order {
+        SortOrder::Asc => "ASC",
+        SortOrder::Desc => "DESC",
+    };
+    query.push_str(&format!(" ORDER BY {} {} NULLS LAST, created_at DESC", sort_column, direction));
 
-    let mut q = sqlx::query_as::<_, Task>(&query);
+    let mut q = sqlx::query_as::<_, Task>(&query).bind(organization_id);
     
-    if let Some(pid) = project_id {
+    if let Some(pid) = filter.project_id {
         q = q.bind(pid);
     }
-    if let Some(aid) = assignee_id {
+    if let Some(aid) = filter.assignee_id {
         q = q.bind(aid);
     }
-    if let Some(s) = status {
+    if let Some(s) = &filter.status {
         q = q.bind(s);
     }
-    if let Some(ptid) = parent_task_id {
+    if let Some(p) = &filter.priority {
+        q = q.bind(p);
+    }
+    if let Some(ptid) = filter.parent_task_id {
         q = q.bind(ptid);
     }
+    if let Some(sid) = filter.sprint_id {
+        q = q.bind(sid);
+    }
+    if let Some(due) = filter.due_before {
+        q = q.bind(due);
+    }
+    if !filter.label_ids.is_empty() {
+        let mut label_ids = filter.label_ids.clone();
+        label_ids.sort();
+        label_ids.dedup();
+        q = q.bind(label_ids);
+    }
 
     let tasks = q.fetch_all(pool).await?;
     Ok(tasks)
 }
 
+#[instrument(level = "debug", skip_all)]
 pub async fn update_task(
     pool: &PgPool,
+    organization_id: Uuid,
     id: Uuid,
     req: &UpdateTaskRequest,
+    actor_id: Option<Uuid>,
 ) -> Result<Task, sqlx::Error> {
     let status_str = req.status.as_ref().map(|s| s.to_string());
     let priority_str = req.priority.as_ref().map(|p| p.to_string());
-    
+    let mut tx = pool.begin().await?;
+
+    let old_status: String =
+        sqlx::query_scalar(
+            "SELECT status FROM tasks WHERE id = $1 AND organization_id = $2 AND deleted_at IS NULL FOR UPDATE",
+        )
+        .bind(id)
+        .bind(organization_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
     let task = sqlx::query_as::<_, Task>(
         r#"
         UPDATE tasks
@@ -104,8 +182,9 @@ pub async fn update_task(
             assignee_id = COALESCE($6, assignee_id),
             due_date = COALESCE($7, due_date),
             progress_percent = COALESCE($8, progress_percent),
+            estimate_minutes = COALESCE($9, estimate_minutes),
             updated_at = NOW()
-        WHERE id = $1
+        WHERE id = $1 AND organization_id = $10 AND deleted_at IS NULL
         RETURNING *
         "#,
     )
@@ -117,14 +196,31 @@ pub async fn update_task(
     .bind(req.assignee_id)
     .bind(req.due_date)
     .bind(req.progress_percent)
-    .fetch_one(pool)
+    .bind(req.estimate_minutes)
+    .bind(organization_id)
+    .fetch_one(&mut *tx)
     .await?;
 
+    if task.status != old_status {
+        record_task_activity(
+            &mut *tx,
+            task.id,
+            actor_id,
+            "status_changed",
+            Some(&old_status),
+            Some(&task.status),
+        )
+        .await?;
+    }
+    tx.commit().await?;
+
     Ok(task)
 }
 
+#[instrument(level = "debug", skip_all)]
 pub async fn update_task_progress(
     pool: &PgPool,
+    organization_id: Uuid,
     id: Uuid,
     progress: i32,
 ) -> Result<Task, sqlx::Error> {
@@ -133,27 +229,100 @@ pub async fn update_task_progress(
         UPDATE tasks
         SET progress_percent = $2,
             updated_at = NOW()
-        WHERE id = $1
+        WHERE id = $1 AND organization_id = $3 AND deleted_at IS NULL
         RETURNING *
         "#,
     )
     .bind(id)
     .bind(progress)
+    .bind(organization_id)
     .fetch_one(pool)
     .await?;
 
     Ok(task)
 }
 
-pub async fn delete_task(pool: &PgPool, id: Uuid) -> Result<(), sqlx::Error> {
+/// Moves a task and its child tasks to the trash. Returns `false` if the task
+/// does not exist or is already deleted.
+#[instrument(level = "debug", skip_all)]
+pub async fn soft_delete_task(
+    pool: &PgPool,
+    organization_id: Uuid,
+    id: Uuid,
+    actor_id: Option<Uuid>,
+) -> Result<bool, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let deleted = sqlx::query(
+        r#"
+        WITH RECURSIVE tree AS (
+            SELECT id FROM tasks WHERE id = $1 AND organization_id = $3 AND deleted_at IS NULL
+            UNION
+            SELECT t.id FROM tasks t JOIN tree ON t.parent_task_id = tree.id
+            WHERE t.deleted_at IS NULL
+        )
+        UPDATE tasks
+        SET deleted_at = NOW(), deleted_by = $2
+        WHERE id IN (SELECT id FROM tree)
+        "#,
+    )
+    .bind(id)
+    .bind(actor_id)
+    .bind(organization_id)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    if deleted == 0 {
+        return Ok(false);
+    }
+
+    record_task_activity(&mut *tx, id, actor_id, "deleted", None, None).await?;
+    tx.commit().await?;
+
+    Ok(true)
+}
+
+/// Restores a task from the trash along with the child tasks deleted with it.
+#[instrument(level = "debug", skip_all)]
+pub async fn restore_task(
+    pool: &PgPool,
+    organization_id: Uuid,
+    id: Uuid,
+    actor_id: Option<Uuid>,
+) -> Result<Option<Task>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
     sqlx::query(
         r#"
-        DELETE FROM tasks WHERE id = $1
+        WITH RECURSIVE tree AS (
+            SELECT id, deleted_at FROM tasks WHERE id = $1 AND organization_id = $2 AND deleted_at IS NOT NULL
+            UNION
+            SELECT t.id, t.deleted_at FROM tasks t JOIN tree ON t.parent_task_id = tree.id
+            WHERE t.deleted_at = tree.deleted_at
+        )
+        UPDATE tasks
+        SET deleted_at = NULL, deleted_by = NULL, updated_at = NOW()
+        WHERE id IN (SELECT id FROM tree)
         "#,
     )
     .bind(id)
-    .execute(pool)
+    .bind(organization_id)
+    .execute(&mut *tx)
+    .await?;
+
+    let task = sqlx::query_as::<_, Task>(
+        "SELECT * FROM tasks WHERE id = $1 AND organization_id = $2 AND deleted_at IS NULL",
+    )
+    .bind(id)
+    .bind(organization_id)
+    .fetch_optional(&mut *tx)
     .await?;
 
-    Ok(())
+    if task.is_some() {
+        record_task_activity(&mut *tx, id, actor_id, "restored", None, None).await?;
+    }
+    tx.commit().await?;
+
+    Ok(task)
 }
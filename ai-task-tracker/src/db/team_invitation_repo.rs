@@ -0,0 +1,218 @@
+use crate::db::add_organization_member;
+use crate::models::{TeamInvitation, TeamMember, TeamRole};
+use sqlx::PgPool;
+use tracing::instrument;
+use uuid::Uuid;
+
+#[instrument(level = "debug", skip_all)]
+pub async fn create_team_invitation(
+    pool: &PgPool,
+    organization_id: Uuid,
+    team_id: Uuid,
+    email: &str,
+    role: &TeamRole,
+    invited_by: Uuid,
+) -> Result<TeamInvitation, sqlx::Error> {
+    let invitation = sqlx::query_as::<_, TeamInvitation>(
+        r#"
+        INSERT INTO team_invitations (team_id, email, role, invited_by, organization_id)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING *
+        "#,
+    )
+    .bind(team_id)
+    .bind(email)
+    .bind(role.to_string())
+    .bind(invited_by)
+    .bind(organization_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(invitation)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn find_team_invitation_by_id(
+    pool: &PgPool,
+    id: Uuid,
+) -> Result<Option<TeamInvitation>, sqlx::Error> {
+    let invitation = sqlx::query_as::<_, TeamInvitation>(
+        r#"
+        SELECT * FROM team_invitations WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(invitation)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn find_pending_team_invitation(
+    pool: &PgPool,
+    team_id: Uuid,
+    email: &str,
+) -> Result<Option<TeamInvitation>, sqlx::Error> {
+    let invitation = sqlx::query_as::<_, TeamInvitation>(
+        r#"
+        SELECT * FROM team_invitations
+        WHERE team_id = $1 AND LOWER(email) = LOWER($2) AND status = 'pending'
+        "#,
+    )
+    .bind(team_id)
+    .bind(email)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(invitation)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn list_team_invitations(
+    pool: &PgPool,
+    team_id: Uuid,
+) -> Result<Vec<TeamInvitation>, sqlx::Error> {
+    let invitations = sqlx::query_as::<_, TeamInvitation>(
+        r#"
+        SELECT * FROM team_invitations
+        WHERE team_id = $1 AND status = 'pending'
+        ORDER BY created_at DESC
+        "#,
+    )
+    .bind(team_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(invitations)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn list_pending_invitations_for_user(
+    pool: &PgPool,
+    user_id: Uuid,
+    email: &str,
+) -> Result<Vec<TeamInvitation>, sqlx::Error> {
+    let invitations = sqlx::query_as::<_, TeamInvitation>(
+        r#"
+        SELECT * FROM team_invitations
+        WHERE status = 'pending'
+          AND (invitee_id = $1 OR LOWER(email) = LOWER($2))
+        ORDER BY created_at DESC
+        "#,
+    )
+    .bind(user_id)
+    .bind(email)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(invitations)
+}
+
+/// Links every pending invitation sent to `email` to the newly registered user.
+#[instrument(level = "debug", skip_all)]
+pub async fn claim_team_invitations(
+    pool: &PgPool,
+    email: &str,
+    user_id: Uuid,
+) -> Result<Vec<TeamInvitation>, sqlx::Error> {
+    let invitations = sqlx::query_as::<_, TeamInvitation>(
+        r#"
+        UPDATE team_invitations
+        SET invitee_id = $2
+        WHERE LOWER(email) = LOWER($1) AND status = 'pending' AND invitee_id IS NULL
+        RETURNING *
+        "#,
+    )
+    .bind(email)
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(invitations)
+}
+
+/// Marks the invitation accepted and adds the user to the team, and to the
+/// team's organization, in one transaction.
+#[instrument(level = "debug", skip_all)]
+pub async fn accept_team_invitation(
+    pool: &PgPool,
+    id: Uuid,
+    user_id: Uuid,
+) -> Result<TeamMember, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let invitation = sqlx::query_as::<_, TeamInvitation>(
+        r#"
+        UPDATE team_invitations
+        SET status = 'accepted',
+            invitee_id = $2,
+            responded_at = NOW()
+        WHERE id = $1 AND status = 'pending'
+        RETURNING *
+        "#,
+    )
+    .bind(id)
+    .bind(user_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let member = sqlx::query_as::<_, TeamMember>(
+        r#"
+        INSERT INTO team_members (team_id, user_id, role)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (team_id, user_id) DO UPDATE SET role = EXCLUDED.role
+        RETURNING *
+        "#,
+    )
+    .bind(invitation.team_id)
+    .bind(user_id)
+    .bind(&invitation.role)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    add_organization_member(&mut *tx, invitation.organization_id, user_id).await?;
+
+    tx.commit().await?;
+
+    Ok(member)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn decline_team_invitation(
+    pool: &PgPool,
+    id: Uuid,
+    user_id: Uuid,
+) -> Result<TeamInvitation, sqlx::Error> {
+    let invitation = sqlx::query_as::<_, TeamInvitation>(
+        r#"
+        UPDATE team_invitations
+        SET status = 'declined',
+            invitee_id = $2,
+            responded_at = NOW()
+        WHERE id = $1 AND status = 'pending'
+        RETURNING *
+        "#,
+    )
+    .bind(id)
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(invitation)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn delete_team_invitation(pool: &PgPool, team_id: Uuid, id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        DELETE FROM team_invitations WHERE id = $1 AND team_id = $2
+        "#,
+    )
+    .bind(id)
+    .bind(team_id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
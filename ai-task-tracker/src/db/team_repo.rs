@@ -1,51 +1,63 @@
-use crate::models::{CreateTeamRequest, Team, TeamMember, TeamMemberWithUser, UpdateTeamRequest};
+use crate::models::{
+    CreateTeamRequest, Team, TeamMember, TeamMemberWithUser, TeamRole, UpdateTeamRequest,
+};
+use chrono::{DateTime, Utc};
 use sqlx::PgPool;
+use tracing::instrument;
 use uuid::Uuid;
 
-pub async fn create_team(pool: &PgPool, req: &CreateTeamRequest) -> Result<Team, sqlx::Error> {
+#[instrument(level = "debug", skip_all)]
+pub async fn create_team(pool: &PgPool, organization_id: Uuid, req: &CreateTeamRequest) -> Result<Team, sqlx::Error> {
     let team = sqlx::query_as::<_, Team>(
         r#"
-        INSERT INTO teams (name, parent_team_id, manager_id)
-        VALUES ($1, $2, $3)
+        INSERT INTO teams (name, parent_team_id, manager_id, organization_id)
+        VALUES ($1, $2, $3, $4)
         RETURNING *
         "#,
     )
     .bind(&req.name)
     .bind(req.parent_team_id)
     .bind(req.manager_id)
+    .bind(organization_id)
     .fetch_one(pool)
     .await?;
 
     Ok(team)
 }
 
-pub async fn find_team_by_id(pool: &PgPool, id: Uuid) -> Result<Option<Team>, sqlx::Error> {
+#[instrument(level = "debug", skip_all)]
+pub async fn find_team_by_id(pool: &PgPool, organization_id: Uuid, id: Uuid) -> Result<Option<Team>, sqlx::Error> {
     let team = sqlx::query_as::<_, Team>(
         r#"
-        SELECT * FROM teams WHERE id = $1
+        SELECT * FROM teams WHERE id = $1 AND organization_id = $2 AND deleted_at IS NULL
         "#,
     )
     .bind(id)
+    .bind(organization_id)
     .fetch_optional(pool)
     .await?;
 
     Ok(team)
 }
 
-pub async fn list_teams(pool: &PgPool) -> Result<Vec<Team>, sqlx::Error> {
+#[instrument(level = "debug", skip_all)]
+pub async fn list_teams(pool: &PgPool, organization_id: Uuid) -> Result<Vec<Team>, sqlx::Error> {
     let teams = sqlx::query_as::<_, Team>(
         r#"
-        SELECT * FROM teams ORDER BY created_at DESC
+        SELECT * FROM teams WHERE organization_id = $1 AND deleted_at IS NULL ORDER BY created_at DESC
         "#,
     )
+    .bind(organization_id)
     .fetch_all(pool)
     .await?;
 
     Ok(teams)
 }
 
+#[instrument(level = "debug", skip_all)]
 pub async fn update_team(
     pool: &PgPool,
+    organization_id: Uuid,
     id: Uuid,
     req: &UpdateTeamRequest,
 ) -> Result<Team, sqlx::Error> {
@@ -56,7 +68,7 @@ pub async fn update_team(
             parent_team_id = COALESCE($3, parent_team_id),
             manager_id = COALESCE($4, manager_id),
             updated_at = NOW()
-        WHERE id = $1
+        WHERE id = $1 AND organization_id = $5 AND deleted_at IS NULL
         RETURNING *
         "#,
     )
@@ -64,45 +76,192 @@ pub async fn update_team(
     .bind(&req.name)
     .bind(req.parent_team_id)
     .bind(req.manager_id)
+    .bind(organization_id)
     .fetch_one(pool)
     .await?;
 
     Ok(team)
 }
 
-pub async fn delete_team(pool: &PgPool, id: Uuid) -> Result<(), sqlx::Error> {
+/// Moves a team, its projects and their tasks to the trash. Returns `false`
+/// if the team does not exist or is already deleted.
+#[instrument(level = "debug", skip_all)]
+pub async fn soft_delete_team(
+    pool: &PgPool,
+    organization_id: Uuid,
+    id: Uuid,
+    actor_id: Option<Uuid>,
+) -> Result<bool, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let deleted = sqlx::query(
+        r#"
+        UPDATE teams SET deleted_at = NOW(), deleted_by = $2
+        WHERE id = $1 AND organization_id = $3 AND deleted_at IS NULL
+        "#,
+    )
+    .bind(id)
+    .bind(actor_id)
+    .bind(organization_id)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    if deleted == 0 {
+        return Ok(false);
+    }
+
     sqlx::query(
         r#"
-        DELETE FROM teams WHERE id = $1
+        UPDATE tasks SET deleted_at = NOW(), deleted_by = $2
+        WHERE project_id IN (SELECT id FROM projects WHERE team_id = $1 AND deleted_at IS NULL)
+          AND deleted_at IS NULL
         "#,
     )
     .bind(id)
-    .execute(pool)
+    .bind(actor_id)
+    .execute(&mut *tx)
     .await?;
 
-    Ok(())
+    sqlx::query(
+        r#"
+        UPDATE projects SET deleted_at = NOW(), deleted_by = $2
+        WHERE team_id = $1 AND deleted_at IS NULL
+        "#,
+    )
+    .bind(id)
+    .bind(actor_id)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(true)
 }
 
+/// Restores a team from the trash along with the projects and tasks deleted with it.
+#[instrument(level = "debug", skip_all)]
+pub async fn restore_team(pool: &PgPool, organization_id: Uuid, id: Uuid) -> Result<Option<Team>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let deleted_at: Option<DateTime<Utc>> = sqlx::query_scalar(
+        "SELECT deleted_at FROM teams WHERE id = $1 AND organization_id = $2 AND deleted_at IS NOT NULL FOR UPDATE",
+    )
+    .bind(id)
+    .bind(organization_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let Some(deleted_at) = deleted_at else {
+        return Ok(None);
+    };
+
+    sqlx::query(
+        r#"
+        UPDATE tasks SET deleted_at = NULL, deleted_by = NULL
+        WHERE project_id IN (SELECT id FROM projects WHERE team_id = $1 AND deleted_at = $2)
+          AND deleted_at = $2
+        "#,
+    )
+    .bind(id)
+    .bind(deleted_at)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        r#"
+        UPDATE projects SET deleted_at = NULL, deleted_by = NULL
+        WHERE team_id = $1 AND deleted_at = $2
+        "#,
+    )
+    .bind(id)
+    .bind(deleted_at)
+    .execute(&mut *tx)
+    .await?;
+
+    let team = sqlx::query_as::<_, Team>(
+        r#"
+        UPDATE teams SET deleted_at = NULL, deleted_by = NULL, updated_at = NOW()
+        WHERE id = $1
+        RETURNING *
+        "#,
+    )
+    .bind(id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(Some(team))
+}
+
+#[instrument(level = "debug", skip_all)]
 pub async fn add_team_member(
     pool: &PgPool,
     team_id: Uuid,
     user_id: Uuid,
+    role: &TeamRole,
 ) -> Result<TeamMember, sqlx::Error> {
     let member = sqlx::query_as::<_, TeamMember>(
         r#"
-        INSERT INTO team_members (team_id, user_id)
-        VALUES ($1, $2)
+        INSERT INTO team_members (team_id, user_id, role)
+        VALUES ($1, $2, $3)
         RETURNING *
         "#,
     )
     .bind(team_id)
     .bind(user_id)
+    .bind(role.to_string())
     .fetch_one(pool)
     .await?;
 
     Ok(member)
 }
 
+#[instrument(level = "debug", skip_all)]
+pub async fn find_team_member(
+    pool: &PgPool,
+    team_id: Uuid,
+    user_id: Uuid,
+) -> Result<Option<TeamMember>, sqlx::Error> {
+    let member = sqlx::query_as::<_, TeamMember>(
+        r#"
+        SELECT * FROM team_members WHERE team_id = $1 AND user_id = $2
+        "#,
+    )
+    .bind(team_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(member)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn update_team_member_role(
+    pool: &PgPool,
+    team_id: Uuid,
+    user_id: Uuid,
+    role: &TeamRole,
+) -> Result<Option<TeamMember>, sqlx::Error> {
+    let member = sqlx::query_as::<_, TeamMember>(
+        r#"
+        UPDATE team_members
+        SET role = $3
+        WHERE team_id = $1 AND user_id = $2
+        RETURNING *
+        "#,
+    )
+    .bind(team_id)
+    .bind(user_id)
+    .bind(role.to_string())
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(member)
+}
+
+#[instrument(level = "debug", skip_all)]
 pub async fn remove_team_member(
     pool: &PgPool,
     team_id: Uuid,
@@ -121,12 +280,12 @@ pub async fn remove_team_member(
     Ok(())
 }
 
-
-
+#[instrument(level = "debug", skip_all)]
 pub async fn list_team_members(pool: &PgPool, team_id: Uuid) -> Result<Vec<TeamMemberWithUser>, sqlx::Error> {
     let members = sqlx::query_as::<_, TeamMemberWithUser>(
         r#"
-        SELECT tm.team_id, tm.user_id, tm.joined_at, u.full_name, u.email, u.role
+        SELECT tm.team_id, tm.user_id, tm.joined_at, u.full_name, u.email, u.role,
+               tm.role AS team_role
         FROM team_members tm
         JOIN users u ON tm.user_id = u.id
         WHERE tm.team_id = $1
@@ -0,0 +1,311 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::{PgPool, Postgres, Transaction};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::db::record_task_activity;
+use crate::models::{
+    flatten_template_tasks, CreateProjectRequest, CreateTemplateRequest, Project, ProjectTemplate,
+    TemplateTask, TemplateTaskInput, UpdateTemplateRequest,
+};
+
+/// Where an instantiated template's tasks go.
+pub enum TemplateTarget<'a> {
+    /// A new project, created by the given user.
+    NewProject(&'a CreateProjectRequest, Uuid),
+    Existing(&'a Project),
+}
+
+async fn insert_template_tasks(
+    tx: &mut Transaction<'_, Postgres>,
+    template_id: Uuid,
+    tasks: &[TemplateTaskInput],
+) -> Result<(), sqlx::Error> {
+    let mut ids: Vec<Uuid> = Vec::new();
+
+    for (position, (parent, task)) in flatten_template_tasks(tasks).into_iter().enumerate() {
+        let priority = task
+            .priority
+            .as_ref()
+            .map(|p| p.to_string())
+            .unwrap_or_else(|| "medium".to_string());
+
+        let id: Uuid = sqlx::query_scalar(
+            r#"
+            INSERT INTO template_tasks (template_id, parent_id, position, title, description, priority,
+                                        estimate_minutes, due_offset_days, assignee_role, subtasks)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            RETURNING id
+            "#,
+        )
+        .bind(template_id)
+        .bind(parent.map(|i| ids[i]))
+        .bind(position as i32)
+        .bind(&task.title)
+        .bind(&task.description)
+        .bind(priority)
+        .bind(task.estimate_minutes)
+        .bind(task.due_offset_days)
+        .bind(task.assignee_role.as_ref().map(|r| r.to_string()))
+        .bind(&task.subtasks)
+        .fetch_one(&mut **tx)
+        .await?;
+
+        ids.push(id);
+    }
+
+    Ok(())
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn create_template(
+    pool: &PgPool,
+    organization_id: Uuid,
+    req: &CreateTemplateRequest,
+    created_by: Option<Uuid>,
+) -> Result<ProjectTemplate, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let template = sqlx::query_as::<_, ProjectTemplate>(
+        r#"
+        INSERT INTO project_templates (name, description, team_id, created_by, organization_id)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING *
+        "#,
+    )
+    .bind(&req.name)
+    .bind(&req.description)
+    .bind(req.team_id)
+    .bind(created_by)
+    .bind(organization_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    insert_template_tasks(&mut tx, template.id, &req.tasks).await?;
+    tx.commit().await?;
+
+    Ok(template)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn find_template_by_id(
+    pool: &PgPool,
+    organization_id: Uuid,
+    id: Uuid,
+) -> Result<Option<ProjectTemplate>, sqlx::Error> {
+    let template = sqlx::query_as::<_, ProjectTemplate>(
+        r#"
+        SELECT * FROM project_templates WHERE id = $1 AND organization_id = $2
+        "#,
+    )
+    .bind(id)
+    .bind(organization_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(template)
+}
+
+/// Templates of a team together with those shared by the whole organization,
+/// or all of the organization's templates.
+#[instrument(level = "debug", skip_all)]
+pub async fn list_templates(
+    pool: &PgPool,
+    organization_id: Uuid,
+    team_id: Option<Uuid>,
+) -> Result<Vec<ProjectTemplate>, sqlx::Error> {
+    let templates = sqlx::query_as::<_, ProjectTemplate>(
+        r#"
+        SELECT * FROM project_templates
+        WHERE organization_id = $2 AND ($1::uuid IS NULL OR team_id = $1 OR team_id IS NULL)
+        ORDER BY name
+        "#,
+    )
+    .bind(team_id)
+    .bind(organization_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(templates)
+}
+
+/// A template's tasks in depth-first order.
+#[instrument(level = "debug", skip_all)]
+pub async fn list_template_tasks(pool: &PgPool, template_id: Uuid) -> Result<Vec<TemplateTask>, sqlx::Error> {
+    let tasks = sqlx::query_as::<_, TemplateTask>(
+        r#"
+        SELECT * FROM template_tasks WHERE template_id = $1 ORDER BY position
+        "#,
+    )
+    .bind(template_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(tasks)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn update_template(
+    pool: &PgPool,
+    organization_id: Uuid,
+    id: Uuid,
+    req: &UpdateTemplateRequest,
+) -> Result<Option<ProjectTemplate>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let template = sqlx::query_as::<_, ProjectTemplate>(
+        r#"
+        UPDATE project_templates
+        SET name = COALESCE($2, name),
+            description = COALESCE($3, description),
+            updated_at = NOW()
+        WHERE id = $1 AND organization_id = $4
+        RETURNING *
+        "#,
+    )
+    .bind(id)
+    .bind(&req.name)
+    .bind(&req.description)
+    .bind(organization_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    if let (Some(template), Some(tasks)) = (&template, &req.tasks) {
+        sqlx::query("DELETE FROM template_tasks WHERE template_id = $1")
+            .bind(template.id)
+            .execute(&mut *tx)
+            .await?;
+        insert_template_tasks(&mut tx, template.id, tasks).await?;
+    }
+    tx.commit().await?;
+
+    Ok(template)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn delete_template(pool: &PgPool, organization_id: Uuid, id: Uuid) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        DELETE FROM project_templates WHERE id = $1 AND organization_id = $2
+        "#,
+    )
+    .bind(id)
+    .bind(organization_id)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// The longest-standing member of a team for each team role, used as the
+/// default assignee for template tasks with that role.
+#[instrument(level = "debug", skip_all)]
+pub async fn team_role_defaults(pool: &PgPool, team_id: Uuid) -> Result<HashMap<String, Uuid>, sqlx::Error> {
+    let rows: Vec<(String, Uuid)> = sqlx::query_as(
+        r#"
+        SELECT DISTINCT ON (role) role, user_id
+        FROM team_members
+        WHERE team_id = $1
+        ORDER BY role, joined_at, user_id
+        "#,
+    )
+    .bind(team_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().collect())
+}
+
+/// Creates the template's tasks, child tasks and subtasks (and the project, for
+/// a new one) in a single transaction. `assignees` maps team roles to users.
+/// Returns the project with the number of tasks and subtasks created.
+#[instrument(level = "debug", skip_all)]
+pub async fn instantiate_template(
+    pool: &PgPool,
+    organization_id: Uuid,
+    tasks: &[TemplateTask],
+    target: TemplateTarget<'_>,
+    start_date: NaiveDate,
+    assignees: &HashMap<String, Uuid>,
+    actor_id: Option<Uuid>,
+) -> Result<(Project, usize, usize), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let project = match target {
+        TemplateTarget::NewProject(req, created_by) => {
+            sqlx::query_as::<_, Project>(
+                r#"
+                INSERT INTO projects (name, description, team_id, created_by, organization_id)
+                VALUES ($1, $2, $3, $4, $5)
+                RETURNING *
+                "#,
+            )
+            .bind(&req.name)
+            .bind(&req.description)
+            .bind(req.team_id)
+            .bind(created_by)
+            .bind(organization_id)
+            .fetch_one(&mut *tx)
+            .await?
+        }
+        TemplateTarget::Existing(project) => project.clone(),
+    };
+
+    let start: DateTime<Utc> = start_date
+        .and_hms_opt(0, 0, 0)
+        .expect("midnight is a valid time")
+        .and_utc();
+    let mut ids: HashMap<Uuid, Uuid> = HashMap::new();
+    let mut subtask_count = 0;
+
+    // Rows are in depth-first order, so a parent is always created before its children
+    for task in tasks {
+        let due_date = task
+            .due_offset_days
+            .map(|days| start + chrono::Duration::days(days.into()));
+        let assignee_id = task.assignee_role.as_ref().and_then(|role| assignees.get(role));
+
+        let id: Uuid = sqlx::query_scalar(
+            r#"
+            INSERT INTO tasks (project_id, parent_task_id, title, description, priority, assignee_id,
+                               due_date, estimate_minutes, organization_id)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            RETURNING id
+            "#,
+        )
+        .bind(project.id)
+        .bind(task.parent_id.and_then(|parent| ids.get(&parent)))
+        .bind(&task.title)
+        .bind(&task.description)
+        .bind(&task.priority)
+        .bind(assignee_id)
+        .bind(due_date)
+        .bind(task.estimate_minutes)
+        .bind(organization_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        record_task_activity(&mut *tx, id, actor_id, "created", None, Some("todo")).await?;
+        ids.insert(task.id, id);
+
+        for title in &task.subtasks {
+            sqlx::query(
+                r#"
+                INSERT INTO subtasks (parent_task_id, title)
+                VALUES ($1, $2)
+                "#,
+            )
+            .bind(id)
+            .bind(title)
+            .execute(&mut *tx)
+            .await?;
+            subtask_count += 1;
+        }
+    }
+
+    tx.commit().await?;
+
+    Ok((project, ids.len(), subtask_count))
+}
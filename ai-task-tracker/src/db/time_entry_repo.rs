@@ -0,0 +1,227 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::PgPool;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::models::{TimeEntry, TimeEntryQuery, TimesheetRow};
+
+/// Fails with a unique violation if the user already has a running timer.
+#[instrument(level = "debug", skip_all)]
+pub async fn start_timer(
+    pool: &PgPool,
+    user_id: Uuid,
+    task_id: Uuid,
+    note: Option<&str>,
+) -> Result<TimeEntry, sqlx::Error> {
+    let entry = sqlx::query_as::<_, TimeEntry>(
+        r#"
+        INSERT INTO time_entries (task_id, user_id, started_at, note)
+        VALUES ($1, $2, NOW(), $3)
+        RETURNING *
+        "#,
+    )
+    .bind(task_id)
+    .bind(user_id)
+    .bind(note)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(entry)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn find_running_timer(pool: &PgPool, user_id: Uuid) -> Result<Option<TimeEntry>, sqlx::Error> {
+    let entry = sqlx::query_as::<_, TimeEntry>(
+        r#"
+        SELECT * FROM time_entries WHERE user_id = $1 AND ended_at IS NULL
+        "#,
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(entry)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn stop_timer(pool: &PgPool, user_id: Uuid) -> Result<Option<TimeEntry>, sqlx::Error> {
+    let entry = sqlx::query_as::<_, TimeEntry>(
+        r#"
+        UPDATE time_entries
+        SET ended_at = NOW(), updated_at = NOW()
+        WHERE user_id = $1 AND ended_at IS NULL
+        RETURNING *
+        "#,
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(entry)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn create_time_entry(
+    pool: &PgPool,
+    user_id: Uuid,
+    task_id: Uuid,
+    started_at: DateTime<Utc>,
+    ended_at: DateTime<Utc>,
+    note: Option<&str>,
+) -> Result<TimeEntry, sqlx::Error> {
+    let entry = sqlx::query_as::<_, TimeEntry>(
+        r#"
+        INSERT INTO time_entries (task_id, user_id, started_at, ended_at, note)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING *
+        "#,
+    )
+    .bind(task_id)
+    .bind(user_id)
+    .bind(started_at)
+    .bind(ended_at)
+    .bind(note)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(entry)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn find_time_entry(pool: &PgPool, organization_id: Uuid, id: Uuid) -> Result<Option<TimeEntry>, sqlx::Error> {
+    let entry = sqlx::query_as::<_, TimeEntry>(
+        r#"
+        SELECT te.* FROM time_entries te
+        JOIN tasks t ON t.id = te.task_id
+        WHERE te.id = $1 AND t.organization_id = $2
+        "#,
+    )
+    .bind(id)
+    .bind(organization_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(entry)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn update_time_entry(
+    pool: &PgPool,
+    id: Uuid,
+    started_at: DateTime<Utc>,
+    ended_at: Option<DateTime<Utc>>,
+    note: Option<&str>,
+) -> Result<TimeEntry, sqlx::Error> {
+    let entry = sqlx::query_as::<_, TimeEntry>(
+        r#"
+        UPDATE time_entries
+        SET started_at = $2, ended_at = $3, note = $4, updated_at = NOW()
+        WHERE id = $1
+        RETURNING *
+        "#,
+    )
+    .bind(id)
+    .bind(started_at)
+    .bind(ended_at)
+    .bind(note)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(entry)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn delete_time_entry(pool: &PgPool, id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        DELETE FROM time_entries WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn list_time_entries(
+    pool: &PgPool,
+    organization_id: Uuid,
+    query: &TimeEntryQuery,
+) -> Result<Vec<TimeEntry>, sqlx::Error> {
+    let entries = sqlx::query_as::<_, TimeEntry>(
+        r#"
+        SELECT te.*
+        FROM time_entries te
+        JOIN tasks t ON t.id = te.task_id
+        WHERE t.deleted_at IS NULL
+          AND t.organization_id = $6
+          AND ($1::uuid IS NULL OR te.task_id = $1)
+          AND ($2::uuid IS NULL OR te.user_id = $2)
+          AND ($3::uuid IS NULL OR t.project_id = $3)
+          AND ($4::date IS NULL OR te.started_at >= $4::date::timestamp AT TIME ZONE 'UTC')
+          AND ($5::date IS NULL OR te.started_at < ($5::date + 1)::timestamp AT TIME ZONE 'UTC')
+        ORDER BY te.started_at DESC
+        "#,
+    )
+    .bind(query.task_id)
+    .bind(query.user_id)
+    .bind(query.project_id)
+    .bind(query.from)
+    .bind(query.to)
+    .bind(organization_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(entries)
+}
+
+/// Finished work between `from` and `to` (inclusive, UTC days), grouped per day, user and task.
+#[instrument(level = "debug", skip_all)]
+pub async fn timesheet_rows(
+    pool: &PgPool,
+    organization_id: Uuid,
+    project_id: Option<Uuid>,
+    user_id: Option<Uuid>,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<TimesheetRow>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, TimesheetRow>(
+        r#"
+        SELECT
+            (te.started_at AT TIME ZONE 'UTC')::date AS date,
+            u.id AS user_id,
+            u.full_name AS user_name,
+            u.email AS user_email,
+            p.id AS project_id,
+            p.name AS project_name,
+            t.id AS task_id,
+            t.title AS task_title,
+            t.estimate_minutes,
+            ROUND(SUM(EXTRACT(EPOCH FROM (te.ended_at - te.started_at))) / 60)::BIGINT AS minutes
+        FROM time_entries te
+        JOIN tasks t ON t.id = te.task_id
+        JOIN projects p ON p.id = t.project_id
+        JOIN users u ON u.id = te.user_id
+        WHERE te.ended_at IS NOT NULL
+          AND t.deleted_at IS NULL
+          AND t.organization_id = $5
+          AND te.started_at >= $3::date::timestamp AT TIME ZONE 'UTC'
+          AND te.started_at < ($4::date + 1)::timestamp AT TIME ZONE 'UTC'
+          AND ($1::uuid IS NULL OR p.id = $1)
+          AND ($2::uuid IS NULL OR u.id = $2)
+        GROUP BY 1, u.id, p.id, t.id
+        ORDER BY date, u.full_name, p.name, t.title
+        "#,
+    )
+    .bind(project_id)
+    .bind(user_id)
+    .bind(from)
+    .bind(to)
+    .bind(organization_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
@@ -0,0 +1,158 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::models::{TrashItem, TrashKind, TrashPurgeSummary};
+
+/// Builds a trash query from one condition per kind; `$1` is the id and `$2` the
+/// organization bound by the caller.
+fn trash_query(team_condition: &str, project_condition: &str, task_condition: &str) -> String {
+    format!(
+        r#"
+        SELECT 'team' AS kind, tm.id, tm.name, tm.id AS team_id, NULL::UUID AS project_id,
+               tm.deleted_at, tm.deleted_by, u.full_name AS deleted_by_name,
+               FALSE AS parent_deleted
+        FROM teams tm
+        LEFT JOIN users u ON u.id = tm.deleted_by
+        WHERE tm.deleted_at IS NOT NULL AND {team_condition}
+        UNION ALL
+        SELECT 'project', p.id, p.name, p.team_id, p.id,
+               p.deleted_at, p.deleted_by, u.full_name,
+               tm.deleted_at IS NOT NULL
+        FROM projects p
+        LEFT JOIN teams tm ON tm.id = p.team_id
+        LEFT JOIN users u ON u.id = p.deleted_by
+        WHERE p.deleted_at IS NOT NULL AND {project_condition}
+        UNION ALL
+        SELECT 'task', t.id, t.title, p.team_id, t.project_id,
+               t.deleted_at, t.deleted_by, u.full_name,
+               p.deleted_at IS NOT NULL OR pt.deleted_at IS NOT NULL
+        FROM tasks t
+        JOIN projects p ON p.id = t.project_id
+        LEFT JOIN tasks pt ON pt.id = t.parent_task_id
+        LEFT JOIN users u ON u.id = t.deleted_by
+        WHERE t.deleted_at IS NOT NULL AND {task_condition}
+        ORDER BY deleted_at DESC
+        "#
+    )
+}
+
+/// Items deleted in a team, leaving out those that went to the trash only
+/// because their team, project or parent task did.
+#[instrument(level = "debug", skip_all)]
+pub async fn list_team_trash(
+    pool: &PgPool,
+    organization_id: Uuid,
+    team_id: Uuid,
+) -> Result<Vec<TrashItem>, sqlx::Error> {
+    let query = trash_query(
+        "tm.id = $1 AND tm.organization_id = $2",
+        "p.team_id = $1 AND p.organization_id = $2 AND p.deleted_at IS DISTINCT FROM tm.deleted_at",
+        "p.team_id = $1 AND t.organization_id = $2 AND t.deleted_at IS DISTINCT FROM p.deleted_at \
+         AND t.deleted_at IS DISTINCT FROM pt.deleted_at",
+    );
+
+    let items = sqlx::query_as::<_, TrashItem>(&query)
+        .bind(team_id)
+        .bind(organization_id)
+        .fetch_all(pool)
+        .await?;
+
+    Ok(items)
+}
+
+/// Like [`list_team_trash`], for a single project (which may have no team).
+#[instrument(level = "debug", skip_all)]
+pub async fn list_project_trash(
+    pool: &PgPool,
+    organization_id: Uuid,
+    project_id: Uuid,
+) -> Result<Vec<TrashItem>, sqlx::Error> {
+    let query = trash_query(
+        "FALSE",
+        "p.id = $1 AND p.organization_id = $2 AND p.deleted_at IS DISTINCT FROM tm.deleted_at",
+        "t.project_id = $1 AND t.organization_id = $2 AND t.deleted_at IS DISTINCT FROM p.deleted_at \
+         AND t.deleted_at IS DISTINCT FROM pt.deleted_at",
+    );
+
+    let items = sqlx::query_as::<_, TrashItem>(&query)
+        .bind(project_id)
+        .bind(organization_id)
+        .fetch_all(pool)
+        .await?;
+
+    Ok(items)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn find_trash_item(
+    pool: &PgPool,
+    organization_id: Uuid,
+    kind: TrashKind,
+    id: Uuid,
+) -> Result<Option<TrashItem>, sqlx::Error> {
+    let query = match kind {
+        TrashKind::Team => trash_query("tm.id = $1 AND tm.organization_id = $2", "FALSE", "FALSE"),
+        TrashKind::Project => trash_query("FALSE", "p.id = $1 AND p.organization_id = $2", "FALSE"),
+        TrashKind::Task => trash_query("FALSE", "FALSE", "t.id = $1 AND t.organization_id = $2"),
+    };
+
+    let item = sqlx::query_as::<_, TrashItem>(&query)
+        .bind(id)
+        .bind(organization_id)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(item)
+}
+
+/// Permanently deletes everything that went to the trash before `cutoff`.
+/// Returns the counts together with the storage keys of the attachments
+/// removed by the cascade, whose blobs the caller still has to delete.
+#[instrument(level = "debug", skip_all)]
+pub async fn purge_trash_before(
+    pool: &PgPool,
+    cutoff: DateTime<Utc>,
+) -> Result<(TrashPurgeSummary, Vec<String>), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let blob_keys = sqlx::query_scalar::<_, String>(
+        r#"
+        SELECT a.storage_key FROM attachments a
+        JOIN tasks t ON t.id = a.task_id
+        JOIN projects p ON p.id = t.project_id
+        LEFT JOIN teams tm ON tm.id = p.team_id
+        WHERE t.deleted_at < $1 OR p.deleted_at < $1 OR tm.deleted_at < $1
+        "#,
+    )
+    .bind(cutoff)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let teams = sqlx::query("DELETE FROM teams WHERE deleted_at < $1")
+        .bind(cutoff)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    let projects = sqlx::query("DELETE FROM projects WHERE deleted_at < $1")
+        .bind(cutoff)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    let tasks = sqlx::query("DELETE FROM tasks WHERE deleted_at < $1")
+        .bind(cutoff)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+    tx.commit().await?;
+
+    let summary = TrashPurgeSummary {
+        teams,
+        projects,
+        tasks,
+        blobs: blob_keys.len(),
+    };
+    Ok((summary, blob_keys))
+}
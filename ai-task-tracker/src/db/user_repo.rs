@@ -1,12 +1,20 @@
-use crate::models::{CreateUserRequest, User};
+use crate::db::add_organization_member;
+use crate::models::{CreateUserRequest, Impersonation, User, UserRole};
+use chrono::{DateTime, Utc};
 use sqlx::PgPool;
+use tracing::instrument;
 use uuid::Uuid;
 
+/// Creates the user as a member of `organization_id`.
+#[instrument(level = "debug", skip_all)]
 pub async fn create_user(
     pool: &PgPool,
     req: &CreateUserRequest,
     password_hash: &str,
+    organization_id: Uuid,
 ) -> Result<User, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
     let user = sqlx::query_as::<_, User>(
         r#"
         INSERT INTO users (email, password_hash, full_name, role)
@@ -18,12 +26,66 @@ pub async fn create_user(
     .bind(password_hash)
     .bind(&req.full_name)
     .bind("member")
-    .fetch_one(pool)
+    .fetch_one(&mut *tx)
     .await?;
 
+    add_organization_member(&mut *tx, organization_id, user.id).await?;
+    tx.commit().await?;
+
     Ok(user)
 }
 
+/// Creates a service account as a member of `organization_id`.
+#[instrument(level = "debug", skip_all)]
+pub async fn create_service_account(
+    pool: &PgPool,
+    full_name: &str,
+    email: Option<&str>,
+    organization_id: Uuid,
+) -> Result<User, sqlx::Error> {
+    let id = Uuid::new_v4();
+    let email = email
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("service-{}@service-accounts.invalid", id));
+    let mut tx = pool.begin().await?;
+
+    let user = sqlx::query_as::<_, User>(
+        r#"
+        INSERT INTO users (id, email, password_hash, full_name, role, is_service_account)
+        VALUES ($1, $2, NULL, $3, 'member', TRUE)
+        RETURNING *
+        "#,
+    )
+    .bind(id)
+    .bind(email)
+    .bind(full_name)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    add_organization_member(&mut *tx, organization_id, user.id).await?;
+    tx.commit().await?;
+
+    Ok(user)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn list_service_accounts(pool: &PgPool, organization_id: Uuid) -> Result<Vec<User>, sqlx::Error> {
+    let users = sqlx::query_as::<_, User>(
+        r#"
+        SELECT * FROM users
+        WHERE is_service_account
+          AND id IN (SELECT user_id FROM organization_members WHERE organization_id = $1)
+        ORDER BY created_at DESC
+        "#,
+    )
+    .bind(organization_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(users)
+}
+
+#[instrument(level = "debug", skip_all)]
 pub async fn find_user_by_email(pool: &PgPool, email: &str) -> Result<Option<User>, sqlx::Error> {
     let user = sqlx::query_as::<_, User>(
         r#"
@@ -37,6 +99,7 @@ pub async fn find_user_by_email(pool: &PgPool, email: &str) -> Result<Option<Use
     Ok(user)
 }
 
+#[instrument(level = "debug", skip_all)]
 pub async fn find_user_by_id(pool: &PgPool, id: Uuid) -> Result<Option<User>, sqlx::Error> {
     let user = sqlx::query_as::<_, User>(
         r#"
@@ -50,18 +113,70 @@ pub async fn find_user_by_id(pool: &PgPool, id: Uuid) -> Result<Option<User>, sq
     Ok(user)
 }
 
-pub async fn list_users(pool: &PgPool) -> Result<Vec<User>, sqlx::Error> {
+#[instrument(level = "debug", skip_all)]
+pub async fn is_platform_admin(pool: &PgPool, id: Uuid) -> Result<bool, sqlx::Error> {
+    let is_platform_admin = sqlx::query_scalar("SELECT is_platform_admin FROM users WHERE id = $1")
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(is_platform_admin.unwrap_or(false))
+}
+
+/// The members of the organization, newest first.
+#[instrument(level = "debug", skip_all)]
+pub async fn list_users(pool: &PgPool, organization_id: Uuid) -> Result<Vec<User>, sqlx::Error> {
+    let users = sqlx::query_as::<_, User>(
+        r#"
+        SELECT * FROM users
+        WHERE id IN (SELECT user_id FROM organization_members WHERE organization_id = $1)
+        ORDER BY created_at DESC
+        "#,
+    )
+    .bind(organization_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(users)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn list_users_by_ids(pool: &PgPool, ids: &[Uuid]) -> Result<Vec<User>, sqlx::Error> {
     let users = sqlx::query_as::<_, User>(
         r#"
-        SELECT * FROM users ORDER BY created_at DESC
+        SELECT * FROM users WHERE id = ANY($1)
         "#,
     )
+    .bind(ids)
     .fetch_all(pool)
     .await?;
 
     Ok(users)
 }
 
+/// Members of the organization with any of the (lowercase) emails.
+#[instrument(level = "debug", skip_all)]
+pub async fn list_users_by_emails(
+    pool: &PgPool,
+    organization_id: Uuid,
+    emails: &[String],
+) -> Result<Vec<User>, sqlx::Error> {
+    let users = sqlx::query_as::<_, User>(
+        r#"
+        SELECT * FROM users
+        WHERE LOWER(email) = ANY($1)
+          AND id IN (SELECT user_id FROM organization_members WHERE organization_id = $2)
+        "#,
+    )
+    .bind(emails)
+    .bind(organization_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(users)
+}
+
+#[instrument(level = "debug", skip_all)]
 pub async fn update_user(
     pool: &PgPool,
     user_id: Uuid,
@@ -74,6 +189,8 @@ pub async fn update_user(
         SET
             full_name = COALESCE($1, full_name),
             password_hash = COALESCE($2, password_hash),
+            -- A new password satisfies a forced reset
+            password_reset_required = password_reset_required AND $2 IS NULL,
             updated_at = NOW()
         WHERE id = $3
         RETURNING *
@@ -87,3 +204,112 @@ pub async fn update_user(
 
     Ok(user)
 }
+
+/// Returns `None` if there is no such user.
+#[instrument(level = "debug", skip_all)]
+pub async fn set_user_role(pool: &PgPool, user_id: Uuid, role: &UserRole) -> Result<Option<User>, sqlx::Error> {
+    let user = sqlx::query_as::<_, User>(
+        r#"
+        UPDATE users SET role = $1, updated_at = NOW()
+        WHERE id = $2
+        RETURNING *
+        "#,
+    )
+    .bind(role.to_string())
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(user)
+}
+
+/// Deactivates or reactivates a user. Deactivating an inactive user keeps the
+/// original time. Returns `None` if there is no such user.
+#[instrument(level = "debug", skip_all)]
+pub async fn set_user_deactivated(pool: &PgPool, user_id: Uuid, deactivated: bool) -> Result<Option<User>, sqlx::Error> {
+    let user = sqlx::query_as::<_, User>(
+        r#"
+        UPDATE users
+        SET deactivated_at = CASE WHEN $1 THEN COALESCE(deactivated_at, NOW()) END,
+            updated_at = NOW()
+        WHERE id = $2
+        RETURNING *
+        "#,
+    )
+    .bind(deactivated)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(user)
+}
+
+/// Returns `None` if there is no such user.
+#[instrument(level = "debug", skip_all)]
+pub async fn require_password_reset(pool: &PgPool, user_id: Uuid) -> Result<Option<User>, sqlx::Error> {
+    let user = sqlx::query_as::<_, User>(
+        r#"
+        UPDATE users SET password_reset_required = TRUE, updated_at = NOW()
+        WHERE id = $1
+        RETURNING *
+        "#,
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(user)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub async fn create_impersonation(
+    pool: &PgPool,
+    organization_id: Uuid,
+    admin_id: Uuid,
+    user_id: Uuid,
+    reason: &str,
+    expires_at: DateTime<Utc>,
+) -> Result<Impersonation, sqlx::Error> {
+    let impersonation = sqlx::query_as::<_, Impersonation>(
+        r#"
+        INSERT INTO impersonations (organization_id, admin_id, user_id, reason, expires_at)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING *
+        "#,
+    )
+    .bind(organization_id)
+    .bind(admin_id)
+    .bind(user_id)
+    .bind(reason)
+    .bind(expires_at)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(impersonation)
+}
+
+/// The organization's impersonations, most recent first, optionally only those
+/// of one impersonated user.
+#[instrument(level = "debug", skip_all)]
+pub async fn list_impersonations(
+    pool: &PgPool,
+    organization_id: Uuid,
+    user_id: Option<Uuid>,
+    limit: i64,
+) -> Result<Vec<Impersonation>, sqlx::Error> {
+    let impersonations = sqlx::query_as::<_, Impersonation>(
+        r#"
+        SELECT * FROM impersonations
+        WHERE organization_id = $1 AND ($2::uuid IS NULL OR user_id = $2)
+        ORDER BY created_at DESC
+        LIMIT $3
+        "#,
+    )
+    .bind(organization_id)
+    .bind(user_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(impersonations)
+}
@@ -0,0 +1,125 @@
+pub mod config;
+pub mod db;
+pub mod middleware;
+pub mod models;
+pub mod openapi;
+pub mod routes;
+pub mod services;
+pub mod storage;
+pub mod telemetry;
+pub mod utils;
+
+use std::sync::Arc;
+
+use actix_cors::Cors;
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceFactory, ServiceRequest, ServiceResponse},
+    web, App,
+};
+use sqlx::PgPool;
+
+use config::Config;
+use db::Repositories;
+use middleware::{AuthMiddleware, RequestMetrics, RequestTracing, REQUEST_ID_HEADER};
+use routes::{
+    admin_user_routes, analytics_routes, api_token_routes, auth_routes, calendar_routes, invitation_routes, label_routes, mfa_routes, notification_routes,
+    configure_health, job_routes, project_routes, sprint_routes, subtask_routes, task_routes, team_routes, template_routes, time_entry_routes,
+    impersonation_routes, timesheet_routes, user_routes, service_account_routes,
+    organization_routes, admin_organization_routes, view_routes,
+};
+use services::{Metrics, OidcClient};
+use storage::BlobStore;
+
+/// What the application shares between server workers (and tests).
+#[derive(Clone)]
+pub struct AppState {
+    pub pool: PgPool,
+    /// What the core handlers read and write through, so tests can swap in memory
+    pub repositories: Repositories,
+    pub config: Config,
+    pub blob_store: Arc<dyn BlobStore>,
+    /// Present when single sign-on is configured
+    pub oidc_client: Option<web::Data<OidcClient>>,
+    pub metrics: Arc<Metrics>,
+}
+
+/// The HTTP application: shared state, middleware and every route.
+pub fn build_app(
+    state: AppState,
+) -> App<
+    impl ServiceFactory<
+        ServiceRequest,
+        Config = (),
+        Response = ServiceResponse<impl MessageBody>,
+        Error = actix_web::Error,
+        InitError = (),
+    >,
+> {
+    let mut cors = Cors::default();
+    for origin in &state.config.cors_allowed_origins {
+        cors = if origin == "*" { cors.allow_any_origin() } else { cors.allowed_origin(origin) };
+    }
+    let cors = cors
+        .allow_any_method()
+        .allow_any_header()
+        .expose_headers([REQUEST_ID_HEADER])
+        .max_age(3600);
+    let jwt = state.config.jwt.clone();
+    let api_docs = state.config.features.api_docs;
+
+    let mut app = App::new()
+        .wrap(cors)
+        .wrap(RequestMetrics { metrics: state.metrics.clone() })
+        .wrap(RequestTracing)
+        .app_data(web::Data::new(state.pool))
+        .app_data(web::Data::from(state.repositories.tasks))
+        .app_data(web::Data::from(state.repositories.projects))
+        .app_data(web::Data::from(state.repositories.teams))
+        .app_data(web::Data::from(state.repositories.users))
+        .app_data(web::Data::new(state.config))
+        .app_data(web::Data::from(state.blob_store))
+        .app_data(web::Data::from(state.metrics));
+    if let Some(oidc_client) = state.oidc_client {
+        app = app.app_data(oidc_client);
+    }
+
+    app.configure(configure_health).service(
+        web::scope("/api")
+            // Public routes (no auth required)
+            .configure(|cfg| {
+                if api_docs {
+                    openapi::configure_openapi(cfg)
+                }
+            })
+            .service(auth_routes())
+            .service(calendar_routes())
+            // Protected routes (auth required)
+            .service(
+                web::scope("")
+                    .wrap(AuthMiddleware { jwt })
+                    .service(user_routes())
+                    .service(organization_routes())
+                    .service(api_token_routes())
+                    .service(service_account_routes())
+                    .service(mfa_routes())
+                    .service(team_routes())
+                    .service(invitation_routes())
+                    .service(notification_routes())
+                    .service(project_routes())
+                    .service(task_routes())
+                    .service(view_routes())
+                    .service(label_routes())
+                    .service(time_entry_routes())
+                    .service(timesheet_routes())
+                    .service(sprint_routes())
+                    .service(template_routes())
+                    .service(subtask_routes())
+                    .service(analytics_routes())
+                    .service(job_routes())
+                    .service(admin_user_routes())
+                    .service(impersonation_routes())
+                    .service(admin_organization_routes()),
+            ),
+    )
+}
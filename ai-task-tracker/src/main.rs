@@ -1,78 +1,101 @@
-mod config;
-mod db;
-mod middleware;
-mod models;
-mod routes;
-mod utils;
+use std::sync::Arc;
 
-use actix_cors::Cors;
-use actix_web::{middleware::Logger, web, App, HttpServer};
+use actix_web::{web, HttpServer};
 use dotenv::dotenv;
 
-use config::Config;
-use middleware::AuthMiddleware;
-use routes::{auth_routes, project_routes, subtask_routes, task_routes, team_routes, user_routes};
+use ai_task_tracker::{
+    build_app,
+    config::Config,
+    db, services, storage, telemetry, AppState,
+};
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     // Load environment variables
     dotenv().ok();
-    env_logger::init();
 
     // Load configuration
-    let config = Config::from_env().expect("Failed to load configuration");
+    let config = Config::load().unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(2);
+    });
+    let telemetry = telemetry::init_telemetry(&config.log).unwrap_or_else(|e| {
+        eprintln!("Failed to set up span export: {}", e);
+        std::process::exit(2);
+    });
+    if let Some(endpoint) = &config.log.otlp_endpoint {
+        log::info!("Exporting spans to {}", endpoint);
+    }
     log::info!("Starting server at {}", config.server_address());
 
     // Create database connection pool
-    let pool = db::create_pool(&config.database_url)
+    let pool = db::create_pool(&config.database)
         .await
         .expect("Failed to create database pool");
 
     log::info!("Database connection pool created");
 
     // Run migrations
-    sqlx::migrate!("./migrations")
-        .run(&pool)
+    db::run_migrations(&pool)
         .await
         .expect("Failed to run migrations");
 
     log::info!("Database migrations completed");
 
-    let server_address = config.server_address();
-    let jwt_secret = config.jwt_secret.clone();
+    let blob_store = storage::blob_store_from_config(&config.storage)
+        .expect("Failed to configure attachment storage");
+    log::info!("Attachment storage backend: {}", config.storage.backend);
+
+    let oidc_client = config.oidc.clone().map(|oidc| web::Data::new(services::OidcClient::new(oidc)));
+    if let Some(oidc) = &config.oidc {
+        log::info!("Single sign-on enabled with issuer {}", oidc.issuer_url);
+    }
+
+    let metrics = Arc::new(services::Metrics::new());
 
-    // Start HTTP server
-    HttpServer::new(move || {
-        let cors = Cors::default()
-            .allow_any_origin()
-            .allow_any_method()
-            .allow_any_header()
-            .max_age(3600);
+    // Job types are registered here as features start using the queue;
+    // recurring ones queue their first run, and then each run queues the next
+    let jobs = services::JobRegistry::new()
+        .register::<services::PurgeTrash>()
+        .register::<services::PollMailbox>();
+    if config.features.trash_purge {
+        services::enqueue_once_at(&pool, &services::PurgeTrash, chrono::Utc::now())
+            .await
+            .expect("Failed to schedule the trash purge");
+    }
+    if let Some(imap) = &config.imap {
+        log::info!("Polling {} on {} for inbound email", imap.mailbox, imap.host);
+        services::enqueue_once_at(&pool, &services::PollMailbox, chrono::Utc::now())
+            .await
+            .expect("Failed to schedule mailbox polling");
+    }
+    let job_context = services::JobContext {
+        pool: pool.clone(),
+        config: config.clone(),
+        blob_store: blob_store.clone(),
+        metrics: metrics.clone(),
+    };
+    let workers = services::JobRunner::new(job_context, jobs).spawn(&config.jobs);
+    log::info!("Started {} job workers", config.jobs.workers);
+
+    let server_address = config.server_address();
+    let state = AppState {
+        repositories: db::Repositories::postgres(pool.clone()),
+        pool,
+        config,
+        blob_store,
+        oidc_client,
+        metrics,
+    };
 
-        App::new()
-            .wrap(cors)
-            .wrap(Logger::default())
-            .app_data(web::Data::new(pool.clone()))
-            .app_data(web::Data::new(config.clone()))
-            .service(
-                web::scope("/api")
-                    // Public routes (no auth required)
-                    .service(auth_routes())
-                    // Protected routes (auth required)
-                    .service(
-                        web::scope("")
-                            .wrap(AuthMiddleware {
-                                jwt_secret: jwt_secret.clone(),
-                            })
-                            .service(user_routes())
-                            .service(team_routes())
-                            .service(project_routes())
-                            .service(task_routes())
-                            .service(subtask_routes()),
-                    ),
-            )
-    })
-    .bind(&server_address)?
-    .run()
-    .await
+    // Start HTTP server; on SIGINT or SIGTERM it stops taking connections and
+    // finishes the requests in flight, then the job workers get the same chance
+    let served = HttpServer::new(move || build_app(state.clone()))
+        .bind(&server_address)?
+        .run()
+        .await;
+    log::info!("Server stopped, waiting for running jobs");
+    workers.shutdown().await;
+    telemetry.shutdown().await;
+    served
 }
@@ -1,19 +1,26 @@
 use actix_web::{
-    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
-    Error, HttpMessage,
+    dev::{forward_ready, Payload, Service, ServiceRequest, ServiceResponse, Transform},
+    http::Method,
+    web, Error, FromRequest, HttpMessage, HttpRequest,
 };
 use futures_util::future::LocalBoxFuture;
+use sqlx::PgPool;
 use std::future::{ready, Ready};
+use std::rc::Rc;
+use uuid::Uuid;
 
-use crate::utils::verify_jwt;
+use crate::config::JwtConfig;
+use crate::db::{authenticate_api_token, scoped_to_organization, UserRepository};
+use crate::models::{ApiScope, MfaPending, User};
+use crate::utils::{hash_api_token, verify_jwt, Claims, API_TOKEN_PREFIX};
 
 pub struct AuthMiddleware {
-    pub jwt_secret: String,
+    pub jwt: JwtConfig,
 }
 
 impl<S, B> Transform<S, ServiceRequest> for AuthMiddleware
 where
-    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
     S::Future: 'static,
     B: 'static,
 {
@@ -25,20 +32,74 @@ where
 
     fn new_transform(&self, service: S) -> Self::Future {
         ready(Ok(AuthMiddlewareService {
-            service,
-            jwt_secret: self.jwt_secret.clone(),
+            service: Rc::new(service),
+            jwt: self.jwt.clone(),
         }))
     }
 }
 
 pub struct AuthMiddlewareService<S> {
-    service: S,
-    jwt_secret: String,
+    service: Rc<S>,
+    jwt: JwtConfig,
+}
+
+fn under(path: &str, prefix: &str) -> bool {
+    path == prefix || path.starts_with(&format!("{}/", prefix))
+}
+
+/// The scope a personal access token needs for a request. Managing tokens,
+/// service accounts and user accounts needs `admin`, so a token cannot mint
+/// itself broader ones or change passwords, as does anything under `/api/admin`.
+fn required_scope(method: &Method, path: &str) -> ApiScope {
+    let read_only = method == Method::GET || method == Method::HEAD;
+
+    if under(path, "/api/tokens")
+        || under(path, "/api/service-accounts")
+        || under(path, "/api/admin")
+        || (!read_only && under(path, "/api/users"))
+    {
+        ApiScope::Admin
+    } else if read_only {
+        ApiScope::ReadTasks
+    } else {
+        ApiScope::WriteTasks
+    }
+}
+
+/// Whether an admin impersonating a user may make a request. Anything that would
+/// outlast the session or touch the account's credentials is off limits, as is
+/// trading the session for one in another organization.
+fn impersonation_allowed(method: &Method, path: &str) -> bool {
+    let read_only = method == Method::GET || method == Method::HEAD;
+
+    !(under(path, "/api/tokens")
+        || under(path, "/api/service-accounts")
+        || under(path, "/api/mfa")
+        || under(path, "/api/admin")
+        || (!read_only && under(path, "/api/users"))
+        || (!read_only && under(path, "/api/organizations")))
+}
+
+/// Whether a user who must change their password may make a request: only to
+/// look themselves up and to set the new password.
+fn password_reset_allowed(user: &User, method: &Method, path: &str) -> bool {
+    (method == Method::GET && path == "/api/users/me")
+        || (method == Method::PUT && path == format!("/api/users/{}", user.id))
+}
+
+/// Whether a token from an unfinished login may be used for a request. Only
+/// users who must set up two-factor authentication get anywhere: to enrollment.
+fn mfa_pending_allowed(pending: Option<MfaPending>, method: &Method, path: &str) -> bool {
+    match pending {
+        None => true,
+        Some(MfaPending::Enroll) => method == Method::POST && under(path, "/api/mfa/totp"),
+        Some(MfaPending::Verify) => false,
+    }
 }
 
 impl<S, B> Service<ServiceRequest> for AuthMiddlewareService<S>
 where
-    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
     S::Future: 'static,
     B: 'static,
 {
@@ -49,7 +110,7 @@ where
     forward_ready!(service);
 
     fn call(&self, req: ServiceRequest) -> Self::Future {
-        let jwt_secret = self.jwt_secret.clone();
+        let service = self.service.clone();
 
         // Extract token from Authorization header
         let auth_header = req.headers().get("Authorization");
@@ -57,36 +118,164 @@ where
         let token = match auth_header {
             Some(header_value) => {
                 let header_str = header_value.to_str().unwrap_or("");
-                if header_str.starts_with("Bearer ") {
-                    Some(header_str[7..].to_string())
-                } else {
-                    None
-                }
+                header_str.strip_prefix("Bearer ").map(|t| t.to_string())
             }
             None => None,
         };
 
-        if let Some(token) = token {
-            match verify_jwt(&token, &jwt_secret) {
-                Ok(claims) => {
-                    // Store claims in request extensions
-                    req.extensions_mut().insert(claims);
-                    let fut = self.service.call(req);
-                    Box::pin(async move {
-                        let res = fut.await?;
-                        Ok(res)
-                    })
-                }
-                Err(_) => {
-                    Box::pin(async move {
-                        Err(actix_web::error::ErrorUnauthorized("Invalid token"))
-                    })
+        let Some(token) = token else {
+            return Box::pin(async move {
+                Err(actix_web::error::ErrorUnauthorized("Missing authorization token"))
+            });
+        };
+
+        // Personal access tokens are looked up in the database rather than verified
+        if token.starts_with(API_TOKEN_PREFIX) {
+            let Some(pool) = req.app_data::<web::Data<PgPool>>().cloned() else {
+                return Box::pin(async move {
+                    Err(actix_web::error::ErrorInternalServerError("Database unavailable"))
+                });
+            };
+
+            return Box::pin(async move {
+                let identity = match authenticate_api_token(&pool, &hash_api_token(&token)).await {
+                    Ok(Some(identity)) => identity,
+                    Ok(None) => return Err(actix_web::error::ErrorUnauthorized("Invalid token")),
+                    Err(e) => {
+                        log::error!("Database error: {}", e);
+                        return Err(actix_web::error::ErrorInternalServerError("Internal server error"));
+                    }
+                };
+
+                let required = required_scope(req.method(), req.path());
+                let granted = identity
+                    .scopes
+                    .iter()
+                    .filter_map(|s| s.parse::<ApiScope>().ok())
+                    .any(|scope| scope.grants(&required));
+                if !granted {
+                    return Err(actix_web::error::ErrorForbidden(format!(
+                        "Token lacks the {} scope",
+                        required
+                    )));
                 }
+
+                let span = tracing::Span::current();
+                span.record("user_id", tracing::field::display(identity.user_id));
+                span.record("organization_id", tracing::field::display(identity.organization_id));
+                req.extensions_mut().insert(Claims {
+                    sub: identity.user_id.to_string(),
+                    email: identity.email,
+                    role: identity.role,
+                    exp: identity.expires_at.map_or(i64::MAX, |t| t.timestamp()),
+                    iss: None,
+                    aud: None,
+                    scopes: Some(identity.scopes),
+                    mfa_pending: None,
+                    impersonator: None,
+                    org_id: Some(identity.organization_id),
+                });
+                scoped_to_organization(identity.organization_id, service.call(req)).await
+            });
+        }
+
+        let claims = match verify_jwt(&token, &self.jwt) {
+            Ok(claims) => claims,
+            Err(_) => {
+                return Box::pin(async move {
+                    Err(actix_web::error::ErrorUnauthorized("Invalid token"))
+                });
             }
-        } else {
-            Box::pin(async move {
-                Err(actix_web::error::ErrorUnauthorized("Missing authorization token"))
-            })
+        };
+        if !mfa_pending_allowed(claims.mfa_pending, req.method(), req.path()) {
+            return Box::pin(async move {
+                Err(actix_web::error::ErrorUnauthorized("Two-factor authentication required"))
+            });
+        }
+        if claims.impersonator.is_some() && !impersonation_allowed(req.method(), req.path()) {
+            return Box::pin(async move {
+                Err(actix_web::error::ErrorForbidden("Not available while impersonating a user"))
+            });
         }
+        let Some(users) = req.app_data::<web::Data<dyn UserRepository>>().cloned() else {
+            return Box::pin(async move {
+                Err(actix_web::error::ErrorInternalServerError("Database unavailable"))
+            });
+        };
+
+        Box::pin(async move {
+            // A session outlives changes to its account, so the account is checked
+            // on every request and its current role replaces the one in the token
+            let user = match Uuid::parse_str(&claims.sub) {
+                Ok(user_id) => users.find_user_by_id(user_id).await,
+                Err(_) => Ok(None),
+            };
+            let user = match user {
+                Ok(Some(user)) => user,
+                Ok(None) => return Err(actix_web::error::ErrorUnauthorized("Invalid token")),
+                Err(e) => {
+                    log::error!("Database error: {}", e);
+                    return Err(actix_web::error::ErrorInternalServerError("Internal server error"));
+                }
+            };
+            if user.deactivated_at.is_some() {
+                return Err(actix_web::error::ErrorForbidden("Account is deactivated"));
+            }
+            // Tokens from an unfinished login are restricted already, and an admin
+            // impersonating the user cannot change their password for them
+            let own_session = claims.mfa_pending.is_none() && claims.impersonator.is_none();
+            if own_session && user.password_reset_required && !password_reset_allowed(&user, req.method(), req.path()) {
+                return Err(actix_web::error::ErrorForbidden("Password reset required"));
+            }
+
+            // Leaving the organization ends the sessions in it
+            let organization_id = match users.organization_ids(user.id).await {
+                Ok(memberships) => match claims.org_id {
+                    Some(id) if memberships.contains(&id) => id,
+                    Some(_) => return Err(actix_web::error::ErrorForbidden("Not a member of this organization")),
+                    None => match memberships.first() {
+                        Some(id) => *id,
+                        None => return Err(actix_web::error::ErrorForbidden("Not a member of any organization")),
+                    },
+                },
+                Err(e) => {
+                    log::error!("Database error: {}", e);
+                    return Err(actix_web::error::ErrorInternalServerError("Internal server error"));
+                }
+            };
+
+            let span = tracing::Span::current();
+            span.record("user_id", tracing::field::display(user.id));
+            if let Some(impersonator) = &claims.impersonator {
+                span.record("impersonator_id", tracing::field::display(impersonator));
+            }
+            span.record("organization_id", tracing::field::display(organization_id));
+            req.extensions_mut().insert(Claims {
+                role: user.role,
+                org_id: Some(organization_id),
+                ..claims
+            });
+            scoped_to_organization(organization_id, service.call(req)).await
+        })
+    }
+}
+
+/// The organization of the authenticated caller, as resolved by
+/// [`AuthMiddleware`]. Rejects requests that did not pass through it.
+#[derive(Debug, Clone, Copy)]
+pub struct CurrentOrganization {
+    pub id: Uuid,
+}
+
+impl FromRequest for CurrentOrganization {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let id = req.extensions().get::<Claims>().and_then(|claims| claims.org_id);
+        ready(
+            id.map(|id| CurrentOrganization { id })
+                .ok_or_else(|| actix_web::error::ErrorUnauthorized("Unauthorized")),
+        )
     }
 }
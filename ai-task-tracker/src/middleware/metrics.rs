@@ -0,0 +1,84 @@
+use actix_web::{
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    Error,
+};
+use futures_util::future::LocalBoxFuture;
+use std::future::{ready, Ready};
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::services::Metrics;
+
+/// Counts and times every request, labelled by the route pattern it matched
+/// (`/api/tasks/{id}`) so ids do not each get their own series. Requests that
+/// never reach a route, such as unknown paths or failed authentication, are
+/// labelled `unmatched`.
+pub struct RequestMetrics {
+    pub metrics: Arc<Metrics>,
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RequestMetrics
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RequestMetricsService<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequestMetricsService {
+            service: Rc::new(service),
+            metrics: self.metrics.clone(),
+        }))
+    }
+}
+
+pub struct RequestMetricsService<S> {
+    service: Rc<S>,
+    metrics: Arc<Metrics>,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestMetricsService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let metrics = self.metrics.clone();
+        let method = req.method().to_string();
+        let started = Instant::now();
+
+        Box::pin(async move {
+            let result = service.call(req).await;
+
+            let (route, status) = match &result {
+                Ok(res) => (res.request().match_pattern(), res.status()),
+                Err(e) => (None, e.as_response_error().status_code()),
+            };
+            let route = route.unwrap_or_else(|| "unmatched".to_string());
+            metrics
+                .http_requests
+                .with_label_values(&[&method, &route, status.as_str()])
+                .inc();
+            metrics
+                .http_request_duration
+                .with_label_values(&[&method, &route])
+                .observe(started.elapsed().as_secs_f64());
+
+            result
+        })
+    }
+}
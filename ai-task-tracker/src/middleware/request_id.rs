@@ -0,0 +1,118 @@
+use actix_web::{
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    error::InternalError,
+    http::header::{HeaderName, HeaderValue},
+    Error, HttpMessage,
+};
+use futures_util::future::LocalBoxFuture;
+use std::future::{ready, Ready};
+use std::rc::Rc;
+use tracing::{
+    field::{display, Empty},
+    Instrument,
+};
+use uuid::Uuid;
+
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// The id a request is logged under, available from the request extensions.
+#[derive(Debug, Clone)]
+pub struct RequestId(pub String);
+
+/// A caller's id is kept if it looks like one; anything else is replaced.
+fn acceptable(id: &str) -> bool {
+    !id.is_empty() && id.len() <= 128 && id.bytes().all(|b| b.is_ascii_graphic())
+}
+
+/// Runs each request inside a span carrying its request id, which is taken
+/// from `X-Request-Id` or generated, and echoed back in the same header. The
+/// auth middleware adds the user id (and that of an impersonating admin) and
+/// the organization to the span once the caller is known.
+pub struct RequestTracing;
+
+impl<S, B> Transform<S, ServiceRequest> for RequestTracing
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RequestTracingService<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequestTracingService {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct RequestTracingService<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestTracingService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+
+        let request_id = req
+            .headers()
+            .get(&REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .filter(|id| acceptable(id))
+            .map(str::to_string)
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+        let header_value = HeaderValue::from_str(&request_id).expect("request ids are visible ASCII");
+
+        let span = tracing::info_span!(
+            "request",
+            request_id = %request_id,
+            method = %req.method(),
+            path = %req.path(),
+            route = Empty,
+            user_id = Empty,
+            impersonator_id = Empty,
+            organization_id = Empty,
+            status = Empty,
+        );
+        req.extensions_mut().insert(RequestId(request_id));
+
+        Box::pin(
+            async move {
+                let span = tracing::Span::current();
+                match service.call(req).await {
+                    Ok(mut res) => {
+                        if let Some(route) = res.request().match_pattern() {
+                            span.record("route", display(route));
+                        }
+                        span.record("status", res.status().as_u16());
+                        res.headers_mut().insert(REQUEST_ID_HEADER, header_value);
+                        Ok(res)
+                    }
+                    // Middleware rejections never reach a response here, so the
+                    // header goes on the response the error will become
+                    Err(e) => {
+                        let mut res = e.error_response();
+                        span.record("status", res.status().as_u16());
+                        res.headers_mut().insert(REQUEST_ID_HEADER, header_value);
+                        Err(InternalError::from_response(e, res).into())
+                    }
+                }
+            }
+            .instrument(span),
+        )
+    }
+}
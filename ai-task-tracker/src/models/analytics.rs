@@ -0,0 +1,56 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::{IntoParams, ToSchema};
+
+/// Common filters for analytics endpoints. Dates are inclusive and default to the last 12 weeks.
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct AnalyticsQuery {
+    pub project_id: Option<Uuid>,
+    pub team_id: Option<Uuid>,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+#[derive(Debug, Serialize, FromRow, ToSchema)]
+pub struct AssigneeWorkload {
+    pub assignee_id: Option<Uuid>,
+    pub full_name: Option<String>,
+    pub email: Option<String>,
+    pub low: i64,
+    pub medium: i64,
+    pub high: i64,
+    pub urgent: i64,
+    pub total: i64,
+}
+
+#[derive(Debug, Serialize, FromRow, ToSchema)]
+pub struct ThroughputBucket {
+    pub week_start: NaiveDate,
+    pub completed: i64,
+}
+
+#[derive(Debug, Serialize, FromRow, ToSchema)]
+pub struct CycleTimeBucket {
+    pub week_start: NaiveDate,
+    pub completed: i64,
+    pub avg_hours: Option<f64>,
+    pub median_hours: Option<f64>,
+}
+
+#[derive(Debug, Serialize, FromRow, ToSchema)]
+pub struct BurndownPoint {
+    pub date: NaiveDate,
+    pub total: i64,
+    pub completed: i64,
+    pub remaining: i64,
+}
+
+#[derive(Debug, Serialize, FromRow, ToSchema)]
+pub struct OverdueBucket {
+    pub assignee_id: Option<Uuid>,
+    pub full_name: Option<String>,
+    pub overdue: i64,
+    pub due_this_week: i64,
+}
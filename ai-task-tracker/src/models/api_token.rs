@@ -0,0 +1,100 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::ToSchema;
+
+/// A token as listed, without the hash it is stored under.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ApiToken {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub organization_id: Uuid,
+    pub name: String,
+    /// The first characters of the token, to tell tokens apart in listings
+    pub token_prefix: String,
+    pub scopes: Vec<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub enum ApiScope {
+    #[serde(rename = "read:tasks")]
+    ReadTasks,
+    #[serde(rename = "write:tasks")]
+    WriteTasks,
+    #[serde(rename = "admin")]
+    Admin,
+}
+
+impl ApiScope {
+    /// Whether holding `self` is enough for a request that needs `required`.
+    pub fn grants(&self, required: &ApiScope) -> bool {
+        match self {
+            ApiScope::Admin => true,
+            ApiScope::WriteTasks => matches!(required, ApiScope::WriteTasks | ApiScope::ReadTasks),
+            ApiScope::ReadTasks => *required == ApiScope::ReadTasks,
+        }
+    }
+}
+
+impl std::fmt::Display for ApiScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ApiScope::ReadTasks => write!(f, "read:tasks"),
+            ApiScope::WriteTasks => write!(f, "write:tasks"),
+            ApiScope::Admin => write!(f, "admin"),
+        }
+    }
+}
+
+impl std::str::FromStr for ApiScope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "read:tasks" => Ok(ApiScope::ReadTasks),
+            "write:tasks" => Ok(ApiScope::WriteTasks),
+            "admin" => Ok(ApiScope::Admin),
+            _ => Err(format!("Invalid token scope: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateApiTokenRequest {
+    pub name: String,
+    pub scopes: Vec<ApiScope>,
+    /// Omit for a token that never expires
+    pub expires_in_days: Option<i64>,
+}
+
+/// Returned once at creation; the plain token cannot be retrieved later.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CreatedApiToken {
+    #[serde(flatten)]
+    pub api_token: ApiToken,
+    pub token: String,
+}
+
+/// The user and scopes behind a valid token.
+#[derive(Debug, Clone, FromRow)]
+pub struct ApiTokenIdentity {
+    pub user_id: Uuid,
+    pub organization_id: Uuid,
+    pub email: String,
+    pub role: String,
+    pub scopes: Vec<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateServiceAccountRequest {
+    pub full_name: String,
+    /// Defaults to an address derived from the account id
+    pub email: Option<String>,
+}
@@ -0,0 +1,32 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Attachment {
+    pub id: Uuid,
+    pub task_id: Uuid,
+    pub uploaded_by: Option<Uuid>,
+    pub file_name: String,
+    pub content_type: String,
+    pub size_bytes: i64,
+    #[serde(skip_serializing)]
+    pub storage_key: String,
+    pub checksum_sha256: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Metadata for a blob that has already been written to storage.
+#[derive(Debug)]
+pub struct NewAttachment {
+    pub id: Uuid,
+    pub task_id: Uuid,
+    pub uploaded_by: Option<Uuid>,
+    pub file_name: String,
+    pub content_type: String,
+    pub size_bytes: i64,
+    pub storage_key: String,
+    pub checksum_sha256: String,
+}
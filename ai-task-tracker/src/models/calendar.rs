@@ -0,0 +1,34 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct CalendarToken {
+    pub user_id: Uuid,
+    pub token: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CalendarFeedResponse {
+    pub token: String,
+    pub url: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A task with a due date, as it appears in a calendar feed.
+#[derive(Debug, Clone, FromRow)]
+pub struct CalendarTask {
+    pub id: Uuid,
+    pub project_name: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub status: String,
+    pub priority: String,
+    pub progress_percent: i32,
+    pub due_date: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
@@ -0,0 +1,64 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Comment {
+    pub id: Uuid,
+    pub task_id: Uuid,
+    pub parent_comment_id: Option<Uuid>,
+    pub author_id: Option<Uuid>,
+    pub body: String,
+    pub body_html: String,
+    pub created_at: DateTime<Utc>,
+    pub edited_at: Option<DateTime<Utc>>,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateCommentRequest {
+    pub body: String,
+    pub parent_comment_id: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateCommentRequest {
+    pub body: String,
+}
+
+/// A rendered comment ready to be stored.
+#[derive(Debug)]
+pub struct NewComment {
+    pub task_id: Uuid,
+    pub parent_comment_id: Option<Uuid>,
+    pub author_id: Uuid,
+    pub body: String,
+    pub body_html: String,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct CommentWithAuthor {
+    pub id: Uuid,
+    pub task_id: Uuid,
+    pub parent_comment_id: Option<Uuid>,
+    pub author_id: Option<Uuid>,
+    pub author_name: Option<String>,
+    pub author_email: Option<String>,
+    pub body: String,
+    pub body_html: String,
+    pub mentions: Vec<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub edited_at: Option<DateTime<Utc>>,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+/// A comment together with its replies, oldest first.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CommentThread {
+    #[serde(flatten)]
+    pub comment: CommentWithAuthor,
+    #[schema(no_recursion)]
+    pub replies: Vec<CommentThread>,
+}
@@ -2,23 +2,52 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
+use utoipa::ToSchema;
 
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+/// An inbound email. Replies to a thread that started a task are `processed`
+/// straight away and threaded onto it through `task_id`.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct EmailLog {
     pub id: Uuid,
     pub sender: String,
     pub subject: Option<String>,
+    /// Plain text, with HTML converted and quoted replies stripped
     pub body_text: Option<String>,
     pub received_at: DateTime<Utc>,
     pub processed: bool,
     pub created_task_id: Option<Uuid>,
     pub created_at: DateTime<Utc>,
+    /// Without the angle brackets
+    pub message_id: Option<String>,
+    pub in_reply_to: Option<String>,
+    /// The task this email is a reply on
+    pub task_id: Option<Uuid>,
+    /// The organization of its task; none for emails on no task
+    pub organization_id: Option<Uuid>,
 }
 
-#[derive(Debug, Deserialize)]
-pub struct IngestEmailRequest {
+/// An email as parsed from the mailbox, before it is stored.
+#[derive(Debug, Clone)]
+pub struct InboundEmail {
     pub sender: String,
     pub subject: Option<String>,
     pub body_text: Option<String>,
-    pub received_at: Option<DateTime<Utc>>,
+    pub received_at: DateTime<Utc>,
+    pub message_id: Option<String>,
+    pub in_reply_to: Option<String>,
+    /// The earlier messages of the thread, oldest first
+    pub references: Vec<String>,
+}
+
+impl InboundEmail {
+    /// Message-IDs of the messages this one replies to, closest first.
+    pub fn thread_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self.in_reply_to.iter().cloned().collect();
+        for id in self.references.iter().rev() {
+            if !ids.contains(id) {
+                ids.push(id.clone());
+            }
+        }
+        ids
+    }
 }
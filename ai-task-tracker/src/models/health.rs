@@ -0,0 +1,11 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// Result of the readiness probe; `pending_migrations` lists versions the
+/// database has not run yet.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReadinessReport {
+    pub ready: bool,
+    pub database: bool,
+    pub pending_migrations: Vec<i64>,
+}
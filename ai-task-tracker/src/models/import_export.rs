@@ -0,0 +1,95 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use utoipa::ToSchema;
+
+use super::{CreateTaskRequest, Project, TaskStatus};
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub enum TransferFormat {
+    #[serde(rename = "csv")]
+    Csv,
+    #[serde(rename = "json")]
+    Json,
+}
+
+impl std::str::FromStr for TransferFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "csv" => Ok(TransferFormat::Csv),
+            "json" => Ok(TransferFormat::Json),
+            _ => Err(format!("Invalid format: {}", s)),
+        }
+    }
+}
+
+/// Full JSON export of a project: every task with its subtasks and progress history.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ProjectExport {
+    pub project: Project,
+    pub exported_at: DateTime<Utc>,
+    pub tasks: Vec<ExportedTask>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ExportedTask {
+    pub id: Uuid,
+    pub parent_task_id: Option<Uuid>,
+    pub title: String,
+    pub description: Option<String>,
+    pub status: String,
+    pub priority: String,
+    pub progress_percent: i32,
+    pub estimate_minutes: Option<i32>,
+    pub assignee_email: Option<String>,
+    pub due_date: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub subtasks: Vec<ExportedSubtask>,
+    pub history: Vec<ExportedHistory>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ExportedSubtask {
+    pub title: String,
+    pub is_completed: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ExportedHistory {
+    pub user_email: String,
+    pub comment: String,
+    pub completion_percentage: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A validated import row, ready to be inserted.
+#[derive(Debug)]
+pub struct ImportedTask {
+    /// Identifier of the row within the import file, used to resolve `parent_ref`.
+    pub reference: Option<String>,
+    pub parent_ref: Option<String>,
+    pub task: CreateTaskRequest,
+    pub status: Option<TaskStatus>,
+    pub progress_percent: Option<i32>,
+    pub subtasks: Vec<ExportedSubtask>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImportRowError {
+    pub row: usize,
+    pub field: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub total_rows: usize,
+    pub valid_rows: usize,
+    pub skipped_rows: usize,
+    pub created_tasks: usize,
+    pub created_subtasks: usize,
+    pub errors: Vec<ImportRowError>,
+}
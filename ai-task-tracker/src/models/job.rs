@@ -0,0 +1,73 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::{IntoParams, ToSchema};
+
+/// A background job as stored in the queue.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Job {
+    pub id: Uuid,
+    /// Which job type runs it, e.g. `send_reminder`
+    pub kind: String,
+    #[schema(value_type = Object)]
+    pub payload: serde_json::Value,
+    pub status: String,
+    pub attempts: i32,
+    pub max_attempts: i32,
+    /// Not run before this time; after a failure, when the next attempt is due
+    pub run_at: DateTime<Utc>,
+    /// The claim holding a running job; only its worker records the outcome
+    pub locked_by: Option<Uuid>,
+    pub locked_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+pub enum JobStatus {
+    #[serde(rename = "pending")]
+    Pending,
+    #[serde(rename = "running")]
+    Running,
+    #[serde(rename = "completed")]
+    Completed,
+    /// Failed on every attempt; only an admin retry runs it again
+    #[serde(rename = "dead")]
+    Dead,
+}
+
+impl std::fmt::Display for JobStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JobStatus::Pending => write!(f, "pending"),
+            JobStatus::Running => write!(f, "running"),
+            JobStatus::Completed => write!(f, "completed"),
+            JobStatus::Dead => write!(f, "dead"),
+        }
+    }
+}
+
+impl std::str::FromStr for JobStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "pending" => Ok(JobStatus::Pending),
+            "running" => Ok(JobStatus::Running),
+            "completed" => Ok(JobStatus::Completed),
+            "dead" => Ok(JobStatus::Dead),
+            _ => Err(format!("Invalid job status: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct JobQuery {
+    /// `dead` (default), `pending`, `running` or `completed`
+    pub status: Option<String>,
+    pub kind: Option<String>,
+    pub limit: Option<i64>,
+}
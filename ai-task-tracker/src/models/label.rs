@@ -0,0 +1,64 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Label {
+    pub id: Uuid,
+    pub team_id: Option<Uuid>,
+    pub project_id: Option<Uuid>,
+    pub name: String,
+    pub color: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateLabelRequest {
+    pub name: String,
+    pub color: Option<String>,
+    pub team_id: Option<Uuid>,
+    pub project_id: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateLabelRequest {
+    pub name: Option<String>,
+    pub color: Option<String>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct LabelQuery {
+    pub team_id: Option<Uuid>,
+    pub project_id: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AddTaskLabelRequest {
+    pub label_id: Uuid,
+}
+
+/// How multiple label filters on a task list combine.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum LabelMatch {
+    /// Tasks carrying every listed label.
+    #[default]
+    All,
+    /// Tasks carrying at least one listed label.
+    Any,
+}
+
+impl std::str::FromStr for LabelMatch {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "all" => Ok(LabelMatch::All),
+            "any" => Ok(LabelMatch::Any),
+            _ => Err(format!("Invalid label match mode: {}", s)),
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+use super::{AuthResponse, UserRole};
+
+/// What a login still needs after the password was accepted.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum MfaPending {
+    /// Enter a code from the enrolled authenticator (or a recovery code)
+    Verify,
+    /// The user's role requires two-factor authentication, which is not set up yet
+    Enroll,
+}
+
+#[derive(Debug, Serialize, FromRow, ToSchema)]
+pub struct MfaStatus {
+    pub enabled: bool,
+    /// Whether the user's role requires two-factor authentication
+    pub required: bool,
+    pub recovery_codes_remaining: i64,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct TotpState {
+    pub totp_secret: Option<String>,
+    pub totp_enabled_at: Option<DateTime<Utc>>,
+    pub mfa_locked_until: Option<DateTime<Utc>>,
+}
+
+/// Returned by a password login that needs a second factor. `mfa_token` is
+/// short-lived and only good for finishing the login.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MfaChallengeResponse {
+    pub mfa_required: MfaPending,
+    pub mfa_token: String,
+}
+
+/// What a password login returns: a session, or a challenge to finish first.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(untagged)]
+pub enum LoginResponse {
+    Session(AuthResponse),
+    MfaChallenge(MfaChallengeResponse),
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct VerifyMfaRequest {
+    pub mfa_token: String,
+    /// A code from the authenticator app, or a recovery code
+    pub code: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TotpEnrollment {
+    pub secret: String,
+    pub otpauth_uri: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct MfaCodeRequest {
+    pub code: String,
+}
+
+/// Recovery codes are only ever shown here. When enrollment finishes a
+/// pending login, the session is included too.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RecoveryCodesResponse {
+    pub recovery_codes: Vec<String>,
+    #[serde(flatten)]
+    pub session: Option<AuthResponse>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct MfaPolicy {
+    pub required_roles: Vec<UserRole>,
+}
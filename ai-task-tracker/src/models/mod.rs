@@ -5,10 +5,49 @@ pub mod task;
 pub mod subtask;
 pub mod email_log;
 pub mod task_history;
+pub mod task_activity;
+pub mod analytics;
+pub mod import_export;
+pub mod calendar;
+pub mod attachment;
+pub mod comment;
+pub mod notification;
+pub mod label;
+pub mod time_entry;
+pub mod sprint;
+pub mod trash;
+pub mod template;
+pub mod api_token;
+pub mod oidc;
+pub mod mfa;
+pub mod health;
+pub mod job;
+pub mod organization;
+pub mod saved_view;
 
 pub use user::*;
 pub use team::*;
 pub use project::*;
 pub use task::*;
 pub use subtask::*;
+pub use email_log::*;
 pub use task_history::*;
+pub use task_activity::*;
+pub use analytics::*;
+pub use import_export::*;
+pub use calendar::*;
+pub use attachment::*;
+pub use comment::*;
+pub use notification::*;
+pub use label::*;
+pub use time_entry::*;
+pub use sprint::*;
+pub use trash::*;
+pub use template::*;
+pub use api_token::*;
+pub use oidc::*;
+pub use mfa::*;
+pub use health::*;
+pub use job::*;
+pub use organization::*;
+pub use saved_view::*;
@@ -0,0 +1,47 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Notification {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub kind: String,
+    pub actor_id: Option<Uuid>,
+    pub task_id: Option<Uuid>,
+    pub comment_id: Option<Uuid>,
+    pub message: String,
+    pub read_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NotificationKind {
+    Mention,
+    Reply,
+}
+
+impl std::fmt::Display for NotificationKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NotificationKind::Mention => write!(f, "mention"),
+            NotificationKind::Reply => write!(f, "reply"),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct NotificationQuery {
+    pub unread: Option<bool>,
+    pub limit: Option<i64>,
+}
+
+/// A notification to create alongside a comment write.
+#[derive(Debug)]
+pub struct NewNotification {
+    pub user_id: Uuid,
+    pub kind: NotificationKind,
+    pub message: String,
+}
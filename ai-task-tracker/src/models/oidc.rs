@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+/// An authorization request waiting for its callback.
+#[derive(Debug, Clone, FromRow)]
+pub struct OidcLoginState {
+    pub state: String,
+    pub nonce: String,
+    pub code_verifier: String,
+}
+
+/// Where to send the browser to sign in; the provider redirects back to
+/// `OIDC_REDIRECT_URL` with `code` and `state`.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct OidcAuthorizeResponse {
+    pub authorization_url: String,
+    pub state: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct OidcCallbackRequest {
+    pub code: String,
+    pub state: String,
+}
@@ -0,0 +1,63 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::ToSchema;
+
+use super::User;
+
+/// The organization created by the migration that introduced them. Users who
+/// sign up on their own join it.
+pub const DEFAULT_ORGANIZATION_SLUG: &str = "default";
+
+/// A tenant: owns users, teams and projects, none of which another
+/// organization's members can see.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Organization {
+    pub id: Uuid,
+    pub name: String,
+    /// Lowercase letters, digits and single hyphens
+    pub slug: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateOrganizationRequest {
+    pub name: String,
+    pub slug: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct InviteOrganizationMemberRequest {
+    pub user_id: Uuid,
+}
+
+/// An offer for an existing user to join an organization, which only takes
+/// effect once they accept it.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct OrganizationInvitation {
+    pub id: Uuid,
+    pub organization_id: Uuid,
+    pub user_id: Uuid,
+    /// `pending`, `accepted` or `declined`
+    pub status: String,
+    pub invited_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub responded_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct OrganizationMember {
+    pub organization_id: Uuid,
+    pub user_id: Uuid,
+    pub joined_at: DateTime<Utc>,
+}
+
+/// A session in another of the user's organizations.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SwitchOrganizationResponse {
+    pub token: String,
+    pub user: User,
+    pub organization: Organization,
+}
@@ -0,0 +1,158 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{types::Json, FromRow};
+use uuid::Uuid;
+use utoipa::ToSchema;
+
+use super::{LabelMatch, SortOrder, Task, TaskFilter, TaskPriority, TaskSortField, TaskStatus};
+
+/// The built-in "My Tasks" view: open it with `GET /api/views/{id}/tasks`.
+pub const MY_TASKS_VIEW_ID: Uuid = Uuid::from_u128(1);
+
+/// The tasks a view shows; unset fields don't restrict them. Relative fields
+/// are resolved each time the view runs.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct ViewFilter {
+    pub project_id: Option<Uuid>,
+    pub assignee_id: Option<Uuid>,
+    /// Tasks assigned to whoever runs the view; takes precedence over `assignee_id`
+    pub assigned_to_me: bool,
+    pub status: Option<TaskStatus>,
+    pub priority: Option<TaskPriority>,
+    pub sprint_id: Option<Uuid>,
+    pub label_ids: Vec<Uuid>,
+    pub label_match: LabelMatch,
+    /// Tasks due within this many days of running the view, overdue ones included
+    pub due_within_days: Option<i32>,
+}
+
+impl ViewFilter {
+    /// The task filter for `user_id` running the view at `now`.
+    pub fn to_task_filter(&self, user_id: Uuid, now: DateTime<Utc>) -> TaskFilter {
+        TaskFilter {
+            project_id: self.project_id,
+            assignee_id: if self.assigned_to_me { Some(user_id) } else { self.assignee_id },
+            status: self.status.as_ref().map(|s| s.to_string()),
+            priority: self.priority.as_ref().map(|p| p.to_string()),
+            parent_task_id: None,
+            sprint_id: self.sprint_id,
+            label_ids: self.label_ids.clone(),
+            label_match: self.label_match,
+            due_before: self.due_within_days.map(|days| now + Duration::days(days.into())),
+            ..TaskFilter::default()
+        }
+    }
+}
+
+/// Task field the results of a view are grouped by.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ViewGroupBy {
+    Status,
+    Priority,
+    Assignee,
+    Project,
+}
+
+impl std::fmt::Display for ViewGroupBy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ViewGroupBy::Status => write!(f, "status"),
+            ViewGroupBy::Priority => write!(f, "priority"),
+            ViewGroupBy::Assignee => write!(f, "assignee"),
+            ViewGroupBy::Project => write!(f, "project"),
+        }
+    }
+}
+
+impl std::str::FromStr for ViewGroupBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "status" => Ok(ViewGroupBy::Status),
+            "priority" => Ok(ViewGroupBy::Priority),
+            "assignee" => Ok(ViewGroupBy::Assignee),
+            "project" => Ok(ViewGroupBy::Project),
+            _ => Err(format!("Invalid grouping: {}", s)),
+        }
+    }
+}
+
+/// A saved task list. Personal views have a `user_id` and team views a
+/// `team_id`; built-in views have neither, nor an organization.
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct SavedView {
+    pub id: Uuid,
+    pub organization_id: Option<Uuid>,
+    pub name: String,
+    pub user_id: Option<Uuid>,
+    pub team_id: Option<Uuid>,
+    #[schema(value_type = ViewFilter)]
+    pub filter: Json<ViewFilter>,
+    #[schema(value_type = TaskSortField)]
+    pub sort_by: String,
+    #[schema(value_type = SortOrder)]
+    pub sort_order: String,
+    #[schema(value_type = Option<ViewGroupBy>)]
+    pub group_by: Option<String>,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl SavedView {
+    pub fn is_builtin(&self) -> bool {
+        self.organization_id.is_none()
+    }
+
+    /// The task filter for `user_id` running the view at `now`, sorted as saved.
+    pub fn task_filter(&self, user_id: Uuid, now: DateTime<Utc>) -> TaskFilter {
+        TaskFilter {
+            sort_by: self.sort_by.parse().unwrap_or_default(),
+            sort_order: self.sort_order.parse().unwrap_or_default(),
+            ..self.filter.to_task_filter(user_id, now)
+        }
+    }
+}
+
+/// Creates a view; it is personal unless `team_id` shares it with a team the
+/// caller belongs to.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateSavedViewRequest {
+    pub name: String,
+    pub team_id: Option<Uuid>,
+    #[serde(default)]
+    pub filter: ViewFilter,
+    pub sort_by: Option<TaskSortField>,
+    pub sort_order: Option<SortOrder>,
+    pub group_by: Option<ViewGroupBy>,
+}
+
+/// Replaces a view's definition; omitted fields reset to their defaults.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateSavedViewRequest {
+    pub name: String,
+    #[serde(default)]
+    pub filter: ViewFilter,
+    pub sort_by: Option<TaskSortField>,
+    pub sort_order: Option<SortOrder>,
+    pub group_by: Option<ViewGroupBy>,
+}
+
+/// Tasks sharing a value of the view's grouping field. `key` is the status,
+/// priority, assignee id or project id, and null for unassigned tasks or an
+/// ungrouped view.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TaskGroup {
+    pub key: Option<String>,
+    pub tasks: Vec<Task>,
+}
+
+/// The result of running a view. Groups come in the order of their first task.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ViewTasks {
+    pub view: SavedView,
+    pub groups: Vec<TaskGroup>,
+}
@@ -0,0 +1,196 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Sprint {
+    pub id: Uuid,
+    pub project_id: Uuid,
+    pub name: String,
+    pub goal: Option<String>,
+    pub kind: String,
+    pub status: String,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub started_at: Option<DateTime<Utc>>,
+    pub closed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub enum SprintKind {
+    #[serde(rename = "sprint")]
+    Sprint,
+    #[serde(rename = "milestone")]
+    Milestone,
+}
+
+impl std::fmt::Display for SprintKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SprintKind::Sprint => write!(f, "sprint"),
+            SprintKind::Milestone => write!(f, "milestone"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub enum SprintStatus {
+    #[serde(rename = "planned")]
+    Planned,
+    #[serde(rename = "active")]
+    Active,
+    #[serde(rename = "closed")]
+    Closed,
+}
+
+impl std::fmt::Display for SprintStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SprintStatus::Planned => write!(f, "planned"),
+            SprintStatus::Active => write!(f, "active"),
+            SprintStatus::Closed => write!(f, "closed"),
+        }
+    }
+}
+
+impl std::str::FromStr for SprintStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "planned" => Ok(SprintStatus::Planned),
+            "active" => Ok(SprintStatus::Active),
+            "closed" => Ok(SprintStatus::Closed),
+            _ => Err(format!("Invalid sprint status: {}", s)),
+        }
+    }
+}
+
+impl Sprint {
+    pub fn status(&self) -> SprintStatus {
+        self.status.parse().unwrap_or(SprintStatus::Planned)
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateSprintRequest {
+    pub project_id: Uuid,
+    pub name: String,
+    pub goal: Option<String>,
+    pub kind: Option<SprintKind>,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateSprintRequest {
+    pub name: Option<String>,
+    pub goal: Option<String>,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct SprintQuery {
+    pub project_id: Option<Uuid>,
+    pub status: Option<SprintStatus>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SprintTasksRequest {
+    pub task_ids: Vec<Uuid>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SetSprintCapacityRequest {
+    pub user_id: Uuid,
+    pub capacity_minutes: i32,
+}
+
+/// Where unfinished tasks go when a sprint closes: a specific sprint, the next
+/// planned sprint of the project, or (if neither is set) back to the backlog.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CloseSprintRequest {
+    pub move_unfinished_to: Option<Uuid>,
+    #[serde(default)]
+    pub move_to_next: bool,
+}
+
+#[derive(Debug, Serialize, FromRow, ToSchema)]
+pub struct SprintCapacity {
+    pub user_id: Uuid,
+    pub user_name: String,
+    pub user_email: String,
+    pub capacity_minutes: i32,
+}
+
+/// A task's membership in a sprint. `outcome` is `completed`, `spilled` or
+/// `removed` once resolved, and `open` while the task is still in a running sprint.
+#[derive(Debug, Serialize, FromRow, ToSchema)]
+pub struct SprintTaskOutcome {
+    pub task_id: Uuid,
+    pub title: String,
+    pub status: String,
+    pub assignee_id: Option<Uuid>,
+    pub estimate_minutes: Option<i32>,
+    pub added_after_start: bool,
+    pub outcome: String,
+}
+
+#[derive(Debug, Serialize, FromRow, ToSchema)]
+pub struct SprintMemberLoad {
+    pub user_id: Uuid,
+    pub user_name: String,
+    pub user_email: String,
+    pub capacity_minutes: Option<i32>,
+    pub assigned_minutes: i64,
+    pub completed_minutes: i64,
+    pub logged_minutes: i64,
+}
+
+#[derive(Debug, Default, Serialize, ToSchema)]
+pub struct SprintTotals {
+    pub tasks: usize,
+    pub estimate_minutes: i64,
+}
+
+impl SprintTotals {
+    pub fn add(&mut self, task: &SprintTaskOutcome) {
+        self.tasks += 1;
+        self.estimate_minutes += task.estimate_minutes.unwrap_or(0) as i64;
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SprintReport {
+    pub sprint: Sprint,
+    /// In the sprint when it started.
+    pub committed: SprintTotals,
+    /// Added after the sprint started.
+    pub added: SprintTotals,
+    pub completed: SprintTotals,
+    pub removed: SprintTotals,
+    /// Unfinished when the sprint closed.
+    pub spillover: SprintTotals,
+    /// Still open in a running or planned sprint.
+    pub remaining: SprintTotals,
+    pub members: Vec<SprintMemberLoad>,
+    pub tasks: Vec<SprintTaskOutcome>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SprintClosePreview {
+    pub unfinished_tasks: Vec<SprintTaskOutcome>,
+    pub suggested_next_sprint: Option<Sprint>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SprintCloseResult {
+    pub report: SprintReport,
+    pub moved_to: Option<Uuid>,
+    pub moved_task_ids: Vec<Uuid>,
+}
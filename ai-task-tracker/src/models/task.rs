@@ -2,10 +2,14 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
+use utoipa::ToSchema;
 
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+use super::LabelMatch;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct Task {
     pub id: Uuid,
+    pub organization_id: Uuid,
     pub project_id: Uuid,
     pub parent_task_id: Option<Uuid>,
     pub title: String,
@@ -16,11 +20,13 @@ pub struct Task {
     pub assignee_id: Option<Uuid>,
     pub due_date: Option<DateTime<Utc>>,
     pub source_email_id: Option<String>,
+    pub estimate_minutes: Option<i32>,
+    pub sprint_id: Option<Uuid>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub enum TaskStatus {
     #[serde(rename = "todo")]
     Todo,
@@ -53,7 +59,7 @@ impl std::str::FromStr for TaskStatus {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub enum TaskPriority {
     #[serde(rename = "low")]
     Low,
@@ -90,7 +96,7 @@ impl std::str::FromStr for TaskPriority {
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct CreateTaskRequest {
     pub project_id: Uuid,
     pub parent_task_id: Option<Uuid>,
@@ -99,9 +105,10 @@ pub struct CreateTaskRequest {
     pub priority: Option<TaskPriority>,
     pub assignee_id: Option<Uuid>,
     pub due_date: Option<DateTime<Utc>>,
+    pub estimate_minutes: Option<i32>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct UpdateTaskRequest {
     pub title: Option<String>,
     pub description: Option<String>,
@@ -110,9 +117,97 @@ pub struct UpdateTaskRequest {
     pub assignee_id: Option<Uuid>,
     pub due_date: Option<DateTime<Utc>>,
     pub progress_percent: Option<i32>,
+    pub estimate_minutes: Option<i32>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct UpdateProgressRequest {
     pub progress_percent: i32,
 }
+
+/// Field a task list is ordered by; ties go to the newest task.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskSortField {
+    #[default]
+    CreatedAt,
+    UpdatedAt,
+    /// Tasks without a due date come last either way
+    DueDate,
+    /// From `low` to `urgent` when ascending
+    Priority,
+    Title,
+}
+
+impl std::fmt::Display for TaskSortField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TaskSortField::CreatedAt => write!(f, "created_at"),
+            TaskSortField::UpdatedAt => write!(f, "updated_at"),
+            TaskSortField::DueDate => write!(f, "due_date"),
+            TaskSortField::Priority => write!(f, "priority"),
+            TaskSortField::Title => write!(f, "title"),
+        }
+    }
+}
+
+impl std::str::FromStr for TaskSortField {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "created_at" => Ok(TaskSortField::CreatedAt),
+            "updated_at" => Ok(TaskSortField::UpdatedAt),
+            "due_date" => Ok(TaskSortField::DueDate),
+            "priority" => Ok(TaskSortField::Priority),
+            "title" => Ok(TaskSortField::Title),
+            _ => Err(format!("Invalid sort field: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+impl std::fmt::Display for SortOrder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SortOrder::Asc => write!(f, "asc"),
+            SortOrder::Desc => write!(f, "desc"),
+        }
+    }
+}
+
+impl std::str::FromStr for SortOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "asc" => Ok(SortOrder::Asc),
+            "desc" => Ok(SortOrder::Desc),
+            _ => Err(format!("Invalid sort order: {}", s)),
+        }
+    }
+}
+
+/// Filters for listing tasks; unset fields don't restrict the result.
+#[derive(Debug, Default)]
+pub struct TaskFilter {
+    pub project_id: Option<Uuid>,
+    pub assignee_id: Option<Uuid>,
+    pub status: Option<String>,
+    pub priority: Option<String>,
+    pub parent_task_id: Option<Uuid>,
+    pub sprint_id: Option<Uuid>,
+    pub label_ids: Vec<Uuid>,
+    pub label_match: LabelMatch,
+    /// Tasks due at or before this time, overdue ones included
+    pub due_before: Option<DateTime<Utc>>,
+    pub sort_by: TaskSortField,
+    pub sort_order: SortOrder,
+}
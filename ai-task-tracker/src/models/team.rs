@@ -2,10 +2,12 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
+use utoipa::ToSchema;
 
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct Team {
     pub id: Uuid,
+    pub organization_id: Uuid,
     pub name: String,
     pub parent_team_id: Option<Uuid>,
     pub manager_id: Option<Uuid>,
@@ -13,34 +15,95 @@ pub struct Team {
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct CreateTeamRequest {
     pub name: String,
     pub parent_team_id: Option<Uuid>,
     pub manager_id: Option<Uuid>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct UpdateTeamRequest {
     pub name: Option<String>,
     pub parent_team_id: Option<Uuid>,
     pub manager_id: Option<Uuid>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct TeamMember {
     pub team_id: Uuid,
     pub user_id: Uuid,
     pub joined_at: DateTime<Utc>,
+    pub role: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+pub enum TeamRole {
+    #[serde(rename = "owner")]
+    Owner,
+    #[serde(rename = "maintainer")]
+    Maintainer,
+    #[serde(rename = "member")]
+    Member,
+    #[serde(rename = "viewer")]
+    Viewer,
 }
 
-#[derive(Debug, Deserialize)]
+impl TeamRole {
+    /// Owners and maintainers may add, remove and re-role members.
+    pub fn can_manage_members(&self) -> bool {
+        matches!(self, TeamRole::Owner | TeamRole::Maintainer)
+    }
+
+    /// Owners and maintainers may rename the team or move it.
+    pub fn can_edit_team(&self) -> bool {
+        matches!(self, TeamRole::Owner | TeamRole::Maintainer)
+    }
+
+    /// Only owners may move the team to the trash.
+    pub fn can_delete_team(&self) -> bool {
+        matches!(self, TeamRole::Owner)
+    }
+}
+
+impl std::fmt::Display for TeamRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TeamRole::Owner => write!(f, "owner"),
+            TeamRole::Maintainer => write!(f, "maintainer"),
+            TeamRole::Member => write!(f, "member"),
+            TeamRole::Viewer => write!(f, "viewer"),
+        }
+    }
+}
+
+impl std::str::FromStr for TeamRole {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "owner" => Ok(TeamRole::Owner),
+            "maintainer" => Ok(TeamRole::Maintainer),
+            "member" => Ok(TeamRole::Member),
+            "viewer" => Ok(TeamRole::Viewer),
+            _ => Err(format!("Invalid team role: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct AddTeamMemberRequest {
     pub user_id: Option<Uuid>,
     pub email: Option<String>,
+    pub role: Option<TeamRole>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateTeamMemberRequest {
+    pub role: TeamRole,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct TeamMemberWithUser {
     pub team_id: Uuid,
     pub user_id: Uuid,
@@ -48,4 +111,19 @@ pub struct TeamMemberWithUser {
     pub full_name: String,
     pub email: String,
     pub role: String,
+    pub team_role: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct TeamInvitation {
+    pub id: Uuid,
+    pub team_id: Uuid,
+    pub organization_id: Uuid,
+    pub email: String,
+    pub role: String,
+    pub status: String,
+    pub invited_by: Option<Uuid>,
+    pub invitee_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub responded_at: Option<DateTime<Utc>>,
 }
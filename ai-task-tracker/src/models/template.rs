@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::{IntoParams, ToSchema};
+
+use super::{Project, TaskPriority, TeamRole};
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ProjectTemplate {
+    pub id: Uuid,
+    pub organization_id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub team_id: Option<Uuid>,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct TemplateTask {
+    pub id: Uuid,
+    pub template_id: Uuid,
+    pub parent_id: Option<Uuid>,
+    pub position: i32,
+    pub title: String,
+    pub description: Option<String>,
+    pub priority: String,
+    pub estimate_minutes: Option<i32>,
+    pub due_offset_days: Option<i32>,
+    pub assignee_role: Option<String>,
+    pub subtasks: Vec<String>,
+}
+
+/// A task in a template, with its child tasks nested under `children`.
+/// `due_offset_days` is counted from the start date given when instantiating,
+/// and `assignee_role` picks a member of the target team with that role.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TemplateTaskInput {
+    pub title: String,
+    pub description: Option<String>,
+    pub priority: Option<TaskPriority>,
+    pub estimate_minutes: Option<i32>,
+    pub due_offset_days: Option<i32>,
+    pub assignee_role: Option<TeamRole>,
+    #[serde(default)]
+    pub subtasks: Vec<String>,
+    #[serde(default)]
+    #[schema(no_recursion)]
+    pub children: Vec<TemplateTaskInput>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateTemplateRequest {
+    pub name: String,
+    pub description: Option<String>,
+    pub team_id: Option<Uuid>,
+    #[serde(default)]
+    pub tasks: Vec<TemplateTaskInput>,
+}
+
+/// Replaces the whole task tree when `tasks` is present.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateTemplateRequest {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub tasks: Option<Vec<TemplateTaskInput>>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct TemplateQuery {
+    pub team_id: Option<Uuid>,
+}
+
+/// Captures an existing project; due dates become offsets from `start_date`,
+/// which defaults to the day the project was created.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SaveProjectAsTemplateRequest {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub start_date: Option<NaiveDate>,
+}
+
+/// Creates a new project from the template, or adds the template's tasks to
+/// `project_id` when given. `assignees` overrides the member picked for a role.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct InstantiateTemplateRequest {
+    pub project_id: Option<Uuid>,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub team_id: Option<Uuid>,
+    pub start_date: Option<NaiveDate>,
+    #[serde(default)]
+    pub assignees: HashMap<TeamRole, Uuid>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TemplateWithTasks {
+    #[serde(flatten)]
+    pub template: ProjectTemplate,
+    pub tasks: Vec<TemplateTaskInput>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct InstantiateTemplateResult {
+    pub project: Project,
+    pub created_tasks: usize,
+    pub created_subtasks: usize,
+}
+
+/// Walks a task tree depth-first, pairing each task with the index of its parent
+/// in the returned list. Parents always come before their children.
+pub fn flatten_template_tasks(tasks: &[TemplateTaskInput]) -> Vec<(Option<usize>, &TemplateTaskInput)> {
+    let mut flat = Vec::new();
+    let mut stack: Vec<(Option<usize>, &TemplateTaskInput)> = tasks.iter().rev().map(|t| (None, t)).collect();
+
+    while let Some((parent, task)) = stack.pop() {
+        let index = flat.len();
+        flat.push((parent, task));
+        stack.extend(task.children.iter().rev().map(|child| (Some(index), child)));
+    }
+
+    flat
+}
@@ -0,0 +1,81 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct TimeEntry {
+    pub id: Uuid,
+    pub task_id: Uuid,
+    pub user_id: Uuid,
+    pub started_at: DateTime<Utc>,
+    /// `None` while the timer is running.
+    pub ended_at: Option<DateTime<Utc>>,
+    pub duration_minutes: Option<i32>,
+    pub note: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct StartTimerRequest {
+    pub task_id: Uuid,
+    pub note: Option<String>,
+}
+
+/// Logs work after the fact; give either `ended_at` or `duration_minutes`.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateTimeEntryRequest {
+    pub task_id: Uuid,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: Option<DateTime<Utc>>,
+    pub duration_minutes: Option<i32>,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateTimeEntryRequest {
+    pub started_at: Option<DateTime<Utc>>,
+    pub ended_at: Option<DateTime<Utc>>,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct TimeEntryQuery {
+    pub task_id: Option<Uuid>,
+    pub user_id: Option<Uuid>,
+    pub project_id: Option<Uuid>,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct TimesheetQuery {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    pub format: Option<String>,
+}
+
+/// Logged minutes for one user on one task on one (UTC) day.
+#[derive(Debug, Serialize, FromRow, ToSchema)]
+pub struct TimesheetRow {
+    pub date: NaiveDate,
+    pub user_id: Uuid,
+    pub user_name: String,
+    pub user_email: String,
+    pub project_id: Uuid,
+    pub project_name: String,
+    pub task_id: Uuid,
+    pub task_title: String,
+    pub estimate_minutes: Option<i32>,
+    pub minutes: i64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Timesheet {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub total_minutes: i64,
+    pub rows: Vec<TimesheetRow>,
+}
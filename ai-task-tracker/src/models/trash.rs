@@ -0,0 +1,61 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TrashKind {
+    Team,
+    Project,
+    Task,
+}
+
+impl std::fmt::Display for TrashKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TrashKind::Team => write!(f, "team"),
+            TrashKind::Project => write!(f, "project"),
+            TrashKind::Task => write!(f, "task"),
+        }
+    }
+}
+
+/// A soft-deleted team, project or task. `parent_deleted` is set while the
+/// containing team, project or parent task is itself in the trash.
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct TrashItem {
+    pub kind: String,
+    pub id: Uuid,
+    pub name: String,
+    pub team_id: Option<Uuid>,
+    pub project_id: Option<Uuid>,
+    pub deleted_at: DateTime<Utc>,
+    pub deleted_by: Option<Uuid>,
+    pub deleted_by_name: Option<String>,
+    pub parent_deleted: bool,
+}
+
+/// A trash item together with when the retention job will purge it.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TrashEntry {
+    #[serde(flatten)]
+    pub item: TrashItem,
+    pub purge_after: DateTime<Utc>,
+}
+
+impl TrashEntry {
+    pub fn new(item: TrashItem, retention_days: i64) -> Self {
+        let purge_after = item.deleted_at + chrono::Duration::days(retention_days);
+        TrashEntry { item, purge_after }
+    }
+}
+
+#[derive(Debug, Default, Serialize, ToSchema)]
+pub struct TrashPurgeSummary {
+    pub teams: u64,
+    pub projects: u64,
+    pub tasks: u64,
+    pub blobs: usize,
+}
@@ -2,20 +2,26 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
+use utoipa::{IntoParams, ToSchema};
 
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct User {
     pub id: Uuid,
     pub email: String,
     #[serde(skip_serializing)]
-    pub password_hash: String,
+    pub password_hash: Option<String>,
     pub full_name: String,
     pub role: String,
+    pub is_service_account: bool,
+    /// Set while an admin has the account switched off
+    pub deactivated_at: Option<DateTime<Utc>>,
+    /// The user must change their password before doing anything else
+    pub password_reset_required: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
 pub enum UserRole {
     #[serde(rename = "admin")]
     Admin,
@@ -48,27 +54,65 @@ impl std::str::FromStr for UserRole {
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct CreateUserRequest {
     pub email: String,
     pub password: String,
     pub full_name: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct LoginRequest {
     pub email: String,
     pub password: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct AuthResponse {
     pub token: String,
     pub user: User,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct UpdateUserRequest {
     pub full_name: Option<String>,
     pub password: Option<String>,
 }
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateUserRoleRequest {
+    pub role: UserRole,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ImpersonateUserRequest {
+    /// Why support needs to act as the user, e.g. a ticket reference; kept in the audit trail
+    pub reason: String,
+}
+
+/// An admin acting as another user, as recorded in the audit trail.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Impersonation {
+    pub id: Uuid,
+    pub organization_id: Uuid,
+    pub admin_id: Uuid,
+    pub user_id: Uuid,
+    pub reason: String,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ImpersonationQuery {
+    /// Only impersonations of this user
+    pub user_id: Option<Uuid>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImpersonationResponse {
+    /// A session token for the user, valid until `expires_at`
+    pub token: String,
+    pub user: User,
+    pub impersonation: Impersonation,
+}
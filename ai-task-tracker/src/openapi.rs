@@ -0,0 +1,145 @@
+use actix_web::{get, web, HttpResponse, Responder};
+use serde::Serialize;
+use utoipa::{
+    openapi::security::{Http, HttpAuthScheme, SecurityScheme},
+    Modify, OpenApi, ToSchema,
+};
+
+use crate::routes::{
+    AdminOrganizationApi, AdminUserApi, AnalyticsApi, ApiTokenApi, AuthApi, CalendarApi, HealthApi, ImpersonationApi,
+    InvitationApi, JobApi, LabelApi, MfaApi, NotificationApi, OrganizationApi, ProjectApi, SavedViewApi,
+    ServiceAccountApi, SprintApi, SubtaskApi, TaskApi, TeamApi, TemplateApi, TimeEntryApi, TimesheetApi, UserApi,
+};
+
+/// Body of every error response.
+#[derive(Debug, Serialize, ToSchema)]
+#[allow(dead_code)] // Handlers build their errors with `json!`; this only documents the shape
+pub struct ErrorResponse {
+    pub error: String,
+}
+
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(Http::builder().scheme(HttpAuthScheme::Bearer).bearer_format("JWT").build()),
+        );
+    }
+}
+
+/// The probes live at the root rather than under `/api`, so they are merged
+/// in instead of nested.
+struct HealthProbes;
+
+impl Modify for HealthProbes {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        openapi.merge(HealthApi::openapi());
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "AI Task Tracker API",
+        description = "Requests are authenticated with a bearer token: a session JWT from login, or a \
+                       personal access token (`pat_...`). Endpoints that need no token say so.",
+        license(name = "MIT")
+    ),
+    nest(
+        (path = "/api/auth", api = AuthApi, tags = ["auth"]),
+        (path = "/api/calendar", api = CalendarApi, tags = ["calendar"]),
+        (path = "/api/users", api = UserApi, tags = ["users"]),
+        (path = "/api/organizations", api = OrganizationApi, tags = ["organizations"]),
+        (path = "/api/tokens", api = ApiTokenApi, tags = ["tokens"]),
+        (path = "/api/service-accounts", api = ServiceAccountApi, tags = ["service accounts"]),
+        (path = "/api/mfa", api = MfaApi, tags = ["mfa"]),
+        (path = "/api/teams", api = TeamApi, tags = ["teams"]),
+        (path = "/api/invitations", api = InvitationApi, tags = ["invitations"]),
+        (path = "/api/notifications", api = NotificationApi, tags = ["notifications"]),
+        (path = "/api/projects", api = ProjectApi, tags = ["projects"]),
+        (path = "/api/tasks", api = TaskApi, tags = ["tasks"]),
+        (path = "/api/views", api = SavedViewApi, tags = ["views"]),
+        (path = "/api/labels", api = LabelApi, tags = ["labels"]),
+        (path = "/api/time-entries", api = TimeEntryApi, tags = ["time entries"]),
+        (path = "/api/timesheets", api = TimesheetApi, tags = ["timesheets"]),
+        (path = "/api/sprints", api = SprintApi, tags = ["sprints"]),
+        (path = "/api/templates", api = TemplateApi, tags = ["templates"]),
+        (path = "/api/subtasks", api = SubtaskApi, tags = ["subtasks"]),
+        (path = "/api/analytics", api = AnalyticsApi, tags = ["analytics"]),
+        (path = "/api/admin/jobs", api = JobApi, tags = ["jobs"]),
+        (path = "/api/admin/users", api = AdminUserApi, tags = ["admin"]),
+        (path = "/api/admin/impersonations", api = ImpersonationApi, tags = ["admin"]),
+        (path = "/api/admin/organizations", api = AdminOrganizationApi, tags = ["admin"]),
+    ),
+    components(schemas(ErrorResponse)),
+    modifiers(&BearerAuth, &HealthProbes),
+    security(("bearer" = [])),
+)]
+pub struct ApiDoc;
+
+/// The specification as served and checked in.
+pub fn openapi_json() -> String {
+    ApiDoc::openapi().to_pretty_json().expect("OpenAPI document serializes")
+}
+
+/// Redoc page for the specification. The viewer script comes from a CDN, so
+/// only the page itself is served from here.
+const DOCS_PAGE: &str = r#"<!DOCTYPE html>
+<html>
+  <head>
+    <title>AI Task Tracker API</title>
+    <meta charset="utf-8" />
+    <meta name="viewport" content="width=device-width, initial-scale=1" />
+  </head>
+  <body>
+    <redoc spec-url="/api/openapi.json"></redoc>
+    <script src="https://cdn.redoc.ly/redoc/v2.1.5/bundles/redoc.standalone.js"></script>
+  </body>
+</html>
+"#;
+
+#[get("/openapi.json")]
+async fn openapi_spec_handler() -> impl Responder {
+    HttpResponse::Ok().content_type("application/json").body(openapi_json())
+}
+
+#[get("/docs")]
+async fn api_docs_handler() -> impl Responder {
+    HttpResponse::Ok().content_type("text/html; charset=utf-8").body(DOCS_PAGE)
+}
+
+/// Public routes serving the specification and its documentation page.
+pub fn configure_openapi(cfg: &mut web::ServiceConfig) {
+    cfg.service(openapi_spec_handler).service(api_docs_handler);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Where the generated specification is checked in, relative to the crate root.
+    const OPENAPI_SPEC_PATH: &str = "docs/openapi.json";
+
+    /// Fails when the routes or models change without regenerating the checked-in
+    /// spec. Run with `UPDATE_OPENAPI=1` to rewrite it.
+    #[test]
+    fn checked_in_spec_is_up_to_date() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join(OPENAPI_SPEC_PATH);
+        let generated = openapi_json() + "\n";
+
+        if std::env::var_os("UPDATE_OPENAPI").is_some() {
+            std::fs::write(&path, &generated).expect("write OpenAPI spec");
+            return;
+        }
+
+        let checked_in = std::fs::read_to_string(&path).unwrap_or_default();
+        assert!(
+            checked_in == generated,
+            "{} is out of date; regenerate it with `UPDATE_OPENAPI=1 cargo test checked_in_spec_is_up_to_date`",
+            OPENAPI_SPEC_PATH
+        );
+    }
+}
@@ -0,0 +1,371 @@
+use actix_web::{get, post, put, web, HttpRequest, HttpResponse, Responder};
+use chrono::{Duration, Utc};
+use sqlx::PgPool;
+use utoipa::OpenApi;
+use uuid::Uuid;
+
+use crate::{
+    config::Config,
+    db::{
+        create_impersonation, find_user_by_id, is_organization_member, list_impersonations,
+        list_user_organization_ids, require_password_reset, set_user_deactivated, set_user_role,
+    },
+    middleware::CurrentOrganization,
+    models::{
+        Impersonation, ImpersonationQuery, ImpersonationResponse, ImpersonateUserRequest, UpdateUserRoleRequest,
+        User, UserRole,
+    },
+    openapi::ErrorResponse,
+    routes::helpers::{internal_error, require_admin},
+    utils::create_impersonation_jwt,
+};
+
+/// How long an impersonation session lasts; it cannot be renewed.
+const IMPERSONATION_MINUTES: i64 = 60;
+
+const DEFAULT_IMPERSONATION_LIMIT: i64 = 50;
+const MAX_IMPERSONATION_LIMIT: i64 = 500;
+
+/// Admins cannot lock themselves out, or leave the instance without an admin.
+fn not_self(admin_id: Uuid, user_id: Uuid, action: &str) -> Result<(), Box<HttpResponse>> {
+    if admin_id == user_id {
+        return Err(Box::new(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("You cannot {} yourself", action)
+        }))));
+    }
+    Ok(())
+}
+
+fn user_not_found() -> HttpResponse {
+    HttpResponse::NotFound().json(serde_json::json!({
+        "error": "User not found"
+    }))
+}
+
+/// Admins only manage the users of the organization they are working in;
+/// anyone else is reported as not found.
+async fn require_member(pool: &PgPool, organization_id: Uuid, user_id: Uuid) -> Result<(), HttpResponse> {
+    match is_organization_member(pool, organization_id, user_id).await {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(user_not_found()),
+        Err(e) => Err(internal_error(e)),
+    }
+}
+
+/// Roles, deactivation and password resets apply to the account everywhere, so
+/// an admin of one organization cannot change them for a user who also belongs
+/// to another.
+async fn require_only_member(pool: &PgPool, organization_id: Uuid, user_id: Uuid) -> Result<(), HttpResponse> {
+    require_member(pool, organization_id, user_id).await?;
+    match list_user_organization_ids(pool, user_id).await {
+        Ok(ids) if ids.iter().all(|id| *id == organization_id) => Ok(()),
+        Ok(_) => Err(HttpResponse::Forbidden().json(serde_json::json!({
+            "error": "User also belongs to another organization"
+        }))),
+        Err(e) => Err(internal_error(e)),
+    }
+}
+
+/// Changes a user's global role. It applies to their open sessions at once.
+#[utoipa::path(
+    request_body = UpdateUserRoleRequest,
+    responses(
+        (status = 200, description = "The updated user", body = User),
+        (status = 400, description = "Invalid request, or the caller's own account", body = ErrorResponse),
+        (status = 403, description = "Admins only, or the user is in another organization too", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+    ),
+)]
+#[put("/{id}/role")]
+async fn update_user_role_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+    req: web::Json<UpdateUserRoleRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let user_id = path.into_inner();
+    let admin_id = match require_admin(&http_req, "manage users") {
+        Ok(admin_id) => admin_id,
+        Err(response) => return *response,
+    };
+    if let Err(response) = not_self(admin_id, user_id, "change the role of") {
+        return *response;
+    }
+    if let Err(response) = require_only_member(&pool, organization.id, user_id).await {
+        return response;
+    }
+
+    match set_user_role(&pool, user_id, &req.role).await {
+        Ok(Some(user)) => {
+            log::info!("Admin {} set the role of user {} to {}", admin_id, user_id, req.role);
+            HttpResponse::Ok().json(user)
+        }
+        Ok(None) => user_not_found(),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Signs a user out everywhere and keeps them out: their sessions and API
+/// tokens stop working and they cannot sign in until reactivated.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The deactivated user", body = User),
+        (status = 400, description = "The caller's own account", body = ErrorResponse),
+        (status = 403, description = "Admins only, or the user is in another organization too", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+    ),
+)]
+#[post("/{id}/deactivate")]
+async fn deactivate_user_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let user_id = path.into_inner();
+    let admin_id = match require_admin(&http_req, "manage users") {
+        Ok(admin_id) => admin_id,
+        Err(response) => return *response,
+    };
+    if let Err(response) = not_self(admin_id, user_id, "deactivate") {
+        return *response;
+    }
+    if let Err(response) = require_only_member(&pool, organization.id, user_id).await {
+        return response;
+    }
+
+    match set_user_deactivated(&pool, user_id, true).await {
+        Ok(Some(user)) => {
+            log::info!("Admin {} deactivated user {}", admin_id, user_id);
+            HttpResponse::Ok().json(user)
+        }
+        Ok(None) => user_not_found(),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Lets a deactivated user back in. Their unexpired sessions and tokens work again.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The reactivated user", body = User),
+        (status = 403, description = "Admins only, or the user is in another organization too", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+    ),
+)]
+#[post("/{id}/reactivate")]
+async fn reactivate_user_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let user_id = path.into_inner();
+    let admin_id = match require_admin(&http_req, "manage users") {
+        Ok(admin_id) => admin_id,
+        Err(response) => return *response,
+    };
+    if let Err(response) = require_only_member(&pool, organization.id, user_id).await {
+        return response;
+    }
+
+    match set_user_deactivated(&pool, user_id, false).await {
+        Ok(Some(user)) => {
+            log::info!("Admin {} reactivated user {}", admin_id, user_id);
+            HttpResponse::Ok().json(user)
+        }
+        Ok(None) => user_not_found(),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Makes a user change their password: until they do, their sessions can only
+/// fetch `/api/users/me` and update their own password.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The updated user", body = User),
+        (status = 400, description = "The user has no password", body = ErrorResponse),
+        (status = 403, description = "Admins only, or the user is in another organization too", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+    ),
+)]
+#[post("/{id}/require-password-reset")]
+async fn require_password_reset_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let user_id = path.into_inner();
+    let admin_id = match require_admin(&http_req, "manage users") {
+        Ok(admin_id) => admin_id,
+        Err(response) => return *response,
+    };
+    if let Err(response) = require_only_member(&pool, organization.id, user_id).await {
+        return response;
+    }
+
+    // Service accounts and single sign-on users have no password to change
+    match find_user_by_id(&pool, user_id).await {
+        Ok(Some(user)) if user.password_hash.is_none() => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "User has no password"
+            }));
+        }
+        Ok(Some(_)) => {}
+        Ok(None) => return user_not_found(),
+        Err(e) => return internal_error(e),
+    }
+
+    match require_password_reset(&pool, user_id).await {
+        Ok(Some(user)) => {
+            log::info!("Admin {} required user {} to reset their password", admin_id, user_id);
+            HttpResponse::Ok().json(user)
+        }
+        Ok(None) => user_not_found(),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Issues a short-lived session as another user, for support, and records it in
+/// the audit trail. The session cannot manage tokens, two-factor authentication
+/// or the account itself, and admins cannot be impersonated.
+#[utoipa::path(
+    request_body = ImpersonateUserRequest,
+    responses(
+        (status = 201, description = "A session as the user", body = ImpersonationResponse),
+        (status = 400, description = "Missing reason, or the user cannot be impersonated", body = ErrorResponse),
+        (status = 403, description = "Admins only", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+    ),
+)]
+#[post("/{id}/impersonate")]
+async fn impersonate_user_handler(
+    pool: web::Data<PgPool>,
+    config: web::Data<Config>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+    req: web::Json<ImpersonateUserRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let user_id = path.into_inner();
+    let admin_id = match require_admin(&http_req, "manage users") {
+        Ok(admin_id) => admin_id,
+        Err(response) => return *response,
+    };
+    if let Err(response) = not_self(admin_id, user_id, "impersonate") {
+        return *response;
+    }
+    if let Err(response) = require_member(&pool, organization.id, user_id).await {
+        return response;
+    }
+    let reason = req.reason.trim();
+    if reason.is_empty() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "A reason is required"
+        }));
+    }
+
+    let user = match find_user_by_id(&pool, user_id).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return user_not_found(),
+        Err(e) => return internal_error(e),
+    };
+    let refusal = if user.role.parse::<UserRole>() == Ok(UserRole::Admin) {
+        Some("Admins cannot be impersonated")
+    } else if user.deactivated_at.is_some() {
+        Some("User is deactivated")
+    } else if user.is_service_account {
+        Some("Service accounts cannot be impersonated")
+    } else {
+        None
+    };
+    if let Some(error) = refusal {
+        return HttpResponse::BadRequest().json(serde_json::json!({ "error": error }));
+    }
+
+    let expires_at = Utc::now() + Duration::minutes(IMPERSONATION_MINUTES);
+    let token = match create_impersonation_jwt(
+        user.id,
+        &user.email,
+        &user.role,
+        admin_id,
+        organization.id,
+        expires_at,
+        &config.jwt,
+    ) {
+        Ok(token) => token,
+        Err(e) => {
+            log::error!("JWT creation error: {}", e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }));
+        }
+    };
+    // The token is only handed out once the audit record exists
+    let created = create_impersonation(&pool, organization.id, admin_id, user.id, reason, expires_at).await;
+    let impersonation = match created {
+        Ok(impersonation) => impersonation,
+        Err(e) => return internal_error(e),
+    };
+    log::warn!("Admin {} is impersonating user {}: {}", admin_id, user.id, reason);
+
+    HttpResponse::Created().json(ImpersonationResponse {
+        token,
+        user,
+        impersonation,
+    })
+}
+
+/// The impersonation audit trail of the caller's organization, most recent first.
+#[utoipa::path(
+    params(ImpersonationQuery),
+    responses(
+        (status = 200, description = "Impersonations", body = Vec<Impersonation>),
+        (status = 403, description = "Admins only", body = ErrorResponse),
+    ),
+)]
+#[get("")]
+async fn list_impersonations_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    query: web::Query<ImpersonationQuery>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    if let Err(response) = require_admin(&http_req, "manage users") {
+        return *response;
+    }
+
+    let limit = query.limit.unwrap_or(DEFAULT_IMPERSONATION_LIMIT).clamp(1, MAX_IMPERSONATION_LIMIT);
+    match list_impersonations(&pool, organization.id, query.user_id, limit).await {
+        Ok(impersonations) => HttpResponse::Ok().json(impersonations),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    update_user_role_handler,
+    deactivate_user_handler,
+    reactivate_user_handler,
+    require_password_reset_handler,
+    impersonate_user_handler,
+))]
+pub struct AdminUserApi;
+
+pub fn admin_user_routes() -> actix_web::Scope {
+    web::scope("/admin/users")
+        .service(update_user_role_handler)
+        .service(deactivate_user_handler)
+        .service(reactivate_user_handler)
+        .service(require_password_reset_handler)
+        .service(impersonate_user_handler)
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(list_impersonations_handler))]
+pub struct ImpersonationApi;
+
+pub fn impersonation_routes() -> actix_web::Scope {
+    web::scope("/admin/impersonations").service(list_impersonations_handler)
+}
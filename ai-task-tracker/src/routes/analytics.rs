@@ -0,0 +1,193 @@
+use actix_web::{get, web, HttpResponse, Responder};
+use chrono::{Duration, NaiveDate, Utc};
+use sqlx::PgPool;
+use utoipa::OpenApi;
+use uuid::Uuid;
+
+use crate::{
+    db::{
+        open_workload_by_assignee, overdue_by_assignee, project_burndown, weekly_cycle_time,
+        weekly_throughput,
+    },
+    models::{AnalyticsQuery, AssigneeWorkload, BurndownPoint, CycleTimeBucket, ThroughputBucket},
+    middleware::CurrentOrganization,
+    openapi::ErrorResponse,
+};
+
+const DEFAULT_RANGE_DAYS: i64 = 84;
+const MAX_RANGE_DAYS: i64 = 366;
+
+/// Resolves the requested date range, defaulting to the last 12 weeks.
+fn date_range(query: &AnalyticsQuery) -> Result<(NaiveDate, NaiveDate), String> {
+    let to = query.to.unwrap_or_else(|| Utc::now().date_naive());
+    let from = query.from.unwrap_or(to - Duration::days(DEFAULT_RANGE_DAYS));
+
+    if from > to {
+        return Err("`from` must not be after `to`".to_string());
+    }
+    if (to - from).num_days() > MAX_RANGE_DAYS {
+        return Err(format!("Date range cannot exceed {} days", MAX_RANGE_DAYS));
+    }
+
+    Ok((from, to))
+}
+
+#[utoipa::path(
+    params(AnalyticsQuery),
+    responses(
+        (status = 200, description = "Open tasks and estimates per assignee", body = Vec<AssigneeWorkload>),
+    ),
+)]
+#[get("/workload")]
+async fn workload_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    query: web::Query<AnalyticsQuery>,
+) -> impl Responder {
+    match open_workload_by_assignee(&pool, organization.id, query.project_id, query.team_id).await {
+        Ok(rows) => HttpResponse::Ok().json(rows),
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }))
+        }
+    }
+}
+
+#[utoipa::path(
+    params(AnalyticsQuery),
+    responses(
+        (status = 200, description = "Completed tasks per bucket", body = Vec<ThroughputBucket>),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+)]
+#[get("/throughput")]
+async fn throughput_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    query: web::Query<AnalyticsQuery>,
+) -> impl Responder {
+    let (from, to) = match date_range(&query) {
+        Ok(range) => range,
+        Err(msg) => return HttpResponse::BadRequest().json(serde_json::json!({ "error": msg })),
+    };
+
+    match weekly_throughput(&pool, organization.id, query.project_id, query.team_id, from, to).await {
+        Ok(buckets) => HttpResponse::Ok().json(buckets),
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }))
+        }
+    }
+}
+
+#[utoipa::path(
+    params(AnalyticsQuery),
+    responses(
+        (status = 200, description = "Cycle time per bucket", body = Vec<CycleTimeBucket>),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+)]
+#[get("/cycle-time")]
+async fn cycle_time_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    query: web::Query<AnalyticsQuery>,
+) -> impl Responder {
+    let (from, to) = match date_range(&query) {
+        Ok(range) => range,
+        Err(msg) => return HttpResponse::BadRequest().json(serde_json::json!({ "error": msg })),
+    };
+
+    match weekly_cycle_time(&pool, organization.id, query.project_id, query.team_id, from, to).await {
+        Ok(buckets) => HttpResponse::Ok().json(buckets),
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }))
+        }
+    }
+}
+
+#[utoipa::path(
+    params(AnalyticsQuery),
+    responses(
+        (status = 200, description = "Remaining work per day", body = Vec<BurndownPoint>),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+)]
+#[get("/burndown/{project_id}")]
+async fn burndown_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+    query: web::Query<AnalyticsQuery>,
+) -> impl Responder {
+    let project_id = path.into_inner();
+    let (from, to) = match date_range(&query) {
+        Ok(range) => range,
+        Err(msg) => return HttpResponse::BadRequest().json(serde_json::json!({ "error": msg })),
+    };
+
+    match project_burndown(&pool, organization.id, project_id, from, to).await {
+        Ok(points) => HttpResponse::Ok().json(points),
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }))
+        }
+    }
+}
+
+#[utoipa::path(
+    params(AnalyticsQuery),
+    responses(
+        (status = 200, description = "Overdue task count, in total and by assignee", body = serde_json::Value),
+    ),
+)]
+#[get("/overdue")]
+async fn overdue_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    query: web::Query<AnalyticsQuery>,
+) -> impl Responder {
+    match overdue_by_assignee(&pool, organization.id, query.project_id, query.team_id).await {
+        Ok(rows) => {
+            let total: i64 = rows.iter().map(|r| r.overdue).sum();
+            HttpResponse::Ok().json(serde_json::json!({
+                "total_overdue": total,
+                "by_assignee": rows,
+            }))
+        }
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }))
+        }
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    workload_handler,
+    throughput_handler,
+    cycle_time_handler,
+    burndown_handler,
+    overdue_handler,
+))]
+pub struct AnalyticsApi;
+
+pub fn analytics_routes() -> actix_web::Scope {
+    web::scope("/analytics")
+        .service(workload_handler)
+        .service(throughput_handler)
+        .service(cycle_time_handler)
+        .service(burndown_handler)
+        .service(overdue_handler)
+}
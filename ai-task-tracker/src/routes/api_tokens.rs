@@ -0,0 +1,306 @@
+use actix_web::{delete, get, post, web, HttpRequest, HttpResponse, Responder};
+use chrono::{Duration, Utc};
+use sqlx::PgPool;
+use utoipa::OpenApi;
+use uuid::Uuid;
+
+use crate::{
+    db::{
+        create_api_token, create_service_account, find_api_token, find_user_by_id, is_organization_member,
+        list_api_tokens, list_service_accounts, revoke_api_token, NewApiToken,
+    },
+    models::{ApiToken, CreateApiTokenRequest, CreateServiceAccountRequest, CreatedApiToken, User},
+    middleware::CurrentOrganization,
+    openapi::ErrorResponse,
+    routes::helpers::{caller, internal_error, require_admin, unauthorized},
+    utils::{generate_api_token, hash_api_token},
+};
+
+/// Characters of a token kept in plain text so listings can tell tokens apart.
+const TOKEN_PREFIX_LENGTH: usize = 12;
+
+/// Validates the request and issues a token for `user_id` in the organization,
+/// returning the plain token in the response.
+async fn issue_token(
+    pool: &PgPool,
+    organization_id: Uuid,
+    user_id: Uuid,
+    req: &CreateApiTokenRequest,
+    created_by: Uuid,
+) -> HttpResponse {
+    if req.name.trim().is_empty() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Token name cannot be empty"
+        }));
+    }
+    if req.scopes.is_empty() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "A token needs at least one scope"
+        }));
+    }
+    if req.expires_in_days.is_some_and(|days| days <= 0) {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "expires_in_days must be positive"
+        }));
+    }
+
+    let token = generate_api_token();
+    let token_hash = hash_api_token(&token);
+    let scopes: Vec<String> = req.scopes.iter().map(|s| s.to_string()).collect();
+    let new_token = NewApiToken {
+        user_id,
+        organization_id,
+        name: req.name.trim(),
+        token_prefix: &token[..TOKEN_PREFIX_LENGTH],
+        token_hash: &token_hash,
+        scopes: &scopes,
+        expires_at: req.expires_in_days.map(|days| Utc::now() + Duration::days(days)),
+        created_by: Some(created_by),
+    };
+
+    match create_api_token(pool, &new_token).await {
+        Ok(api_token) => HttpResponse::Created().json(CreatedApiToken { api_token, token }),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 201, description = "The new token; its secret is only shown here", body = CreatedApiToken),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+)]
+#[post("")]
+async fn create_api_token_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    req: web::Json<CreateApiTokenRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let Some((user_id, _)) = caller(&http_req) else {
+        return unauthorized();
+    };
+
+    issue_token(&pool, organization.id, user_id, &req, user_id).await
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The caller's tokens", body = Vec<ApiToken>),
+    ),
+)]
+#[get("")]
+async fn list_api_tokens_handler(pool: web::Data<PgPool>, http_req: HttpRequest) -> impl Responder {
+    let Some((user_id, _)) = caller(&http_req) else {
+        return unauthorized();
+    };
+
+    match list_api_tokens(&pool, user_id).await {
+        Ok(tokens) => HttpResponse::Ok().json(tokens),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Revokes one of the caller's tokens; admins can revoke any token issued in
+/// their organization.
+#[utoipa::path(
+    responses(
+        (status = 204, description = "Token revoked"),
+        (status = 404, description = "Token not found", body = ErrorResponse),
+        (status = 409, description = "Token already revoked", body = ErrorResponse),
+    ),
+)]
+#[delete("/{id}")]
+async fn revoke_api_token_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let Some((user_id, is_admin)) = caller(&http_req) else {
+        return unauthorized();
+    };
+
+    let not_found = || {
+        HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Token not found"
+        }))
+    };
+    match find_api_token(&pool, path.into_inner()).await {
+        Ok(Some(token)) if token.user_id == user_id || (is_admin && token.organization_id == organization.id) => {
+            match revoke_api_token(&pool, token.id).await {
+                Ok(true) => HttpResponse::NoContent().finish(),
+                Ok(false) => HttpResponse::Conflict().json(serde_json::json!({
+                    "error": "Token is already revoked"
+                })),
+                Err(e) => internal_error(e),
+            }
+        }
+        Ok(_) => not_found(),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 201, description = "The new service account", body = User),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 403, description = "Admins only", body = ErrorResponse),
+    ),
+)]
+#[post("")]
+async fn create_service_account_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    req: web::Json<CreateServiceAccountRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    if let Err(response) = require_admin(&http_req, "manage service accounts") {
+        return *response;
+    }
+    if req.full_name.trim().is_empty() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Name cannot be empty"
+        }));
+    }
+
+    match create_service_account(&pool, req.full_name.trim(), req.email.as_deref(), organization.id).await {
+        Ok(user) => HttpResponse::Created().json(user),
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "User with this email already exists"
+            }))
+        }
+        Err(e) => internal_error(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The organization's service accounts", body = Vec<User>),
+        (status = 403, description = "Admins only", body = ErrorResponse),
+    ),
+)]
+#[get("")]
+async fn list_service_accounts_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    http_req: HttpRequest,
+) -> impl Responder {
+    if let Err(response) = require_admin(&http_req, "manage service accounts") {
+        return *response;
+    }
+
+    match list_service_accounts(&pool, organization.id).await {
+        Ok(accounts) => HttpResponse::Ok().json(accounts),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Looks up a service account of the organization, or the 404 response if `id` is not one.
+async fn check_service_account(pool: &PgPool, organization_id: Uuid, id: Uuid) -> Option<HttpResponse> {
+    let not_found = || {
+        HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Service account not found"
+        }))
+    };
+
+    match find_user_by_id(pool, id).await {
+        Ok(Some(user)) if user.is_service_account => {}
+        Ok(_) => return Some(not_found()),
+        Err(e) => return Some(internal_error(e)),
+    }
+    match is_organization_member(pool, organization_id, id).await {
+        Ok(true) => None,
+        Ok(false) => Some(not_found()),
+        Err(e) => Some(internal_error(e)),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 201, description = "The new token; its secret is only shown here", body = CreatedApiToken),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 403, description = "Admins only", body = ErrorResponse),
+        (status = 404, description = "Service account not found", body = ErrorResponse),
+    ),
+)]
+#[post("/{id}/tokens")]
+async fn create_service_account_token_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+    req: web::Json<CreateApiTokenRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let admin_id = match require_admin(&http_req, "manage service accounts") {
+        Ok(admin_id) => admin_id,
+        Err(response) => return *response,
+    };
+    let account_id = path.into_inner();
+    if let Some(response) = check_service_account(&pool, organization.id, account_id).await {
+        return response;
+    }
+
+    issue_token(&pool, organization.id, account_id, &req, admin_id).await
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The service account's tokens", body = Vec<ApiToken>),
+        (status = 403, description = "Admins only", body = ErrorResponse),
+        (status = 404, description = "Service account not found", body = ErrorResponse),
+    ),
+)]
+#[get("/{id}/tokens")]
+async fn list_service_account_tokens_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    if let Err(response) = require_admin(&http_req, "manage service accounts") {
+        return *response;
+    }
+    let account_id = path.into_inner();
+    if let Some(response) = check_service_account(&pool, organization.id, account_id).await {
+        return response;
+    }
+
+    match list_api_tokens(&pool, account_id).await {
+        Ok(tokens) => HttpResponse::Ok().json(tokens),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    create_api_token_handler,
+    list_api_tokens_handler,
+    revoke_api_token_handler,
+))]
+pub struct ApiTokenApi;
+
+pub fn api_token_routes() -> actix_web::Scope {
+    web::scope("/tokens")
+        .service(create_api_token_handler)
+        .service(list_api_tokens_handler)
+        .service(revoke_api_token_handler)
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    create_service_account_handler,
+    list_service_accounts_handler,
+    create_service_account_token_handler,
+    list_service_account_tokens_handler,
+))]
+pub struct ServiceAccountApi;
+
+pub fn service_account_routes() -> actix_web::Scope {
+    web::scope("/service-accounts")
+        .service(create_service_account_handler)
+        .service(list_service_accounts_handler)
+        .service(create_service_account_token_handler)
+        .service(list_service_account_tokens_handler)
+}
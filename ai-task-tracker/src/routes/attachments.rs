@@ -0,0 +1,275 @@
+use actix_multipart::Multipart;
+use actix_web::{delete, get, post, web, HttpRequest, HttpResponse, Responder};
+use futures_util::StreamExt;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    config::Config,
+    db::{create_attachment, delete_attachment, find_attachment, find_task_by_id, list_attachments},
+    models::{Attachment, NewAttachment},
+    middleware::CurrentOrganization,
+    openapi::ErrorResponse,
+    routes::helpers::{caller_id, internal_error},
+    storage::{BlobStore, StorageError},
+};
+
+/// Keeps only the final path component and drops characters that would break headers.
+fn sanitize_file_name(name: &str) -> String {
+    let base = name.rsplit(['/', '\\']).next().unwrap_or("");
+    let cleaned: String = base
+        .chars()
+        .filter(|c| !c.is_control() && *c != '"')
+        .take(255)
+        .collect();
+    if cleaned.trim().is_empty() {
+        "attachment".to_string()
+    } else {
+        cleaned
+    }
+}
+
+/// Determines the content type from the file's bytes rather than trusting the client.
+fn sniff_content_type(data: &[u8]) -> String {
+    if let Some(kind) = infer::get(data) {
+        return kind.mime_type().to_string();
+    }
+    if std::str::from_utf8(data).is_ok() {
+        "text/plain".to_string()
+    } else {
+        "application/octet-stream".to_string()
+    }
+}
+
+/// Removes attachments stored earlier in an upload that failed part way, so
+/// the upload leaves nothing behind.
+async fn discard_attachments(pool: &PgPool, store: &dyn BlobStore, attachments: &[Attachment]) {
+    for attachment in attachments {
+        if let Err(e) = delete_attachment(pool, attachment.id).await {
+            log::warn!("Failed to remove attachment {} of a failed upload: {}", attachment.id, e);
+        }
+        if let Err(e) = store.delete(&attachment.storage_key).await {
+            log::warn!("Failed to remove orphaned blob {}: {}", attachment.storage_key, e);
+        }
+    }
+}
+
+#[utoipa::path(
+    request_body(content_type = "multipart/form-data", description = "One or more files"),
+    responses(
+        (status = 201, description = "The stored attachments", body = Vec<Attachment>),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Task not found", body = ErrorResponse),
+        (status = 413, description = "File too large", body = ErrorResponse),
+    ),
+)]
+#[post("/{id}/attachments")]
+pub async fn upload_attachments_handler(
+    pool: web::Data<PgPool>,
+    store: web::Data<dyn BlobStore>,
+    config: web::Data<Config>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+    mut payload: Multipart,
+) -> impl Responder {
+    let task_id = path.into_inner();
+    let max_bytes = config.storage.max_attachment_bytes;
+    let uploaded_by = caller_id(&http_req);
+
+    match find_task_by_id(&pool, organization.id, task_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Task not found"
+            }));
+        }
+        Err(e) => return internal_error(e),
+    }
+
+    // Every part is read and checked before any is stored, so a rejected upload stores nothing
+    let mut files = Vec::new();
+    while let Some(field) = payload.next().await {
+        let mut field = match field {
+            Ok(field) => field,
+            Err(e) => {
+                return HttpResponse::BadRequest().json(serde_json::json!({
+                    "error": format!("Invalid multipart body: {}", e)
+                }));
+            }
+        };
+
+        // Only file parts become attachments
+        let file_name = match field.content_disposition().and_then(|cd| cd.get_filename()) {
+            Some(name) => sanitize_file_name(name),
+            None => continue,
+        };
+
+        let mut data = web::BytesMut::new();
+        while let Some(chunk) = field.next().await {
+            let chunk = match chunk {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    return HttpResponse::BadRequest().json(serde_json::json!({
+                        "error": format!("Invalid multipart body: {}", e)
+                    }));
+                }
+            };
+            if data.len() + chunk.len() > max_bytes {
+                return HttpResponse::PayloadTooLarge().json(serde_json::json!({
+                    "error": format!("{} exceeds the {} byte attachment limit", file_name, max_bytes)
+                }));
+            }
+            data.extend_from_slice(&chunk);
+        }
+        let data = data.freeze();
+
+        let id = Uuid::new_v4();
+        let new = NewAttachment {
+            id,
+            task_id,
+            uploaded_by,
+            content_type: sniff_content_type(&data),
+            size_bytes: data.len() as i64,
+            storage_key: format!("tasks/{}/{}", task_id, id),
+            checksum_sha256: hex::encode(Sha256::digest(&data)),
+            file_name,
+        };
+        files.push((new, data));
+    }
+
+    if files.is_empty() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "No file parts in the upload"
+        }));
+    }
+
+    let mut created = Vec::new();
+    for (new, data) in files {
+        if let Err(e) = store.put(&new.storage_key, &new.content_type, data).await {
+            log::error!("Storage error: {}", e);
+            discard_attachments(&pool, store.get_ref(), &created).await;
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }));
+        }
+
+        match create_attachment(&pool, &new).await {
+            Ok(attachment) => created.push(attachment),
+            Err(e) => {
+                if let Err(e) = store.delete(&new.storage_key).await {
+                    log::warn!("Failed to remove orphaned blob {}: {}", new.storage_key, e);
+                }
+                discard_attachments(&pool, store.get_ref(), &created).await;
+                return internal_error(e);
+            }
+        }
+    }
+
+    HttpResponse::Created().json(created)
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The task's attachments", body = Vec<Attachment>),
+    ),
+)]
+#[get("/{id}/attachments")]
+pub async fn list_attachments_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    let task_id = path.into_inner();
+
+    match list_attachments(&pool, organization.id, task_id).await {
+        Ok(attachments) => HttpResponse::Ok().json(attachments),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The file contents", body = Vec<u8>, content_type = "application/octet-stream"),
+        (status = 404, description = "Attachment not found", body = ErrorResponse),
+    ),
+)]
+#[get("/{id}/attachments/{attachment_id}")]
+pub async fn download_attachment_handler(
+    pool: web::Data<PgPool>,
+    store: web::Data<dyn BlobStore>,
+    organization: CurrentOrganization,
+    path: web::Path<(Uuid, Uuid)>,
+) -> impl Responder {
+    let (task_id, attachment_id) = path.into_inner();
+
+    let attachment = match find_attachment(&pool, organization.id, task_id, attachment_id).await {
+        Ok(Some(attachment)) => attachment,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Attachment not found"
+            }));
+        }
+        Err(e) => return internal_error(e),
+    };
+
+    match store.get(&attachment.storage_key).await {
+        Ok(data) => HttpResponse::Ok()
+            .content_type(attachment.content_type.as_str())
+            .insert_header((
+                "Content-Disposition",
+                format!("attachment; filename=\"{}\"", attachment.file_name),
+            ))
+            .insert_header(("X-Content-Type-Options", "nosniff"))
+            .body(data),
+        Err(StorageError::NotFound(key)) => {
+            log::error!("Attachment {} is missing blob {}", attachment.id, key);
+            HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Attachment content not found"
+            }))
+        }
+        Err(e) => {
+            log::error!("Storage error: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }))
+        }
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 204, description = "Attachment deleted"),
+        (status = 404, description = "Attachment not found", body = ErrorResponse),
+    ),
+)]
+#[delete("/{id}/attachments/{attachment_id}")]
+pub async fn delete_attachment_handler(
+    pool: web::Data<PgPool>,
+    store: web::Data<dyn BlobStore>,
+    organization: CurrentOrganization,
+    path: web::Path<(Uuid, Uuid)>,
+) -> impl Responder {
+    let (task_id, attachment_id) = path.into_inner();
+
+    let attachment = match find_attachment(&pool, organization.id, task_id, attachment_id).await {
+        Ok(Some(attachment)) => attachment,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Attachment not found"
+            }));
+        }
+        Err(e) => return internal_error(e),
+    };
+
+    if let Err(e) = delete_attachment(&pool, attachment.id).await {
+        return internal_error(e);
+    }
+
+    if let Err(e) = store.delete(&attachment.storage_key).await {
+        log::warn!("Failed to delete blob {}: {}", attachment.storage_key, e);
+    }
+
+    HttpResponse::NoContent().finish()
+}
@@ -1,19 +1,55 @@
-use actix_web::{post, web, HttpResponse, Responder};
+use actix_web::{get, post, web, HttpResponse, Responder};
 use sqlx::PgPool;
+use utoipa::OpenApi;
 
 use crate::{
     config::Config,
-    db::{create_user, find_user_by_email},
-    models::{AuthResponse, CreateUserRequest, LoginRequest},
-    utils::{create_jwt, hash_password, verify_password},
+    db::{
+        claim_team_invitations, create_oidc_login_state, create_user, find_organization_by_slug, find_user_by_email,
+        find_user_by_id, find_user_by_identity, get_mfa_status, link_user_identity, provision_oidc_user, take_oidc_login_state,
+    },
+    models::{
+        AuthResponse, CreateUserRequest, LoginRequest, LoginResponse, MfaChallengeResponse, MfaPending,
+        OidcAuthorizeResponse, OidcCallbackRequest, OidcLoginState, User, VerifyMfaRequest,
+        DEFAULT_ORGANIZATION_SLUG,
+    },
+    openapi::ErrorResponse,
+    routes::helpers::internal_error,
+    services::{check_mfa_code, IdTokenClaims, MfaCheck, OidcClient, OidcError, Pkce},
+    utils::{create_jwt, create_mfa_token, generate_token, hash_password, verify_jwt, verify_password},
 };
+use uuid::Uuid;
 
+const OIDC_STATE_LENGTH: usize = 32;
+
+/// The organization that self-registered and newly provisioned users join.
+async fn default_organization_id(pool: &PgPool) -> Result<Uuid, sqlx::Error> {
+    find_organization_by_slug(pool, DEFAULT_ORGANIZATION_SLUG)
+        .await?
+        .map(|organization| organization.id)
+        .ok_or(sqlx::Error::RowNotFound)
+}
+
+#[utoipa::path(
+    responses(
+        (status = 201, description = "The new user and a session token", body = AuthResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 403, description = "Registration is disabled", body = ErrorResponse),
+    ),
+    security(()),
+)]
 #[post("/register")]
 async fn register(
     pool: web::Data<PgPool>,
     config: web::Data<Config>,
     req: web::Json<CreateUserRequest>,
 ) -> impl Responder {
+    if !config.features.registration {
+        return HttpResponse::Forbidden().json(serde_json::json!({
+            "error": "Registration is disabled"
+        }));
+    }
+
     // Check if user already exists
     match find_user_by_email(&pool, &req.email).await {
         Ok(Some(_)) => {
@@ -31,7 +67,7 @@ async fn register(
     }
 
     // Hash password
-    let password_hash = match hash_password(&req.password) {
+    let password_hash = match hash_password(&req.password, config.bcrypt_cost) {
         Ok(hash) => hash,
         Err(e) => {
             log::error!("Password hashing error: {}", e);
@@ -41,16 +77,31 @@ async fn register(
         }
     };
 
+    let organization_id = match default_organization_id(&pool).await {
+        Ok(id) => id,
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }));
+        }
+    };
+
     // Create user
-    match create_user(&pool, &req, &password_hash).await {
+    match create_user(&pool, &req, &password_hash, organization_id).await {
         Ok(user) => {
+            // Link pending team invitations sent to this email
+            if let Err(e) = claim_team_invitations(&pool, &user.email, user.id).await {
+                log::error!("Failed to claim team invitations: {}", e);
+            }
+
             // Generate JWT token
             let token = match create_jwt(
                 user.id,
                 &user.email,
                 &user.role.to_string(),
-                &config.jwt_secret,
-                config.jwt_expiration_hours,
+                None,
+                &config.jwt,
             ) {
                 Ok(token) => token,
                 Err(e) => {
@@ -72,6 +123,46 @@ async fn register(
     }
 }
 
+/// Accounts with two-factor authentication (or whose role requires it) get a
+/// short-lived token for the second step instead of a session, however they
+/// proved who they are.
+async fn session_or_mfa_challenge(pool: &PgPool, config: &Config, user: User) -> HttpResponse {
+    let pending = match get_mfa_status(pool, user.id).await {
+        Ok(Some(status)) if status.enabled => Some(MfaPending::Verify),
+        Ok(Some(status)) if status.required => Some(MfaPending::Enroll),
+        Ok(_) => None,
+        Err(e) => return internal_error(e),
+    };
+    let response = match pending {
+        Some(pending) => create_mfa_token(user.id, &user.email, &user.role, pending, &config.jwt).map(|mfa_token| {
+            LoginResponse::MfaChallenge(MfaChallengeResponse {
+                mfa_required: pending,
+                mfa_token,
+            })
+        }),
+        None => create_jwt(user.id, &user.email, &user.role.to_string(), None, &config.jwt)
+            .map(|token| LoginResponse::Session(AuthResponse { token, user })),
+    };
+
+    match response {
+        Ok(response) => HttpResponse::Ok().json(response),
+        Err(e) => {
+            log::error!("JWT creation error: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }))
+        }
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "A session token, or a two-factor challenge to complete first", body = LoginResponse),
+        (status = 401, description = "Invalid email or password", body = ErrorResponse),
+        (status = 403, description = "Account is deactivated", body = ErrorResponse),
+    ),
+    security(()),
+)]
 #[post("/login")]
 async fn login(
     pool: web::Data<PgPool>,
@@ -94,28 +185,17 @@ async fn login(
         }
     };
 
-    // Verify password
-    match verify_password(&req.password, &user.password_hash) {
-        Ok(true) => {
-            // Generate JWT token
-            let token = match create_jwt(
-                user.id,
-                &user.email,
-                &user.role.to_string(),
-                &config.jwt_secret,
-                config.jwt_expiration_hours,
-            ) {
-                Ok(token) => token,
-                Err(e) => {
-                    log::error!("JWT creation error: {}", e);
-                    return HttpResponse::InternalServerError().json(serde_json::json!({
-                        "error": "Internal server error"
-                    }));
-                }
-            };
+    // Service accounts have no password and can only use API tokens
+    let Some(password_hash) = user.password_hash.as_deref() else {
+        return HttpResponse::Unauthorized().json(serde_json::json!({
+            "error": "Invalid email or password"
+        }));
+    };
 
-            HttpResponse::Ok().json(AuthResponse { token, user })
-        }
+    // Verify password
+    match verify_password(&req.password, password_hash) {
+        Ok(true) if user.deactivated_at.is_some() => account_deactivated(),
+        Ok(true) => session_or_mfa_challenge(&pool, &config, user).await,
         Ok(false) => HttpResponse::Unauthorized().json(serde_json::json!({
             "error": "Invalid email or password"
         })),
@@ -128,8 +208,277 @@ async fn login(
     }
 }
 
+/// Finishes a password login for an account with two-factor authentication,
+/// given the `mfa_token` from `/login` and a TOTP or recovery code.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The session token", body = AuthResponse),
+        (status = 401, description = "Invalid challenge or code", body = ErrorResponse),
+        (status = 403, description = "Account is deactivated", body = ErrorResponse),
+        (status = 429, description = "Too many invalid codes", body = ErrorResponse),
+    ),
+    security(()),
+)]
+#[post("/mfa/verify")]
+async fn verify_mfa(
+    pool: web::Data<PgPool>,
+    config: web::Data<Config>,
+    req: web::Json<VerifyMfaRequest>,
+) -> impl Responder {
+    let user_id = match verify_jwt(&req.mfa_token, &config.jwt) {
+        Ok(claims) if claims.mfa_pending == Some(MfaPending::Verify) => Uuid::parse_str(&claims.sub).ok(),
+        _ => None,
+    };
+    let Some(user_id) = user_id else {
+        return HttpResponse::Unauthorized().json(serde_json::json!({
+            "error": "Invalid or expired login; please sign in again"
+        }));
+    };
+
+    match check_mfa_code(&pool, user_id, &req.code).await {
+        Ok(MfaCheck::Accepted) => {}
+        Ok(MfaCheck::Rejected) => {
+            return HttpResponse::Unauthorized().json(serde_json::json!({
+                "error": "Invalid code"
+            }));
+        }
+        Ok(MfaCheck::LockedOut) => {
+            return HttpResponse::TooManyRequests().json(serde_json::json!({
+                "error": "Too many invalid codes; try again later"
+            }));
+        }
+        Err(e) => return internal_error(e),
+    }
+
+    let user = match find_user_by_id(&pool, user_id).await {
+        Ok(Some(user)) => user,
+        Ok(None) => {
+            return HttpResponse::Unauthorized().json(serde_json::json!({
+                "error": "Invalid or expired login; please sign in again"
+            }));
+        }
+        Err(e) => return internal_error(e),
+    };
+    if user.deactivated_at.is_some() {
+        return account_deactivated();
+    }
+
+    match create_jwt(
+        user.id,
+        &user.email,
+        &user.role.to_string(),
+        None,
+        &config.jwt,
+    ) {
+        Ok(token) => HttpResponse::Ok().json(AuthResponse { token, user }),
+        Err(e) => {
+            log::error!("JWT creation error: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }))
+        }
+    }
+}
+
+fn account_deactivated() -> HttpResponse {
+    HttpResponse::Forbidden().json(serde_json::json!({
+        "error": "Account is deactivated"
+    }))
+}
+
+fn sso_not_configured() -> HttpResponse {
+    HttpResponse::NotFound().json(serde_json::json!({
+        "error": "Single sign-on is not configured"
+    }))
+}
+
+/// Starts a single sign-on. The browser goes to `authorization_url`, and the
+/// provider sends it back to `OIDC_REDIRECT_URL` with a code for the callback.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Where to send the user to sign in", body = OidcAuthorizeResponse),
+        (status = 404, description = "Single sign-on is not configured", body = ErrorResponse),
+        (status = 502, description = "Identity provider unavailable", body = ErrorResponse),
+    ),
+    security(()),
+)]
+#[get("/oidc/authorize")]
+async fn oidc_authorize(pool: web::Data<PgPool>, oidc: Option<web::Data<OidcClient>>) -> impl Responder {
+    let Some(oidc) = oidc else {
+        return sso_not_configured();
+    };
+
+    let pkce = Pkce::generate();
+    let login_state = OidcLoginState {
+        state: generate_token(OIDC_STATE_LENGTH),
+        nonce: generate_token(OIDC_STATE_LENGTH),
+        code_verifier: pkce.verifier,
+    };
+
+    let authorization_url = match oidc
+        .authorization_url(&login_state.state, &login_state.nonce, &pkce.challenge)
+        .await
+    {
+        Ok(url) => url,
+        Err(e) => {
+            log::error!("OIDC error: {}", e);
+            return HttpResponse::BadGateway().json(serde_json::json!({
+                "error": "Identity provider is unavailable"
+            }));
+        }
+    };
+
+    match create_oidc_login_state(&pool, &login_state).await {
+        Ok(()) => HttpResponse::Ok().json(OidcAuthorizeResponse {
+            authorization_url,
+            state: login_state.state,
+        }),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Finds the user behind a verified identity: by the linked provider account,
+/// else by email (linking the account), else by provisioning a new user. The
+/// email is only trusted for the last two once the provider says it verified
+/// it; otherwise anyone could claim an account by its address.
+async fn oidc_user(pool: &PgPool, claims: &IdTokenClaims, email: &str) -> Result<User, HttpResponse> {
+    let (issuer, subject) = (claims.iss.as_str(), claims.sub.as_str());
+    if let Some(user) = find_user_by_identity(pool, issuer, subject, email).await.map_err(internal_error)? {
+        return Ok(user);
+    }
+    if claims.email_verified != Some(true) {
+        return Err(HttpResponse::Forbidden().json(serde_json::json!({
+            "error": "Email address is not verified"
+        })));
+    }
+
+    match find_user_by_email(pool, email).await.map_err(internal_error)? {
+        Some(user) if user.is_service_account => Err(HttpResponse::Forbidden().json(serde_json::json!({
+            "error": "Service accounts cannot sign in"
+        }))),
+        Some(user) => {
+            link_user_identity(pool, user.id, issuer, subject, email)
+                .await
+                .map_err(internal_error)?;
+            Ok(user)
+        }
+        None => {
+            let full_name = claims.name.as_deref().filter(|n| !n.trim().is_empty()).unwrap_or(email);
+            let organization_id = default_organization_id(pool).await.map_err(internal_error)?;
+            let user = match provision_oidc_user(pool, email, full_name, issuer, subject, organization_id).await {
+                Ok(user) => user,
+                Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+                    return Err(HttpResponse::Conflict().json(serde_json::json!({
+                        "error": "Account is being created by another sign-in; try again"
+                    })));
+                }
+                Err(e) => return Err(internal_error(e)),
+            };
+
+            // Link pending team invitations sent to this email
+            if let Err(e) = claim_team_invitations(pool, &user.email, user.id).await {
+                log::error!("Failed to claim team invitations: {}", e);
+            }
+            Ok(user)
+        }
+    }
+}
+
+/// Completes a single sign-on with the `code` and `state` the provider
+/// redirected back with, returning a session or two-factor challenge like `/login`.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "A session token, or a two-factor challenge to complete first", body = LoginResponse),
+        (status = 400, description = "Unknown or expired state", body = ErrorResponse),
+        (status = 401, description = "Sign-in rejected", body = ErrorResponse),
+        (status = 403, description = "Email domain not allowed or account deactivated", body = ErrorResponse),
+        (status = 404, description = "Single sign-on is not configured", body = ErrorResponse),
+        (status = 502, description = "Identity provider unavailable", body = ErrorResponse),
+    ),
+    security(()),
+)]
+#[post("/oidc/callback")]
+async fn oidc_callback(
+    pool: web::Data<PgPool>,
+    config: web::Data<Config>,
+    oidc: Option<web::Data<OidcClient>>,
+    req: web::Json<OidcCallbackRequest>,
+) -> impl Responder {
+    let Some(oidc) = oidc else {
+        return sso_not_configured();
+    };
+
+    let login_state = match take_oidc_login_state(&pool, &req.state).await {
+        Ok(Some(login_state)) => login_state,
+        Ok(None) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "Unknown or expired sign-in request; please start again"
+            }));
+        }
+        Err(e) => return internal_error(e),
+    };
+
+    let claims = match oidc
+        .exchange_code(&req.code, &login_state.code_verifier, &login_state.nonce)
+        .await
+    {
+        Ok(claims) => claims,
+        Err(e @ OidcError::Http(_)) => {
+            log::error!("OIDC error: {}", e);
+            return HttpResponse::BadGateway().json(serde_json::json!({
+                "error": "Identity provider is unavailable"
+            }));
+        }
+        Err(e) => {
+            log::warn!("OIDC sign-in rejected: {}", e);
+            return HttpResponse::Unauthorized().json(serde_json::json!({
+                "error": "Single sign-on failed"
+            }));
+        }
+    };
+
+    let Some(email) = claims.email.as_deref().filter(|e| !e.is_empty()) else {
+        return HttpResponse::Forbidden().json(serde_json::json!({
+            "error": "The identity provider did not share an email address"
+        }));
+    };
+    if claims.email_verified == Some(false) {
+        return HttpResponse::Forbidden().json(serde_json::json!({
+            "error": "Email address is not verified"
+        }));
+    }
+    if !oidc.email_allowed(email) {
+        return HttpResponse::Forbidden().json(serde_json::json!({
+            "error": "Email domain is not allowed to sign in"
+        }));
+    }
+
+    let user = match oidc_user(&pool, &claims, email).await {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+    if user.deactivated_at.is_some() {
+        return account_deactivated();
+    }
+
+    session_or_mfa_challenge(&pool, &config, user).await
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    register,
+    login,
+    verify_mfa,
+    oidc_authorize,
+    oidc_callback,
+))]
+pub struct AuthApi;
+
 pub fn auth_routes() -> actix_web::Scope {
     web::scope("/auth")
         .service(register)
         .service(login)
+        .service(verify_mfa)
+        .service(oidc_authorize)
+        .service(oidc_callback)
 }
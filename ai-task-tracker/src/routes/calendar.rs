@@ -0,0 +1,106 @@
+use actix_web::{get, web, HttpResponse, Responder};
+use sqlx::PgPool;
+use utoipa::OpenApi;
+use uuid::Uuid;
+
+use crate::{
+    db::{across_organizations, find_calendar_token, find_user_by_id, list_calendar_tasks},
+    openapi::ErrorResponse,
+    services::{render_calendar, CalendarEntryKind},
+};
+
+/// Public iCalendar feed; the secret token in the URL is the only credential.
+#[utoipa::path(
+    params(
+        ("project_id" = Option<Uuid>, Query, description = "Only tasks in this project"),
+        ("type" = Option<String>, Query, description = "`event` (default) or `todo` entries"),
+    ),
+    responses(
+        (status = 200, description = "The iCalendar feed", body = String, content_type = "text/calendar"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Calendar not found", body = ErrorResponse),
+    ),
+    security(()),
+)]
+#[get("/{token}.ics")]
+async fn calendar_feed_handler(
+    pool: web::Data<PgPool>,
+    path: web::Path<String>,
+    query: web::Query<std::collections::HashMap<String, String>>,
+) -> impl Responder {
+    let token = path.into_inner();
+
+    let project_id = match query.get("project_id").map(|id| Uuid::parse_str(id)) {
+        Some(Ok(id)) => Some(id),
+        Some(Err(_)) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "Invalid project_id"
+            }));
+        }
+        None => None,
+    };
+    let kind = match query.get("type").map(|t| t.parse::<CalendarEntryKind>()) {
+        Some(Ok(kind)) => kind,
+        Some(Err(e)) => return HttpResponse::BadRequest().json(serde_json::json!({ "error": e })),
+        None => CalendarEntryKind::Event,
+    };
+
+    let calendar_token = match find_calendar_token(&pool, &token).await {
+        Ok(Some(calendar_token)) => calendar_token,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Calendar not found"
+            }));
+        }
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }));
+        }
+    };
+
+    // A deactivated user's feed goes dark with the rest of their access
+    let user = match find_user_by_id(&pool, calendar_token.user_id).await {
+        Ok(Some(user)) if user.deactivated_at.is_none() => user,
+        Ok(_) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Calendar not found"
+            }));
+        }
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }));
+        }
+    };
+
+    // The feed has no session to take an organization from; it covers every
+    // organization the user belongs to
+    match across_organizations(list_calendar_tasks(&pool, user.id, project_id)).await {
+        Ok(tasks) => {
+            let name = format!("Tasks for {}", user.full_name);
+            HttpResponse::Ok()
+                .content_type("text/calendar; charset=utf-8")
+                .insert_header(("Cache-Control", "private, max-age=300"))
+                .body(render_calendar(&name, &tasks, kind))
+        }
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }))
+        }
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    calendar_feed_handler,
+))]
+pub struct CalendarApi;
+
+pub fn calendar_routes() -> actix_web::Scope {
+    web::scope("/calendar").service(calendar_feed_handler)
+}
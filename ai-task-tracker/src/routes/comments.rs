@@ -0,0 +1,377 @@
+use std::collections::HashMap;
+
+use actix_web::{delete, get, post, put, web, HttpMessage, HttpRequest, HttpResponse, Responder};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    db::{
+        create_comment, find_comment, find_task_by_id, find_user_by_id, list_comment_mentions,
+        list_comments, list_users_by_emails, soft_delete_comment, update_comment,
+    },
+    models::{
+        Comment, CommentThread, CommentWithAuthor, CreateCommentRequest, NewComment,
+        NewNotification, NotificationKind, UpdateCommentRequest, UserRole,
+    },
+    middleware::CurrentOrganization,
+    openapi::ErrorResponse,
+    services::{extract_mentions, render_markdown},
+    utils::Claims,
+};
+
+const MAX_COMMENT_LENGTH: usize = 10_000;
+
+fn validate_body(body: &str) -> Result<(), String> {
+    if body.trim().is_empty() {
+        return Err("Comment body cannot be empty".to_string());
+    }
+    if body.chars().count() > MAX_COMMENT_LENGTH {
+        return Err(format!("Comment body exceeds {} characters", MAX_COMMENT_LENGTH));
+    }
+    Ok(())
+}
+
+/// Resolves `@email` mentions to members of the organization; other addresses stay plain text.
+async fn resolve_mentions(pool: &PgPool, organization_id: Uuid, body: &str) -> Result<Vec<Uuid>, sqlx::Error> {
+    let emails = extract_mentions(body);
+    if emails.is_empty() {
+        return Ok(Vec::new());
+    }
+    let users = list_users_by_emails(pool, organization_id, &emails).await?;
+    Ok(users.into_iter().map(|u| u.id).collect())
+}
+
+/// Nests comments under their parents. Deleted comments are kept only while
+/// they still have replies, so threads don't lose their shape.
+fn build_threads(comments: Vec<CommentWithAuthor>) -> Vec<CommentThread> {
+    let mut children: HashMap<Option<Uuid>, Vec<CommentWithAuthor>> = HashMap::new();
+    for comment in comments {
+        children.entry(comment.parent_comment_id).or_default().push(comment);
+    }
+
+    fn collect(
+        parent: Option<Uuid>,
+        children: &mut HashMap<Option<Uuid>, Vec<CommentWithAuthor>>,
+    ) -> Vec<CommentThread> {
+        let Some(comments) = children.remove(&parent) else {
+            return Vec::new();
+        };
+        comments
+            .into_iter()
+            .filter_map(|comment| {
+                let replies = collect(Some(comment.id), children);
+                if comment.deleted_at.is_some() && replies.is_empty() {
+                    None
+                } else {
+                    Some(CommentThread { comment, replies })
+                }
+            })
+            .collect()
+    }
+
+    collect(None, &mut children)
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Top-level comments with their replies", body = Vec<CommentThread>),
+    ),
+)]
+#[get("/{id}/comments")]
+pub async fn list_comments_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    let task_id = path.into_inner();
+
+    match list_comments(&pool, organization.id, task_id).await {
+        Ok(comments) => HttpResponse::Ok().json(build_threads(comments)),
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }))
+        }
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 201, description = "The new comment", body = Comment),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Task or parent comment not found", body = ErrorResponse),
+    ),
+)]
+#[post("/{id}/comments")]
+pub async fn create_comment_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+    req: web::Json<CreateCommentRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let task_id = path.into_inner();
+
+    let author_id = match http_req.extensions().get::<Claims>() {
+        Some(claims) => match Uuid::parse_str(&claims.sub) {
+            Ok(id) => id,
+            Err(_) => {
+                return HttpResponse::Unauthorized().json(serde_json::json!({
+                    "error": "Invalid user ID"
+                }))
+            }
+        },
+        None => {
+            return HttpResponse::Unauthorized().json(serde_json::json!({
+                "error": "Unauthorized"
+            }))
+        }
+    };
+
+    if let Err(e) = validate_body(&req.body) {
+        return HttpResponse::BadRequest().json(serde_json::json!({ "error": e }));
+    }
+
+    let task = match find_task_by_id(&pool, organization.id, task_id).await {
+        Ok(Some(task)) => task,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Task not found"
+            }));
+        }
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }));
+        }
+    };
+
+    let parent = match req.parent_comment_id {
+        Some(parent_id) => match find_comment(&pool, organization.id, task_id, parent_id).await {
+            Ok(Some(parent)) if parent.deleted_at.is_none() => Some(parent),
+            Ok(_) => {
+                return HttpResponse::BadRequest().json(serde_json::json!({
+                    "error": "Parent comment not found on this task"
+                }));
+            }
+            Err(e) => {
+                log::error!("Database error: {}", e);
+                return HttpResponse::InternalServerError().json(serde_json::json!({
+                    "error": "Internal server error"
+                }));
+            }
+        },
+        None => None,
+    };
+
+    let (author, mentions) = match tokio::try_join!(
+        find_user_by_id(&pool, author_id),
+        resolve_mentions(&pool, organization.id, &req.body)
+    ) {
+        Ok((Some(author), mentions)) => (author, mentions),
+        Ok((None, _)) => {
+            return HttpResponse::Unauthorized().json(serde_json::json!({
+                "error": "User not found"
+            }));
+        }
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }));
+        }
+    };
+
+    let mut notifications: Vec<NewNotification> = mentions
+        .iter()
+        .filter(|id| **id != author_id)
+        .map(|id| NewNotification {
+            user_id: *id,
+            kind: NotificationKind::Mention,
+            message: format!("{} mentioned you on \"{}\"", author.full_name, task.title),
+        })
+        .collect();
+    // Reply notifications go to the parent's author unless a mention already covers them
+    if let Some(parent_author) = parent.as_ref().and_then(|p| p.author_id) {
+        if parent_author != author_id && !mentions.contains(&parent_author) {
+            notifications.push(NewNotification {
+                user_id: parent_author,
+                kind: NotificationKind::Reply,
+                message: format!("{} replied to your comment on \"{}\"", author.full_name, task.title),
+            });
+        }
+    }
+
+    let new = NewComment {
+        task_id,
+        parent_comment_id: parent.map(|p| p.id),
+        author_id,
+        body_html: render_markdown(&req.body),
+        body: req.into_inner().body,
+    };
+
+    match create_comment(&pool, &new, &mentions, &notifications).await {
+        Ok(comment) => HttpResponse::Created().json(comment),
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }))
+        }
+    }
+}
+
+/// Loads a live comment and checks that the caller may change it.
+async fn authorize_comment_change(
+    pool: &PgPool,
+    organization_id: Uuid,
+    http_req: &HttpRequest,
+    task_id: Uuid,
+    comment_id: Uuid,
+    allow_admin: bool,
+) -> Result<Comment, HttpResponse> {
+    let (user_id, is_admin) = match http_req.extensions().get::<Claims>() {
+        Some(claims) => match Uuid::parse_str(&claims.sub) {
+            Ok(id) => (id, claims.role == UserRole::Admin.to_string()),
+            Err(_) => {
+                return Err(HttpResponse::Unauthorized().json(serde_json::json!({
+                    "error": "Invalid user ID"
+                })))
+            }
+        },
+        None => {
+            return Err(HttpResponse::Unauthorized().json(serde_json::json!({
+                "error": "Unauthorized"
+            })))
+        }
+    };
+
+    let comment = match find_comment(pool, organization_id, task_id, comment_id).await {
+        Ok(Some(comment)) if comment.deleted_at.is_none() => comment,
+        Ok(_) => {
+            return Err(HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Comment not found"
+            })));
+        }
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            return Err(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            })));
+        }
+    };
+
+    if comment.author_id != Some(user_id) && !(allow_admin && is_admin) {
+        return Err(HttpResponse::Forbidden().json(serde_json::json!({
+            "error": "Only the author can change this comment"
+        })));
+    }
+
+    Ok(comment)
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The updated comment", body = Comment),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 403, description = "Not the comment's author", body = ErrorResponse),
+        (status = 404, description = "Comment not found", body = ErrorResponse),
+    ),
+)]
+#[put("/{id}/comments/{comment_id}")]
+pub async fn update_comment_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    path: web::Path<(Uuid, Uuid)>,
+    req: web::Json<UpdateCommentRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let (task_id, comment_id) = path.into_inner();
+
+    if let Err(e) = validate_body(&req.body) {
+        return HttpResponse::BadRequest().json(serde_json::json!({ "error": e }));
+    }
+
+    let comment = match authorize_comment_change(&pool, organization.id, &http_req, task_id, comment_id, false).await {
+        Ok(comment) => comment,
+        Err(response) => return response,
+    };
+
+    let lookups = tokio::try_join!(
+        find_task_by_id(&pool, organization.id, task_id),
+        find_user_by_id(&pool, comment.author_id.unwrap_or_default()),
+        list_comment_mentions(&pool, comment.id),
+        resolve_mentions(&pool, organization.id, &req.body)
+    );
+    let (task, author, previous, mentions) = match lookups {
+        Ok((Some(task), Some(author), previous, mentions)) => (task, author, previous, mentions),
+        Ok(_) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Comment not found"
+            }));
+        }
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }));
+        }
+    };
+
+    // Only users newly mentioned by this edit are notified
+    let notifications: Vec<NewNotification> = mentions
+        .iter()
+        .filter(|id| Some(**id) != comment.author_id && !previous.contains(id))
+        .map(|id| NewNotification {
+            user_id: *id,
+            kind: NotificationKind::Mention,
+            message: format!("{} mentioned you on \"{}\"", author.full_name, task.title),
+        })
+        .collect();
+
+    let body_html = render_markdown(&req.body);
+    match update_comment(&pool, &comment, &req.body, &body_html, &mentions, &notifications).await {
+        Ok(comment) => HttpResponse::Ok().json(comment),
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }))
+        }
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 204, description = "Comment deleted"),
+        (status = 403, description = "Not the comment's author", body = ErrorResponse),
+        (status = 404, description = "Comment not found", body = ErrorResponse),
+    ),
+)]
+#[delete("/{id}/comments/{comment_id}")]
+pub async fn delete_comment_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    path: web::Path<(Uuid, Uuid)>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let (task_id, comment_id) = path.into_inner();
+
+    // Admins may remove comments for moderation, but never edit them
+    let comment = match authorize_comment_change(&pool, organization.id, &http_req, task_id, comment_id, true).await {
+        Ok(comment) => comment,
+        Err(response) => return response,
+    };
+
+    match soft_delete_comment(&pool, comment.id).await {
+        Ok(()) => HttpResponse::NoContent().finish(),
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }))
+        }
+    }
+}
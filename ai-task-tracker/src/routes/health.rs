@@ -0,0 +1,80 @@
+use actix_web::{get, web, HttpResponse, Responder};
+use sqlx::PgPool;
+use utoipa::OpenApi;
+
+use crate::{db::pending_migrations, models::ReadinessReport, services::Metrics};
+
+/// Liveness probe: answers as long as the process is serving requests.
+#[utoipa::path(
+    tag = "health",
+    responses(
+        (status = 200, description = "The server is running"),
+    ),
+    security(()),
+)]
+#[get("/healthz")]
+async fn healthz_handler() -> impl Responder {
+    HttpResponse::Ok().json(serde_json::json!({ "status": "ok" }))
+}
+
+/// Readiness probe: the database is reachable and every migration has run.
+#[utoipa::path(
+    tag = "health",
+    responses(
+        (status = 200, description = "Ready to serve traffic", body = ReadinessReport),
+        (status = 503, description = "Not ready", body = ReadinessReport),
+    ),
+    security(()),
+)]
+#[get("/readyz")]
+async fn readyz_handler(pool: web::Data<PgPool>) -> impl Responder {
+    let report = match pending_migrations(&pool).await {
+        Ok(pending) => ReadinessReport {
+            ready: pending.is_empty(),
+            database: true,
+            pending_migrations: pending,
+        },
+        Err(e) => {
+            log::warn!("Readiness check failed: {}", e);
+            ReadinessReport {
+                ready: false,
+                database: false,
+                pending_migrations: Vec::new(),
+            }
+        }
+    };
+
+    if report.ready {
+        HttpResponse::Ok().json(report)
+    } else {
+        HttpResponse::ServiceUnavailable().json(report)
+    }
+}
+
+/// Request, connection pool and business metrics in the Prometheus text format.
+#[utoipa::path(
+    tag = "health",
+    responses(
+        (status = 200, description = "Current metrics", body = String, content_type = "text/plain; version=0.0.4"),
+    ),
+    security(()),
+)]
+#[get("/metrics")]
+async fn metrics_handler(metrics: web::Data<Metrics>, pool: web::Data<PgPool>) -> impl Responder {
+    HttpResponse::Ok()
+        .content_type(prometheus::TEXT_FORMAT)
+        .body(metrics.render(&pool))
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    healthz_handler,
+    readyz_handler,
+    metrics_handler,
+))]
+pub struct HealthApi;
+
+/// Probes and metrics, served outside `/api` and without authentication.
+pub fn configure_health(cfg: &mut web::ServiceConfig) {
+    cfg.service(healthz_handler).service(readyz_handler).service(metrics_handler);
+}
@@ -0,0 +1,65 @@
+use actix_web::{HttpMessage, HttpRequest, HttpResponse};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{db::is_platform_admin, models::UserRole, utils::Claims};
+
+/// The authenticated caller's id and claims.
+pub(crate) fn caller_claims(http_req: &HttpRequest) -> Option<(Uuid, Claims)> {
+    let claims = http_req.extensions().get::<Claims>()?.clone();
+    let user_id = Uuid::parse_str(&claims.sub).ok()?;
+    Some((user_id, claims))
+}
+
+/// The authenticated caller's id, e.g. for attributing activity.
+pub(crate) fn caller_id(http_req: &HttpRequest) -> Option<Uuid> {
+    http_req
+        .extensions()
+        .get::<Claims>()
+        .and_then(|claims| Uuid::parse_str(&claims.sub).ok())
+}
+
+/// The caller's id and whether they are a global admin.
+pub(crate) fn caller(http_req: &HttpRequest) -> Option<(Uuid, bool)> {
+    caller_claims(http_req).map(|(user_id, claims)| (user_id, claims.role.parse::<UserRole>() == Ok(UserRole::Admin)))
+}
+
+/// The caller's id if they are a global admin, otherwise the error response.
+/// `action` completes "Only admins can ..." in the 403.
+pub(crate) fn require_admin(http_req: &HttpRequest, action: &str) -> Result<Uuid, Box<HttpResponse>> {
+    match caller(http_req) {
+        Some((user_id, true)) => Ok(user_id),
+        Some(_) => Err(Box::new(HttpResponse::Forbidden().json(serde_json::json!({
+            "error": format!("Only admins can {}", action)
+        })))),
+        None => Err(Box::new(unauthorized())),
+    }
+}
+
+/// The caller's id if they are a platform admin, who manages what every
+/// organization shares, otherwise the error response.
+pub(crate) async fn require_platform_admin(
+    pool: &PgPool,
+    http_req: &HttpRequest,
+    action: &str,
+) -> Result<Uuid, Box<HttpResponse>> {
+    let admin_id = require_admin(http_req, action)?;
+    match is_platform_admin(pool, admin_id).await {
+        Ok(true) => Ok(admin_id),
+        Ok(false) => Err(Box::new(HttpResponse::Forbidden().json(serde_json::json!({
+            "error": format!("Only platform admins can {}", action)
+        })))),
+        Err(e) => Err(Box::new(internal_error(e))),
+    }
+}
+
+pub(crate) fn unauthorized() -> HttpResponse {
+    HttpResponse::Unauthorized().json(serde_json::json!({"error": "Unauthorized"}))
+}
+
+pub(crate) fn internal_error(e: sqlx::Error) -> HttpResponse {
+    log::error!("Database error: {}", e);
+    HttpResponse::InternalServerError().json(serde_json::json!({
+        "error": "Internal server error"
+    }))
+}
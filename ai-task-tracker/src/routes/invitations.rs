@@ -0,0 +1,194 @@
+use actix_web::{get, post, web, HttpMessage, HttpRequest, HttpResponse, Responder};
+use sqlx::PgPool;
+use utoipa::OpenApi;
+use uuid::Uuid;
+
+use crate::{
+    db::{
+        accept_team_invitation, decline_team_invitation, find_team_invitation_by_id,
+        list_pending_invitations_for_user,
+    },
+    models::{TeamInvitation, TeamMember},
+    openapi::ErrorResponse,
+    utils::Claims,
+};
+
+/// An invitation belongs to the caller if it was claimed by them or sent to their email.
+fn is_invitee(invitation: &TeamInvitation, user_id: Uuid, email: &str) -> bool {
+    invitation.invitee_id == Some(user_id) || invitation.email.eq_ignore_ascii_case(email)
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Pending invitations for the caller", body = Vec<TeamInvitation>),
+    ),
+)]
+#[get("")]
+async fn list_my_invitations_handler(pool: web::Data<PgPool>, req: HttpRequest) -> impl Responder {
+    let claims = match req.extensions().get::<Claims>() {
+        Some(claims) => claims.clone(),
+        None => {
+            return HttpResponse::Unauthorized().json(serde_json::json!({
+                "error": "Unauthorized"
+            }));
+        }
+    };
+
+    let user_id = match Uuid::parse_str(&claims.sub) {
+        Ok(id) => id,
+        Err(_) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "Invalid user ID"
+            }));
+        }
+    };
+
+    match list_pending_invitations_for_user(&pool, user_id, &claims.email).await {
+        Ok(invitations) => HttpResponse::Ok().json(invitations),
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }))
+        }
+    }
+}
+
+/// Loads a pending invitation and checks that it is addressed to the caller.
+async fn load_own_pending_invitation(
+    pool: &PgPool,
+    invitation_id: Uuid,
+    user_id: Uuid,
+    email: &str,
+) -> Result<TeamInvitation, HttpResponse> {
+    match find_team_invitation_by_id(pool, invitation_id).await {
+        Ok(Some(invitation)) if is_invitee(&invitation, user_id, email) => {
+            if invitation.status != "pending" {
+                return Err(HttpResponse::Conflict().json(serde_json::json!({
+                    "error": format!("Invitation already {}", invitation.status)
+                })));
+            }
+            Ok(invitation)
+        }
+        Ok(_) => Err(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Invitation not found"
+        }))),
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            Err(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            })))
+        }
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The new team membership", body = TeamMember),
+        (status = 404, description = "Invitation not found", body = ErrorResponse),
+        (status = 409, description = "Invitation no longer pending", body = ErrorResponse),
+    ),
+)]
+#[post("/{id}/accept")]
+async fn accept_invitation_handler(
+    pool: web::Data<PgPool>,
+    path: web::Path<Uuid>,
+    req: HttpRequest,
+) -> impl Responder {
+    let invitation_id = path.into_inner();
+
+    let claims = match req.extensions().get::<Claims>() {
+        Some(claims) => claims.clone(),
+        None => {
+            return HttpResponse::Unauthorized().json(serde_json::json!({
+                "error": "Unauthorized"
+            }));
+        }
+    };
+
+    let user_id = match Uuid::parse_str(&claims.sub) {
+        Ok(id) => id,
+        Err(_) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "Invalid user ID"
+            }));
+        }
+    };
+
+    if let Err(resp) = load_own_pending_invitation(&pool, invitation_id, user_id, &claims.email).await {
+        return resp;
+    }
+
+    match accept_team_invitation(&pool, invitation_id, user_id).await {
+        Ok(member) => HttpResponse::Ok().json(member),
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }))
+        }
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The declined invitation", body = TeamInvitation),
+        (status = 404, description = "Invitation not found", body = ErrorResponse),
+        (status = 409, description = "Invitation no longer pending", body = ErrorResponse),
+    ),
+)]
+#[post("/{id}/decline")]
+async fn decline_invitation_handler(
+    pool: web::Data<PgPool>,
+    path: web::Path<Uuid>,
+    req: HttpRequest,
+) -> impl Responder {
+    let invitation_id = path.into_inner();
+
+    let claims = match req.extensions().get::<Claims>() {
+        Some(claims) => claims.clone(),
+        None => {
+            return HttpResponse::Unauthorized().json(serde_json::json!({
+                "error": "Unauthorized"
+            }));
+        }
+    };
+
+    let user_id = match Uuid::parse_str(&claims.sub) {
+        Ok(id) => id,
+        Err(_) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "Invalid user ID"
+            }));
+        }
+    };
+
+    if let Err(resp) = load_own_pending_invitation(&pool, invitation_id, user_id, &claims.email).await {
+        return resp;
+    }
+
+    match decline_team_invitation(&pool, invitation_id, user_id).await {
+        Ok(invitation) => HttpResponse::Ok().json(invitation),
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }))
+        }
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    list_my_invitations_handler,
+    accept_invitation_handler,
+    decline_invitation_handler,
+))]
+pub struct InvitationApi;
+
+pub fn invitation_routes() -> actix_web::Scope {
+    web::scope("/invitations")
+        .service(list_my_invitations_handler)
+        .service(accept_invitation_handler)
+        .service(decline_invitation_handler)
+}
@@ -0,0 +1,107 @@
+use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder};
+use sqlx::PgPool;
+use utoipa::OpenApi;
+use uuid::Uuid;
+
+use crate::{
+    db::{find_job, list_jobs, retry_dead_job},
+    models::{Job, JobQuery, JobStatus},
+    openapi::ErrorResponse,
+    routes::helpers::{internal_error, require_platform_admin},
+};
+
+const DEFAULT_JOB_LIMIT: i64 = 50;
+const MAX_JOB_LIMIT: i64 = 500;
+
+/// Jobs in one state, most recently updated first. Without a `status`, lists
+/// the dead jobs: those that failed on every attempt.
+#[utoipa::path(
+    params(JobQuery),
+    responses(
+        (status = 200, description = "The matching jobs", body = Vec<Job>),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 403, description = "Platform admins only", body = ErrorResponse),
+    ),
+)]
+#[get("")]
+async fn list_jobs_handler(
+    pool: web::Data<PgPool>,
+    query: web::Query<JobQuery>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    if let Err(response) = require_platform_admin(&pool, &http_req, "manage background jobs").await {
+        return *response;
+    }
+
+    let status = match query.status.as_deref().map(str::parse::<JobStatus>) {
+        None => JobStatus::Dead,
+        Some(Ok(status)) => status,
+        Some(Err(e)) => return HttpResponse::BadRequest().json(serde_json::json!({ "error": e })),
+    };
+    let limit = query.limit.unwrap_or(DEFAULT_JOB_LIMIT).clamp(1, MAX_JOB_LIMIT);
+
+    match list_jobs(&pool, &status.to_string(), query.kind.as_deref(), limit).await {
+        Ok(jobs) => HttpResponse::Ok().json(jobs),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The job", body = Job),
+        (status = 403, description = "Platform admins only", body = ErrorResponse),
+        (status = 404, description = "Job not found", body = ErrorResponse),
+    ),
+)]
+#[get("/{id}")]
+async fn get_job_handler(pool: web::Data<PgPool>, path: web::Path<Uuid>, http_req: HttpRequest) -> impl Responder {
+    if let Err(response) = require_platform_admin(&pool, &http_req, "manage background jobs").await {
+        return *response;
+    }
+
+    match find_job(&pool, path.into_inner()).await {
+        Ok(Some(job)) => HttpResponse::Ok().json(job),
+        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Job not found"
+        })),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Queues a dead job again with a fresh set of attempts.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The job, pending again", body = Job),
+        (status = 403, description = "Platform admins only", body = ErrorResponse),
+        (status = 404, description = "No dead job with this id", body = ErrorResponse),
+    ),
+)]
+#[post("/{id}/retry")]
+async fn retry_job_handler(pool: web::Data<PgPool>, path: web::Path<Uuid>, http_req: HttpRequest) -> impl Responder {
+    if let Err(response) = require_platform_admin(&pool, &http_req, "manage background jobs").await {
+        return *response;
+    }
+
+    match retry_dead_job(&pool, path.into_inner()).await {
+        Ok(Some(job)) => HttpResponse::Ok().json(job),
+        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Dead job not found"
+        })),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    list_jobs_handler,
+    get_job_handler,
+    retry_job_handler,
+))]
+pub struct JobApi;
+
+pub fn job_routes() -> actix_web::Scope {
+    web::scope("/admin/jobs")
+        .service(list_jobs_handler)
+        .service(get_job_handler)
+        .service(retry_job_handler)
+}
@@ -0,0 +1,360 @@
+use actix_web::{delete, get, post, put, web, HttpRequest, HttpResponse, Responder};
+use sqlx::PgPool;
+use utoipa::OpenApi;
+use uuid::Uuid;
+
+use crate::{
+    db::{
+        add_task_label, create_label, delete_label, find_label_by_id, find_label_for_task,
+        find_task_by_id, list_project_labels, list_task_labels, list_team_labels,
+        remove_task_label, update_label,
+    },
+    models::{AddTaskLabelRequest, CreateLabelRequest, Label, LabelQuery, UpdateLabelRequest},
+    middleware::CurrentOrganization,
+    openapi::ErrorResponse,
+    routes::helpers::caller_id,
+};
+
+const DEFAULT_LABEL_COLOR: &str = "#6b7280";
+const MAX_LABEL_NAME_LENGTH: usize = 50;
+
+fn validate_name(name: &str) -> Result<(), String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Label name cannot be empty".to_string());
+    }
+    if name.chars().count() > MAX_LABEL_NAME_LENGTH {
+        return Err(format!("Label name exceeds {} characters", MAX_LABEL_NAME_LENGTH));
+    }
+    Ok(())
+}
+
+fn validate_color(color: &str) -> Result<(), String> {
+    let valid = color.len() == 7
+        && color.starts_with('#')
+        && color[1..].chars().all(|c| c.is_ascii_hexdigit());
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid color {}: expected #rrggbb", color))
+    }
+}
+
+fn duplicate_name_response() -> HttpResponse {
+    HttpResponse::Conflict().json(serde_json::json!({
+        "error": "A label with this name already exists"
+    }))
+}
+
+#[utoipa::path(
+    responses(
+        (status = 201, description = "The new label", body = Label),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Team or project not found", body = ErrorResponse),
+        (status = 409, description = "A label with this name already exists", body = ErrorResponse),
+    ),
+)]
+#[post("")]
+async fn create_label_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    req: web::Json<CreateLabelRequest>,
+) -> impl Responder {
+    if req.team_id.is_some() == req.project_id.is_some() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Specify exactly one of team_id or project_id"
+        }));
+    }
+
+    let color = req.color.as_deref().unwrap_or(DEFAULT_LABEL_COLOR).to_lowercase();
+    if let Err(e) = validate_name(&req.name).and_then(|_| validate_color(&color)) {
+        return HttpResponse::BadRequest().json(serde_json::json!({ "error": e }));
+    }
+
+    match create_label(&pool, organization.id, &req, &color).await {
+        Ok(Some(label)) => HttpResponse::Created().json(label),
+        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Team or project not found"
+        })),
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => duplicate_name_response(),
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }))
+        }
+    }
+}
+
+#[utoipa::path(
+    params(LabelQuery),
+    responses(
+        (status = 200, description = "The labels", body = Vec<Label>),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+)]
+#[get("")]
+async fn list_labels_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    query: web::Query<LabelQuery>,
+) -> impl Responder {
+    let result = match (query.team_id, query.project_id) {
+        (None, Some(project_id)) => list_project_labels(&pool, organization.id, project_id).await,
+        (Some(team_id), None) => list_team_labels(&pool, organization.id, team_id).await,
+        _ => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "Specify exactly one of team_id or project_id"
+            }));
+        }
+    };
+
+    match result {
+        Ok(labels) => HttpResponse::Ok().json(labels),
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }))
+        }
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The label", body = Label),
+        (status = 404, description = "Label not found", body = ErrorResponse),
+    ),
+)]
+#[get("/{id}")]
+async fn get_label_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    match find_label_by_id(&pool, organization.id, path.into_inner()).await {
+        Ok(Some(label)) => HttpResponse::Ok().json(label),
+        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Label not found"
+        })),
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }))
+        }
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The updated label", body = Label),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Label not found", body = ErrorResponse),
+        (status = 409, description = "A label with this name already exists", body = ErrorResponse),
+    ),
+)]
+#[put("/{id}")]
+async fn update_label_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+    req: web::Json<UpdateLabelRequest>,
+) -> impl Responder {
+    let mut req = req.into_inner();
+    req.color = req.color.map(|c| c.to_lowercase());
+
+    let validation = req
+        .name
+        .as_deref()
+        .map_or(Ok(()), validate_name)
+        .and_then(|_| req.color.as_deref().map_or(Ok(()), validate_color));
+    if let Err(e) = validation {
+        return HttpResponse::BadRequest().json(serde_json::json!({ "error": e }));
+    }
+
+    match update_label(&pool, organization.id, path.into_inner(), &req).await {
+        Ok(Some(label)) => HttpResponse::Ok().json(label),
+        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Label not found"
+        })),
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => duplicate_name_response(),
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }))
+        }
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 204, description = "Label deleted"),
+        (status = 404, description = "Label not found", body = ErrorResponse),
+    ),
+)]
+#[delete("/{id}")]
+async fn delete_label_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    match delete_label(&pool, organization.id, path.into_inner()).await {
+        Ok(true) => HttpResponse::NoContent().finish(),
+        Ok(false) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Label not found"
+        })),
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }))
+        }
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The task's labels", body = Vec<Label>),
+    ),
+)]
+#[get("/{id}/labels")]
+pub async fn list_task_labels_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    match list_task_labels(&pool, organization.id, path.into_inner()).await {
+        Ok(labels) => HttpResponse::Ok().json(labels),
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }))
+        }
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 201, description = "Label added", body = Label),
+        (status = 200, description = "The task already had the label", body = Label),
+        (status = 400, description = "Label not available in the task's project", body = ErrorResponse),
+        (status = 404, description = "Task or label not found", body = ErrorResponse),
+    ),
+)]
+#[post("/{id}/labels")]
+pub async fn add_task_label_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+    req: web::Json<AddTaskLabelRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let task_id = path.into_inner();
+
+    match find_task_by_id(&pool, organization.id, task_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Task not found"
+            }));
+        }
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }));
+        }
+    }
+
+    let label = match find_label_for_task(&pool, organization.id, task_id, req.label_id).await {
+        Ok(Some(label)) => label,
+        Ok(None) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "Label is not available in this task's project or team"
+            }));
+        }
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }));
+        }
+    };
+
+    match add_task_label(&pool, task_id, &label, caller_id(&http_req)).await {
+        Ok(true) => HttpResponse::Created().json(label),
+        Ok(false) => HttpResponse::Ok().json(label),
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }))
+        }
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 204, description = "Label removed"),
+        (status = 404, description = "Task or label not found", body = ErrorResponse),
+    ),
+)]
+#[delete("/{id}/labels/{label_id}")]
+pub async fn remove_task_label_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    path: web::Path<(Uuid, Uuid)>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let (task_id, label_id) = path.into_inner();
+
+    let label = match find_label_by_id(&pool, organization.id, label_id).await {
+        Ok(Some(label)) => label,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Label not found"
+            }));
+        }
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }));
+        }
+    };
+
+    match remove_task_label(&pool, task_id, &label, caller_id(&http_req)).await {
+        Ok(true) => HttpResponse::NoContent().finish(),
+        Ok(false) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Task does not have this label"
+        })),
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }))
+        }
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    create_label_handler,
+    list_labels_handler,
+    get_label_handler,
+    update_label_handler,
+    delete_label_handler,
+))]
+pub struct LabelApi;
+
+pub fn label_routes() -> actix_web::Scope {
+    web::scope("/labels")
+        .service(create_label_handler)
+        .service(list_labels_handler)
+        .service(get_label_handler)
+        .service(update_label_handler)
+        .service(delete_label_handler)
+}
@@ -0,0 +1,309 @@
+use actix_web::{delete, get, post, put, web, HttpRequest, HttpResponse, Responder};
+use chrono::Utc;
+use sqlx::PgPool;
+use utoipa::OpenApi;
+use uuid::Uuid;
+
+use crate::{
+    config::Config,
+    db::{
+        disable_totp, enable_totp, find_totp_state, find_user_by_id, get_mfa_status, list_mfa_required_roles,
+        replace_recovery_codes, set_mfa_required_roles, set_pending_totp_secret,
+    },
+    models::{
+        AuthResponse, MfaCodeRequest, MfaPending, MfaPolicy, MfaStatus, RecoveryCodesResponse, TotpEnrollment,
+    },
+    openapi::ErrorResponse,
+    routes::helpers::{caller_claims, caller_id, internal_error, require_platform_admin},
+    services::{check_mfa_code, generate_recovery_codes, MfaCheck},
+    utils::{create_jwt, generate_totp_secret, totp_uri, verify_totp},
+};
+
+/// Shown as the account's provider in authenticator apps.
+const TOTP_ISSUER: &str = "AI Task Tracker";
+
+/// The response for a second factor that was not accepted, if it was not.
+async fn reject_code(pool: &PgPool, user_id: Uuid, code: &str) -> Option<HttpResponse> {
+    match check_mfa_code(pool, user_id, code).await {
+        Ok(MfaCheck::Accepted) => None,
+        Ok(MfaCheck::Rejected) => Some(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Invalid code"
+        }))),
+        Ok(MfaCheck::LockedOut) => Some(HttpResponse::TooManyRequests().json(serde_json::json!({
+            "error": "Too many invalid codes; try again later"
+        }))),
+        Err(e) => Some(internal_error(e)),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The caller's two-factor status", body = MfaStatus),
+    ),
+)]
+#[get("")]
+async fn get_mfa_status_handler(pool: web::Data<PgPool>, http_req: HttpRequest) -> impl Responder {
+    let Some(user_id) = caller_id(&http_req) else {
+        return HttpResponse::Unauthorized().json(serde_json::json!({"error": "Unauthorized"}));
+    };
+
+    match get_mfa_status(&pool, user_id).await {
+        Ok(Some(status)) => HttpResponse::Ok().json(status),
+        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "User not found"
+        })),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Starts TOTP enrollment with a new secret; confirm it with a code to turn
+/// two-factor authentication on.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The secret to add to an authenticator app", body = TotpEnrollment),
+        (status = 409, description = "Two-factor authentication is already enabled", body = ErrorResponse),
+    ),
+)]
+#[post("/totp")]
+async fn start_totp_enrollment(pool: web::Data<PgPool>, http_req: HttpRequest) -> impl Responder {
+    let Some((user_id, claims)) = caller_claims(&http_req) else {
+        return HttpResponse::Unauthorized().json(serde_json::json!({"error": "Unauthorized"}));
+    };
+
+    let secret = generate_totp_secret();
+    match set_pending_totp_secret(&pool, user_id, &secret).await {
+        Ok(true) => HttpResponse::Ok().json(TotpEnrollment {
+            otpauth_uri: totp_uri(&secret, &claims.email, TOTP_ISSUER),
+            secret,
+        }),
+        Ok(false) => HttpResponse::Conflict().json(serde_json::json!({
+            "error": "Two-factor authentication is already enabled"
+        })),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Turns two-factor authentication on and returns the recovery codes. When this
+/// completes a login that required enrollment, the session is returned as well.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Recovery codes, and the session when completing a login", body = RecoveryCodesResponse),
+        (status = 400, description = "Invalid code", body = ErrorResponse),
+        (status = 409, description = "Two-factor authentication is already enabled", body = ErrorResponse),
+    ),
+)]
+#[post("/totp/confirm")]
+async fn confirm_totp_enrollment(
+    pool: web::Data<PgPool>,
+    config: web::Data<Config>,
+    req: web::Json<MfaCodeRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let Some((user_id, claims)) = caller_claims(&http_req) else {
+        return HttpResponse::Unauthorized().json(serde_json::json!({"error": "Unauthorized"}));
+    };
+
+    let state = match find_totp_state(&pool, user_id).await {
+        Ok(Some(state)) => state,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "User not found"
+            }));
+        }
+        Err(e) => return internal_error(e),
+    };
+    if state.totp_enabled_at.is_some() {
+        return HttpResponse::Conflict().json(serde_json::json!({
+            "error": "Two-factor authentication is already enabled"
+        }));
+    }
+    let Some(secret) = state.totp_secret else {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Start enrollment first"
+        }));
+    };
+    let Some(step) = verify_totp(&secret, &req.code, Utc::now().timestamp()) else {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Invalid code"
+        }));
+    };
+
+    let (recovery_codes, hashes) = generate_recovery_codes();
+    if let Err(e) = enable_totp(&pool, user_id, step, &hashes).await {
+        return internal_error(e);
+    }
+
+    let session = if claims.mfa_pending == Some(MfaPending::Enroll) {
+        let user = match find_user_by_id(&pool, user_id).await {
+            Ok(Some(user)) => user,
+            Ok(None) => {
+                return HttpResponse::NotFound().json(serde_json::json!({
+                    "error": "User not found"
+                }));
+            }
+            Err(e) => return internal_error(e),
+        };
+        match create_jwt(user.id, &user.email, &user.role, None, &config.jwt) {
+            Ok(token) => Some(AuthResponse { token, user }),
+            Err(e) => {
+                log::error!("JWT creation error: {}", e);
+                return HttpResponse::InternalServerError().json(serde_json::json!({
+                    "error": "Internal server error"
+                }));
+            }
+        }
+    } else {
+        None
+    };
+
+    HttpResponse::Ok().json(RecoveryCodesResponse { recovery_codes, session })
+}
+
+/// Turns two-factor authentication off, given a current code. Not allowed when
+/// the user's role requires it.
+#[utoipa::path(
+    responses(
+        (status = 204, description = "Two-factor authentication turned off"),
+        (status = 400, description = "Invalid code", body = ErrorResponse),
+        (status = 403, description = "The caller's role requires two-factor authentication", body = ErrorResponse),
+        (status = 429, description = "Too many invalid codes", body = ErrorResponse),
+    ),
+)]
+#[delete("/totp")]
+async fn disable_totp_handler(
+    pool: web::Data<PgPool>,
+    req: web::Json<MfaCodeRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let Some(user_id) = caller_id(&http_req) else {
+        return HttpResponse::Unauthorized().json(serde_json::json!({"error": "Unauthorized"}));
+    };
+
+    match get_mfa_status(&pool, user_id).await {
+        Ok(Some(status)) if !status.enabled => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "Two-factor authentication is not enabled"
+            }));
+        }
+        Ok(Some(status)) if status.required => {
+            return HttpResponse::Forbidden().json(serde_json::json!({
+                "error": "Your role requires two-factor authentication"
+            }));
+        }
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "User not found"
+            }));
+        }
+        Err(e) => return internal_error(e),
+    }
+    if let Some(response) = reject_code(&pool, user_id, &req.code).await {
+        return response;
+    }
+
+    match disable_totp(&pool, user_id).await {
+        Ok(()) => HttpResponse::NoContent().finish(),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Replaces all recovery codes, given a current code.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The new recovery codes", body = RecoveryCodesResponse),
+        (status = 400, description = "Invalid code", body = ErrorResponse),
+        (status = 429, description = "Too many invalid codes", body = ErrorResponse),
+    ),
+)]
+#[post("/recovery-codes")]
+async fn regenerate_recovery_codes(
+    pool: web::Data<PgPool>,
+    req: web::Json<MfaCodeRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let Some(user_id) = caller_id(&http_req) else {
+        return HttpResponse::Unauthorized().json(serde_json::json!({"error": "Unauthorized"}));
+    };
+    if let Some(response) = reject_code(&pool, user_id, &req.code).await {
+        return response;
+    }
+
+    let (recovery_codes, hashes) = generate_recovery_codes();
+    match replace_recovery_codes(&pool, user_id, &hashes).await {
+        Ok(()) => HttpResponse::Ok().json(RecoveryCodesResponse {
+            recovery_codes,
+            session: None,
+        }),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Roles that must use two-factor authentication", body = MfaPolicy),
+        (status = 403, description = "Platform admins only", body = ErrorResponse),
+    ),
+)]
+#[get("/policy")]
+async fn get_mfa_policy(pool: web::Data<PgPool>, http_req: HttpRequest) -> impl Responder {
+    if let Err(response) = require_platform_admin(&pool, &http_req, "manage the two-factor policy").await {
+        return *response;
+    }
+
+    match list_mfa_required_roles(&pool).await {
+        Ok(roles) => HttpResponse::Ok().json(MfaPolicy {
+            required_roles: roles.iter().filter_map(|r| r.parse().ok()).collect(),
+        }),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Sets which roles must use two-factor authentication. Users with those roles
+/// who have not set it up are made to at their next login.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The updated policy", body = MfaPolicy),
+        (status = 403, description = "Platform admins only", body = ErrorResponse),
+    ),
+)]
+#[put("/policy")]
+async fn update_mfa_policy(
+    pool: web::Data<PgPool>,
+    req: web::Json<MfaPolicy>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let admin_id = match require_platform_admin(&pool, &http_req, "manage the two-factor policy").await {
+        Ok(id) => id,
+        Err(response) => return *response,
+    };
+
+    let roles: Vec<String> = req.required_roles.iter().map(|r| r.to_string()).collect();
+    match set_mfa_required_roles(&pool, &roles, admin_id).await {
+        Ok(()) => HttpResponse::Ok().json(req.into_inner()),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    get_mfa_status_handler,
+    start_totp_enrollment,
+    confirm_totp_enrollment,
+    disable_totp_handler,
+    regenerate_recovery_codes,
+    get_mfa_policy,
+    update_mfa_policy,
+))]
+pub struct MfaApi;
+
+pub fn mfa_routes() -> actix_web::Scope {
+    web::scope("/mfa")
+        .service(get_mfa_status_handler)
+        .service(start_totp_enrollment)
+        .service(confirm_totp_enrollment)
+        .service(disable_totp_handler)
+        .service(regenerate_recovery_codes)
+        .service(get_mfa_policy)
+        .service(update_mfa_policy)
+}
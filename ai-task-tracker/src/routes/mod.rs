@@ -5,6 +5,24 @@ pub mod projects;
 pub mod tasks;
 pub mod subtasks;
 pub mod task_history;
+pub mod invitations;
+pub mod analytics;
+pub mod calendar;
+pub mod attachments;
+pub mod comments;
+pub mod notifications;
+pub mod labels;
+pub mod time_entries;
+pub mod sprints;
+pub mod templates;
+pub mod api_tokens;
+pub mod mfa;
+pub mod health;
+pub mod jobs;
+pub mod admin;
+pub mod organizations;
+pub mod views;
+pub(crate) mod helpers;
 
 pub use auth::*;
 pub use users::*;
@@ -12,4 +30,18 @@ pub use teams::*;
 pub use projects::*;
 pub use tasks::*;
 pub use subtasks::*;
-pub use task_history::*;
+pub use invitations::*;
+pub use analytics::*;
+pub use calendar::*;
+pub use notifications::*;
+pub use labels::*;
+pub use time_entries::*;
+pub use sprints::*;
+pub use templates::*;
+pub use api_tokens::*;
+pub use mfa::*;
+pub use health::*;
+pub use jobs::*;
+pub use admin::*;
+pub use organizations::*;
+pub use views::*;
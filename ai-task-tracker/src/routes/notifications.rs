@@ -0,0 +1,161 @@
+use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder};
+use sqlx::PgPool;
+use utoipa::OpenApi;
+use uuid::Uuid;
+
+use crate::{
+    db::{
+        count_unread_notifications, list_notifications, mark_all_notifications_read,
+        mark_notification_read,
+    },
+    models::{Notification, NotificationQuery},
+    openapi::ErrorResponse,
+    routes::helpers::caller_id,
+};
+
+const DEFAULT_NOTIFICATION_LIMIT: i64 = 50;
+const MAX_NOTIFICATION_LIMIT: i64 = 200;
+
+#[utoipa::path(
+    params(NotificationQuery),
+    responses(
+        (status = 200, description = "The caller's notifications, newest first", body = Vec<Notification>),
+    ),
+)]
+#[get("")]
+async fn list_notifications_handler(
+    pool: web::Data<PgPool>,
+    query: web::Query<NotificationQuery>,
+    req: HttpRequest,
+) -> impl Responder {
+    let user_id = match caller_id(&req) {
+        Some(id) => id,
+        None => {
+            return HttpResponse::Unauthorized().json(serde_json::json!({
+                "error": "Unauthorized"
+            }));
+        }
+    };
+
+    let unread_only = query.unread.unwrap_or(false);
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_NOTIFICATION_LIMIT)
+        .clamp(1, MAX_NOTIFICATION_LIMIT);
+
+    match list_notifications(&pool, user_id, unread_only, limit).await {
+        Ok(notifications) => HttpResponse::Ok().json(notifications),
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }))
+        }
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Number of unread notifications", body = serde_json::Value),
+    ),
+)]
+#[get("/unread-count")]
+async fn unread_count_handler(pool: web::Data<PgPool>, req: HttpRequest) -> impl Responder {
+    let user_id = match caller_id(&req) {
+        Some(id) => id,
+        None => {
+            return HttpResponse::Unauthorized().json(serde_json::json!({
+                "error": "Unauthorized"
+            }));
+        }
+    };
+
+    match count_unread_notifications(&pool, user_id).await {
+        Ok(count) => HttpResponse::Ok().json(serde_json::json!({ "unread": count })),
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }))
+        }
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The notification", body = Notification),
+        (status = 404, description = "Notification not found", body = ErrorResponse),
+    ),
+)]
+#[post("/{id}/read")]
+async fn mark_read_handler(
+    pool: web::Data<PgPool>,
+    path: web::Path<Uuid>,
+    req: HttpRequest,
+) -> impl Responder {
+    let user_id = match caller_id(&req) {
+        Some(id) => id,
+        None => {
+            return HttpResponse::Unauthorized().json(serde_json::json!({
+                "error": "Unauthorized"
+            }));
+        }
+    };
+
+    match mark_notification_read(&pool, user_id, path.into_inner()).await {
+        Ok(Some(notification)) => HttpResponse::Ok().json(notification),
+        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Notification not found"
+        })),
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }))
+        }
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Number of notifications marked read", body = serde_json::Value),
+    ),
+)]
+#[post("/read-all")]
+async fn mark_all_read_handler(pool: web::Data<PgPool>, req: HttpRequest) -> impl Responder {
+    let user_id = match caller_id(&req) {
+        Some(id) => id,
+        None => {
+            return HttpResponse::Unauthorized().json(serde_json::json!({
+                "error": "Unauthorized"
+            }));
+        }
+    };
+
+    match mark_all_notifications_read(&pool, user_id).await {
+        Ok(updated) => HttpResponse::Ok().json(serde_json::json!({ "marked_read": updated })),
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }))
+        }
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    list_notifications_handler,
+    unread_count_handler,
+    mark_all_read_handler,
+    mark_read_handler,
+))]
+pub struct NotificationApi;
+
+pub fn notification_routes() -> actix_web::Scope {
+    web::scope("/notifications")
+        .service(list_notifications_handler)
+        .service(unread_count_handler)
+        .service(mark_all_read_handler)
+        .service(mark_read_handler)
+}
@@ -0,0 +1,449 @@
+use actix_web::{delete, get, post, web, HttpMessage, HttpRequest, HttpResponse, Responder};
+use sqlx::PgPool;
+use utoipa::OpenApi;
+use uuid::Uuid;
+
+use crate::{
+    config::Config,
+    db::{
+        accept_organization_invitation, create_organization, create_organization_invitation,
+        decline_organization_invitation, find_organization_by_id, find_organization_invitation_by_id,
+        find_user_by_id, is_organization_member, list_organization_members, list_pending_organization_invitations,
+        list_user_organizations, remove_organization_member, scoped_to_organization,
+    },
+    models::{
+        CreateOrganizationRequest, InviteOrganizationMemberRequest, Organization, OrganizationInvitation,
+        OrganizationMember, SwitchOrganizationResponse,
+    },
+    openapi::ErrorResponse,
+    routes::helpers::{caller_id, internal_error, require_admin, unauthorized},
+    utils::{create_jwt, Claims},
+};
+
+const MAX_SLUG_LENGTH: usize = 63;
+
+fn organization_not_found() -> HttpResponse {
+    HttpResponse::NotFound().json(serde_json::json!({
+        "error": "Organization not found"
+    }))
+}
+
+/// Admins only manage the organizations they belong to; the others are
+/// reported as not found.
+async fn require_member(pool: &PgPool, organization_id: Uuid, admin_id: Uuid) -> Result<(), HttpResponse> {
+    match is_organization_member(pool, organization_id, admin_id).await {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(organization_not_found()),
+        Err(e) => Err(internal_error(e)),
+    }
+}
+
+/// Lowercase letters and digits, in groups separated by single hyphens.
+fn is_valid_slug(slug: &str) -> bool {
+    slug.len() <= MAX_SLUG_LENGTH
+        && slug
+            .split('-')
+            .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit()))
+}
+
+/// The organizations the caller belongs to, in the order they joined them.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The caller's organizations", body = Vec<Organization>),
+    ),
+)]
+#[get("")]
+async fn list_my_organizations_handler(pool: web::Data<PgPool>, http_req: HttpRequest) -> impl Responder {
+    let user_id = match http_req.extensions().get::<Claims>().and_then(|c| Uuid::parse_str(&c.sub).ok()) {
+        Some(id) => id,
+        None => return HttpResponse::Unauthorized().json(serde_json::json!({"error": "Unauthorized"})),
+    };
+
+    match list_user_organizations(&pool, user_id).await {
+        Ok(organizations) => HttpResponse::Ok().json(organizations),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Trades the caller's session for one in another of their organizations.
+/// Personal access tokens stay in the organization they were issued in.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "A session in the organization", body = SwitchOrganizationResponse),
+        (status = 403, description = "Not a member, or a personal access token", body = ErrorResponse),
+        (status = 404, description = "Organization not found", body = ErrorResponse),
+    ),
+)]
+#[post("/{id}/switch")]
+async fn switch_organization_handler(
+    pool: web::Data<PgPool>,
+    config: web::Data<Config>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let organization_id = path.into_inner();
+    let (user_id, scoped) = match http_req
+        .extensions()
+        .get::<Claims>()
+        .and_then(|c| Some((Uuid::parse_str(&c.sub).ok()?, c.scopes.is_some())))
+    {
+        Some(caller) => caller,
+        None => return HttpResponse::Unauthorized().json(serde_json::json!({"error": "Unauthorized"})),
+    };
+    if scoped {
+        return HttpResponse::Forbidden().json(serde_json::json!({
+            "error": "Personal access tokens cannot switch organizations"
+        }));
+    }
+
+    let organization = match find_organization_by_id(&pool, organization_id).await {
+        Ok(Some(organization)) => organization,
+        Ok(None) => return organization_not_found(),
+        Err(e) => return internal_error(e),
+    };
+    match is_organization_member(&pool, organization.id, user_id).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return HttpResponse::Forbidden().json(serde_json::json!({
+                "error": "Not a member of this organization"
+            }));
+        }
+        Err(e) => return internal_error(e),
+    }
+    let user = match find_user_by_id(&pool, user_id).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json(serde_json::json!({"error": "Unauthorized"})),
+        Err(e) => return internal_error(e),
+    };
+
+    match create_jwt(user.id, &user.email, &user.role.to_string(), Some(organization.id), &config.jwt) {
+        Ok(token) => HttpResponse::Ok().json(SwitchOrganizationResponse { token, user, organization }),
+        Err(e) => {
+            log::error!("JWT creation error: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }))
+        }
+    }
+}
+
+/// Loads a pending invitation and checks that it is addressed to the caller.
+async fn load_own_pending_invitation(
+    pool: &PgPool,
+    invitation_id: Uuid,
+    user_id: Uuid,
+) -> Result<OrganizationInvitation, HttpResponse> {
+    match find_organization_invitation_by_id(pool, invitation_id).await {
+        Ok(Some(invitation)) if invitation.user_id == user_id => {
+            if invitation.status != "pending" {
+                return Err(HttpResponse::Conflict().json(serde_json::json!({
+                    "error": format!("Invitation already {}", invitation.status)
+                })));
+            }
+            Ok(invitation)
+        }
+        Ok(_) => Err(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Invitation not found"
+        }))),
+        Err(e) => Err(internal_error(e)),
+    }
+}
+
+/// Invitations to other organizations waiting for the caller's answer.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Pending invitations for the caller", body = Vec<OrganizationInvitation>),
+    ),
+)]
+#[get("/invitations")]
+async fn list_my_organization_invitations_handler(pool: web::Data<PgPool>, http_req: HttpRequest) -> impl Responder {
+    let Some(user_id) = caller_id(&http_req) else {
+        return unauthorized();
+    };
+
+    match list_pending_organization_invitations(&pool, user_id).await {
+        Ok(invitations) => HttpResponse::Ok().json(invitations),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Joins the organization the invitation is from.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The accepted invitation", body = OrganizationInvitation),
+        (status = 404, description = "Invitation not found", body = ErrorResponse),
+        (status = 409, description = "Invitation no longer pending", body = ErrorResponse),
+    ),
+)]
+#[post("/invitations/{id}/accept")]
+async fn accept_organization_invitation_handler(
+    pool: web::Data<PgPool>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let invitation_id = path.into_inner();
+    let Some(user_id) = caller_id(&http_req) else {
+        return unauthorized();
+    };
+    if let Err(response) = load_own_pending_invitation(&pool, invitation_id, user_id).await {
+        return response;
+    }
+
+    match accept_organization_invitation(&pool, invitation_id).await {
+        Ok(invitation) => {
+            log::info!("User {} joined organization {}", user_id, invitation.organization_id);
+            HttpResponse::Ok().json(invitation)
+        }
+        Err(e) => internal_error(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The declined invitation", body = OrganizationInvitation),
+        (status = 404, description = "Invitation not found", body = ErrorResponse),
+        (status = 409, description = "Invitation no longer pending", body = ErrorResponse),
+    ),
+)]
+#[post("/invitations/{id}/decline")]
+async fn decline_organization_invitation_handler(
+    pool: web::Data<PgPool>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let invitation_id = path.into_inner();
+    let Some(user_id) = caller_id(&http_req) else {
+        return unauthorized();
+    };
+    if let Err(response) = load_own_pending_invitation(&pool, invitation_id, user_id).await {
+        return response;
+    }
+
+    match decline_organization_invitation(&pool, invitation_id).await {
+        Ok(invitation) => HttpResponse::Ok().json(invitation),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The organizations the admin belongs to", body = Vec<Organization>),
+        (status = 403, description = "Admins only", body = ErrorResponse),
+    ),
+)]
+#[get("")]
+async fn list_organizations_handler(pool: web::Data<PgPool>, http_req: HttpRequest) -> impl Responder {
+    let admin_id = match require_admin(&http_req, "manage organizations") {
+        Ok(admin_id) => admin_id,
+        Err(response) => return *response,
+    };
+
+    match list_user_organizations(&pool, admin_id).await {
+        Ok(organizations) => HttpResponse::Ok().json(organizations),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Creates an organization with the calling admin as its first member.
+#[utoipa::path(
+    request_body = CreateOrganizationRequest,
+    responses(
+        (status = 201, description = "Organization created", body = Organization),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 403, description = "Admins only", body = ErrorResponse),
+        (status = 409, description = "Slug already taken", body = ErrorResponse),
+    ),
+)]
+#[post("")]
+async fn create_organization_handler(
+    pool: web::Data<PgPool>,
+    req: web::Json<CreateOrganizationRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let admin_id = match require_admin(&http_req, "manage organizations") {
+        Ok(admin_id) => admin_id,
+        Err(response) => return *response,
+    };
+    if req.name.trim().is_empty() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Organization name cannot be empty"
+        }));
+    }
+    if !is_valid_slug(&req.slug) {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Slug must be lowercase letters and digits separated by single hyphens"
+        }));
+    }
+
+    match create_organization(&pool, &req, admin_id).await {
+        Ok(organization) => {
+            log::info!("Admin {} created organization {}", admin_id, organization.id);
+            HttpResponse::Created().json(organization)
+        }
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => HttpResponse::Conflict().json(serde_json::json!({
+            "error": "An organization with this slug already exists"
+        })),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The organization's members", body = Vec<OrganizationMember>),
+        (status = 403, description = "Admins only", body = ErrorResponse),
+        (status = 404, description = "Organization not found", body = ErrorResponse),
+    ),
+)]
+#[get("/{id}/members")]
+async fn list_organization_members_handler(
+    pool: web::Data<PgPool>,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let organization_id = path.into_inner();
+    let admin_id = match require_admin(&http_req, "manage organizations") {
+        Ok(admin_id) => admin_id,
+        Err(response) => return *response,
+    };
+    if let Err(response) = require_member(&pool, organization_id, admin_id).await {
+        return response;
+    }
+
+    match list_organization_members(&pool, organization_id).await {
+        Ok(members) => HttpResponse::Ok().json(members),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Invites an existing user to the organization. They only join it once they
+/// accept the invitation.
+#[utoipa::path(
+    request_body = InviteOrganizationMemberRequest,
+    responses(
+        (status = 201, description = "The invitation", body = OrganizationInvitation),
+        (status = 403, description = "Admins only", body = ErrorResponse),
+        (status = 404, description = "Organization or user not found", body = ErrorResponse),
+        (status = 409, description = "Already a member, or already invited", body = ErrorResponse),
+    ),
+)]
+#[post("/{id}/invitations")]
+async fn invite_organization_member_handler(
+    pool: web::Data<PgPool>,
+    path: web::Path<Uuid>,
+    req: web::Json<InviteOrganizationMemberRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let organization_id = path.into_inner();
+    let admin_id = match require_admin(&http_req, "manage organizations") {
+        Ok(admin_id) => admin_id,
+        Err(response) => return *response,
+    };
+    if let Err(response) = require_member(&pool, organization_id, admin_id).await {
+        return response;
+    }
+
+    match find_user_by_id(&pool, req.user_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "User not found"
+            }));
+        }
+        Err(e) => return internal_error(e),
+    }
+    match is_organization_member(&pool, organization_id, req.user_id).await {
+        Ok(false) => {}
+        Ok(true) => {
+            return HttpResponse::Conflict().json(serde_json::json!({
+                "error": "User is already a member"
+            }));
+        }
+        Err(e) => return internal_error(e),
+    }
+
+    match create_organization_invitation(&pool, organization_id, req.user_id, admin_id).await {
+        Ok(invitation) => {
+            log::info!("Admin {} invited user {} to organization {}", admin_id, req.user_id, organization_id);
+            HttpResponse::Created().json(invitation)
+        }
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => HttpResponse::Conflict().json(serde_json::json!({
+            "error": "User already has a pending invitation"
+        })),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Removes a user from the organization and its teams, ending their sessions in it.
+#[utoipa::path(
+    responses(
+        (status = 204, description = "Member removed"),
+        (status = 403, description = "Admins only", body = ErrorResponse),
+        (status = 404, description = "Not a member", body = ErrorResponse),
+    ),
+)]
+#[delete("/{id}/members/{user_id}")]
+async fn remove_organization_member_handler(
+    pool: web::Data<PgPool>,
+    path: web::Path<(Uuid, Uuid)>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let (organization_id, user_id) = path.into_inner();
+    let admin_id = match require_admin(&http_req, "manage organizations") {
+        Ok(admin_id) => admin_id,
+        Err(response) => return *response,
+    };
+    if let Err(response) = require_member(&pool, organization_id, admin_id).await {
+        return response;
+    }
+
+    // The admin's own organization may be another one, whose teams are all
+    // the row-level security policies would let the removal see
+    match scoped_to_organization(organization_id, remove_organization_member(&pool, organization_id, user_id)).await {
+        Ok(true) => {
+            log::info!("Admin {} removed user {} from organization {}", admin_id, user_id, organization_id);
+            HttpResponse::NoContent().finish()
+        }
+        Ok(false) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Member not found"
+        })),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    list_my_organizations_handler,
+    switch_organization_handler,
+    list_my_organization_invitations_handler,
+    accept_organization_invitation_handler,
+    decline_organization_invitation_handler,
+))]
+pub struct OrganizationApi;
+
+pub fn organization_routes() -> actix_web::Scope {
+    web::scope("/organizations")
+        .service(list_my_organizations_handler)
+        .service(list_my_organization_invitations_handler)
+        .service(accept_organization_invitation_handler)
+        .service(decline_organization_invitation_handler)
+        .service(switch_organization_handler)
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    list_organizations_handler,
+    create_organization_handler,
+    list_organization_members_handler,
+    invite_organization_member_handler,
+    remove_organization_member_handler,
+))]
+pub struct AdminOrganizationApi;
+
+pub fn admin_organization_routes() -> actix_web::Scope {
+    web::scope("/admin/organizations")
+        .service(list_organizations_handler)
+        .service(create_organization_handler)
+        .service(list_organization_members_handler)
+        .service(invite_organization_member_handler)
+        .service(remove_organization_member_handler)
+}
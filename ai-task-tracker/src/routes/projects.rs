@@ -1,16 +1,34 @@
 use actix_web::{delete, get, post, put, web, HttpMessage, HttpRequest, HttpResponse, Responder};
 use sqlx::PgPool;
+use utoipa::OpenApi;
 use uuid::Uuid;
 
 use crate::{
-    db::{create_project, delete_project, find_project_by_id, list_projects, update_project},
-    models::{CreateProjectRequest, UpdateProjectRequest},
+    config::Config,
+    db::{find_project_by_id, find_trash_item, import_project_tasks, list_project_trash, ProjectRepository},
+    models::{
+        CreateProjectRequest, ImportReport, Project, ProjectExport, TransferFormat, TrashEntry, TrashKind,
+        UpdateProjectRequest,
+    },
+    middleware::CurrentOrganization,
+    openapi::ErrorResponse,
+    services::{build_project_export, export_to_csv, parse_rows, validate_rows, IMPORT_FIELDS},
     utils::Claims,
 };
 
+/// Largest import file accepted, in bytes.
+const MAX_IMPORT_BYTES: usize = 10 * 1024 * 1024;
+
+#[utoipa::path(
+    responses(
+        (status = 201, description = "The new project", body = Project),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+)]
 #[post("")]
 async fn create_project_handler(
-    pool: web::Data<PgPool>,
+    projects: web::Data<dyn ProjectRepository>,
+    organization: CurrentOrganization,
     req_http: HttpRequest,
     req: web::Json<CreateProjectRequest>,
 ) -> impl Responder {
@@ -33,7 +51,7 @@ async fn create_project_handler(
         }
     };
 
-    match create_project(&pool, &req, user_id).await {
+    match projects.create_project(organization.id, &req, user_id).await {
         Ok(project) => HttpResponse::Created().json(project),
         Err(e) => {
             log::error!("Database error: {}", e);
@@ -44,16 +62,25 @@ async fn create_project_handler(
     }
 }
 
+#[utoipa::path(
+    params(
+        ("team_id" = Option<Uuid>, Query, description = "Only projects of this team"),
+    ),
+    responses(
+        (status = 200, description = "The projects", body = Vec<Project>),
+    ),
+)]
 #[get("")]
 async fn list_projects_handler(
-    pool: web::Data<PgPool>,
+    projects: web::Data<dyn ProjectRepository>,
+    organization: CurrentOrganization,
     query: web::Query<std::collections::HashMap<String, String>>,
 ) -> impl Responder {
     let team_id = query
         .get("team_id")
         .and_then(|id| Uuid::parse_str(id).ok());
 
-    match list_projects(&pool, team_id).await {
+    match projects.list_projects(organization.id, team_id).await {
         Ok(projects) => HttpResponse::Ok().json(projects),
         Err(e) => {
             log::error!("Database error: {}", e);
@@ -64,11 +91,21 @@ async fn list_projects_handler(
     }
 }
 
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The project", body = Project),
+        (status = 404, description = "Project not found", body = ErrorResponse),
+    ),
+)]
 #[get("/{id}")]
-async fn get_project_handler(pool: web::Data<PgPool>, path: web::Path<Uuid>) -> impl Responder {
+async fn get_project_handler(
+    projects: web::Data<dyn ProjectRepository>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+) -> impl Responder {
     let project_id = path.into_inner();
 
-    match find_project_by_id(&pool, project_id).await {
+    match projects.find_project_by_id(organization.id, project_id).await {
         Ok(Some(project)) => HttpResponse::Ok().json(project),
         Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
             "error": "Project not found"
@@ -82,15 +119,21 @@ async fn get_project_handler(pool: web::Data<PgPool>, path: web::Path<Uuid>) ->
     }
 }
 
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The updated project", body = Project),
+    ),
+)]
 #[put("/{id}")]
 async fn update_project_handler(
-    pool: web::Data<PgPool>,
+    projects: web::Data<dyn ProjectRepository>,
+    organization: CurrentOrganization,
     path: web::Path<Uuid>,
     req: web::Json<UpdateProjectRequest>,
 ) -> impl Responder {
     let project_id = path.into_inner();
 
-    match update_project(&pool, project_id, &req).await {
+    match projects.update_project(organization.id, project_id, &req).await {
         Ok(project) => HttpResponse::Ok().json(project),
         Err(e) => {
             log::error!("Database error: {}", e);
@@ -101,12 +144,111 @@ async fn update_project_handler(
     }
 }
 
+#[utoipa::path(
+    responses(
+        (status = 204, description = "Project moved to the trash"),
+        (status = 404, description = "Project not found", body = ErrorResponse),
+    ),
+)]
 #[delete("/{id}")]
-async fn delete_project_handler(pool: web::Data<PgPool>, path: web::Path<Uuid>) -> impl Responder {
+async fn delete_project_handler(
+    projects: web::Data<dyn ProjectRepository>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+    req_http: HttpRequest,
+) -> impl Responder {
+    let project_id = path.into_inner();
+    let actor_id = req_http
+        .extensions()
+        .get::<Claims>()
+        .and_then(|claims| Uuid::parse_str(&claims.sub).ok());
+
+    // The project and its tasks stay in the trash until the retention job purges them
+    match projects.soft_delete_project(organization.id, project_id, actor_id).await {
+        Ok(true) => HttpResponse::NoContent().finish(),
+        Ok(false) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Project not found"
+        })),
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }))
+        }
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The restored project", body = Project),
+        (status = 404, description = "Project in trash not found", body = ErrorResponse),
+        (status = 409, description = "The project's team is in the trash", body = ErrorResponse),
+    ),
+)]
+#[post("/{id}/restore")]
+async fn restore_project_handler(
+    pool: web::Data<PgPool>,
+    projects: web::Data<dyn ProjectRepository>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+) -> impl Responder {
     let project_id = path.into_inner();
 
-    match delete_project(&pool, project_id).await {
-        Ok(_) => HttpResponse::NoContent().finish(),
+    match find_trash_item(&pool, organization.id, TrashKind::Project, project_id).await {
+        Ok(Some(item)) if item.parent_deleted => {
+            return HttpResponse::Conflict().json(serde_json::json!({
+                "error": "Restore the project's team first"
+            }));
+        }
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Project not found in trash"
+            }));
+        }
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }));
+        }
+    }
+
+    match projects.restore_project(organization.id, project_id).await {
+        Ok(Some(project)) => HttpResponse::Ok().json(project),
+        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Project not found in trash"
+        })),
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }))
+        }
+    }
+}
+
+/// Deleted tasks of a project, and the project itself if it is in the trash.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Trashed items in the project", body = Vec<TrashEntry>),
+    ),
+)]
+#[get("/{id}/trash")]
+async fn project_trash_handler(
+    pool: web::Data<PgPool>,
+    config: web::Data<Config>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    match list_project_trash(&pool, organization.id, path.into_inner()).await {
+        Ok(items) => {
+            let entries: Vec<TrashEntry> = items
+                .into_iter()
+                .map(|item| TrashEntry::new(item, config.trash_retention_days))
+                .collect();
+            HttpResponse::Ok().json(entries)
+        }
         Err(e) => {
             log::error!("Database error: {}", e);
             HttpResponse::InternalServerError().json(serde_json::json!({
@@ -116,11 +258,226 @@ async fn delete_project_handler(pool: web::Data<PgPool>, path: web::Path<Uuid>)
     }
 }
 
+#[utoipa::path(
+    params(
+        ("format" = Option<String>, Query, description = "`json` (default) or `csv`"),
+    ),
+    responses(
+        (status = 200, description = "The project and its tasks, as JSON or CSV", body = ProjectExport),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Project not found", body = ErrorResponse),
+    ),
+)]
+#[get("/{id}/export")]
+async fn export_project_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+    query: web::Query<std::collections::HashMap<String, String>>,
+) -> impl Responder {
+    let project_id = path.into_inner();
+
+    let format = match query.get("format").map(|f| f.parse::<TransferFormat>()) {
+        Some(Ok(format)) => format,
+        Some(Err(e)) => return HttpResponse::BadRequest().json(serde_json::json!({ "error": e })),
+        None => TransferFormat::Json,
+    };
+
+    let project = match find_project_by_id(&pool, organization.id, project_id).await {
+        Ok(Some(project)) => project,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Project not found"
+            }));
+        }
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }));
+        }
+    };
+
+    let export = match build_project_export(&pool, project).await {
+        Ok(export) => export,
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }));
+        }
+    };
+
+    match format {
+        TransferFormat::Json => HttpResponse::Ok().json(export),
+        TransferFormat::Csv => match export_to_csv(&export) {
+            Ok(csv) => HttpResponse::Ok()
+                .content_type("text/csv; charset=utf-8")
+                .insert_header((
+                    "Content-Disposition",
+                    format!("attachment; filename=\"project-{}.csv\"", project_id),
+                ))
+                .body(csv),
+            Err(e) => {
+                log::error!("CSV export error: {}", e);
+                HttpResponse::InternalServerError().json(serde_json::json!({
+                    "error": "Internal server error"
+                }))
+            }
+        },
+    }
+}
+
+/// Imports tasks from CSV or JSON. Query parameters:
+/// `format` (csv|json), `dry_run` (validate only), and `map.<field>=<column>` to map
+/// source columns onto task fields.
+#[utoipa::path(
+    params(
+        ("format" = Option<String>, Query, description = "`json` or `csv`; defaults from the Content-Type"),
+        ("dry_run" = Option<bool>, Query, description = "Validate without importing"),
+    ),
+    request_body(content = String, description = "CSV or JSON rows; map source columns with `map.<field>=<column>` query parameters", content_type = "text/csv"),
+    responses(
+        (status = 201, description = "Tasks imported", body = ImportReport),
+        (status = 200, description = "Dry run passed", body = ImportReport),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Project not found", body = ErrorResponse),
+        (status = 422, description = "Rows failed validation; nothing was imported", body = ImportReport),
+    ),
+)]
+#[post("/{id}/import")]
+async fn import_project_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+    query: web::Query<std::collections::HashMap<String, String>>,
+    req_http: HttpRequest,
+    body: web::Bytes,
+) -> impl Responder {
+    let project_id = path.into_inner();
+    let actor_id = req_http
+        .extensions()
+        .get::<Claims>()
+        .and_then(|claims| Uuid::parse_str(&claims.sub).ok());
+
+    let format = match query.get("format").map(|f| f.parse::<TransferFormat>()) {
+        Some(Ok(format)) => format,
+        Some(Err(e)) => return HttpResponse::BadRequest().json(serde_json::json!({ "error": e })),
+        None => {
+            let content_type = req_http
+                .headers()
+                .get("Content-Type")
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or("");
+            if content_type.starts_with("text/csv") {
+                TransferFormat::Csv
+            } else {
+                TransferFormat::Json
+            }
+        }
+    };
+    let dry_run = matches!(query.get("dry_run").map(|v| v.as_str()), Some("true" | "1"));
+
+    let mut mapping = std::collections::HashMap::new();
+    for (key, column) in query.iter() {
+        if let Some(field) = key.strip_prefix("map.") {
+            if !IMPORT_FIELDS.contains(&field) {
+                return HttpResponse::BadRequest().json(serde_json::json!({
+                    "error": format!("Unknown import field: {}", field)
+                }));
+            }
+            mapping.insert(field.to_string(), column.clone());
+        }
+    }
+
+    match find_project_by_id(&pool, organization.id, project_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Project not found"
+            }));
+        }
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }));
+        }
+    }
+
+    let rows = match parse_rows(format, &body, &mapping) {
+        Ok(rows) => rows,
+        Err(e) => return HttpResponse::BadRequest().json(serde_json::json!({ "error": e })),
+    };
+    let total_rows = rows.len();
+
+    let validated = match validate_rows(&pool, organization.id, project_id, rows).await {
+        Ok(validated) => validated,
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }));
+        }
+    };
+
+    let invalid_rows: std::collections::HashSet<usize> = validated.errors.iter().map(|e| e.row).collect();
+    let mut report = ImportReport {
+        dry_run,
+        total_rows,
+        valid_rows: total_rows - validated.skipped - invalid_rows.len(),
+        skipped_rows: validated.skipped,
+        created_tasks: 0,
+        created_subtasks: 0,
+        errors: validated.errors,
+    };
+
+    if dry_run {
+        return HttpResponse::Ok().json(report);
+    }
+    if !report.errors.is_empty() {
+        return HttpResponse::UnprocessableEntity().json(report);
+    }
+
+    match import_project_tasks(&pool, organization.id, &validated.tasks, actor_id).await {
+        Ok((tasks, subtasks)) => {
+            report.created_tasks = tasks;
+            report.created_subtasks = subtasks;
+            HttpResponse::Created().json(report)
+        }
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }))
+        }
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    create_project_handler,
+    list_projects_handler,
+    get_project_handler,
+    update_project_handler,
+    delete_project_handler,
+    restore_project_handler,
+    project_trash_handler,
+    export_project_handler,
+    import_project_handler,
+))]
+pub struct ProjectApi;
+
 pub fn project_routes() -> actix_web::Scope {
     web::scope("/projects")
+        .app_data(web::PayloadConfig::new(MAX_IMPORT_BYTES))
         .service(create_project_handler)
         .service(list_projects_handler)
         .service(get_project_handler)
         .service(update_project_handler)
         .service(delete_project_handler)
+        .service(restore_project_handler)
+        .service(project_trash_handler)
+        .service(export_project_handler)
+        .service(import_project_handler)
 }
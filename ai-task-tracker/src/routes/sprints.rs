@@ -0,0 +1,577 @@
+use actix_web::{delete, get, post, put, web, HttpRequest, HttpResponse, Responder};
+use sqlx::PgPool;
+use utoipa::OpenApi;
+use uuid::Uuid;
+
+use crate::{
+    db::{
+        add_tasks_to_sprint, close_sprint, create_sprint, delete_sprint, delete_sprint_capacity,
+        find_next_sprint, find_project_by_id, find_sprint_by_id, list_sprint_capacities,
+        list_sprint_task_outcomes, list_sprints, remove_task_from_sprint, set_sprint_capacity,
+        start_sprint, update_sprint,
+    },
+    models::{
+        CloseSprintRequest, CreateSprintRequest, SetSprintCapacityRequest, Sprint, SprintCapacity,
+        SprintClosePreview, SprintCloseResult, SprintQuery, SprintReport, SprintStatus, SprintTaskOutcome,
+        SprintTasksRequest, UpdateSprintRequest,
+    },
+    middleware::CurrentOrganization,
+    openapi::ErrorResponse,
+    routes::helpers::{caller_id, internal_error},
+    services::build_sprint_report,
+};
+
+fn sprint_closed_response() -> HttpResponse {
+    HttpResponse::Conflict().json(serde_json::json!({
+        "error": "Sprint is closed"
+    }))
+}
+
+/// Loads a sprint of the organization or produces the 404/500 response.
+async fn load_sprint(pool: &PgPool, organization_id: Uuid, id: Uuid) -> Result<Sprint, HttpResponse> {
+    match find_sprint_by_id(pool, organization_id, id).await {
+        Ok(Some(sprint)) => Ok(sprint),
+        Ok(None) => Err(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Sprint not found"
+        }))),
+        Err(e) => Err(internal_error(e)),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 201, description = "The new sprint", body = Sprint),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Project not found", body = ErrorResponse),
+    ),
+)]
+#[post("")]
+async fn create_sprint_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    req: web::Json<CreateSprintRequest>,
+) -> impl Responder {
+    if req.name.trim().is_empty() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Sprint name cannot be empty"
+        }));
+    }
+    if req.end_date < req.start_date {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "end_date must not be before start_date"
+        }));
+    }
+
+    match find_project_by_id(&pool, organization.id, req.project_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Project not found"
+            }));
+        }
+        Err(e) => return internal_error(e),
+    }
+
+    match create_sprint(&pool, &req).await {
+        Ok(sprint) => HttpResponse::Created().json(sprint),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[utoipa::path(
+    params(SprintQuery),
+    responses(
+        (status = 200, description = "The sprints", body = Vec<Sprint>),
+    ),
+)]
+#[get("")]
+async fn list_sprints_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    query: web::Query<SprintQuery>,
+) -> impl Responder {
+    match list_sprints(&pool, organization.id, query.project_id, query.status.as_ref()).await {
+        Ok(sprints) => HttpResponse::Ok().json(sprints),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The sprint", body = Sprint),
+        (status = 404, description = "Sprint not found", body = ErrorResponse),
+    ),
+)]
+#[get("/{id}")]
+async fn get_sprint_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    match load_sprint(&pool, organization.id, path.into_inner()).await {
+        Ok(sprint) => HttpResponse::Ok().json(sprint),
+        Err(response) => response,
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The updated sprint", body = Sprint),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Sprint not found", body = ErrorResponse),
+    ),
+)]
+#[put("/{id}")]
+async fn update_sprint_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+    req: web::Json<UpdateSprintRequest>,
+) -> impl Responder {
+    let sprint = match load_sprint(&pool, organization.id, path.into_inner()).await {
+        Ok(sprint) => sprint,
+        Err(response) => return response,
+    };
+    if sprint.status() == SprintStatus::Closed {
+        return sprint_closed_response();
+    }
+
+    let start_date = req.start_date.unwrap_or(sprint.start_date);
+    let end_date = req.end_date.unwrap_or(sprint.end_date);
+    if end_date < start_date {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "end_date must not be before start_date"
+        }));
+    }
+
+    match update_sprint(&pool, sprint.id, &req).await {
+        Ok(sprint) => HttpResponse::Ok().json(sprint),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 204, description = "Sprint deleted"),
+        (status = 404, description = "Sprint not found", body = ErrorResponse),
+    ),
+)]
+#[delete("/{id}")]
+async fn delete_sprint_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    let sprint = match load_sprint(&pool, organization.id, path.into_inner()).await {
+        Ok(sprint) => sprint,
+        Err(response) => return response,
+    };
+
+    match delete_sprint(&pool, sprint.id).await {
+        Ok(()) => HttpResponse::NoContent().finish(),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The started sprint", body = Sprint),
+        (status = 404, description = "Sprint not found", body = ErrorResponse),
+        (status = 409, description = "Sprint cannot be started", body = ErrorResponse),
+    ),
+)]
+#[post("/{id}/start")]
+async fn start_sprint_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    let sprint = match load_sprint(&pool, organization.id, path.into_inner()).await {
+        Ok(sprint) => sprint,
+        Err(response) => return response,
+    };
+    if sprint.status() != SprintStatus::Planned {
+        return HttpResponse::Conflict().json(serde_json::json!({
+            "error": format!("Sprint is already {}", sprint.status)
+        }));
+    }
+
+    match start_sprint(&pool, sprint.id).await {
+        Ok(sprint) => HttpResponse::Ok().json(sprint),
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            HttpResponse::Conflict().json(serde_json::json!({
+                "error": "Another sprint is already active in this project"
+            }))
+        }
+        Err(e) => internal_error(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Tasks added and skipped", body = serde_json::Value),
+        (status = 404, description = "Sprint not found", body = ErrorResponse),
+    ),
+)]
+#[post("/{id}/tasks")]
+async fn add_sprint_tasks_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+    req: web::Json<SprintTasksRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let sprint = match load_sprint(&pool, organization.id, path.into_inner()).await {
+        Ok(sprint) => sprint,
+        Err(response) => return response,
+    };
+    if sprint.status() == SprintStatus::Closed {
+        return sprint_closed_response();
+    }
+
+    match add_tasks_to_sprint(&pool, &sprint, &req.task_ids, caller_id(&http_req)).await {
+        Ok(added) => {
+            // Anything not moved is from another project, missing, or already in the sprint
+            let skipped: Vec<Uuid> = req
+                .task_ids
+                .iter()
+                .filter(|id| !added.contains(id))
+                .copied()
+                .collect();
+            HttpResponse::Ok().json(serde_json::json!({
+                "added": added,
+                "skipped": skipped
+            }))
+        }
+        Err(e) => internal_error(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The sprint's tasks", body = Vec<SprintTaskOutcome>),
+        (status = 404, description = "Sprint not found", body = ErrorResponse),
+    ),
+)]
+#[get("/{id}/tasks")]
+async fn list_sprint_tasks_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    let sprint = match load_sprint(&pool, organization.id, path.into_inner()).await {
+        Ok(sprint) => sprint,
+        Err(response) => return response,
+    };
+
+    match list_sprint_task_outcomes(&pool, sprint.id).await {
+        Ok(tasks) => HttpResponse::Ok().json(tasks),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 204, description = "Task removed from the sprint"),
+        (status = 404, description = "Sprint or task not found", body = ErrorResponse),
+    ),
+)]
+#[delete("/{id}/tasks/{task_id}")]
+async fn remove_sprint_task_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    path: web::Path<(Uuid, Uuid)>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let (sprint_id, task_id) = path.into_inner();
+
+    let sprint = match load_sprint(&pool, organization.id, sprint_id).await {
+        Ok(sprint) => sprint,
+        Err(response) => return response,
+    };
+    if sprint.status() == SprintStatus::Closed {
+        return sprint_closed_response();
+    }
+
+    match remove_task_from_sprint(&pool, &sprint, task_id, caller_id(&http_req)).await {
+        Ok(true) => HttpResponse::NoContent().finish(),
+        Ok(false) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Task is not in this sprint"
+        })),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Capacity per member", body = Vec<SprintCapacity>),
+        (status = 404, description = "Sprint not found", body = ErrorResponse),
+    ),
+)]
+#[get("/{id}/capacity")]
+async fn list_capacity_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    let sprint = match load_sprint(&pool, organization.id, path.into_inner()).await {
+        Ok(sprint) => sprint,
+        Err(response) => return response,
+    };
+
+    match list_sprint_capacities(&pool, sprint.id).await {
+        Ok(capacities) => HttpResponse::Ok().json(capacities),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Capacity per member", body = Vec<SprintCapacity>),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Sprint not found", body = ErrorResponse),
+    ),
+)]
+#[put("/{id}/capacity")]
+async fn set_capacity_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+    req: web::Json<SetSprintCapacityRequest>,
+) -> impl Responder {
+    if req.capacity_minutes < 0 {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Capacity cannot be negative"
+        }));
+    }
+
+    let sprint = match load_sprint(&pool, organization.id, path.into_inner()).await {
+        Ok(sprint) => sprint,
+        Err(response) => return response,
+    };
+    if sprint.status() == SprintStatus::Closed {
+        return sprint_closed_response();
+    }
+
+    if let Err(e) = set_sprint_capacity(&pool, sprint.id, req.user_id, req.capacity_minutes).await {
+        return match e {
+            sqlx::Error::Database(e) if e.is_foreign_key_violation() => {
+                HttpResponse::NotFound().json(serde_json::json!({
+                    "error": "User not found"
+                }))
+            }
+            e => internal_error(e),
+        };
+    }
+
+    match list_sprint_capacities(&pool, sprint.id).await {
+        Ok(capacities) => HttpResponse::Ok().json(capacities),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 204, description = "Capacity removed"),
+        (status = 404, description = "Sprint or capacity not found", body = ErrorResponse),
+    ),
+)]
+#[delete("/{id}/capacity/{user_id}")]
+async fn delete_capacity_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    path: web::Path<(Uuid, Uuid)>,
+) -> impl Responder {
+    let (sprint_id, user_id) = path.into_inner();
+
+    if let Err(response) = load_sprint(&pool, organization.id, sprint_id).await {
+        return response;
+    }
+
+    match delete_sprint_capacity(&pool, sprint_id, user_id).await {
+        Ok(true) => HttpResponse::NoContent().finish(),
+        Ok(false) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "No capacity set for this user"
+        })),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The sprint report", body = SprintReport),
+        (status = 404, description = "Sprint not found", body = ErrorResponse),
+    ),
+)]
+#[get("/{id}/report")]
+async fn sprint_report_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    let sprint = match load_sprint(&pool, organization.id, path.into_inner()).await {
+        Ok(sprint) => sprint,
+        Err(response) => return response,
+    };
+
+    match build_sprint_report(&pool, sprint).await {
+        Ok(report) => HttpResponse::Ok().json(report),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Shows what closing would leave unfinished and which sprint it would move to.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "What closing the sprint would do", body = SprintClosePreview),
+        (status = 404, description = "Sprint not found", body = ErrorResponse),
+    ),
+)]
+#[get("/{id}/close")]
+async fn close_preview_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    let sprint = match load_sprint(&pool, organization.id, path.into_inner()).await {
+        Ok(sprint) => sprint,
+        Err(response) => return response,
+    };
+    if sprint.status() == SprintStatus::Closed {
+        return sprint_closed_response();
+    }
+
+    let tasks = match list_sprint_task_outcomes(&pool, sprint.id).await {
+        Ok(tasks) => tasks,
+        Err(e) => return internal_error(e),
+    };
+    let suggested_next_sprint = match find_next_sprint(&pool, &sprint).await {
+        Ok(next) => next,
+        Err(e) => return internal_error(e),
+    };
+
+    HttpResponse::Ok().json(SprintClosePreview {
+        unfinished_tasks: tasks
+            .into_iter()
+            .filter(|t| t.outcome == "open" && t.status != "done")
+            .collect(),
+        suggested_next_sprint,
+    })
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The closed sprint and its report", body = SprintCloseResult),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Sprint not found", body = ErrorResponse),
+        (status = 409, description = "Sprint is not active", body = ErrorResponse),
+    ),
+)]
+#[post("/{id}/close")]
+async fn close_sprint_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+    req: web::Json<CloseSprintRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let sprint = match load_sprint(&pool, organization.id, path.into_inner()).await {
+        Ok(sprint) => sprint,
+        Err(response) => return response,
+    };
+    if sprint.status() == SprintStatus::Closed {
+        return sprint_closed_response();
+    }
+
+    let target = match (req.move_unfinished_to, req.move_to_next) {
+        (Some(_), true) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "Use either move_unfinished_to or move_to_next, not both"
+            }));
+        }
+        (Some(target_id), false) => match load_sprint(&pool, organization.id, target_id).await {
+            Ok(target) => Some(target),
+            Err(response) => return response,
+        },
+        (None, true) => match find_next_sprint(&pool, &sprint).await {
+            Ok(Some(next)) => Some(next),
+            Ok(None) => {
+                return HttpResponse::Conflict().json(serde_json::json!({
+                    "error": "There is no planned sprint to move unfinished tasks to"
+                }));
+            }
+            Err(e) => return internal_error(e),
+        },
+        (None, false) => None,
+    };
+
+    if let Some(target) = &target {
+        if target.id == sprint.id || target.project_id != sprint.project_id {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "Unfinished tasks can only move to another sprint of the same project"
+            }));
+        }
+        if target.status() == SprintStatus::Closed {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "Cannot move tasks into a closed sprint"
+            }));
+        }
+    }
+
+    let moved_task_ids = match close_sprint(&pool, &sprint, target.as_ref(), caller_id(&http_req)).await {
+        Ok(moved) => moved,
+        Err(e) => return internal_error(e),
+    };
+
+    let closed = match load_sprint(&pool, organization.id, sprint.id).await {
+        Ok(sprint) => sprint,
+        Err(response) => return response,
+    };
+    match build_sprint_report(&pool, closed).await {
+        Ok(report) => HttpResponse::Ok().json(SprintCloseResult {
+            report,
+            moved_to: target.map(|t| t.id),
+            moved_task_ids,
+        }),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    create_sprint_handler,
+    list_sprints_handler,
+    get_sprint_handler,
+    update_sprint_handler,
+    delete_sprint_handler,
+    start_sprint_handler,
+    add_sprint_tasks_handler,
+    list_sprint_tasks_handler,
+    remove_sprint_task_handler,
+    list_capacity_handler,
+    set_capacity_handler,
+    delete_capacity_handler,
+    sprint_report_handler,
+    close_preview_handler,
+    close_sprint_handler,
+))]
+pub struct SprintApi;
+
+pub fn sprint_routes() -> actix_web::Scope {
+    web::scope("/sprints")
+        .service(create_sprint_handler)
+        .service(list_sprints_handler)
+        .service(get_sprint_handler)
+        .service(update_sprint_handler)
+        .service(delete_sprint_handler)
+        .service(start_sprint_handler)
+        .service(add_sprint_tasks_handler)
+        .service(list_sprint_tasks_handler)
+        .service(remove_sprint_task_handler)
+        .service(list_capacity_handler)
+        .service(set_capacity_handler)
+        .service(delete_capacity_handler)
+        .service(sprint_report_handler)
+        .service(close_preview_handler)
+        .service(close_sprint_handler)
+}
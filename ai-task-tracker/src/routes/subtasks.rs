@@ -1,21 +1,54 @@
 use actix_web::{put, delete, web, HttpResponse, Responder};
-use sqlx::PgPool;
+use utoipa::OpenApi;
 use uuid::Uuid;
 
 use crate::{
-    db::{delete_subtask, update_subtask},
-    models::UpdateSubtaskRequest,
+    db::TaskRepository,
+    models::{Subtask, UpdateSubtaskRequest},
+    middleware::CurrentOrganization,
+    openapi::ErrorResponse,
 };
 
+/// The 404/500 response unless the subtask exists in the organization.
+async fn require_subtask(
+    tasks: &dyn TaskRepository,
+    organization_id: Uuid,
+    subtask_id: Uuid,
+) -> Result<(), HttpResponse> {
+    match tasks.find_subtask_by_id(organization_id, subtask_id).await {
+        Ok(Some(_)) => Ok(()),
+        Ok(None) => Err(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Subtask not found"
+        }))),
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            Err(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            })))
+        }
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The updated subtask", body = Subtask),
+        (status = 404, description = "Subtask not found", body = ErrorResponse),
+    ),
+)]
 #[put("/{id}")]
 async fn update_subtask_handler(
-    pool: web::Data<PgPool>,
+    tasks: web::Data<dyn TaskRepository>,
+    organization: CurrentOrganization,
     path: web::Path<Uuid>,
     req: web::Json<UpdateSubtaskRequest>,
 ) -> impl Responder {
     let subtask_id = path.into_inner();
 
-    match update_subtask(&pool, subtask_id, &req).await {
+    if let Err(response) = require_subtask(&**tasks, organization.id, subtask_id).await {
+        return response;
+    }
+
+    match tasks.update_subtask(subtask_id, &req).await {
         Ok(subtask) => HttpResponse::Ok().json(subtask),
         Err(e) => {
             log::error!("Database error: {}", e);
@@ -26,11 +59,25 @@ async fn update_subtask_handler(
     }
 }
 
+#[utoipa::path(
+    responses(
+        (status = 204, description = "Subtask deleted"),
+        (status = 404, description = "Subtask not found", body = ErrorResponse),
+    ),
+)]
 #[delete("/{id}")]
-async fn delete_subtask_handler(pool: web::Data<PgPool>, path: web::Path<Uuid>) -> impl Responder {
+async fn delete_subtask_handler(
+    tasks: web::Data<dyn TaskRepository>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+) -> impl Responder {
     let subtask_id = path.into_inner();
 
-    match delete_subtask(&pool, subtask_id).await {
+    if let Err(response) = require_subtask(&**tasks, organization.id, subtask_id).await {
+        return response;
+    }
+
+    match tasks.delete_subtask(subtask_id).await {
         Ok(_) => HttpResponse::NoContent().finish(),
         Err(e) => {
             log::error!("Database error: {}", e);
@@ -41,6 +88,13 @@ async fn delete_subtask_handler(pool: web::Data<PgPool>, path: web::Path<Uuid>)
     }
 }
 
+#[derive(OpenApi)]
+#[openapi(paths(
+    update_subtask_handler,
+    delete_subtask_handler,
+))]
+pub struct SubtaskApi;
+
 pub fn subtask_routes() -> actix_web::Scope {
     web::scope("/subtasks")
         .service(update_subtask_handler)
@@ -1,27 +1,90 @@
-use actix_web::{delete, get, post, put, web, HttpResponse, Responder};
+use actix_web::{delete, get, post, put, web, HttpMessage, HttpRequest, HttpResponse, Responder};
 use sqlx::PgPool;
+use utoipa::OpenApi;
 use uuid::Uuid;
 
 use crate::{
-    db::{
-        create_subtask, create_task, delete_task, find_task_by_id,
-        list_subtasks, list_tasks, update_task, update_task_progress,
-        create_task_history, list_task_history,
-    },
+    db::{find_trash_item, ProjectRepository, TaskRepository},
     models::{
-        CreateSubtaskRequest, CreateTaskRequest, UpdateProgressRequest,
-        UpdateTaskRequest, CreateTaskHistoryRequest,
+        CreateSubtaskRequest, CreateTaskHistoryRequest, CreateTaskRequest, EmailLog, LabelMatch, Subtask, Task,
+        TaskActivity, TaskFilter, TaskHistory, TaskHistoryWithUser, TaskStatus, TrashKind,
+        UpdateProgressRequest, UpdateTaskRequest,
+    },
+    middleware::CurrentOrganization,
+    openapi::ErrorResponse,
+    routes::attachments::{
+        delete_attachment_handler, download_attachment_handler, list_attachments_handler,
+        upload_attachments_handler,
     },
+    routes::comments::{
+        create_comment_handler, delete_comment_handler, list_comments_handler,
+        update_comment_handler,
+    },
+    routes::helpers::caller_id,
+    routes::labels::{add_task_label_handler, list_task_labels_handler, remove_task_label_handler},
+    services::Metrics,
     utils::Claims,
 };
 
+/// Checks that the task exists in the caller's organization, for routes about
+/// its subtasks, history, activity and emails.
+async fn require_task(tasks: &dyn TaskRepository, organization_id: Uuid, task_id: Uuid) -> Result<(), HttpResponse> {
+    match tasks.find_task_by_id(organization_id, task_id).await {
+        Ok(Some(_)) => Ok(()),
+        Ok(None) => Err(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Task not found"
+        }))),
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            Err(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            })))
+        }
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 201, description = "The new task", body = Task),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Project not found", body = ErrorResponse),
+    ),
+)]
 #[post("")]
 async fn create_task_handler(
-    pool: web::Data<PgPool>,
+    tasks: web::Data<dyn TaskRepository>,
+    projects: web::Data<dyn ProjectRepository>,
+    metrics: web::Data<Metrics>,
+    organization: CurrentOrganization,
     req: web::Json<CreateTaskRequest>,
+    http_req: HttpRequest,
 ) -> impl Responder {
-    match create_task(&pool, &req).await {
-        Ok(task) => HttpResponse::Created().json(task),
+    if req.estimate_minutes.is_some_and(|m| m < 0) {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Estimate cannot be negative"
+        }));
+    }
+
+    match projects.find_project_by_id(organization.id, req.project_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Project not found"
+            }));
+        }
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }));
+        }
+    }
+
+    match tasks.create_task(organization.id, &req, caller_id(&http_req)).await {
+        Ok(task) => {
+            metrics.tasks_created.inc();
+            HttpResponse::Created().json(task)
+        }
         Err(e) => {
             log::error!("Database error: {}", e);
             HttpResponse::InternalServerError().json(serde_json::json!({
@@ -31,9 +94,25 @@ async fn create_task_handler(
     }
 }
 
+#[utoipa::path(
+    params(
+        ("project_id" = Option<Uuid>, Query, description = "Only tasks in this project"),
+        ("assignee_id" = Option<Uuid>, Query, description = "Only tasks assigned to this user"),
+        ("status" = Option<TaskStatus>, Query, description = "Only tasks with this status"),
+        ("parent_task_id" = Option<Uuid>, Query, description = "Only subtasks of this task"),
+        ("sprint_id" = Option<Uuid>, Query, description = "Only tasks in this sprint"),
+        ("labels" = Option<String>, Query, description = "Comma-separated label ids"),
+        ("label_match" = Option<String>, Query, description = "`all` (default) or `any` of the labels"),
+    ),
+    responses(
+        (status = 200, description = "The matching tasks", body = Vec<Task>),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    ),
+)]
 #[get("")]
 async fn list_tasks_handler(
-    pool: web::Data<PgPool>,
+    tasks: web::Data<dyn TaskRepository>,
+    organization: CurrentOrganization,
     query: web::Query<std::collections::HashMap<String, String>>,
 ) -> impl Responder {
     let project_id = query
@@ -46,8 +125,48 @@ async fn list_tasks_handler(
     let parent_task_id = query
         .get("parent_task_id")
         .and_then(|id| Uuid::parse_str(id).ok());
+    let sprint_id = query
+        .get("sprint_id")
+        .and_then(|id| Uuid::parse_str(id).ok());
+
+    // Unlike the other filters, a bad label id is an error rather than ignored,
+    // since dropping it would silently widen the result
+    let label_ids = match query.get("labels") {
+        Some(ids) => match ids
+            .split(',')
+            .filter(|id| !id.trim().is_empty())
+            .map(|id| Uuid::parse_str(id.trim()))
+            .collect::<Result<Vec<_>, _>>()
+        {
+            Ok(ids) => ids,
+            Err(_) => {
+                return HttpResponse::BadRequest().json(serde_json::json!({
+                    "error": "labels must be a comma-separated list of label ids"
+                }));
+            }
+        },
+        None => Vec::new(),
+    };
+    let label_match = match query.get("label_match").map(|m| m.parse::<LabelMatch>()) {
+        Some(Ok(mode)) => mode,
+        Some(Err(e)) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({ "error": e }));
+        }
+        None => LabelMatch::default(),
+    };
 
-    match list_tasks(&pool, project_id, assignee_id, status, parent_task_id).await {
+    let filter = TaskFilter {
+        project_id,
+        assignee_id,
+        status,
+        parent_task_id,
+        sprint_id,
+        label_ids,
+        label_match,
+        ..TaskFilter::default()
+    };
+
+    match tasks.list_tasks(organization.id, &filter).await {
         Ok(tasks) => HttpResponse::Ok().json(tasks),
         Err(e) => {
             log::error!("Database error: {}", e);
@@ -58,11 +177,21 @@ async fn list_tasks_handler(
     }
 }
 
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The task", body = Task),
+        (status = 404, description = "Task not found", body = ErrorResponse),
+    ),
+)]
 #[get("/{id}")]
-async fn get_task_handler(pool: web::Data<PgPool>, path: web::Path<Uuid>) -> impl Responder {
+async fn get_task_handler(
+    tasks: web::Data<dyn TaskRepository>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+) -> impl Responder {
     let task_id = path.into_inner();
 
-    match find_task_by_id(&pool, task_id).await {
+    match tasks.find_task_by_id(organization.id, task_id).await {
         Ok(Some(task)) => HttpResponse::Ok().json(task),
         Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
             "error": "Task not found"
@@ -76,15 +205,30 @@ async fn get_task_handler(pool: web::Data<PgPool>, path: web::Path<Uuid>) -> imp
     }
 }
 
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The updated task", body = Task),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Task not found", body = ErrorResponse),
+    ),
+)]
 #[put("/{id}")]
 async fn update_task_handler(
-    pool: web::Data<PgPool>,
+    tasks: web::Data<dyn TaskRepository>,
+    organization: CurrentOrganization,
     path: web::Path<Uuid>,
     req: web::Json<UpdateTaskRequest>,
+    http_req: HttpRequest,
 ) -> impl Responder {
     let task_id = path.into_inner();
 
-    match update_task(&pool, task_id, &req).await {
+    if req.estimate_minutes.is_some_and(|m| m < 0) {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Estimate cannot be negative"
+        }));
+    }
+
+    match tasks.update_task(organization.id, task_id, &req, caller_id(&http_req)).await {
         Ok(task) => HttpResponse::Ok().json(task),
         Err(e) => {
             log::error!("Database error: {}", e);
@@ -95,9 +239,17 @@ async fn update_task_handler(
     }
 }
 
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The updated task", body = Task),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Task not found", body = ErrorResponse),
+    ),
+)]
 #[put("/{id}/progress")]
 async fn update_progress_handler(
-    pool: web::Data<PgPool>,
+    tasks: web::Data<dyn TaskRepository>,
+    organization: CurrentOrganization,
     path: web::Path<Uuid>,
     req: web::Json<UpdateProgressRequest>,
 ) -> impl Responder {
@@ -109,7 +261,7 @@ async fn update_progress_handler(
         }));
     }
 
-    match update_task_progress(&pool, task_id, req.progress_percent).await {
+    match tasks.update_task_progress(organization.id, task_id, req.progress_percent).await {
         Ok(task) => HttpResponse::Ok().json(task),
         Err(e) => {
             log::error!("Database error: {}", e);
@@ -120,12 +272,78 @@ async fn update_progress_handler(
     }
 }
 
+#[utoipa::path(
+    responses(
+        (status = 204, description = "Task moved to the trash"),
+        (status = 404, description = "Task not found", body = ErrorResponse),
+    ),
+)]
 #[delete("/{id}")]
-async fn delete_task_handler(pool: web::Data<PgPool>, path: web::Path<Uuid>) -> impl Responder {
+async fn delete_task_handler(
+    tasks: web::Data<dyn TaskRepository>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let task_id = path.into_inner();
+
+    // The task stays in the trash until the retention job purges it
+    match tasks.soft_delete_task(organization.id, task_id, caller_id(&http_req)).await {
+        Ok(true) => HttpResponse::NoContent().finish(),
+        Ok(false) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Task not found"
+        })),
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }))
+        }
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The restored task", body = Task),
+        (status = 404, description = "Task in trash not found", body = ErrorResponse),
+        (status = 409, description = "The task's project is in the trash", body = ErrorResponse),
+    ),
+)]
+#[post("/{id}/restore")]
+async fn restore_task_handler(
+    pool: web::Data<PgPool>,
+    tasks: web::Data<dyn TaskRepository>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+) -> impl Responder {
     let task_id = path.into_inner();
 
-    match delete_task(&pool, task_id).await {
-        Ok(_) => HttpResponse::NoContent().finish(),
+    match find_trash_item(&pool, organization.id, TrashKind::Task, task_id).await {
+        Ok(Some(item)) if item.parent_deleted => {
+            return HttpResponse::Conflict().json(serde_json::json!({
+                "error": "Restore the task's project or parent task first"
+            }));
+        }
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Task not found in trash"
+            }));
+        }
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }));
+        }
+    }
+
+    match tasks.restore_task(organization.id, task_id, caller_id(&http_req)).await {
+        Ok(Some(task)) => HttpResponse::Ok().json(task),
+        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Task not found in trash"
+        })),
         Err(e) => {
             log::error!("Database error: {}", e);
             HttpResponse::InternalServerError().json(serde_json::json!({
@@ -136,15 +354,26 @@ async fn delete_task_handler(pool: web::Data<PgPool>, path: web::Path<Uuid>) ->
 }
 
 // Subtask routes
+#[utoipa::path(
+    responses(
+        (status = 201, description = "The new subtask", body = Subtask),
+        (status = 404, description = "Task not found", body = ErrorResponse),
+    ),
+)]
 #[post("/{id}/subtasks")]
 async fn create_subtask_handler(
-    pool: web::Data<PgPool>,
+    tasks: web::Data<dyn TaskRepository>,
+    organization: CurrentOrganization,
     path: web::Path<Uuid>,
     req: web::Json<CreateSubtaskRequest>,
 ) -> impl Responder {
     let task_id = path.into_inner();
 
-    match create_subtask(&pool, task_id, &req).await {
+    if let Err(response) = require_task(&**tasks, organization.id, task_id).await {
+        return response;
+    }
+
+    match tasks.create_subtask(task_id, &req).await {
         Ok(subtask) => HttpResponse::Created().json(subtask),
         Err(e) => {
             log::error!("Database error: {}", e);
@@ -155,11 +384,25 @@ async fn create_subtask_handler(
     }
 }
 
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The task's subtasks", body = Vec<Subtask>),
+        (status = 404, description = "Task not found", body = ErrorResponse),
+    ),
+)]
 #[get("/{id}/subtasks")]
-async fn list_subtasks_handler(pool: web::Data<PgPool>, path: web::Path<Uuid>) -> impl Responder {
+async fn list_subtasks_handler(
+    tasks: web::Data<dyn TaskRepository>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+) -> impl Responder {
     let task_id = path.into_inner();
 
-    match list_subtasks(&pool, task_id).await {
+    if let Err(response) = require_task(&**tasks, organization.id, task_id).await {
+        return response;
+    }
+
+    match tasks.list_subtasks(task_id).await {
         Ok(subtasks) => HttpResponse::Ok().json(subtasks),
         Err(e) => {
             log::error!("Database error: {}", e);
@@ -170,17 +413,26 @@ async fn list_subtasks_handler(pool: web::Data<PgPool>, path: web::Path<Uuid>) -
     }
 }
 
+#[utoipa::path(
+    responses(
+        (status = 201, description = "The new history entry", body = TaskHistory),
+        (status = 404, description = "Task not found", body = ErrorResponse),
+    ),
+)]
 #[post("/{task_id}/history")]
 async fn create_history_handler(
-    pool: web::Data<PgPool>,
+    tasks: web::Data<dyn TaskRepository>,
+    organization: CurrentOrganization,
     path: web::Path<Uuid>,
     req: web::Json<CreateTaskHistoryRequest>,
-    http_req: actix_web::HttpRequest,
+    http_req: HttpRequest,
 ) -> impl Responder {
-    use actix_web::HttpMessage;
-    
     let task_id = path.into_inner();
 
+    if let Err(response) = require_task(&**tasks, organization.id, task_id).await {
+        return response;
+    }
+
     // Get user ID from claims
     let user_id = match http_req.extensions().get::<Claims>() {
         Some(claims) => match Uuid::parse_str(&claims.sub) {
@@ -198,7 +450,7 @@ async fn create_history_handler(
         }
     };
 
-    match create_task_history(&pool, task_id, user_id, &req).await {
+    match tasks.create_task_history(task_id, user_id, &req).await {
         Ok(history) => HttpResponse::Created().json(history),
         Err(e) => {
             log::error!("Database error: {}", e);
@@ -209,14 +461,25 @@ async fn create_history_handler(
     }
 }
 
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The task's history", body = Vec<TaskHistoryWithUser>),
+        (status = 404, description = "Task not found", body = ErrorResponse),
+    ),
+)]
 #[get("/{task_id}/history")]
 async fn list_history_handler(
-    pool: web::Data<PgPool>,
+    tasks: web::Data<dyn TaskRepository>,
+    organization: CurrentOrganization,
     path: web::Path<Uuid>,
 ) -> impl Responder {
     let task_id = path.into_inner();
 
-    match list_task_history(&pool, task_id).await {
+    if let Err(response) = require_task(&**tasks, organization.id, task_id).await {
+        return response;
+    }
+
+    match tasks.list_task_history(task_id).await {
         Ok(history) => HttpResponse::Ok().json(history),
         Err(e) => {
             log::error!("Database error: {}", e);
@@ -227,6 +490,94 @@ async fn list_history_handler(
     }
 }
 
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The task's activity feed", body = Vec<TaskActivity>),
+        (status = 404, description = "Task not found", body = ErrorResponse),
+    ),
+)]
+#[get("/{task_id}/activity")]
+async fn list_activity_handler(
+    tasks: web::Data<dyn TaskRepository>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    let task_id = path.into_inner();
+
+    if let Err(response) = require_task(&**tasks, organization.id, task_id).await {
+        return response;
+    }
+
+    match tasks.list_task_activity(task_id).await {
+        Ok(activity) => HttpResponse::Ok().json(activity),
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }))
+        }
+    }
+}
+
+/// The email the task was created from and the replies threaded onto it.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The task's emails, oldest first", body = Vec<EmailLog>),
+        (status = 404, description = "Task not found", body = ErrorResponse),
+    ),
+)]
+#[get("/{task_id}/emails")]
+async fn list_emails_handler(
+    tasks: web::Data<dyn TaskRepository>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    let task_id = path.into_inner();
+
+    if let Err(response) = require_task(&**tasks, organization.id, task_id).await {
+        return response;
+    }
+
+    match tasks.list_task_emails(task_id).await {
+        Ok(emails) => HttpResponse::Ok().json(emails),
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }))
+        }
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    create_task_handler,
+    list_tasks_handler,
+    get_task_handler,
+    update_task_handler,
+    update_progress_handler,
+    delete_task_handler,
+    restore_task_handler,
+    create_subtask_handler,
+    list_subtasks_handler,
+    create_history_handler,
+    list_history_handler,
+    list_activity_handler,
+    list_emails_handler,
+    crate::routes::comments::create_comment_handler,
+    crate::routes::comments::list_comments_handler,
+    crate::routes::comments::update_comment_handler,
+    crate::routes::comments::delete_comment_handler,
+    crate::routes::labels::list_task_labels_handler,
+    crate::routes::labels::add_task_label_handler,
+    crate::routes::labels::remove_task_label_handler,
+    crate::routes::attachments::upload_attachments_handler,
+    crate::routes::attachments::list_attachments_handler,
+    crate::routes::attachments::download_attachment_handler,
+    crate::routes::attachments::delete_attachment_handler,
+))]
+pub struct TaskApi;
+
 pub fn task_routes() -> actix_web::Scope {
     web::scope("/tasks")
         .service(create_task_handler)
@@ -235,8 +586,22 @@ pub fn task_routes() -> actix_web::Scope {
         .service(update_task_handler)
         .service(update_progress_handler)
         .service(delete_task_handler)
+        .service(restore_task_handler)
         .service(create_subtask_handler)
         .service(list_subtasks_handler)
         .service(create_history_handler)
         .service(list_history_handler)
+        .service(list_activity_handler)
+        .service(list_emails_handler)
+        .service(create_comment_handler)
+        .service(list_comments_handler)
+        .service(update_comment_handler)
+        .service(delete_comment_handler)
+        .service(list_task_labels_handler)
+        .service(add_task_label_handler)
+        .service(remove_task_label_handler)
+        .service(upload_attachments_handler)
+        .service(list_attachments_handler)
+        .service(download_attachment_handler)
+        .service(delete_attachment_handler)
 }
@@ -1,140 +1,348 @@
-use actix_web::{delete, get, post, put, web, HttpResponse, Responder};
-use sqlx::PgPool;
+use actix_web::{delete, get, post, put, web, HttpRequest, HttpResponse, Responder};
+use utoipa::OpenApi;
 use uuid::Uuid;
 
 use crate::{
-    db::{
-        add_team_member, create_team, delete_team, find_team_by_id, list_team_members,
-        list_teams, remove_team_member, update_team,
+    config::Config,
+    db::{TeamRepository, UserRepository},
+    models::{
+        AddTeamMemberRequest, CreateTeamRequest, Team, TeamInvitation, TeamMember, TeamMemberWithUser,
+        TeamRole, TrashEntry, UpdateTeamMemberRequest, UpdateTeamRequest, UserRole,
     },
-    models::{AddTeamMemberRequest, CreateTeamRequest, UpdateTeamRequest},
+    middleware::CurrentOrganization,
+    openapi::ErrorResponse,
+    routes::helpers::{caller_claims, caller_id, internal_error, unauthorized},
+    utils::Claims,
 };
 
+/// Checks that the team exists in the caller's organization, for routes about
+/// its members and invitations.
+async fn require_team(teams: &dyn TeamRepository, organization_id: Uuid, team_id: Uuid) -> Result<(), HttpResponse> {
+    match teams.find_team_by_id(organization_id, team_id).await {
+        Ok(Some(_)) => Ok(()),
+        Ok(None) => Err(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Team not found"
+        }))),
+        Err(e) => Err(internal_error(e)),
+    }
+}
+
+/// Whether the user belongs to the organization.
+async fn in_organization(
+    users: &dyn UserRepository,
+    organization_id: Uuid,
+    user_id: Uuid,
+) -> Result<bool, sqlx::Error> {
+    Ok(users.organization_ids(user_id).await?.contains(&organization_id))
+}
+
+/// Returns the caller's role in the team, treating global admins as owners.
+async fn caller_team_role(
+    teams: &dyn TeamRepository,
+    team_id: Uuid,
+    user_id: Uuid,
+    claims: &Claims,
+) -> Result<Option<TeamRole>, sqlx::Error> {
+    if claims.role.parse::<UserRole>() == Ok(UserRole::Admin) {
+        return Ok(Some(TeamRole::Owner));
+    }
+
+    let member = teams.find_team_member(team_id, user_id).await?;
+    Ok(member.and_then(|m| m.role.parse().ok()))
+}
+
+/// Checks that the caller's role in the team passes `allowed`, returning the
+/// caller's id; `error` is the 403 message otherwise.
+async fn require_team_role(
+    teams: &dyn TeamRepository,
+    team_id: Uuid,
+    http_req: &HttpRequest,
+    allowed: fn(&TeamRole) -> bool,
+    error: &str,
+) -> Result<Uuid, HttpResponse> {
+    let Some((caller_id, claims)) = caller_claims(http_req) else {
+        return Err(unauthorized());
+    };
+    match caller_team_role(teams, team_id, caller_id, &claims).await {
+        Ok(Some(role)) if allowed(&role) => Ok(caller_id),
+        Ok(_) => Err(HttpResponse::Forbidden().json(serde_json::json!({ "error": error }))),
+        Err(e) => Err(internal_error(e)),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 201, description = "The new team", body = Team),
+    ),
+)]
 #[post("")]
 async fn create_team_handler(
-    pool: web::Data<PgPool>,
+    teams: web::Data<dyn TeamRepository>,
+    organization: CurrentOrganization,
     req: web::Json<CreateTeamRequest>,
-    http_req: actix_web::HttpRequest,
+    http_req: HttpRequest,
 ) -> impl Responder {
-    use actix_web::HttpMessage;
-    use crate::utils::Claims;
-
-    // Get user ID from claims
-    let user_id = match http_req.extensions().get::<Claims>() {
-        Some(claims) => match uuid::Uuid::parse_str(&claims.sub) {
-            Ok(id) => id,
-            Err(_) => return HttpResponse::Unauthorized().json(serde_json::json!({"error": "Invalid user ID"})),
-        },
-        None => return HttpResponse::Unauthorized().json(serde_json::json!({"error": "Unauthorized"})),
+    let Some(user_id) = caller_id(&http_req) else {
+        return unauthorized();
     };
 
-    match create_team(&pool, &req).await {
+    match teams.create_team(organization.id, &req).await {
         Ok(team) => {
-            // Auto-add creator as member
-            if let Err(e) = crate::db::add_team_member(&pool, team.id, user_id).await {
+            // Auto-add creator as owner
+            if let Err(e) = teams.add_team_member(team.id, user_id, &TeamRole::Owner).await {
                 log::error!("Failed to auto-add creator to team: {}", e);
                 // We don't fail the request if adding member fails, but we log it
             }
             HttpResponse::Created().json(team)
         },
-        Err(e) => {
-            log::error!("Database error: {}", e);
-            HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Internal server error"
-            }))
-        }
+        Err(e) => internal_error(e),
     }
 }
 
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The organization's teams", body = Vec<Team>),
+    ),
+)]
 #[get("")]
-async fn list_teams_handler(pool: web::Data<PgPool>) -> impl Responder {
-    match list_teams(&pool).await {
+async fn list_teams_handler(teams: web::Data<dyn TeamRepository>, organization: CurrentOrganization) -> impl Responder {
+    match teams.list_teams(organization.id).await {
         Ok(teams) => HttpResponse::Ok().json(teams),
-        Err(e) => {
-            log::error!("Database error: {}", e);
-            HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Internal server error"
-            }))
-        }
+        Err(e) => internal_error(e),
     }
 }
 
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The team", body = Team),
+        (status = 404, description = "Team not found", body = ErrorResponse),
+    ),
+)]
 #[get("/{id}")]
-async fn get_team_handler(pool: web::Data<PgPool>, path: web::Path<Uuid>) -> impl Responder {
+async fn get_team_handler(
+    teams: web::Data<dyn TeamRepository>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+) -> impl Responder {
     let team_id = path.into_inner();
 
-    match find_team_by_id(&pool, team_id).await {
+    match teams.find_team_by_id(organization.id, team_id).await {
         Ok(Some(team)) => HttpResponse::Ok().json(team),
         Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
             "error": "Team not found"
         })),
-        Err(e) => {
-            log::error!("Database error: {}", e);
-            HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Internal server error"
-            }))
-        }
+        Err(e) => internal_error(e),
     }
 }
 
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The updated team", body = Team),
+        (status = 403, description = "Only team owners and maintainers can edit the team", body = ErrorResponse),
+        (status = 404, description = "Team not found", body = ErrorResponse),
+    ),
+)]
 #[put("/{id}")]
 async fn update_team_handler(
-    pool: web::Data<PgPool>,
+    teams: web::Data<dyn TeamRepository>,
+    organization: CurrentOrganization,
     path: web::Path<Uuid>,
     req: web::Json<UpdateTeamRequest>,
+    http_req: HttpRequest,
 ) -> impl Responder {
     let team_id = path.into_inner();
 
-    match update_team(&pool, team_id, &req).await {
+    if let Err(response) = require_team(teams.get_ref(), organization.id, team_id).await {
+        return response;
+    }
+    let error = "Only team owners and maintainers can edit the team";
+    let allowed = require_team_role(teams.get_ref(), team_id, &http_req, TeamRole::can_edit_team, error);
+    if let Err(response) = allowed.await {
+        return response;
+    }
+
+    match teams.update_team(organization.id, team_id, &req).await {
         Ok(team) => HttpResponse::Ok().json(team),
-        Err(e) => {
-            log::error!("Database error: {}", e);
-            HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Internal server error"
-            }))
-        }
+        Err(e) => internal_error(e),
     }
 }
 
+#[utoipa::path(
+    responses(
+        (status = 204, description = "Team moved to the trash"),
+        (status = 403, description = "Only team owners can delete the team", body = ErrorResponse),
+        (status = 404, description = "Team not found", body = ErrorResponse),
+    ),
+)]
 #[delete("/{id}")]
-async fn delete_team_handler(pool: web::Data<PgPool>, path: web::Path<Uuid>) -> impl Responder {
+async fn delete_team_handler(
+    teams: web::Data<dyn TeamRepository>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+) -> impl Responder {
     let team_id = path.into_inner();
 
-    match delete_team(&pool, team_id).await {
-        Ok(_) => HttpResponse::NoContent().finish(),
-        Err(e) => {
-            log::error!("Database error: {}", e);
-            HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Internal server error"
-            }))
+    if let Err(response) = require_team(teams.get_ref(), organization.id, team_id).await {
+        return response;
+    }
+    let error = "Only team owners can delete the team";
+    let allowed = TeamRole::can_delete_team;
+    let actor_id = match require_team_role(teams.get_ref(), team_id, &http_req, allowed, error).await {
+        Ok(caller_id) => caller_id,
+        Err(response) => return response,
+    };
+
+    // The team, its projects and their tasks stay in the trash until the retention job purges them
+    match teams.soft_delete_team(organization.id, team_id, Some(actor_id)).await {
+        Ok(true) => HttpResponse::NoContent().finish(),
+        Ok(false) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Team not found"
+        })),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The restored team", body = Team),
+        (status = 404, description = "Team in trash not found", body = ErrorResponse),
+    ),
+)]
+#[post("/{id}/restore")]
+async fn restore_team_handler(
+    teams: web::Data<dyn TeamRepository>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    let team_id = path.into_inner();
+
+    match teams.restore_team(organization.id, team_id).await {
+        Ok(Some(team)) => HttpResponse::Ok().json(team),
+        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Team not found in trash"
+        })),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Everything deleted in the team: the team itself, its projects and their tasks.
+/// Items that were only removed along with their parent are restored with it and not listed.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Trashed items in the team", body = Vec<TrashEntry>),
+    ),
+)]
+#[get("/{id}/trash")]
+async fn team_trash_handler(
+    teams: web::Data<dyn TeamRepository>,
+    config: web::Data<Config>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    match teams.list_team_trash(organization.id, path.into_inner()).await {
+        Ok(items) => {
+            let entries: Vec<TrashEntry> = items
+                .into_iter()
+                .map(|item| TrashEntry::new(item, config.trash_retention_days))
+                .collect();
+            HttpResponse::Ok().json(entries)
         }
+        Err(e) => internal_error(e),
     }
 }
 
+#[utoipa::path(
+    responses(
+        (status = 201, description = "The user was added", body = TeamMember),
+        (status = 202, description = "An invitation was sent", body = TeamInvitation),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 403, description = "Only team admins can add members", body = ErrorResponse),
+        (status = 404, description = "Team not found", body = ErrorResponse),
+        (status = 409, description = "Already a member or invited", body = ErrorResponse),
+    ),
+)]
 #[post("/{id}/members")]
 async fn add_member_handler(
-    pool: web::Data<PgPool>,
+    teams: web::Data<dyn TeamRepository>,
+    users: web::Data<dyn UserRepository>,
+    organization: CurrentOrganization,
     path: web::Path<Uuid>,
     req: web::Json<AddTeamMemberRequest>,
+    http_req: HttpRequest,
 ) -> impl Responder {
     let team_id = path.into_inner();
 
+    if let Err(response) = require_team(teams.get_ref(), organization.id, team_id).await {
+        return response;
+    }
+
+    let Some((caller_id, claims)) = caller_claims(&http_req) else {
+
+        return unauthorized();
+
+    };
+
+    let caller_role = match caller_team_role(teams.get_ref(), team_id, caller_id, &claims).await {
+        Ok(role) => role,
+        Err(e) => return internal_error(e),
+    };
+    let role = req.role.clone().unwrap_or(TeamRole::Member);
+
+    match &caller_role {
+        Some(r) if r.can_manage_members() => {}
+        _ => {
+            return HttpResponse::Forbidden().json(serde_json::json!({
+                "error": "Only team owners and maintainers can add members"
+            }));
+        }
+    }
+    if role == TeamRole::Owner && caller_role != Some(TeamRole::Owner) {
+        return HttpResponse::Forbidden().json(serde_json::json!({
+            "error": "Only team owners can grant the owner role"
+        }));
+    }
+
     let user_id = if let Some(id) = req.user_id {
-        id
+        match in_organization(users.get_ref(), organization.id, id).await {
+            Ok(true) => id,
+            Ok(false) => {
+                return HttpResponse::BadRequest().json(serde_json::json!({
+                    "error": "User is not a member of this organization; invite them by email instead"
+                }));
+            }
+            Err(e) => return internal_error(e),
+        }
     } else if let Some(email) = &req.email {
-        match crate::db::find_user_by_email(&pool, email).await {
+        let existing = match users.find_user_by_email(email).await {
+            Ok(Some(user)) => match in_organization(users.get_ref(), organization.id, user.id).await {
+                Ok(true) => Ok(Some(user)),
+                Ok(false) => Ok(None),
+                Err(e) => Err(e),
+            },
+            other => other,
+        };
+        match existing {
             Ok(Some(user)) => user.id,
             Ok(None) => {
-                return HttpResponse::NotFound().json(serde_json::json!({
-                    "error": "User not found"
-                }));
-            }
-            Err(e) => {
-                log::error!("Database error: {}", e);
-                return HttpResponse::InternalServerError().json(serde_json::json!({
-                    "error": "Internal server error"
-                }));
+                // Not registered yet, or in another organization: record an invitation
+                // that is claimed on register and joins the organization when accepted
+                match teams.find_pending_team_invitation(team_id, email).await {
+                    Ok(Some(_)) => {
+                        return HttpResponse::Conflict().json(serde_json::json!({
+                            "error": "An invitation for this email is already pending"
+                        }));
+                    }
+                    Ok(None) => {}
+                    Err(e) => return internal_error(e),
+                }
+
+                return match teams.create_team_invitation(organization.id, team_id, email, &role, caller_id).await {
+                    Ok(invitation) => HttpResponse::Accepted().json(invitation),
+                    Err(e) => internal_error(e),
+                };
             }
+            Err(e) => return internal_error(e),
         }
     } else {
         return HttpResponse::BadRequest().json(serde_json::json!({
@@ -142,50 +350,247 @@ async fn add_member_handler(
         }));
     };
 
-    match add_team_member(&pool, team_id, user_id).await {
+    match teams.add_team_member(team_id, user_id, &role).await {
         Ok(member) => HttpResponse::Created().json(member),
-        Err(e) => {
-            log::error!("Database error: {}", e);
-            HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Internal server error"
-            }))
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => HttpResponse::Conflict().json(serde_json::json!({
+            "error": "User is already a member of the team"
+        })),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The updated membership", body = TeamMember),
+        (status = 403, description = "Only team admins can change roles", body = ErrorResponse),
+        (status = 404, description = "Member not found", body = ErrorResponse),
+    ),
+)]
+#[put("/{id}/members/{user_id}")]
+async fn update_member_handler(
+    teams: web::Data<dyn TeamRepository>,
+    organization: CurrentOrganization,
+    path: web::Path<(Uuid, Uuid)>,
+    req: web::Json<UpdateTeamMemberRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let (team_id, user_id) = path.into_inner();
+
+    if let Err(response) = require_team(teams.get_ref(), organization.id, team_id).await {
+        return response;
+    }
+
+    let Some((caller_id, claims)) = caller_claims(&http_req) else {
+
+        return unauthorized();
+
+    };
+
+    let caller_role = match caller_team_role(teams.get_ref(), team_id, caller_id, &claims).await {
+        Ok(role) => role,
+        Err(e) => return internal_error(e),
+    };
+
+    match &caller_role {
+        Some(r) if r.can_manage_members() => {}
+        _ => {
+            return HttpResponse::Forbidden().json(serde_json::json!({
+                "error": "Only team owners and maintainers can change member roles"
+            }));
+        }
+    }
+
+    let current = match teams.find_team_member(team_id, user_id).await {
+        Ok(Some(member)) => member,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Team member not found"
+            }));
         }
+        Err(e) => return internal_error(e),
+    };
+
+    let touches_owner = req.role == TeamRole::Owner || current.role == TeamRole::Owner.to_string();
+    if touches_owner && caller_role != Some(TeamRole::Owner) {
+        return HttpResponse::Forbidden().json(serde_json::json!({
+            "error": "Only team owners can grant or revoke the owner role"
+        }));
+    }
+
+    match teams.update_team_member_role(team_id, user_id, &req.role).await {
+        Ok(Some(member)) => HttpResponse::Ok().json(member),
+        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Team member not found"
+        })),
+        Err(e) => internal_error(e),
     }
 }
 
+#[utoipa::path(
+    responses(
+        (status = 204, description = "Member removed"),
+        (status = 403, description = "Only team admins can remove members", body = ErrorResponse),
+        (status = 404, description = "Team not found", body = ErrorResponse),
+    ),
+)]
 #[delete("/{id}/members/{user_id}")]
 async fn remove_member_handler(
-    pool: web::Data<PgPool>,
+    teams: web::Data<dyn TeamRepository>,
+    organization: CurrentOrganization,
     path: web::Path<(Uuid, Uuid)>,
+    http_req: HttpRequest,
 ) -> impl Responder {
     let (team_id, user_id) = path.into_inner();
 
-    match remove_team_member(&pool, team_id, user_id).await {
-        Ok(_) => HttpResponse::NoContent().finish(),
-        Err(e) => {
-            log::error!("Database error: {}", e);
-            HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Internal server error"
-            }))
+    if let Err(response) = require_team(teams.get_ref(), organization.id, team_id).await {
+        return response;
+    }
+
+    let Some((caller_id, claims)) = caller_claims(&http_req) else {
+
+        return unauthorized();
+
+    };
+
+    // Members may always leave a team themselves
+    if caller_id != user_id {
+        let caller_role = match caller_team_role(teams.get_ref(), team_id, caller_id, &claims).await {
+            Ok(Some(role)) if role.can_manage_members() => role,
+            Ok(_) => {
+                return HttpResponse::Forbidden().json(serde_json::json!({
+                    "error": "Only team owners and maintainers can remove members"
+                }));
+            }
+            Err(e) => return internal_error(e),
+        };
+
+        match teams.find_team_member(team_id, user_id).await {
+            Ok(Some(member))
+                if member.role == TeamRole::Owner.to_string() && caller_role != TeamRole::Owner =>
+            {
+                return HttpResponse::Forbidden().json(serde_json::json!({
+                    "error": "Only team owners can remove an owner"
+                }));
+            }
+            Ok(_) => {}
+            Err(e) => return internal_error(e),
         }
     }
+
+    match teams.remove_team_member(team_id, user_id).await {
+        Ok(_) => HttpResponse::NoContent().finish(),
+        Err(e) => internal_error(e),
+    }
 }
 
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The team's members", body = Vec<TeamMemberWithUser>),
+        (status = 404, description = "Team not found", body = ErrorResponse),
+    ),
+)]
 #[get("/{id}/members")]
-async fn list_members_handler(pool: web::Data<PgPool>, path: web::Path<Uuid>) -> impl Responder {
+async fn list_members_handler(
+    teams: web::Data<dyn TeamRepository>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+) -> impl Responder {
     let team_id = path.into_inner();
 
-    match list_team_members(&pool, team_id).await {
+    if let Err(response) = require_team(teams.get_ref(), organization.id, team_id).await {
+        return response;
+    }
+
+    match teams.list_team_members(team_id).await {
         Ok(members) => HttpResponse::Ok().json(members),
-        Err(e) => {
-            log::error!("Database error: {}", e);
-            HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Internal server error"
-            }))
+        Err(e) => internal_error(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The team's invitations", body = Vec<TeamInvitation>),
+        (status = 404, description = "Team not found", body = ErrorResponse),
+    ),
+)]
+#[get("/{id}/invitations")]
+async fn list_invitations_handler(
+    teams: web::Data<dyn TeamRepository>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    let team_id = path.into_inner();
+
+    if let Err(response) = require_team(teams.get_ref(), organization.id, team_id).await {
+        return response;
+    }
+
+    match teams.list_team_invitations(team_id).await {
+        Ok(invitations) => HttpResponse::Ok().json(invitations),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 204, description = "Invitation revoked"),
+        (status = 403, description = "Only team admins can revoke invitations", body = ErrorResponse),
+        (status = 404, description = "Team not found", body = ErrorResponse),
+    ),
+)]
+#[delete("/{id}/invitations/{invitation_id}")]
+async fn revoke_invitation_handler(
+    teams: web::Data<dyn TeamRepository>,
+    organization: CurrentOrganization,
+    path: web::Path<(Uuid, Uuid)>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let (team_id, invitation_id) = path.into_inner();
+
+    if let Err(response) = require_team(teams.get_ref(), organization.id, team_id).await {
+        return response;
+    }
+
+    let Some((caller_id, claims)) = caller_claims(&http_req) else {
+
+        return unauthorized();
+
+    };
+
+    match caller_team_role(teams.get_ref(), team_id, caller_id, &claims).await {
+        Ok(Some(role)) if role.can_manage_members() => {}
+        Ok(_) => {
+            return HttpResponse::Forbidden().json(serde_json::json!({
+                "error": "Only team owners and maintainers can revoke invitations"
+            }));
         }
+        Err(e) => return internal_error(e),
+    }
+
+    match teams.delete_team_invitation(team_id, invitation_id).await {
+        Ok(_) => HttpResponse::NoContent().finish(),
+        Err(e) => internal_error(e),
     }
 }
 
+#[derive(OpenApi)]
+#[openapi(paths(
+    create_team_handler,
+    list_teams_handler,
+    get_team_handler,
+    update_team_handler,
+    delete_team_handler,
+    restore_team_handler,
+    team_trash_handler,
+    add_member_handler,
+    update_member_handler,
+    remove_member_handler,
+    list_members_handler,
+    list_invitations_handler,
+    revoke_invitation_handler,
+))]
+pub struct TeamApi;
+
 pub fn team_routes() -> actix_web::Scope {
     web::scope("/teams")
         .service(create_team_handler)
@@ -193,7 +598,12 @@ pub fn team_routes() -> actix_web::Scope {
         .service(get_team_handler)
         .service(update_team_handler)
         .service(delete_team_handler)
+        .service(restore_team_handler)
+        .service(team_trash_handler)
         .service(add_member_handler)
+        .service(update_member_handler)
         .service(remove_member_handler)
         .service(list_members_handler)
+        .service(list_invitations_handler)
+        .service(revoke_invitation_handler)
 }
@@ -0,0 +1,382 @@
+use std::collections::HashMap;
+use utoipa::OpenApi;
+
+use actix_web::{delete, get, post, put, web, HttpRequest, HttpResponse, Responder};
+use chrono::Utc;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    db::{
+        create_template, delete_template, find_project_by_id, find_team_by_id, find_template_by_id,
+        instantiate_template, list_subtasks_for_project, list_tasks, list_team_members, list_template_tasks,
+        list_templates, team_role_defaults, update_template, TemplateTarget,
+    },
+    models::{
+        flatten_template_tasks, CreateProjectRequest, CreateTemplateRequest, InstantiateTemplateRequest,
+        InstantiateTemplateResult, ProjectTemplate, SaveProjectAsTemplateRequest, TaskFilter, TemplateQuery,
+        TemplateTaskInput, TemplateWithTasks, UpdateTemplateRequest,
+    },
+    middleware::CurrentOrganization,
+    openapi::ErrorResponse,
+    routes::helpers::{caller_id, internal_error},
+    services::{template_task_tree, template_tasks_from_project},
+};
+
+/// Most tasks (including child tasks) a single template may hold.
+const MAX_TEMPLATE_TASKS: usize = 1000;
+
+fn validate_template_tasks(tasks: &[TemplateTaskInput]) -> Result<(), String> {
+    let flat = flatten_template_tasks(tasks);
+    if flat.len() > MAX_TEMPLATE_TASKS {
+        return Err(format!("A template can hold at most {} tasks", MAX_TEMPLATE_TASKS));
+    }
+
+    for (_, task) in flat {
+        if task.title.trim().is_empty() {
+            return Err("Task title cannot be empty".to_string());
+        }
+        if task.estimate_minutes.is_some_and(|m| m < 0) {
+            return Err(format!("Estimate of \"{}\" cannot be negative", task.title));
+        }
+        if task.subtasks.iter().any(|s| s.trim().is_empty()) {
+            return Err(format!("Subtask titles of \"{}\" cannot be empty", task.title));
+        }
+    }
+    Ok(())
+}
+
+/// A template with its task tree, or the error response.
+async fn load_template(pool: &PgPool, template: ProjectTemplate) -> HttpResponse {
+    match list_template_tasks(pool, template.id).await {
+        Ok(rows) => HttpResponse::Ok().json(TemplateWithTasks {
+            template,
+            tasks: template_task_tree(&rows),
+        }),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 201, description = "The new template", body = TemplateWithTasks),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Team not found", body = ErrorResponse),
+    ),
+)]
+#[post("")]
+async fn create_template_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    req: web::Json<CreateTemplateRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    if req.name.trim().is_empty() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Template name cannot be empty"
+        }));
+    }
+    if let Err(e) = validate_template_tasks(&req.tasks) {
+        return HttpResponse::BadRequest().json(serde_json::json!({ "error": e }));
+    }
+
+    match create_template(&pool, organization.id, &req, caller_id(&http_req)).await {
+        Ok(template) => HttpResponse::Created().json(TemplateWithTasks {
+            template,
+            tasks: req.into_inner().tasks,
+        }),
+        Err(sqlx::Error::Database(e)) if e.is_foreign_key_violation() => {
+            HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Team not found"
+            }))
+        }
+        Err(e) => internal_error(e),
+    }
+}
+
+#[utoipa::path(
+    params(TemplateQuery),
+    responses(
+        (status = 200, description = "The templates", body = Vec<ProjectTemplate>),
+    ),
+)]
+#[get("")]
+async fn list_templates_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    query: web::Query<TemplateQuery>,
+) -> impl Responder {
+    match list_templates(&pool, organization.id, query.team_id).await {
+        Ok(templates) => HttpResponse::Ok().json(templates),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The template and its tasks", body = TemplateWithTasks),
+        (status = 404, description = "Template not found", body = ErrorResponse),
+    ),
+)]
+#[get("/{id}")]
+async fn get_template_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    match find_template_by_id(&pool, organization.id, path.into_inner()).await {
+        Ok(Some(template)) => load_template(&pool, template).await,
+        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Template not found"
+        })),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The updated template", body = TemplateWithTasks),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Template not found", body = ErrorResponse),
+    ),
+)]
+#[put("/{id}")]
+async fn update_template_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+    req: web::Json<UpdateTemplateRequest>,
+) -> impl Responder {
+    if req.name.as_deref().is_some_and(|n| n.trim().is_empty()) {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Template name cannot be empty"
+        }));
+    }
+    if let Some(Err(e)) = req.tasks.as_deref().map(validate_template_tasks) {
+        return HttpResponse::BadRequest().json(serde_json::json!({ "error": e }));
+    }
+
+    match update_template(&pool, organization.id, path.into_inner(), &req).await {
+        Ok(Some(template)) => load_template(&pool, template).await,
+        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Template not found"
+        })),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 204, description = "Template deleted"),
+        (status = 404, description = "Template not found", body = ErrorResponse),
+    ),
+)]
+#[delete("/{id}")]
+async fn delete_template_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    match delete_template(&pool, organization.id, path.into_inner()).await {
+        Ok(true) => HttpResponse::NoContent().finish(),
+        Ok(false) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Template not found"
+        })),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Saves an existing project's task tree as a new template for the project's team.
+#[utoipa::path(
+    responses(
+        (status = 201, description = "The new template", body = TemplateWithTasks),
+        (status = 404, description = "Project not found", body = ErrorResponse),
+    ),
+)]
+#[post("/from-project/{project_id}")]
+async fn save_project_as_template_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+    req: web::Json<SaveProjectAsTemplateRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let project = match find_project_by_id(&pool, organization.id, path.into_inner()).await {
+        Ok(Some(project)) => project,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Project not found"
+            }));
+        }
+        Err(e) => return internal_error(e),
+    };
+
+    let filter = TaskFilter {
+        project_id: Some(project.id),
+        ..Default::default()
+    };
+    let tasks = match list_tasks(&pool, organization.id, &filter).await {
+        Ok(tasks) => tasks,
+        Err(e) => return internal_error(e),
+    };
+    let subtasks = match list_subtasks_for_project(&pool, project.id).await {
+        Ok(subtasks) => subtasks,
+        Err(e) => return internal_error(e),
+    };
+    let roles: HashMap<Uuid, String> = match project.team_id {
+        Some(team_id) => match list_team_members(&pool, team_id).await {
+            Ok(members) => members.into_iter().map(|m| (m.user_id, m.team_role)).collect(),
+            Err(e) => return internal_error(e),
+        },
+        None => HashMap::new(),
+    };
+
+    let req = req.into_inner();
+    let start_date = req.start_date.unwrap_or_else(|| project.created_at.date_naive());
+    let template_req = CreateTemplateRequest {
+        name: req.name.unwrap_or_else(|| project.name.clone()),
+        description: req.description.or_else(|| project.description.clone()),
+        team_id: project.team_id,
+        tasks: template_tasks_from_project(&tasks, &subtasks, &roles, start_date),
+    };
+
+    match create_template(&pool, organization.id, &template_req, caller_id(&http_req)).await {
+        Ok(template) => HttpResponse::Created().json(TemplateWithTasks {
+            template,
+            tasks: template_req.tasks,
+        }),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 201, description = "The new project and its tasks", body = InstantiateTemplateResult),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Template or team not found", body = ErrorResponse),
+    ),
+)]
+#[post("/{id}/instantiate")]
+async fn instantiate_template_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+    req: web::Json<InstantiateTemplateRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let Some(user_id) = caller_id(&http_req) else {
+        return HttpResponse::Unauthorized().json(serde_json::json!({"error": "Unauthorized"}));
+    };
+
+    let template = match find_template_by_id(&pool, organization.id, path.into_inner()).await {
+        Ok(Some(template)) => template,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Template not found"
+            }));
+        }
+        Err(e) => return internal_error(e),
+    };
+    let tasks = match list_template_tasks(&pool, template.id).await {
+        Ok(tasks) => tasks,
+        Err(e) => return internal_error(e),
+    };
+
+    let existing = match req.project_id {
+        Some(_) if req.name.is_some() || req.team_id.is_some() => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "name and team_id only apply when creating a new project"
+            }));
+        }
+        Some(project_id) => match find_project_by_id(&pool, organization.id, project_id).await {
+            Ok(Some(project)) => Some(project),
+            Ok(None) => {
+                return HttpResponse::NotFound().json(serde_json::json!({
+                    "error": "Project not found"
+                }));
+            }
+            Err(e) => return internal_error(e),
+        },
+        None => None,
+    };
+
+    let new_project = CreateProjectRequest {
+        name: req.name.clone().unwrap_or_else(|| template.name.clone()),
+        description: req.description.clone().or_else(|| template.description.clone()),
+        team_id: req.team_id.or(template.team_id),
+    };
+    if new_project.name.trim().is_empty() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Project name cannot be empty"
+        }));
+    }
+
+    let team_id = match &existing {
+        Some(project) => project.team_id,
+        None => new_project.team_id,
+    };
+    let mut assignees = match team_id {
+        Some(team_id) => {
+            match find_team_by_id(&pool, organization.id, team_id).await {
+                Ok(Some(_)) => {}
+                Ok(None) => {
+                    return HttpResponse::NotFound().json(serde_json::json!({
+                        "error": "Team not found"
+                    }));
+                }
+                Err(e) => return internal_error(e),
+            }
+            match team_role_defaults(&pool, team_id).await {
+                Ok(defaults) => defaults,
+                Err(e) => return internal_error(e),
+            }
+        }
+        None => HashMap::new(),
+    };
+    for (role, user_id) in &req.assignees {
+        assignees.insert(role.to_string(), *user_id);
+    }
+
+    let target = match &existing {
+        Some(project) => TemplateTarget::Existing(project),
+        None => TemplateTarget::NewProject(&new_project, user_id),
+    };
+    let start_date = req.start_date.unwrap_or_else(|| Utc::now().date_naive());
+
+    match instantiate_template(&pool, organization.id, &tasks, target, start_date, &assignees, Some(user_id)).await {
+        Ok((project, created_tasks, created_subtasks)) => HttpResponse::Created().json(InstantiateTemplateResult {
+            project,
+            created_tasks,
+            created_subtasks,
+        }),
+        Err(sqlx::Error::Database(e)) if e.is_foreign_key_violation() => {
+            HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "An assignee does not exist"
+            }))
+        }
+        Err(e) => internal_error(e),
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    create_template_handler,
+    list_templates_handler,
+    get_template_handler,
+    update_template_handler,
+    delete_template_handler,
+    save_project_as_template_handler,
+    instantiate_template_handler,
+))]
+pub struct TemplateApi;
+
+pub fn template_routes() -> actix_web::Scope {
+    web::scope("/templates")
+        .service(create_template_handler)
+        .service(list_templates_handler)
+        .service(get_template_handler)
+        .service(update_template_handler)
+        .service(delete_template_handler)
+        .service(save_project_as_template_handler)
+        .service(instantiate_template_handler)
+}
@@ -0,0 +1,451 @@
+use actix_web::{delete, get, post, put, web, HttpRequest, HttpResponse, Responder};
+use chrono::{Duration, NaiveDate, Utc};
+use sqlx::PgPool;
+use utoipa::OpenApi;
+use uuid::Uuid;
+
+use crate::{
+    db::{
+        create_time_entry, delete_time_entry, find_project_by_id, find_running_timer,
+        find_task_by_id, find_time_entry, list_time_entries, start_timer, stop_timer,
+        timesheet_rows, update_time_entry,
+    },
+    models::{
+        CreateTimeEntryRequest, StartTimerRequest, TimeEntry, TimeEntryQuery, Timesheet, TimesheetQuery,
+        TransferFormat, UpdateTimeEntryRequest, UserRole,
+    },
+    middleware::CurrentOrganization,
+    openapi::ErrorResponse,
+    routes::helpers::{caller_claims, caller_id, internal_error, unauthorized},
+    services::timesheet_to_csv,
+};
+
+const MAX_ENTRY_MINUTES: i64 = 24 * 60;
+const DEFAULT_TIMESHEET_DAYS: i64 = 6;
+const MAX_TIMESHEET_DAYS: i64 = 366;
+
+/// Checks the bounds shared by manual entries and edits.
+fn validate_span(started_at: chrono::DateTime<Utc>, ended_at: chrono::DateTime<Utc>) -> Result<(), String> {
+    if ended_at < started_at {
+        return Err("ended_at must not be before started_at".to_string());
+    }
+    if ended_at > Utc::now() + Duration::minutes(1) {
+        return Err("Time entries cannot end in the future".to_string());
+    }
+    if (ended_at - started_at).num_minutes() > MAX_ENTRY_MINUTES {
+        return Err(format!("A time entry cannot exceed {} minutes", MAX_ENTRY_MINUTES));
+    }
+    Ok(())
+}
+
+/// Resolves the timesheet range, defaulting to the last seven days.
+fn timesheet_range(query: &TimesheetQuery) -> Result<(NaiveDate, NaiveDate), String> {
+    let to = query.to.unwrap_or_else(|| Utc::now().date_naive());
+    let from = query.from.unwrap_or(to - Duration::days(DEFAULT_TIMESHEET_DAYS));
+
+    if from > to {
+        return Err("`from` must not be after `to`".to_string());
+    }
+    if (to - from).num_days() > MAX_TIMESHEET_DAYS {
+        return Err(format!("Date range cannot exceed {} days", MAX_TIMESHEET_DAYS));
+    }
+
+    Ok((from, to))
+}
+
+#[utoipa::path(
+    responses(
+        (status = 201, description = "The running entry", body = TimeEntry),
+        (status = 404, description = "Task not found", body = ErrorResponse),
+        (status = 409, description = "A timer is already running", body = ErrorResponse),
+    ),
+)]
+#[post("/timer")]
+async fn start_timer_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    req: web::Json<StartTimerRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let Some(user_id) = caller_id(&http_req) else {
+        return unauthorized();
+    };
+
+    match find_task_by_id(&pool, organization.id, req.task_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Task not found"
+            }));
+        }
+        Err(e) => return internal_error(e),
+    }
+
+    match start_timer(&pool, user_id, req.task_id, req.note.as_deref()).await {
+        Ok(entry) => HttpResponse::Created().json(entry),
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            let running = find_running_timer(&pool, user_id).await.ok().flatten();
+            HttpResponse::Conflict().json(serde_json::json!({
+                "error": "A timer is already running; stop it first",
+                "running": running
+            }))
+        }
+        Err(e) => internal_error(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The running entry", body = TimeEntry),
+        (status = 404, description = "Running timer not found", body = ErrorResponse),
+    ),
+)]
+#[get("/timer")]
+async fn get_timer_handler(pool: web::Data<PgPool>, http_req: HttpRequest) -> impl Responder {
+    let Some(user_id) = caller_id(&http_req) else {
+        return unauthorized();
+    };
+
+    match find_running_timer(&pool, user_id).await {
+        Ok(Some(entry)) => HttpResponse::Ok().json(entry),
+        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "No timer is running"
+        })),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The stopped entry", body = TimeEntry),
+        (status = 404, description = "Running timer not found", body = ErrorResponse),
+    ),
+)]
+#[post("/timer/stop")]
+async fn stop_timer_handler(pool: web::Data<PgPool>, http_req: HttpRequest) -> impl Responder {
+    let Some(user_id) = caller_id(&http_req) else {
+        return unauthorized();
+    };
+
+    match stop_timer(&pool, user_id).await {
+        Ok(Some(entry)) => HttpResponse::Ok().json(entry),
+        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "No timer is running"
+        })),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 201, description = "The new entry", body = TimeEntry),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Task not found", body = ErrorResponse),
+    ),
+)]
+#[post("")]
+async fn create_time_entry_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    req: web::Json<CreateTimeEntryRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let Some(user_id) = caller_id(&http_req) else {
+        return unauthorized();
+    };
+
+    let ended_at = match (req.ended_at, req.duration_minutes) {
+        (Some(ended_at), None) => ended_at,
+        (None, Some(minutes)) if minutes > 0 => req.started_at + Duration::minutes(minutes as i64),
+        (None, Some(_)) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "duration_minutes must be positive"
+            }));
+        }
+        _ => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "Specify exactly one of ended_at or duration_minutes"
+            }));
+        }
+    };
+    if let Err(e) = validate_span(req.started_at, ended_at) {
+        return HttpResponse::BadRequest().json(serde_json::json!({ "error": e }));
+    }
+
+    match find_task_by_id(&pool, organization.id, req.task_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Task not found"
+            }));
+        }
+        Err(e) => return internal_error(e),
+    }
+
+    match create_time_entry(&pool, user_id, req.task_id, req.started_at, ended_at, req.note.as_deref()).await {
+        Ok(entry) => HttpResponse::Created().json(entry),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[utoipa::path(
+    params(TimeEntryQuery),
+    responses(
+        (status = 200, description = "The entries", body = Vec<TimeEntry>),
+    ),
+)]
+#[get("")]
+async fn list_time_entries_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    query: web::Query<TimeEntryQuery>,
+) -> impl Responder {
+    match list_time_entries(&pool, organization.id, &query).await {
+        Ok(entries) => HttpResponse::Ok().json(entries),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The updated entry", body = TimeEntry),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 403, description = "Not the entry's owner", body = ErrorResponse),
+        (status = 404, description = "Time entry not found", body = ErrorResponse),
+    ),
+)]
+#[put("/{id}")]
+async fn update_time_entry_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+    req: web::Json<UpdateTimeEntryRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let Some(user_id) = caller_id(&http_req) else {
+        return unauthorized();
+    };
+
+    let entry = match find_time_entry(&pool, organization.id, path.into_inner()).await {
+        Ok(Some(entry)) => entry,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Time entry not found"
+            }));
+        }
+        Err(e) => return internal_error(e),
+    };
+
+    if entry.user_id != user_id {
+        return HttpResponse::Forbidden().json(serde_json::json!({
+            "error": "You can only edit your own time entries"
+        }));
+    }
+
+    let started_at = req.started_at.unwrap_or(entry.started_at);
+    let ended_at = req.ended_at.or(entry.ended_at);
+    let span = match ended_at {
+        Some(ended_at) => validate_span(started_at, ended_at),
+        None if started_at > Utc::now() => Err("A running timer cannot start in the future".to_string()),
+        None => Ok(()),
+    };
+    if let Err(e) = span {
+        return HttpResponse::BadRequest().json(serde_json::json!({ "error": e }));
+    }
+
+    let note = req.note.as_deref().or(entry.note.as_deref());
+    match update_time_entry(&pool, entry.id, started_at, ended_at, note).await {
+        Ok(entry) => HttpResponse::Ok().json(entry),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 204, description = "Entry deleted"),
+        (status = 403, description = "Not the entry's owner", body = ErrorResponse),
+        (status = 404, description = "Time entry not found", body = ErrorResponse),
+    ),
+)]
+#[delete("/{id}")]
+async fn delete_time_entry_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let Some((user_id, claims)) = caller_claims(&http_req) else {
+        return unauthorized();
+    };
+
+    let entry = match find_time_entry(&pool, organization.id, path.into_inner()).await {
+        Ok(Some(entry)) => entry,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Time entry not found"
+            }));
+        }
+        Err(e) => return internal_error(e),
+    };
+
+    if entry.user_id != user_id && claims.role.parse::<UserRole>() != Ok(UserRole::Admin) {
+        return HttpResponse::Forbidden().json(serde_json::json!({
+            "error": "You can only delete your own time entries"
+        }));
+    }
+
+    match delete_time_entry(&pool, entry.id).await {
+        Ok(()) => HttpResponse::NoContent().finish(),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    start_timer_handler,
+    get_timer_handler,
+    stop_timer_handler,
+    create_time_entry_handler,
+    list_time_entries_handler,
+    update_time_entry_handler,
+    delete_time_entry_handler,
+))]
+pub struct TimeEntryApi;
+
+pub fn time_entry_routes() -> actix_web::Scope {
+    web::scope("/time-entries")
+        .service(start_timer_handler)
+        .service(get_timer_handler)
+        .service(stop_timer_handler)
+        .service(create_time_entry_handler)
+        .service(list_time_entries_handler)
+        .service(update_time_entry_handler)
+        .service(delete_time_entry_handler)
+}
+
+async fn timesheet_response(
+    pool: &PgPool,
+    organization_id: Uuid,
+    project_id: Option<Uuid>,
+    user_id: Option<Uuid>,
+    query: &TimesheetQuery,
+    file_stem: String,
+) -> HttpResponse {
+    let format = match query.format.as_deref().map(|f| f.parse::<TransferFormat>()) {
+        Some(Ok(format)) => format,
+        Some(Err(e)) => return HttpResponse::BadRequest().json(serde_json::json!({ "error": e })),
+        None => TransferFormat::Json,
+    };
+    let (from, to) = match timesheet_range(query) {
+        Ok(range) => range,
+        Err(msg) => return HttpResponse::BadRequest().json(serde_json::json!({ "error": msg })),
+    };
+
+    let rows = match timesheet_rows(pool, organization_id, project_id, user_id, from, to).await {
+        Ok(rows) => rows,
+        Err(e) => return internal_error(e),
+    };
+    let timesheet = Timesheet {
+        from,
+        to,
+        total_minutes: rows.iter().map(|r| r.minutes).sum(),
+        rows,
+    };
+
+    match format {
+        TransferFormat::Json => HttpResponse::Ok().json(timesheet),
+        TransferFormat::Csv => match timesheet_to_csv(&timesheet) {
+            Ok(csv) => HttpResponse::Ok()
+                .content_type("text/csv; charset=utf-8")
+                .insert_header((
+                    "Content-Disposition",
+                    format!("attachment; filename=\"{}-{}-{}.csv\"", file_stem, from, to),
+                ))
+                .body(csv),
+            Err(e) => {
+                log::error!("CSV export error: {}", e);
+                HttpResponse::InternalServerError().json(serde_json::json!({
+                    "error": "Internal server error"
+                }))
+            }
+        },
+    }
+}
+
+#[utoipa::path(
+    params(TimesheetQuery),
+    responses(
+        (status = 200, description = "Hours per user, task and day, as JSON or CSV", body = Timesheet),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Project not found", body = ErrorResponse),
+    ),
+)]
+#[get("/projects/{project_id}")]
+async fn project_timesheet_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+    query: web::Query<TimesheetQuery>,
+) -> impl Responder {
+    let project_id = path.into_inner();
+
+    match find_project_by_id(&pool, organization.id, project_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Project not found"
+            }));
+        }
+        Err(e) => return internal_error(e),
+    }
+
+    let file_stem = format!("timesheet-project-{}", project_id);
+    timesheet_response(&pool, organization.id, Some(project_id), None, &query, file_stem).await
+}
+
+#[utoipa::path(
+    params(TimesheetQuery),
+    responses(
+        (status = 200, description = "Hours per task and day, as JSON or CSV", body = Timesheet),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 403, description = "Only admins can see other users' timesheets", body = ErrorResponse),
+    ),
+)]
+#[get("/users/{user_id}")]
+async fn user_timesheet_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+    query: web::Query<TimesheetQuery>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let Some((caller, claims)) = caller_claims(&http_req) else {
+        return unauthorized();
+    };
+    let user_id = path.into_inner();
+
+    // Other people's timesheets are visible to managers and admins only
+    let can_view = caller == user_id
+        || matches!(claims.role.parse::<UserRole>(), Ok(UserRole::Admin) | Ok(UserRole::Manager));
+    if !can_view {
+        return HttpResponse::Forbidden().json(serde_json::json!({
+            "error": "You can only view your own timesheet"
+        }));
+    }
+
+    let file_stem = format!("timesheet-user-{}", user_id);
+    timesheet_response(&pool, organization.id, None, Some(user_id), &query, file_stem).await
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    project_timesheet_handler,
+    user_timesheet_handler,
+))]
+pub struct TimesheetApi;
+
+pub fn timesheet_routes() -> actix_web::Scope {
+    web::scope("/timesheets")
+        .service(project_timesheet_handler)
+        .service(user_timesheet_handler)
+}
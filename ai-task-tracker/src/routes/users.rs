@@ -1,15 +1,35 @@
 use actix_web::{get, post, put, web, HttpMessage, HttpRequest, HttpResponse, Responder};
 use sqlx::PgPool;
+use utoipa::OpenApi;
 use uuid::Uuid;
 
 use crate::{
-    db::{create_user, find_user_by_email, find_user_by_id, list_users, update_user},
-    models::{CreateUserRequest, UpdateUserRequest},
-    utils::{hash_password, Claims},
+    config::Config,
+    db::{find_calendar_token_by_user, upsert_calendar_token, UserRepository},
+    models::{CalendarFeedResponse, CalendarToken, CreateUserRequest, UpdateUserRequest, User},
+    middleware::CurrentOrganization,
+    openapi::ErrorResponse,
+    utils::{generate_token, hash_password, Claims},
 };
 
+const CALENDAR_TOKEN_LENGTH: usize = 40;
+
+fn calendar_feed_response(token: CalendarToken) -> CalendarFeedResponse {
+    CalendarFeedResponse {
+        url: format!("/api/calendar/{}.ics", token.token),
+        token: token.token,
+        created_at: token.created_at,
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The caller", body = User),
+        (status = 404, description = "User not found", body = ErrorResponse),
+    ),
+)]
 #[get("/me")]
-async fn get_current_user(pool: web::Data<PgPool>, req: HttpRequest) -> impl Responder {
+async fn get_current_user(users: web::Data<dyn UserRepository>, req: HttpRequest) -> impl Responder {
     // Get claims from request extensions (set by auth middleware)
     let claims = match req.extensions().get::<Claims>() {
         Some(claims) => claims.clone(),
@@ -31,7 +51,7 @@ async fn get_current_user(pool: web::Data<PgPool>, req: HttpRequest) -> impl Res
     };
 
     // Fetch user from database
-    match find_user_by_id(&pool, user_id).await {
+    match users.find_user_by_id(user_id).await {
         Ok(Some(user)) => HttpResponse::Ok().json(user),
         Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
             "error": "User not found"
@@ -45,9 +65,14 @@ async fn get_current_user(pool: web::Data<PgPool>, req: HttpRequest) -> impl Res
     }
 }
 
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Members of the caller's organization", body = Vec<User>),
+    ),
+)]
 #[get("")]
-async fn list_users_handler(pool: web::Data<PgPool>) -> impl Responder {
-    match list_users(&pool).await {
+async fn list_users_handler(users: web::Data<dyn UserRepository>, organization: CurrentOrganization) -> impl Responder {
+    match users.list_users(organization.id).await {
         Ok(users) => HttpResponse::Ok().json(users),
         Err(e) => {
             log::error!("Database error: {}", e);
@@ -58,20 +83,28 @@ async fn list_users_handler(pool: web::Data<PgPool>) -> impl Responder {
     }
 }
 
+#[utoipa::path(
+    responses(
+        (status = 201, description = "The new user", body = User),
+        (status = 409, description = "Email already in use", body = ErrorResponse),
+    ),
+)]
 #[post("")]
 async fn create_user_handler(
-    pool: web::Data<PgPool>,
+    users: web::Data<dyn UserRepository>,
+    config: web::Data<Config>,
+    organization: CurrentOrganization,
     req: web::Json<CreateUserRequest>,
 ) -> impl Responder {
     // Check if user already exists
-    if let Ok(Some(_)) = find_user_by_email(&pool, &req.email).await {
+    if let Ok(Some(_)) = users.find_user_by_email(&req.email).await {
         return HttpResponse::Conflict().json(serde_json::json!({
             "error": "User already exists"
         }));
     }
 
     // Hash password
-    let password_hash = match hash_password(&req.password) {
+    let password_hash = match hash_password(&req.password, config.bcrypt_cost) {
         Ok(hash) => hash,
         Err(e) => {
             log::error!("Password hashing error: {}", e);
@@ -81,7 +114,7 @@ async fn create_user_handler(
         }
     };
 
-    match create_user(&pool, &req, &password_hash).await {
+    match users.create_user(&req, &password_hash, organization.id).await {
         Ok(user) => HttpResponse::Created().json(user),
         Err(e) => {
             log::error!("Database error: {}", e);
@@ -92,16 +125,40 @@ async fn create_user_handler(
     }
 }
 
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The updated user", body = User),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+    ),
+)]
 #[put("/{id}")]
 async fn update_user_handler(
-    pool: web::Data<PgPool>,
+    users: web::Data<dyn UserRepository>,
+    config: web::Data<Config>,
+    organization: CurrentOrganization,
     path: web::Path<Uuid>,
     req: web::Json<UpdateUserRequest>,
 ) -> impl Responder {
     let user_id = path.into_inner();
 
+    match users.organization_ids(user_id).await {
+        Ok(ids) if ids.contains(&organization.id) => {}
+        Ok(_) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "User not found"
+            }));
+        }
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }));
+        }
+    }
+
     let password_hash = if let Some(password) = &req.password {
-        match hash_password(password) {
+        match hash_password(password, config.bcrypt_cost) {
             Ok(hash) => Some(hash),
             Err(e) => {
                 log::error!("Password hashing error: {}", e);
@@ -114,8 +171,100 @@ async fn update_user_handler(
         None
     };
 
-    match update_user(&pool, user_id, req.full_name.clone(), password_hash).await {
+    match users.update_user(user_id, req.full_name.clone(), password_hash).await {
         Ok(user) => HttpResponse::Ok().json(user),
+        Err(sqlx::Error::Database(e)) if e.is_check_violation() => {
+            HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "Service accounts cannot have a password"
+            }))
+        }
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }))
+        }
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The caller's calendar feed URL", body = CalendarFeedResponse),
+    ),
+)]
+#[get("/me/calendar")]
+async fn get_calendar_feed_handler(pool: web::Data<PgPool>, req: HttpRequest) -> impl Responder {
+    let claims = match req.extensions().get::<Claims>() {
+        Some(claims) => claims.clone(),
+        None => {
+            return HttpResponse::Unauthorized().json(serde_json::json!({
+                "error": "Unauthorized"
+            }));
+        }
+    };
+
+    let user_id = match Uuid::parse_str(&claims.sub) {
+        Ok(id) => id,
+        Err(_) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "Invalid user ID"
+            }));
+        }
+    };
+
+    // The feed token is created on first request
+    let existing = match find_calendar_token_by_user(&pool, user_id).await {
+        Ok(existing) => existing,
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }));
+        }
+    };
+    let token = match existing {
+        Some(token) => token,
+        None => match upsert_calendar_token(&pool, user_id, &generate_token(CALENDAR_TOKEN_LENGTH)).await {
+            Ok(token) => token,
+            Err(e) => {
+                log::error!("Database error: {}", e);
+                return HttpResponse::InternalServerError().json(serde_json::json!({
+                    "error": "Internal server error"
+                }));
+            }
+        },
+    };
+
+    HttpResponse::Ok().json(calendar_feed_response(token))
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The new calendar feed URL", body = CalendarFeedResponse),
+    ),
+)]
+#[post("/me/calendar/rotate")]
+async fn rotate_calendar_feed_handler(pool: web::Data<PgPool>, req: HttpRequest) -> impl Responder {
+    let claims = match req.extensions().get::<Claims>() {
+        Some(claims) => claims.clone(),
+        None => {
+            return HttpResponse::Unauthorized().json(serde_json::json!({
+                "error": "Unauthorized"
+            }));
+        }
+    };
+
+    let user_id = match Uuid::parse_str(&claims.sub) {
+        Ok(id) => id,
+        Err(_) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "Invalid user ID"
+            }));
+        }
+    };
+
+    match upsert_calendar_token(&pool, user_id, &generate_token(CALENDAR_TOKEN_LENGTH)).await {
+        Ok(token) => HttpResponse::Ok().json(calendar_feed_response(token)),
         Err(e) => {
             log::error!("Database error: {}", e);
             HttpResponse::InternalServerError().json(serde_json::json!({
@@ -125,9 +274,22 @@ async fn update_user_handler(
     }
 }
 
+#[derive(OpenApi)]
+#[openapi(paths(
+    get_current_user,
+    get_calendar_feed_handler,
+    rotate_calendar_feed_handler,
+    list_users_handler,
+    create_user_handler,
+    update_user_handler,
+))]
+pub struct UserApi;
+
 pub fn user_routes() -> actix_web::Scope {
     web::scope("/users")
         .service(get_current_user)
+        .service(get_calendar_feed_handler)
+        .service(rotate_calendar_feed_handler)
         .service(list_users_handler)
         .service(create_user_handler)
         .service(update_user_handler)
@@ -0,0 +1,326 @@
+use actix_web::{delete, get, post, put, web, HttpRequest, HttpResponse, Responder};
+use chrono::Utc;
+use sqlx::PgPool;
+use utoipa::OpenApi;
+use uuid::Uuid;
+
+use crate::{
+    db::{
+        create_saved_view, delete_saved_view, find_saved_view, find_team_by_id, find_team_member, list_saved_views,
+        update_saved_view, TaskRepository,
+    },
+    models::{
+        CreateSavedViewRequest, SavedView, Task, TaskGroup, TeamRole, UpdateSavedViewRequest, ViewFilter,
+        ViewGroupBy, ViewTasks,
+    },
+    middleware::CurrentOrganization,
+    openapi::ErrorResponse,
+    routes::helpers::{caller, internal_error, unauthorized},
+};
+
+const MAX_NAME_LENGTH: usize = 100;
+const MAX_DUE_WITHIN_DAYS: i32 = 3650;
+
+fn view_not_found() -> HttpResponse {
+    HttpResponse::NotFound().json(serde_json::json!({
+        "error": "View not found"
+    }))
+}
+
+fn validate_view(name: &str, filter: &ViewFilter) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("View name cannot be empty".to_string());
+    }
+    if name.chars().count() > MAX_NAME_LENGTH {
+        return Err(format!("View name cannot be longer than {} characters", MAX_NAME_LENGTH));
+    }
+    if filter.due_within_days.is_some_and(|days| !(0..=MAX_DUE_WITHIN_DAYS).contains(&days)) {
+        return Err(format!("due_within_days must be between 0 and {}", MAX_DUE_WITHIN_DAYS));
+    }
+    Ok(())
+}
+
+/// Whether the caller may change or delete a view they can see. Personal views
+/// are only visible to their owner; team views can be changed by whoever
+/// created them and by the team's owners and maintainers.
+async fn may_change(pool: &PgPool, view: &SavedView, user_id: Uuid, is_admin: bool) -> Result<bool, sqlx::Error> {
+    if view.is_builtin() {
+        return Ok(false);
+    }
+    let Some(team_id) = view.team_id else {
+        return Ok(true);
+    };
+    if is_admin || view.created_by == Some(user_id) {
+        return Ok(true);
+    }
+    let member = find_team_member(pool, team_id, user_id).await?;
+    Ok(member.and_then(|m| m.role.parse::<TeamRole>().ok()).is_some_and(|role| role.can_manage_members()))
+}
+
+/// The view, if the caller may change it, or the response saying why not.
+async fn load_for_change(
+    pool: &PgPool,
+    organization_id: Uuid,
+    id: Uuid,
+    user_id: Uuid,
+    is_admin: bool,
+) -> Result<SavedView, HttpResponse> {
+    let view = match find_saved_view(pool, organization_id, user_id, id).await {
+        Ok(Some(view)) => view,
+        Ok(None) => return Err(view_not_found()),
+        Err(e) => return Err(internal_error(e)),
+    };
+    if view.is_builtin() {
+        return Err(HttpResponse::Forbidden().json(serde_json::json!({
+            "error": "Built-in views cannot be changed"
+        })));
+    }
+    match may_change(pool, &view, user_id, is_admin).await {
+        Ok(true) => Ok(view),
+        Ok(false) => Err(HttpResponse::Forbidden().json(serde_json::json!({
+            "error": "Only the view's creator and team maintainers can change a team view"
+        }))),
+        Err(e) => Err(internal_error(e)),
+    }
+}
+
+/// Splits sorted tasks by the grouping field, keeping their order.
+fn group_tasks(tasks: Vec<Task>, group_by: Option<ViewGroupBy>) -> Vec<TaskGroup> {
+    let Some(group_by) = group_by else {
+        return vec![TaskGroup { key: None, tasks }];
+    };
+
+    let mut groups: Vec<TaskGroup> = Vec::new();
+    for task in tasks {
+        let key = match group_by {
+            ViewGroupBy::Status => Some(task.status.clone()),
+            ViewGroupBy::Priority => Some(task.priority.clone()),
+            ViewGroupBy::Assignee => task.assignee_id.map(|id| id.to_string()),
+            ViewGroupBy::Project => Some(task.project_id.to_string()),
+        };
+        match groups.iter_mut().find(|group| group.key == key) {
+            Some(group) => group.tasks.push(task),
+            None => groups.push(TaskGroup { key, tasks: vec![task] }),
+        }
+    }
+    groups
+}
+
+#[utoipa::path(
+    request_body = CreateSavedViewRequest,
+    responses(
+        (status = 201, description = "The new view", body = SavedView),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 403, description = "Not a member of the team", body = ErrorResponse),
+        (status = 404, description = "Team not found", body = ErrorResponse),
+    ),
+)]
+#[post("")]
+async fn create_view_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    req: web::Json<CreateSavedViewRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let Some((user_id, is_admin)) = caller(&http_req) else {
+        return unauthorized();
+    };
+    if let Err(e) = validate_view(&req.name, &req.filter) {
+        return HttpResponse::BadRequest().json(serde_json::json!({ "error": e }));
+    }
+
+    if let Some(team_id) = req.team_id {
+        match find_team_by_id(&pool, organization.id, team_id).await {
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                return HttpResponse::NotFound().json(serde_json::json!({
+                    "error": "Team not found"
+                }));
+            }
+            Err(e) => return internal_error(e),
+        }
+        match find_team_member(&pool, team_id, user_id).await {
+            Ok(Some(_)) => {}
+            Ok(None) if is_admin => {}
+            Ok(None) => {
+                return HttpResponse::Forbidden().json(serde_json::json!({
+                    "error": "Only team members can share views with a team"
+                }));
+            }
+            Err(e) => return internal_error(e),
+        }
+    }
+
+    match create_saved_view(&pool, organization.id, &req, user_id).await {
+        Ok(view) => HttpResponse::Created().json(view),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// The built-in views, the caller's own and those of their teams.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The views the caller can run", body = Vec<SavedView>),
+    ),
+)]
+#[get("")]
+async fn list_views_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let Some((user_id, _)) = caller(&http_req) else {
+        return unauthorized();
+    };
+
+    match list_saved_views(&pool, organization.id, user_id).await {
+        Ok(views) => HttpResponse::Ok().json(views),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The view", body = SavedView),
+        (status = 404, description = "View not found", body = ErrorResponse),
+    ),
+)]
+#[get("/{id}")]
+async fn get_view_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let Some((user_id, _)) = caller(&http_req) else {
+        return unauthorized();
+    };
+
+    match find_saved_view(&pool, organization.id, user_id, path.into_inner()).await {
+        Ok(Some(view)) => HttpResponse::Ok().json(view),
+        Ok(None) => view_not_found(),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[utoipa::path(
+    request_body = UpdateSavedViewRequest,
+    responses(
+        (status = 200, description = "The updated view", body = SavedView),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 403, description = "A built-in view, or someone else's team view", body = ErrorResponse),
+        (status = 404, description = "View not found", body = ErrorResponse),
+    ),
+)]
+#[put("/{id}")]
+async fn update_view_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+    req: web::Json<UpdateSavedViewRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let Some((user_id, is_admin)) = caller(&http_req) else {
+        return unauthorized();
+    };
+    if let Err(e) = validate_view(&req.name, &req.filter) {
+        return HttpResponse::BadRequest().json(serde_json::json!({ "error": e }));
+    }
+    let view = match load_for_change(&pool, organization.id, path.into_inner(), user_id, is_admin).await {
+        Ok(view) => view,
+        Err(response) => return response,
+    };
+
+    match update_saved_view(&pool, organization.id, view.id, &req).await {
+        Ok(Some(view)) => HttpResponse::Ok().json(view),
+        Ok(None) => view_not_found(),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 204, description = "View deleted"),
+        (status = 403, description = "A built-in view, or someone else's team view", body = ErrorResponse),
+        (status = 404, description = "View not found", body = ErrorResponse),
+    ),
+)]
+#[delete("/{id}")]
+async fn delete_view_handler(
+    pool: web::Data<PgPool>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let Some((user_id, is_admin)) = caller(&http_req) else {
+        return unauthorized();
+    };
+    let view = match load_for_change(&pool, organization.id, path.into_inner(), user_id, is_admin).await {
+        Ok(view) => view,
+        Err(response) => return response,
+    };
+
+    match delete_saved_view(&pool, organization.id, view.id).await {
+        Ok(true) => HttpResponse::NoContent().finish(),
+        Ok(false) => view_not_found(),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Runs the view for the caller: its filter, sorted and grouped as saved.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The view and its tasks", body = ViewTasks),
+        (status = 404, description = "View not found", body = ErrorResponse),
+    ),
+)]
+#[get("/{id}/tasks")]
+async fn view_tasks_handler(
+    pool: web::Data<PgPool>,
+    tasks: web::Data<dyn TaskRepository>,
+    organization: CurrentOrganization,
+    path: web::Path<Uuid>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let Some((user_id, _)) = caller(&http_req) else {
+        return unauthorized();
+    };
+    let view = match find_saved_view(&pool, organization.id, user_id, path.into_inner()).await {
+        Ok(Some(view)) => view,
+        Ok(None) => return view_not_found(),
+        Err(e) => return internal_error(e),
+    };
+
+    let filter = view.task_filter(user_id, Utc::now());
+    match tasks.list_tasks(organization.id, &filter).await {
+        Ok(rows) => {
+            let group_by = view.group_by.as_deref().and_then(|g| g.parse().ok());
+            HttpResponse::Ok().json(ViewTasks {
+                groups: group_tasks(rows, group_by),
+                view,
+            })
+        }
+        Err(e) => internal_error(e),
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    create_view_handler,
+    list_views_handler,
+    get_view_handler,
+    update_view_handler,
+    delete_view_handler,
+    view_tasks_handler,
+))]
+pub struct SavedViewApi;
+
+pub fn view_routes() -> actix_web::Scope {
+    web::scope("/views")
+        .service(create_view_handler)
+        .service(list_views_handler)
+        .service(get_view_handler)
+        .service(update_view_handler)
+        .service(delete_view_handler)
+        .service(view_tasks_handler)
+}
@@ -0,0 +1,193 @@
+use chrono::{DateTime, Duration, Timelike, Utc};
+
+use crate::models::CalendarTask;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CalendarEntryKind {
+    /// VEVENT entries, shown by every calendar app.
+    Event,
+    /// VTODO entries, for task-aware clients.
+    Todo,
+}
+
+impl std::str::FromStr for CalendarEntryKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "event" | "vevent" => Ok(CalendarEntryKind::Event),
+            "todo" | "vtodo" => Ok(CalendarEntryKind::Todo),
+            _ => Err(format!("Invalid calendar entry type: {}", s)),
+        }
+    }
+}
+
+/// Stable UID for a task, so calendar apps update entries instead of duplicating them.
+pub fn task_uid(task: &CalendarTask) -> String {
+    format!("task-{}@ai-task-tracker", task.id)
+}
+
+fn escape_text(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace(['\r', '\n'], "\\n")
+}
+
+fn format_timestamp(dt: &DateTime<Utc>) -> String {
+    dt.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Folds content lines longer than 75 octets, as RFC 5545 requires.
+fn push_line(out: &mut String, line: &str) {
+    let mut width = 0;
+    for ch in line.chars() {
+        let len = ch.len_utf8();
+        if width + len > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(ch);
+        width += len;
+    }
+    out.push_str("\r\n");
+}
+
+fn priority_value(priority: &str) -> u8 {
+    match priority {
+        "urgent" => 1,
+        "high" => 3,
+        "medium" => 5,
+        _ => 9,
+    }
+}
+
+fn push_common(out: &mut String, task: &CalendarTask) {
+    push_line(out, &format!("UID:{}", task_uid(task)));
+    push_line(out, &format!("DTSTAMP:{}", format_timestamp(&task.updated_at)));
+    push_line(out, &format!("CREATED:{}", format_timestamp(&task.created_at)));
+    push_line(out, &format!("LAST-MODIFIED:{}", format_timestamp(&task.updated_at)));
+    push_line(out, &format!("SUMMARY:{}", escape_text(&task.title)));
+    let description = match &task.description {
+        Some(d) => format!("{}\n\nProject: {}", d, task.project_name),
+        None => format!("Project: {}", task.project_name),
+    };
+    push_line(out, &format!("DESCRIPTION:{}", escape_text(&description)));
+    push_line(out, &format!("CATEGORIES:{}", escape_text(&task.project_name)));
+    push_line(out, &format!("PRIORITY:{}", priority_value(&task.priority)));
+}
+
+pub fn render_calendar(name: &str, tasks: &[CalendarTask], kind: CalendarEntryKind) -> String {
+    let mut out = String::new();
+    push_line(&mut out, "BEGIN:VCALENDAR");
+    push_line(&mut out, "VERSION:2.0");
+    push_line(&mut out, "PRODID:-//AI Task Tracker//Task Due Dates//EN");
+    push_line(&mut out, "CALSCALE:GREGORIAN");
+    push_line(&mut out, "METHOD:PUBLISH");
+    push_line(&mut out, &format!("X-WR-CALNAME:{}", escape_text(name)));
+
+    for task in tasks {
+        match kind {
+            CalendarEntryKind::Event => {
+                push_line(&mut out, "BEGIN:VEVENT");
+                push_common(&mut out, task);
+                // Midnight due dates are treated as all-day deadlines
+                let due = task.due_date;
+                if due.hour() == 0 && due.minute() == 0 && due.second() == 0 {
+                    let day = due.date_naive();
+                    push_line(&mut out, &format!("DTSTART;VALUE=DATE:{}", day.format("%Y%m%d")));
+                    push_line(&mut out, &format!("DTEND;VALUE=DATE:{}", (day + Duration::days(1)).format("%Y%m%d")));
+                    push_line(&mut out, "TRANSP:TRANSPARENT");
+                } else {
+                    push_line(&mut out, &format!("DTSTART:{}", format_timestamp(&due)));
+                    push_line(&mut out, &format!("DTEND:{}", format_timestamp(&(due + Duration::minutes(30)))));
+                }
+                push_line(&mut out, "STATUS:CONFIRMED");
+                push_line(&mut out, "END:VEVENT");
+            }
+            CalendarEntryKind::Todo => {
+                push_line(&mut out, "BEGIN:VTODO");
+                push_common(&mut out, task);
+                push_line(&mut out, &format!("DUE:{}", format_timestamp(&task.due_date)));
+                let status = match task.status.as_str() {
+                    "done" => "COMPLETED",
+                    "in_progress" => "IN-PROCESS",
+                    _ => "NEEDS-ACTION",
+                };
+                push_line(&mut out, &format!("STATUS:{}", status));
+                push_line(&mut out, &format!("PERCENT-COMPLETE:{}", task.progress_percent));
+                if task.status == "done" {
+                    push_line(&mut out, &format!("COMPLETED:{}", format_timestamp(&task.updated_at)));
+                }
+                push_line(&mut out, "END:VTODO");
+            }
+        }
+    }
+
+    push_line(&mut out, "END:VCALENDAR");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(title: &str, description: Option<&str>, due_date: &str) -> CalendarTask {
+        let created = DateTime::parse_from_rfc3339("2026-10-01T08:00:00Z").unwrap().with_timezone(&Utc);
+        CalendarTask {
+            id: uuid::Uuid::nil(),
+            project_name: "Ops, EU".to_string(),
+            title: title.to_string(),
+            description: description.map(str::to_string),
+            status: "in_progress".to_string(),
+            priority: "high".to_string(),
+            progress_percent: 40,
+            due_date: DateTime::parse_from_rfc3339(due_date).unwrap().with_timezone(&Utc),
+            created_at: created,
+            updated_at: created,
+        }
+    }
+
+    #[test]
+    fn text_values_cannot_break_out_of_their_property() {
+        assert_eq!(escape_text(r"a\b;c,d"), r"a\\b\;c\,d");
+        assert_eq!(escape_text("one\r\ntwo\nthree\rfour"), r"one\ntwo\nthree\nfour");
+
+        let tasks = [task("Fix it\r\nEND:VEVENT", None, "2026-11-02T15:00:00Z")];
+        let calendar = render_calendar("Me", &tasks, CalendarEntryKind::Event);
+        let lines: Vec<&str> = calendar.split("\r\n").collect();
+        assert!(lines.contains(&r"SUMMARY:Fix it\nEND:VEVENT"), "{}", calendar);
+        assert_eq!(lines.iter().filter(|line| **line == "END:VEVENT").count(), 1);
+    }
+
+    #[test]
+    fn long_lines_are_folded_at_75_octets_without_splitting_characters() {
+        let mut out = String::new();
+        push_line(&mut out, &"x".repeat(75));
+        assert_eq!(out, format!("{}\r\n", "x".repeat(75)));
+
+        // Two-byte characters never straddle a fold
+        let line = format!("SUMMARY:{}", "é".repeat(100));
+        let mut out = String::new();
+        push_line(&mut out, &line);
+        let physical: Vec<&str> = out.trim_end_matches("\r\n").split("\r\n").collect();
+        assert!(physical.len() > 1);
+        assert!(physical.iter().all(|l| l.len() <= 75), "{:?}", physical);
+        assert!(physical[1..].iter().all(|l| l.starts_with(' ')));
+        assert_eq!(out.trim_end_matches("\r\n").replace("\r\n ", ""), line);
+    }
+
+    #[test]
+    fn midnight_due_dates_are_all_day_events() {
+        let tasks = [task("Renew certificate", Some("Both domains"), "2026-11-02T00:00:00Z")];
+        let events = render_calendar("Me", &tasks, CalendarEntryKind::Event);
+        assert!(events.contains("DTSTART;VALUE=DATE:20261102\r\nDTEND;VALUE=DATE:20261103\r\n"));
+        assert!(events.contains("DESCRIPTION:Both domains\\n\\nProject: Ops\\, EU\r\n"));
+        assert!(events.contains("UID:task-00000000-0000-0000-0000-000000000000@ai-task-tracker\r\n"));
+
+        let todos = render_calendar("Me", &tasks, CalendarEntryKind::Todo);
+        assert!(todos.contains("DUE:20261102T000000Z\r\nSTATUS:IN-PROCESS\r\nPERCENT-COMPLETE:40\r\n"));
+    }
+}
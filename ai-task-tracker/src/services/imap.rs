@@ -0,0 +1,257 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio_rustls::{rustls, TlsConnector};
+
+use crate::config::ImapConfig;
+
+/// How long the server gets to answer a command, connecting included.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, thiserror::Error)]
+pub enum ImapError {
+    #[error("connection to the IMAP server failed: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("TLS setup failed: {0}")]
+    Tls(String),
+    #[error("IMAP server refused {command}: {message}")]
+    Refused { command: &'static str, message: String },
+    #[error("unexpected IMAP response: {0}")]
+    Protocol(String),
+    #[error("IMAP server did not answer within {} seconds", COMMAND_TIMEOUT.as_secs())]
+    Timeout,
+    #[error("message of {size} bytes is larger than the {limit} bytes allowed")]
+    TooLarge { size: usize, limit: usize },
+}
+
+trait Connection: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Connection for T {}
+
+/// An untagged response line, with the literals sent inside it. Literals over
+/// the size limit are read past and only their size is kept.
+struct Untagged {
+    text: String,
+    literals: Vec<Vec<u8>>,
+    too_large: Option<usize>,
+}
+
+/// A logged-in session with an IMAP4rev1 server, covering just what reading a
+/// mailbox needs: messages are addressed by UID and fetched whole.
+pub struct ImapSession {
+    stream: BufReader<Box<dyn Connection>>,
+    next_tag: u32,
+    max_literal_bytes: usize,
+}
+
+/// `value` as an IMAP quoted string.
+fn quoted(value: &str) -> Result<String, ImapError> {
+    if value.contains(['\r', '\n']) {
+        return Err(ImapError::Protocol("arguments cannot contain line breaks".to_string()));
+    }
+    Ok(format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\"")))
+}
+
+/// The size of the literal announced at the end of `line`, as in `BODY[] {1234}`.
+fn literal_size(line: &str) -> Option<usize> {
+    let size = line.strip_suffix('}')?.rsplit_once('{')?.1;
+    size.parse().ok()
+}
+
+/// Whether `text` is the FETCH response carrying the whole body of the
+/// message with `uid`. Servers may send other FETCH responses alongside it,
+/// such as unsolicited flag updates for any message in the mailbox.
+fn is_body_of(text: &str, uid: u32) -> bool {
+    let mut words = text.split_whitespace();
+    let is_fetch = words.next() == Some("*")
+        && words.next().is_some_and(|n| n.parse::<u32>().is_ok())
+        && words.next() == Some("FETCH");
+    if !is_fetch {
+        return false;
+    }
+    let attributes: Vec<&str> = words.flat_map(|word| word.split(['(', ')'])).filter(|a| !a.is_empty()).collect();
+    let has_uid = attributes.windows(2).any(|pair| pair[0].eq_ignore_ascii_case("UID") && pair[1] == uid.to_string());
+    has_uid && attributes.iter().any(|a| a.eq_ignore_ascii_case("BODY[]"))
+}
+
+async fn tls_connect(host: &str, tcp: TcpStream) -> Result<Box<dyn Connection>, ImapError> {
+    let mut roots = rustls::RootCertStore::empty();
+    roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    let config = rustls::ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .map_err(|e| ImapError::Tls(e.to_string()))?
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    let server_name =
+        rustls::pki_types::ServerName::try_from(host.to_string()).map_err(|e| ImapError::Tls(e.to_string()))?;
+
+    let stream = TlsConnector::from(Arc::new(config)).connect(server_name, tcp).await?;
+    Ok(Box::new(stream))
+}
+
+impl ImapSession {
+    /// Connects to the configured server and logs in.
+    pub async fn connect(config: &ImapConfig) -> Result<Self, ImapError> {
+        let connect = async {
+            let tcp = TcpStream::connect((config.host.as_str(), config.port)).await?;
+            let stream: Box<dyn Connection> = if config.tls {
+                tls_connect(&config.host, tcp).await?
+            } else {
+                Box::new(tcp)
+            };
+
+            let mut session = ImapSession {
+                stream: BufReader::new(stream),
+                next_tag: 1,
+                max_literal_bytes: config.max_message_bytes,
+            };
+            let greeting = session.read_line().await?;
+            if !greeting.starts_with("* OK") && !greeting.starts_with("* PREAUTH") {
+                return Err(ImapError::Refused {
+                    command: "the connection",
+                    message: greeting,
+                });
+            }
+            Ok(session)
+        };
+        let mut session = tokio::time::timeout(COMMAND_TIMEOUT, connect)
+            .await
+            .map_err(|_| ImapError::Timeout)??;
+
+        let login = format!("LOGIN {} {}", quoted(&config.username)?, quoted(&config.password)?);
+        session.command("LOGIN", &login).await?;
+        Ok(session)
+    }
+
+    async fn read_line(&mut self) -> Result<String, ImapError> {
+        let mut line = Vec::new();
+        if self.stream.read_until(b'\n', &mut line).await? == 0 {
+            return Err(ImapError::Protocol("connection closed by the server".to_string()));
+        }
+        Ok(String::from_utf8_lossy(&line).trim_end_matches(['\r', '\n']).to_string())
+    }
+
+    /// Reads one response, following the literals it contains. Literals are
+    /// only held in memory up to `max_literal_bytes`; larger ones are read
+    /// past so the rest of the response can still be read.
+    async fn read_response(&mut self) -> Result<Untagged, ImapError> {
+        let mut text = self.read_line().await?;
+        let mut literals = Vec::new();
+        let mut too_large = None;
+        while let Some(size) = literal_size(&text) {
+            if size > self.max_literal_bytes {
+                let skipped = tokio::io::copy(&mut (&mut self.stream).take(size as u64), &mut tokio::io::sink()).await?;
+                if skipped < size as u64 {
+                    return Err(ImapError::Protocol("connection closed by the server".to_string()));
+                }
+                too_large = Some(size);
+            } else {
+                let mut literal = vec![0; size];
+                self.stream.read_exact(&mut literal).await?;
+                literals.push(literal);
+            }
+            text.push_str(&self.read_line().await?);
+        }
+        Ok(Untagged { text, literals, too_large })
+    }
+
+    /// Sends a command and returns the untagged responses that came before its
+    /// completion. `name` is for errors; `command` is never logged, as it may
+    /// hold the password.
+    async fn command(&mut self, name: &'static str, command: &str) -> Result<Vec<Untagged>, ImapError> {
+        let tag = format!("A{} ", self.next_tag);
+        self.next_tag += 1;
+
+        let exchange = async {
+            self.stream.get_mut().write_all(format!("{}{}\r\n", tag, command).as_bytes()).await?;
+            self.stream.get_mut().flush().await?;
+
+            let mut untagged = Vec::new();
+            loop {
+                let response = self.read_response().await?;
+                let Some(status) = response.text.strip_prefix(&tag) else {
+                    untagged.push(response);
+                    continue;
+                };
+                if status.starts_with("OK") {
+                    return Ok(untagged);
+                }
+                return Err(ImapError::Refused {
+                    command: name,
+                    message: status.to_string(),
+                });
+            }
+        };
+        tokio::time::timeout(COMMAND_TIMEOUT, exchange).await.map_err(|_| ImapError::Timeout)?
+    }
+
+    /// Opens `mailbox` for reading and writing flags.
+    pub async fn select(&mut self, mailbox: &str) -> Result<(), ImapError> {
+        self.command("SELECT", &format!("SELECT {}", quoted(mailbox)?)).await?;
+        Ok(())
+    }
+
+    /// UIDs of the messages in the selected mailbox without the `\Seen` flag.
+    pub async fn unseen(&mut self) -> Result<Vec<u32>, ImapError> {
+        let responses = self.command("SEARCH", "UID SEARCH UNSEEN").await?;
+        let mut uids = Vec::new();
+        for response in responses {
+            if let Some(found) = response.text.strip_prefix("* SEARCH") {
+                for uid in found.split_whitespace() {
+                    uids.push(uid.parse().map_err(|_| ImapError::Protocol(response.text.clone()))?);
+                }
+            }
+        }
+        uids.sort_unstable();
+        Ok(uids)
+    }
+
+    /// The raw RFC 822 message with `uid`, without marking it seen; `None` if
+    /// it has been expunged. A message over the size limit is
+    /// [`ImapError::TooLarge`], and the session can carry on.
+    pub async fn fetch(&mut self, uid: u32) -> Result<Option<Vec<u8>>, ImapError> {
+        let responses = self.command("FETCH", &format!("UID FETCH {} BODY.PEEK[]", uid)).await?;
+        let Some(response) = responses.into_iter().find(|response| is_body_of(&response.text, uid)) else {
+            return Ok(None);
+        };
+        if let Some(size) = response.too_large {
+            return Err(ImapError::TooLarge {
+                size,
+                limit: self.max_literal_bytes,
+            });
+        }
+        Ok(response.literals.into_iter().next())
+    }
+
+    pub async fn mark_seen(&mut self, uid: u32) -> Result<(), ImapError> {
+        self.command("STORE", &format!("UID STORE {} +FLAGS.SILENT (\\Seen)", uid)).await?;
+        Ok(())
+    }
+
+    /// Ends the session; the server closing the connection first is fine.
+    pub async fn logout(mut self) {
+        if let Err(e) = self.command("LOGOUT", "LOGOUT").await {
+            log::debug!("IMAP logout failed: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_body_of_the_requested_message_is_taken() {
+        assert!(is_body_of("* 3 FETCH (UID 42 BODY[] {120})", 42));
+        assert!(is_body_of("* 3 FETCH (BODY[] {120} UID 42)", 42));
+        assert!(is_body_of("* 3 FETCH (uid 42 body[] {120})", 42));
+
+        assert!(!is_body_of("* 3 FETCH (FLAGS (\\Seen))", 42), "unsolicited flag update");
+        assert!(!is_body_of("* 3 FETCH (UID 42 FLAGS (\\Seen))", 42), "no body");
+        assert!(!is_body_of("* 4 FETCH (UID 43 BODY[] {120})", 42), "another message");
+        assert!(!is_body_of("* 42 FETCH (BODY[] {120})", 42), "sequence number is not the UID");
+        assert!(!is_body_of("* 3 EXISTS", 3));
+    }
+}
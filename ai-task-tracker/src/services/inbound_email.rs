@@ -0,0 +1,171 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use mail_parser::{HeaderValue, MessageParser, PartType};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+use crate::{
+    config::ImapConfig,
+    db::{across_organizations, find_thread_task, insert_email_log},
+    models::{EmailLog, InboundEmail},
+    services::{enqueue_once_at, BackgroundJob, ImapError, ImapSession, JobContext, Metrics},
+};
+
+/// Longest sender and subject stored, in characters.
+const MAX_HEADER_LENGTH: usize = 255;
+
+/// Width HTML bodies are rendered at; wide enough that paragraphs are not wrapped.
+const HTML_TEXT_WIDTH: usize = 10_000;
+
+fn truncate(value: &str) -> String {
+    value.trim().chars().take(MAX_HEADER_LENGTH).collect()
+}
+
+/// The Message-IDs in an `In-Reply-To` or `References` header.
+fn message_ids(value: &HeaderValue) -> Vec<String> {
+    value.as_text_list().unwrap_or_default().iter().map(|id| id.to_string()).collect()
+}
+
+/// Whether `line` introduces the quoted message in a reply, as in
+/// "On Mon, 5 Oct 2026, Ana <ana@example.com> wrote:", which some clients
+/// break after the address.
+fn is_reply_header(line: &str, next: Option<&str>) -> bool {
+    let line = line.trim();
+    if line.starts_with("-----Original Message-----") {
+        return true;
+    }
+    if line.starts_with("From:") && next.is_some_and(|next| next.trim_start().starts_with("Sent:")) {
+        return true;
+    }
+    line.starts_with("On ") && (line.ends_with("wrote:") || next.is_some_and(|next| next.trim().ends_with("wrote:")))
+}
+
+/// What the sender wrote in a reply: quoted lines (`>`) are dropped, and so is
+/// everything from the line that introduces the quoted message.
+pub fn strip_quoted_reply(text: &str) -> String {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut kept = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        if is_reply_header(line, lines.get(i + 1).copied()) {
+            break;
+        }
+        if !line.trim_start().starts_with('>') {
+            kept.push(line.trim_end());
+        }
+    }
+    kept.join("\n").trim().to_string()
+}
+
+/// Parses a raw RFC 822 message. The body is the text/plain part, or the HTML
+/// part converted to text when there is none, with quoted replies stripped.
+/// Returns `None` for messages that cannot be parsed or name no sender.
+pub fn parse_email(raw: &[u8]) -> Option<InboundEmail> {
+    let message = MessageParser::default().parse(raw)?;
+    let sender = message.from()?.first()?.address()?;
+
+    let body = message
+        .text_bodies()
+        .filter_map(|part| match &part.body {
+            PartType::Text(text) => Some(text.to_string()),
+            PartType::Html(html) => html2text::from_read(html.as_bytes(), HTML_TEXT_WIDTH).ok(),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let body_text = strip_quoted_reply(&body);
+
+    Some(InboundEmail {
+        sender: truncate(sender),
+        subject: message.subject().map(truncate).filter(|s| !s.is_empty()),
+        body_text: Some(body_text).filter(|b| !b.is_empty()),
+        received_at: message
+            .date()
+            .and_then(|date| DateTime::from_timestamp(date.to_timestamp(), 0))
+            .unwrap_or_else(Utc::now),
+        message_id: message.message_id().map(str::to_string),
+        in_reply_to: message_ids(message.in_reply_to()).into_iter().next(),
+        references: message_ids(message.references()),
+    })
+}
+
+/// Stores an email, threaded onto the task its thread started if it is a
+/// reply from a member of the task's organization; other emails are stored on
+/// no task. Returns `None` if an email with its Message-ID was stored before.
+/// The mailbox serves every organization, so this sees all of their rows.
+pub async fn ingest_email(pool: &PgPool, email: &InboundEmail) -> Result<Option<EmailLog>, sqlx::Error> {
+    across_organizations(async {
+        let thread = find_thread_task(pool, &email.thread_ids(), &email.sender).await?;
+        let (task_id, organization_id) = thread.unzip();
+        insert_email_log(pool, email, task_id, organization_id).await
+    })
+    .await
+}
+
+/// Stores the unseen messages in the configured mailbox and marks them seen,
+/// one at a time. A message that fails to store stays unseen for the next
+/// poll while the others are read; only losing the IMAP session ends the poll
+/// early. Returns how many were new.
+pub async fn poll_mailbox(pool: &PgPool, config: &ImapConfig, metrics: &Metrics) -> anyhow::Result<usize> {
+    let mut session = ImapSession::connect(config).await?;
+    session.select(&config.mailbox).await?;
+
+    let mut stored = 0;
+    for uid in session.unseen().await? {
+        let raw = match session.fetch(uid).await {
+            Ok(Some(raw)) => raw,
+            Ok(None) => continue,
+            // Left unseen it would be fetched again on every poll
+            Err(e @ ImapError::TooLarge { .. }) => {
+                log::warn!("Skipping message {} in {}: {}", uid, config.mailbox, e);
+                metrics.emails_processed.inc();
+                session.mark_seen(uid).await?;
+                continue;
+            }
+            Err(e) => return Err(e.into()),
+        };
+        match parse_email(&raw) {
+            Some(email) => match ingest_email(pool, &email).await {
+                Ok(Some(_)) => stored += 1,
+                Ok(None) => {}
+                Err(e) => {
+                    log::error!("Storing message {} from {} failed: {}", uid, config.mailbox, e);
+                    continue;
+                }
+            },
+            None => log::warn!("Skipping message {} in {}: no sender could be read", uid, config.mailbox),
+        }
+        metrics.emails_processed.inc();
+        session.mark_seen(uid).await?;
+    }
+
+    session.logout().await;
+    Ok(stored)
+}
+
+/// Runs [`poll_mailbox`], then queues itself again to run after
+/// `imap.poll_interval`, while a mailbox is configured. A failed poll is
+/// logged and left to the next one rather than retried.
+#[derive(Serialize, Deserialize)]
+pub struct PollMailbox;
+
+#[async_trait]
+impl BackgroundJob for PollMailbox {
+    const KIND: &'static str = "poll_mailbox";
+    const KEEP_COMPLETED: bool = false;
+
+    async fn run(&self, ctx: &JobContext) -> anyhow::Result<()> {
+        let Some(config) = &ctx.config.imap else {
+            return Ok(());
+        };
+
+        match poll_mailbox(&ctx.pool, config, &ctx.metrics).await {
+            Ok(0) => {}
+            Ok(stored) => log::info!("Stored {} new emails from {}", stored, config.mailbox),
+            Err(e) => log::error!("Polling {} failed: {:#}", config.mailbox, e),
+        }
+
+        let next_run = Utc::now() + chrono::Duration::from_std(config.poll_interval)?;
+        enqueue_once_at(&ctx.pool, &PollMailbox, next_run).await?;
+        Ok(())
+    }
+}
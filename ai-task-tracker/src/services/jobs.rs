@@ -0,0 +1,253 @@
+use std::collections::{HashMap, HashSet};
+use std::panic::AssertUnwindSafe;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures_util::{future::BoxFuture, FutureExt};
+use serde::{de::DeserializeOwned, Serialize};
+use sqlx::PgPool;
+use tokio::sync::watch;
+use tracing::Instrument;
+use uuid::Uuid;
+
+use crate::{
+    config::{Config, JobsConfig},
+    db::{
+        across_organizations, claim_next_job, complete_job, discard_completed_job, enqueue_job,
+        enqueue_job_unless_pending, fail_job, heartbeat_job,
+    },
+    models::Job,
+    services::Metrics,
+    storage::BlobStore,
+};
+
+/// A running job whose worker has not finished it within this long is
+/// presumed dead and handed to another worker.
+const STALE_LOCK_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+
+/// How often a worker renews the lock on the job it is running, well within
+/// [`STALE_LOCK_TIMEOUT`].
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How long shutdown waits for running jobs before leaving them for other
+/// workers to take over once their lock goes stale.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// What a job can reach while it runs.
+#[derive(Clone)]
+pub struct JobContext {
+    pub pool: PgPool,
+    pub config: Config,
+    pub blob_store: Arc<dyn BlobStore>,
+    pub metrics: Arc<Metrics>,
+}
+
+/// A type of background work. The value itself is the job's payload, stored
+/// as JSON until a worker runs it.
+///
+/// Jobs run at least once: a job whose worker dies or stalls before recording
+/// the outcome is run again by another worker, so `run` must be safe to repeat.
+#[async_trait]
+pub trait BackgroundJob: Serialize + DeserializeOwned + Send + Sync + 'static {
+    /// Identifies the type in the queue; must not change while jobs of it are queued
+    const KIND: &'static str;
+    /// Attempts before the job is given up on as dead
+    const MAX_ATTEMPTS: i32 = 5;
+    /// Whether a finished run stays in the queue as `completed`. Recurring
+    /// jobs turn this off so each run does not leave another row behind.
+    const KEEP_COMPLETED: bool = true;
+
+    async fn run(&self, ctx: &JobContext) -> anyhow::Result<()>;
+}
+
+/// Queues `job` to run as soon as a worker is free.
+pub async fn enqueue<J: BackgroundJob>(pool: &PgPool, job: &J) -> Result<Job, sqlx::Error> {
+    enqueue_at(pool, job, Utc::now()).await
+}
+
+/// Queues `job` to run once `run_at` has passed.
+pub async fn enqueue_at<J: BackgroundJob>(pool: &PgPool, job: &J, run_at: DateTime<Utc>) -> Result<Job, sqlx::Error> {
+    let payload = serde_json::to_value(job).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+    enqueue_job(pool, J::KIND, &payload, run_at, J::MAX_ATTEMPTS).await
+}
+
+/// Queues `job` to run once `run_at` has passed, unless a job of its kind is
+/// already waiting to run. Recurring jobs queue their next run this way, so
+/// each server starting one up and a run repeated after a takeover do not
+/// leave several copies queued.
+pub async fn enqueue_once_at<J: BackgroundJob>(
+    pool: &PgPool,
+    job: &J,
+    run_at: DateTime<Utc>,
+) -> Result<Option<Job>, sqlx::Error> {
+    let payload = serde_json::to_value(job).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+    enqueue_job_unless_pending(pool, J::KIND, &payload, run_at, J::MAX_ATTEMPTS).await
+}
+
+/// Wait before the next attempt after `attempts` failures: 30 seconds,
+/// doubling each time, at most an hour.
+pub fn retry_delay(attempts: i32) -> chrono::Duration {
+    let exponent = attempts.clamp(1, 8) - 1;
+    chrono::Duration::seconds((30_i64 << exponent).min(60 * 60))
+}
+
+type JobHandler = Box<dyn Fn(JobContext, serde_json::Value) -> BoxFuture<'static, anyhow::Result<()>> + Send + Sync>;
+
+/// The job types workers know how to run, by kind.
+#[derive(Default)]
+pub struct JobRegistry {
+    handlers: HashMap<&'static str, JobHandler>,
+    discard_completed: HashSet<&'static str>,
+}
+
+impl JobRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register<J: BackgroundJob>(mut self) -> Self {
+        let handler: JobHandler = Box::new(|ctx, payload| {
+            Box::pin(async move {
+                let job: J = serde_json::from_value(payload)?;
+                job.run(&ctx).await
+            })
+        });
+        self.handlers.insert(J::KIND, handler);
+        if !J::KEEP_COMPLETED {
+            self.discard_completed.insert(J::KIND);
+        }
+        self
+    }
+}
+
+/// Claims jobs from the queue and runs them.
+#[derive(Clone)]
+pub struct JobRunner {
+    ctx: JobContext,
+    registry: Arc<JobRegistry>,
+}
+
+impl JobRunner {
+    pub fn new(ctx: JobContext, registry: JobRegistry) -> Self {
+        JobRunner {
+            ctx,
+            registry: Arc::new(registry),
+        }
+    }
+
+    /// Runs the next due job, if any, and records how it went. Returns whether
+    /// there was one. Jobs see every organization's rows.
+    pub async fn run_next(&self) -> Result<bool, sqlx::Error> {
+        let stale_before = Utc::now() - chrono::Duration::from_std(STALE_LOCK_TIMEOUT).expect("timeout fits");
+        let lock = Uuid::new_v4();
+        let Some(job) = claim_next_job(&self.ctx.pool, stale_before, lock).await? else {
+            return Ok(false);
+        };
+
+        let span = tracing::info_span!("job", job_id = %job.id, kind = %job.kind, attempt = job.attempts);
+        across_organizations(self.run(job, lock)).instrument(span).await?;
+        Ok(true)
+    }
+
+    async fn run(&self, job: Job, lock: Uuid) -> Result<(), sqlx::Error> {
+        let handle = async {
+            match self.registry.handlers.get(job.kind.as_str()) {
+                // Panics count as failures rather than taking the worker down
+                Some(handler) => AssertUnwindSafe(handler(self.ctx.clone(), job.payload.clone()))
+                    .catch_unwind()
+                    .await
+                    .unwrap_or_else(|_| Err(anyhow::anyhow!("job panicked"))),
+                None => Err(anyhow::anyhow!("no handler registered for job kind {}", job.kind)),
+            }
+        };
+        let outcome = tokio::select! {
+            outcome = handle => outcome,
+            () = self.heartbeat(job.id, lock) => unreachable!("heartbeats never end"),
+        };
+
+        let recorded = match outcome {
+            Ok(()) if self.registry.discard_completed.contains(job.kind.as_str()) => {
+                discard_completed_job(&self.ctx.pool, job.id, lock).await?
+            }
+            Ok(()) => complete_job(&self.ctx.pool, job.id, lock).await?,
+            Err(e) => {
+                let error = format!("{:#}", e);
+                if job.attempts < job.max_attempts {
+                    let retry_at = Utc::now() + retry_delay(job.attempts);
+                    log::warn!("Job failed, retrying at {}: {}", retry_at, error);
+                    fail_job(&self.ctx.pool, job.id, lock, &error, Some(retry_at)).await?
+                } else {
+                    log::error!("Job failed after {} attempts: {}", job.attempts, error);
+                    fail_job(&self.ctx.pool, job.id, lock, &error, None).await?
+                }
+            }
+        };
+        if !recorded {
+            log::warn!("Job was taken over by another worker; its outcome here is not recorded");
+        }
+        Ok(())
+    }
+
+    /// Renews the lock on a running job every [`HEARTBEAT_INTERVAL`] so it is
+    /// not taken for stale. Runs until dropped.
+    async fn heartbeat(&self, job_id: Uuid, lock: Uuid) {
+        let mut ticker = actix_web::rt::time::interval(HEARTBEAT_INTERVAL);
+        // The claim itself set locked_at
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            match heartbeat_job(&self.ctx.pool, job_id, lock).await {
+                Ok(true) => {}
+                Ok(false) => log::warn!("Lost the lock on the running job to another worker"),
+                Err(e) => log::error!("Renewing the job lock failed: {}", e),
+            }
+        }
+    }
+
+    /// Starts `config.workers` workers that poll the queue until
+    /// [`JobWorkers::shutdown`] is called.
+    pub fn spawn(self, config: &JobsConfig) -> JobWorkers {
+        let (stop, stopped) = watch::channel(false);
+        let handles = (0..config.workers)
+            .map(|_| {
+                let runner = self.clone();
+                let mut stopped = stopped.clone();
+                let poll_interval = config.poll_interval;
+                actix_web::rt::spawn(async move {
+                    while !*stopped.borrow() {
+                        match runner.run_next().await {
+                            Ok(true) => continue,
+                            Ok(false) => {}
+                            Err(e) => log::error!("Job queue error: {}", e),
+                        }
+                        tokio::select! {
+                            _ = stopped.changed() => {}
+                            _ = actix_web::rt::time::sleep(poll_interval) => {}
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        JobWorkers { stop, handles }
+    }
+}
+
+/// Handle on running workers.
+pub struct JobWorkers {
+    stop: watch::Sender<bool>,
+    handles: Vec<actix_web::rt::task::JoinHandle<()>>,
+}
+
+impl JobWorkers {
+    /// Lets the workers finish the jobs they are running, then stops them.
+    pub async fn shutdown(self) {
+        let _ = self.stop.send(true);
+        let finished = futures_util::future::join_all(self.handles);
+        if actix_web::rt::time::timeout(SHUTDOWN_TIMEOUT, finished).await.is_err() {
+            log::warn!("Stopped waiting for running jobs; other workers take them over once their lock goes stale");
+        }
+    }
+}
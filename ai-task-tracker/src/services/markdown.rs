@@ -0,0 +1,127 @@
+use pulldown_cmark::{html, Event, Options, Parser, Tag, TagEnd};
+
+fn markdown_options() -> Options {
+    Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS
+}
+
+/// Renders comment markdown to HTML and strips anything that could run script
+/// (raw `<script>`, event handlers, `javascript:` links, ...).
+pub fn render_markdown(body: &str) -> String {
+    let parser = Parser::new_ext(body, markdown_options());
+    let mut unsafe_html = String::new();
+    html::push_html(&mut unsafe_html, parser);
+
+    ammonia::Builder::default()
+        .link_rel(Some("noopener noreferrer nofollow"))
+        .clean(&unsafe_html)
+        .to_string()
+}
+
+fn is_email_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '%' | '+' | '-')
+}
+
+fn push_mentions(text: &str, out: &mut Vec<String>) {
+    let chars: Vec<char> = text.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        // A mention starts at an `@` that isn't itself part of a word or address
+        if chars[i] != '@' || (i > 0 && (is_email_char(chars[i - 1]) || chars[i - 1] == '@')) {
+            i += 1;
+            continue;
+        }
+
+        let start = i + 1;
+        let mut end = start;
+        let mut at_count = 0;
+        while end < chars.len() && (is_email_char(chars[end]) || chars[end] == '@') {
+            if chars[end] == '@' {
+                at_count += 1;
+            }
+            end += 1;
+        }
+
+        let candidate: String = chars[start..end].iter().collect();
+        // Trailing punctuation belongs to the sentence, not the address
+        let candidate = candidate.trim_end_matches(['.', '-', '_']);
+        if at_count == 1 {
+            if let Some((local, domain)) = candidate.split_once('@') {
+                if !local.is_empty() && domain.contains('.') && !domain.starts_with('.') {
+                    let email = candidate.to_lowercase();
+                    if !out.contains(&email) {
+                        out.push(email);
+                    }
+                }
+            }
+        }
+        i = end.max(start);
+    }
+}
+
+/// Collects the distinct `@email` mentions in a comment, lowercased.
+/// Mentions inside code spans and code blocks are ignored.
+pub fn extract_mentions(body: &str) -> Vec<String> {
+    let mut mentions = Vec::new();
+    let mut in_code_block = false;
+
+    for event in Parser::new_ext(body, markdown_options()) {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => in_code_block = true,
+            Event::End(TagEnd::CodeBlock) => in_code_block = false,
+            Event::Text(text) if !in_code_block => push_mentions(&text, &mut mentions),
+            _ => {}
+        }
+    }
+
+    mentions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rendered_comments_cannot_run_script() {
+        let attacks = [
+            "<script>alert(1)</script>",
+            "<img src=x onerror=alert(1)>",
+            "[click](javascript:alert(1))",
+            "[click](JaVaScRiPt:alert(1))",
+            "[click](&#106;avascript:alert(1))",
+            "<a href=\"javascript:alert(1)\">click</a>",
+            "<svg onload=alert(1)></svg>",
+            "<iframe src=\"https://evil.example\"></iframe>",
+            "<p style=\"background:url(javascript:alert(1))\">hi</p>",
+            "<form action=\"https://evil.example\"><button>Go</button></form>",
+            "![x](javascript:alert(1))",
+            "<object data=\"data:text/html;base64,PHNjcmlwdD5hbGVydCgxKTwvc2NyaXB0Pg==\"></object>",
+        ];
+        let forbidden = [
+            "<script", "onerror", "onload", "javascript:", "<iframe", "style=", "<form", "<object", "data:",
+        ];
+        for attack in attacks {
+            let html = render_markdown(attack).to_lowercase();
+            for forbidden in forbidden {
+                assert!(!html.contains(forbidden), "{:?} rendered as {:?}", attack, html);
+            }
+        }
+    }
+
+    #[test]
+    fn markdown_formatting_survives_sanitizing() {
+        let body = "**bold** and ~~gone~~\n\n- [x] done\n\n| a |\n|---|\n| 1 |\n\n[docs](https://example.com/docs)";
+        let html = render_markdown(body);
+        assert!(html.contains("<strong>bold</strong>"), "{}", html);
+        assert!(html.contains("<del>gone</del>"), "{}", html);
+        assert!(html.contains("<table>"), "{}", html);
+        let link = "<a href=\"https://example.com/docs\" rel=\"noopener noreferrer nofollow\">docs</a>";
+        assert!(html.contains(link), "{}", html);
+    }
+
+    #[test]
+    fn mentions_are_email_addresses_outside_code() {
+        let body = "Thanks @Ana@Example.com, cc @bo@example.org. and @ana@example.com again\n\
+                    Not me@example.com or @@x@example.com or @nobody or `@code@example.com`\n\n    @block@example.com";
+        assert_eq!(extract_mentions(body), vec!["ana@example.com", "bo@example.org"]);
+    }
+}
@@ -0,0 +1,81 @@
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry,
+    TextEncoder,
+};
+use sqlx::PgPool;
+
+/// Everything `/metrics` reports. Each instance has its own registry, so
+/// tests do not see each other's counts.
+pub struct Metrics {
+    registry: Registry,
+    pub http_requests: IntCounterVec,
+    pub http_request_duration: HistogramVec,
+    pool_connections: IntGaugeVec,
+    pool_max_connections: IntGauge,
+    pub tasks_created: IntCounter,
+    /// Inbound emails handled, whatever their outcome
+    pub emails_processed: IntCounter,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "HTTP requests handled, by route pattern"),
+            &["method", "route", "status"],
+        )
+        .expect("valid metric");
+        let http_request_duration = HistogramVec::new(
+            HistogramOpts::new("http_request_duration_seconds", "Time spent handling HTTP requests"),
+            &["method", "route"],
+        )
+        .expect("valid metric");
+        let pool_connections = IntGaugeVec::new(
+            Opts::new("db_pool_connections", "Open database connections, by whether they are in use"),
+            &["state"],
+        )
+        .expect("valid metric");
+        let pool_max_connections =
+            IntGauge::new("db_pool_max_connections", "Most connections the pool will open").expect("valid metric");
+        let tasks_created = IntCounter::new("tasks_created_total", "Tasks created").expect("valid metric");
+        let emails_processed =
+            IntCounter::new("emails_processed_total", "Inbound emails processed").expect("valid metric");
+
+        let registry = Registry::new();
+        registry.register(Box::new(http_requests.clone())).expect("unique metric");
+        registry.register(Box::new(http_request_duration.clone())).expect("unique metric");
+        registry.register(Box::new(pool_connections.clone())).expect("unique metric");
+        registry.register(Box::new(pool_max_connections.clone())).expect("unique metric");
+        registry.register(Box::new(tasks_created.clone())).expect("unique metric");
+        registry.register(Box::new(emails_processed.clone())).expect("unique metric");
+
+        Metrics {
+            registry,
+            http_requests,
+            http_request_duration,
+            pool_connections,
+            pool_max_connections,
+            tasks_created,
+            emails_processed,
+        }
+    }
+
+    /// Samples the pool and renders every metric in the Prometheus text format.
+    pub fn render(&self, pool: &PgPool) -> String {
+        let idle = pool.num_idle() as i64;
+        self.pool_connections.with_label_values(&["idle"]).set(idle);
+        self.pool_connections.with_label_values(&["in_use"]).set(pool.size() as i64 - idle);
+        self.pool_max_connections.set(pool.options().get_max_connections() as i64);
+
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .expect("metrics encode");
+        String::from_utf8(buffer).expect("metrics are UTF-8")
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
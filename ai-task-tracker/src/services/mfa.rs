@@ -0,0 +1,50 @@
+use chrono::Utc;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::db::{find_totp_state, record_mfa_failure, record_totp_step, use_recovery_code};
+use crate::utils::{generate_recovery_code, hash_recovery_code, is_totp_code, verify_totp};
+
+const RECOVERY_CODE_COUNT: usize = 10;
+
+pub enum MfaCheck {
+    Accepted,
+    Rejected,
+    /// Too many wrong codes; no code is checked until the lockout ends
+    LockedOut,
+}
+
+/// Checks a second factor for a user with two-factor authentication enabled:
+/// a current TOTP code not used before, or an unused recovery code, which is
+/// spent. Wrong codes count towards a temporary lockout.
+pub async fn check_mfa_code(pool: &PgPool, user_id: Uuid, code: &str) -> Result<MfaCheck, sqlx::Error> {
+    let Some(state) = find_totp_state(pool, user_id).await? else {
+        return Ok(MfaCheck::Rejected);
+    };
+    if state.mfa_locked_until.is_some_and(|until| until > Utc::now()) {
+        return Ok(MfaCheck::LockedOut);
+    }
+
+    let accepted = match (&state.totp_secret, state.totp_enabled_at) {
+        (Some(secret), Some(_)) if is_totp_code(code) => match verify_totp(secret, code, Utc::now().timestamp()) {
+            Some(step) => record_totp_step(pool, user_id, step).await?,
+            None => false,
+        },
+        (Some(_), Some(_)) => use_recovery_code(pool, user_id, &hash_recovery_code(code)).await?,
+        _ => false,
+    };
+
+    if accepted {
+        Ok(MfaCheck::Accepted)
+    } else {
+        record_mfa_failure(pool, user_id).await?;
+        Ok(MfaCheck::Rejected)
+    }
+}
+
+/// A fresh set of recovery codes, with the hashes to store for them.
+pub fn generate_recovery_codes() -> (Vec<String>, Vec<String>) {
+    let codes: Vec<String> = (0..RECOVERY_CODE_COUNT).map(|_| generate_recovery_code()).collect();
+    let hashes = codes.iter().map(|code| hash_recovery_code(code)).collect();
+    (codes, hashes)
+}
@@ -0,0 +1,29 @@
+pub mod icalendar;
+pub mod markdown;
+pub mod project_export;
+pub mod project_import;
+pub mod timesheet;
+pub mod sprint_report;
+pub mod trash_purge;
+pub mod project_template;
+pub mod oidc;
+pub mod mfa;
+pub mod metrics;
+pub mod jobs;
+pub mod imap;
+pub mod inbound_email;
+
+pub use icalendar::*;
+pub use markdown::*;
+pub use project_export::*;
+pub use project_import::*;
+pub use timesheet::*;
+pub use sprint_report::*;
+pub use trash_purge::*;
+pub use project_template::*;
+pub use oidc::*;
+pub use mfa::*;
+pub use metrics::*;
+pub use jobs::*;
+pub use imap::*;
+pub use inbound_email::*;
@@ -0,0 +1,231 @@
+use std::sync::RwLock;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::{decode, decode_header, jwk::JwkSet, Algorithm, DecodingKey, Validation};
+use reqwest::Url;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use tokio::sync::OnceCell;
+
+use crate::config::OidcConfig;
+use crate::utils::generate_token;
+
+const PKCE_VERIFIER_LENGTH: usize = 64;
+
+#[derive(Debug, thiserror::Error)]
+pub enum OidcError {
+    #[error("identity provider request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("identity provider error: {0}")]
+    Provider(String),
+    #[error("invalid ID token: {0}")]
+    InvalidToken(String),
+}
+
+/// The parts of the provider's discovery document the login flow needs.
+#[derive(Debug, Clone, Deserialize)]
+struct ProviderMetadata {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    jwks_uri: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    id_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenErrorResponse {
+    error: String,
+    error_description: Option<String>,
+}
+
+/// The verified claims of an ID token.
+#[derive(Debug, Clone, Deserialize)]
+pub struct IdTokenClaims {
+    pub iss: String,
+    pub sub: String,
+    pub nonce: Option<String>,
+    pub email: Option<String>,
+    /// `Some(true)` only if the provider checked the address; accounts are only matched by a checked one
+    pub email_verified: Option<bool>,
+    pub name: Option<String>,
+}
+
+/// A PKCE verifier with its S256 challenge.
+pub struct Pkce {
+    pub verifier: String,
+    pub challenge: String,
+}
+
+impl Pkce {
+    pub fn generate() -> Self {
+        let verifier = generate_token(PKCE_VERIFIER_LENGTH);
+        let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+        Pkce { verifier, challenge }
+    }
+}
+
+/// Relying-party side of the OpenID Connect authorization code flow. The
+/// discovery document is fetched on first use; signing keys are refetched
+/// when a token names a key that is not known yet, to follow key rotation.
+pub struct OidcClient {
+    http: reqwest::Client,
+    config: OidcConfig,
+    metadata: OnceCell<ProviderMetadata>,
+    jwks: RwLock<JwkSet>,
+}
+
+impl OidcClient {
+    pub fn new(config: OidcConfig) -> Self {
+        OidcClient {
+            http: reqwest::Client::new(),
+            config,
+            metadata: OnceCell::new(),
+            jwks: RwLock::new(JwkSet { keys: Vec::new() }),
+        }
+    }
+
+    async fn metadata(&self) -> Result<&ProviderMetadata, OidcError> {
+        self.metadata
+            .get_or_try_init(|| async {
+                let url = format!(
+                    "{}/.well-known/openid-configuration",
+                    self.config.issuer_url.trim_end_matches('/')
+                );
+                let metadata: ProviderMetadata = self.get_json(&url).await?;
+
+                if metadata.issuer.trim_end_matches('/') != self.config.issuer_url.trim_end_matches('/') {
+                    return Err(OidcError::Provider(format!(
+                        "discovery document is for issuer {}",
+                        metadata.issuer
+                    )));
+                }
+                Ok(metadata)
+            })
+            .await
+    }
+
+    async fn get_json<T: serde::de::DeserializeOwned>(&self, url: &str) -> Result<T, OidcError> {
+        let response = self.http.get(url).send().await?.error_for_status()?;
+        let body = response.bytes().await?;
+        serde_json::from_slice(&body).map_err(|e| OidcError::Provider(format!("invalid response from {}: {}", url, e)))
+    }
+
+    /// Whether `email` belongs to one of the allowed domains.
+    pub fn email_allowed(&self, email: &str) -> bool {
+        if self.config.allowed_email_domains.is_empty() {
+            return true;
+        }
+        email
+            .rsplit_once('@')
+            .map(|(_, domain)| domain.to_lowercase())
+            .is_some_and(|domain| self.config.allowed_email_domains.contains(&domain))
+    }
+
+    /// The provider URL that starts a sign-in for the given request parameters.
+    pub async fn authorization_url(&self, state: &str, nonce: &str, code_challenge: &str) -> Result<String, OidcError> {
+        let metadata = self.metadata().await?;
+        let url = Url::parse_with_params(
+            &metadata.authorization_endpoint,
+            &[
+                ("response_type", "code"),
+                ("client_id", self.config.client_id.as_str()),
+                ("redirect_uri", self.config.redirect_url.as_str()),
+                ("scope", self.config.scopes.as_str()),
+                ("state", state),
+                ("nonce", nonce),
+                ("code_challenge", code_challenge),
+                ("code_challenge_method", "S256"),
+            ],
+        )
+        .map_err(|e| OidcError::Provider(format!("invalid authorization endpoint: {}", e)))?;
+
+        Ok(url.to_string())
+    }
+
+    /// Redeems an authorization code and returns the verified ID token claims.
+    pub async fn exchange_code(&self, code: &str, code_verifier: &str, nonce: &str) -> Result<IdTokenClaims, OidcError> {
+        let metadata = self.metadata().await?;
+
+        let mut request = self.http.post(&metadata.token_endpoint).form(&[
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", self.config.redirect_url.as_str()),
+            ("client_id", self.config.client_id.as_str()),
+            ("code_verifier", code_verifier),
+        ]);
+        if let Some(secret) = &self.config.client_secret {
+            request = request.basic_auth(&self.config.client_id, Some(secret));
+        }
+
+        let response = request.send().await?;
+        let status = response.status();
+        let body = response.bytes().await?;
+        if !status.is_success() {
+            let message = match serde_json::from_slice::<TokenErrorResponse>(&body) {
+                Ok(e) => format!("{} {}", e.error, e.error_description.unwrap_or_default()),
+                Err(_) => format!("token endpoint returned {}", status),
+            };
+            return Err(OidcError::Provider(message.trim().to_string()));
+        }
+
+        let tokens: TokenResponse = serde_json::from_slice(&body)
+            .map_err(|e| OidcError::Provider(format!("invalid token response: {}", e)))?;
+        let id_token = tokens
+            .id_token
+            .ok_or_else(|| OidcError::Provider("token response has no ID token".to_string()))?;
+
+        self.verify_id_token(&id_token, nonce).await
+    }
+
+    /// Checks the signature against the provider's keys, then issuer, audience,
+    /// expiry and nonce.
+    async fn verify_id_token(&self, id_token: &str, nonce: &str) -> Result<IdTokenClaims, OidcError> {
+        let metadata = self.metadata().await?;
+        let header = decode_header(id_token).map_err(|e| OidcError::InvalidToken(e.to_string()))?;
+        if matches!(header.alg, Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512) {
+            return Err(OidcError::InvalidToken(format!("{:?} tokens are not accepted", header.alg)));
+        }
+
+        let key = match self.decoding_key(header.kid.as_deref())? {
+            Some(key) => key,
+            None => {
+                let jwks: JwkSet = self.get_json(&metadata.jwks_uri).await?;
+                *self.jwks.write().expect("JWKS lock poisoned") = jwks;
+                self.decoding_key(header.kid.as_deref())?
+                    .ok_or_else(|| OidcError::InvalidToken("signed with an unknown key".to_string()))?
+            }
+        };
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_audience(&[&self.config.client_id]);
+        validation.set_issuer(&[&metadata.issuer]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+
+        let claims = decode::<IdTokenClaims>(id_token, &key, &validation)
+            .map_err(|e| OidcError::InvalidToken(e.to_string()))?
+            .claims;
+
+        if claims.nonce.as_deref() != Some(nonce) {
+            return Err(OidcError::InvalidToken("nonce does not match".to_string()));
+        }
+        Ok(claims)
+    }
+
+    /// The cached key with the given id, or the only key when the token names none.
+    fn decoding_key(&self, kid: Option<&str>) -> Result<Option<DecodingKey>, OidcError> {
+        let jwks = self.jwks.read().expect("JWKS lock poisoned");
+        let jwk = match kid {
+            Some(kid) => jwks.find(kid),
+            None if jwks.keys.len() == 1 => jwks.keys.first(),
+            None => None,
+        };
+
+        jwk.map(DecodingKey::from_jwk)
+            .transpose()
+            .map_err(|e| OidcError::InvalidToken(format!("unusable signing key: {}", e)))
+    }
+}
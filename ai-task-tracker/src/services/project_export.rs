@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+
+use chrono::Utc;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    db::{list_subtasks_for_project, list_task_history_for_project, list_tasks, list_users_by_ids},
+    models::{ExportedHistory, ExportedSubtask, ExportedTask, Project, ProjectExport, TaskFilter},
+};
+
+/// Column order of the CSV export. `record_type` is `task`, `subtask` or `history`;
+/// subtask and history rows point at their task through `parent_id`.
+pub const CSV_COLUMNS: [&str; 16] = [
+    "record_type",
+    "id",
+    "parent_id",
+    "title",
+    "description",
+    "status",
+    "priority",
+    "progress_percent",
+    "estimate_minutes",
+    "assignee_email",
+    "due_date",
+    "is_completed",
+    "comment",
+    "completion_percentage",
+    "author_email",
+    "created_at",
+];
+
+pub async fn build_project_export(pool: &PgPool, project: Project) -> Result<ProjectExport, sqlx::Error> {
+    let filter = TaskFilter {
+        project_id: Some(project.id),
+        ..Default::default()
+    };
+    let tasks = list_tasks(pool, project.organization_id, &filter).await?;
+    let subtasks = list_subtasks_for_project(pool, project.id).await?;
+    let history = list_task_history_for_project(pool, project.id).await?;
+
+    let assignee_ids: Vec<Uuid> = tasks.iter().filter_map(|t| t.assignee_id).collect();
+    let emails: HashMap<Uuid, String> = list_users_by_ids(pool, &assignee_ids)
+        .await?
+        .into_iter()
+        .map(|u| (u.id, u.email))
+        .collect();
+
+    let mut subtasks_by_task: HashMap<Uuid, Vec<ExportedSubtask>> = HashMap::new();
+    for subtask in subtasks {
+        subtasks_by_task
+            .entry(subtask.parent_task_id)
+            .or_default()
+            .push(ExportedSubtask {
+                title: subtask.title,
+                is_completed: subtask.is_completed,
+            });
+    }
+
+    let mut history_by_task: HashMap<Uuid, Vec<ExportedHistory>> = HashMap::new();
+    for entry in history {
+        history_by_task
+            .entry(entry.task_id)
+            .or_default()
+            .push(ExportedHistory {
+                user_email: entry.user_email,
+                comment: entry.comment,
+                completion_percentage: entry.completion_percentage,
+                created_at: entry.created_at,
+            });
+    }
+
+    // Oldest first so parents precede their children when re-imported
+    let mut exported: Vec<ExportedTask> = tasks
+        .into_iter()
+        .map(|task| ExportedTask {
+            assignee_email: task.assignee_id.and_then(|id| emails.get(&id).cloned()),
+            subtasks: subtasks_by_task.remove(&task.id).unwrap_or_default(),
+            history: history_by_task.remove(&task.id).unwrap_or_default(),
+            id: task.id,
+            parent_task_id: task.parent_task_id,
+            title: task.title,
+            description: task.description,
+            status: task.status,
+            priority: task.priority,
+            progress_percent: task.progress_percent,
+            estimate_minutes: task.estimate_minutes,
+            due_date: task.due_date,
+            created_at: task.created_at,
+        })
+        .collect();
+    exported.sort_by_key(|t| t.created_at);
+
+    Ok(ProjectExport {
+        project,
+        exported_at: Utc::now(),
+        tasks: exported,
+    })
+}
+
+pub fn export_to_csv(export: &ProjectExport) -> Result<Vec<u8>, csv::Error> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(CSV_COLUMNS)?;
+
+    for task in &export.tasks {
+        let id = task.id.to_string();
+        writer.write_record([
+            "task",
+            &id,
+            &task.parent_task_id.map(|p| p.to_string()).unwrap_or_default(),
+            &task.title,
+            task.description.as_deref().unwrap_or(""),
+            &task.status,
+            &task.priority,
+            &task.progress_percent.to_string(),
+            &task.estimate_minutes.map(|m| m.to_string()).unwrap_or_default(),
+            task.assignee_email.as_deref().unwrap_or(""),
+            &task.due_date.map(|d| d.to_rfc3339()).unwrap_or_default(),
+            "",
+            "",
+            "",
+            "",
+            &task.created_at.to_rfc3339(),
+        ])?;
+
+        for subtask in &task.subtasks {
+            writer.write_record([
+                "subtask",
+                "",
+                &id,
+                &subtask.title,
+                "",
+                "",
+                "",
+                "",
+                "",
+                "",
+                "",
+                &subtask.is_completed.to_string(),
+                "",
+                "",
+                "",
+                "",
+            ])?;
+        }
+
+        for entry in &task.history {
+            writer.write_record([
+                "history",
+                "",
+                &id,
+                "",
+                "",
+                "",
+                "",
+                "",
+                "",
+                "",
+                "",
+                "",
+                &entry.comment,
+                &entry.completion_percentage.to_string(),
+                &entry.user_email,
+                &entry.created_at.to_rfc3339(),
+            ])?;
+        }
+    }
+
+    writer.into_inner().map_err(|e| e.into_error().into())
+}
@@ -0,0 +1,458 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    db::{find_task_by_id, find_user_by_email, is_organization_member},
+    models::{
+        CreateTaskRequest, ExportedSubtask, ImportRowError, ImportedTask, TaskPriority, TaskStatus,
+        TransferFormat,
+    },
+};
+
+/// Fields an import column can be mapped to.
+pub const IMPORT_FIELDS: [&str; 12] = [
+    "record_type",
+    "id",
+    "parent_id",
+    "title",
+    "description",
+    "status",
+    "priority",
+    "progress_percent",
+    "estimate_minutes",
+    "assignee_email",
+    "due_date",
+    "is_completed",
+];
+
+/// Header spellings recognised without an explicit mapping.
+const FIELD_ALIASES: [(&str, &str); 14] = [
+    ("name", "title"),
+    ("summary", "title"),
+    ("task", "title"),
+    ("task_name", "title"),
+    ("ref", "id"),
+    ("reference", "id"),
+    ("parent", "parent_id"),
+    ("parent_task_id", "parent_id"),
+    ("assignee", "assignee_email"),
+    ("owner", "assignee_email"),
+    ("due", "due_date"),
+    ("deadline", "due_date"),
+    ("progress", "progress_percent"),
+    ("estimate", "estimate_minutes"),
+];
+
+/// One row of an import file with its values keyed by import field.
+#[derive(Debug)]
+pub struct RawRow {
+    pub row: usize,
+    pub fields: HashMap<String, String>,
+    pub subtasks: Vec<ExportedSubtask>,
+}
+
+impl RawRow {
+    fn get(&self, field: &str) -> Option<&str> {
+        self.fields
+            .get(field)
+            .map(|v| v.trim())
+            .filter(|v| !v.is_empty())
+    }
+}
+
+/// Rows that passed validation, the row-level errors, and how many rows were ignored.
+pub struct ValidatedImport {
+    pub tasks: Vec<ImportedTask>,
+    pub errors: Vec<ImportRowError>,
+    pub skipped: usize,
+}
+
+fn normalize_header(header: &str) -> String {
+    header.trim().to_lowercase().replace([' ', '-'], "_")
+}
+
+/// Resolves a source column/key to an import field, honouring the caller's mapping first.
+fn resolve_field(column: &str, mapping: &HashMap<String, String>) -> Option<String> {
+    let normalized = normalize_header(column);
+
+    if let Some((field, _)) = mapping
+        .iter()
+        .find(|(_, source)| normalize_header(source) == normalized)
+    {
+        return Some(field.clone());
+    }
+    if IMPORT_FIELDS.contains(&normalized.as_str()) {
+        return Some(normalized);
+    }
+    FIELD_ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == normalized)
+        .map(|(_, field)| field.to_string())
+}
+
+pub fn parse_rows(
+    format: TransferFormat,
+    body: &[u8],
+    mapping: &HashMap<String, String>,
+) -> Result<Vec<RawRow>, String> {
+    match format {
+        TransferFormat::Csv => parse_csv_rows(body, mapping),
+        TransferFormat::Json => parse_json_rows(body, mapping),
+    }
+}
+
+fn parse_csv_rows(body: &[u8], mapping: &HashMap<String, String>) -> Result<Vec<RawRow>, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(body);
+
+    let headers = reader
+        .headers()
+        .map_err(|e| format!("Invalid CSV header: {}", e))?
+        .clone();
+    let columns: Vec<Option<String>> = headers.iter().map(|h| resolve_field(h, mapping)).collect();
+
+    if !columns.iter().any(|c| c.as_deref() == Some("title")) {
+        return Err("CSV has no title column; map one with map.title=<column>".to_string());
+    }
+
+    let mut rows = Vec::new();
+    for (index, record) in reader.records().enumerate() {
+        // Header is line 1
+        let line = index + 2;
+        let record = record.map_err(|e| format!("Invalid CSV on line {}: {}", line, e))?;
+
+        let fields = columns
+            .iter()
+            .zip(record.iter())
+            .filter_map(|(field, value)| field.clone().map(|f| (f, value.to_string())))
+            .collect();
+
+        rows.push(RawRow {
+            row: line,
+            fields,
+            subtasks: Vec::new(),
+        });
+    }
+
+    Ok(rows)
+}
+
+fn json_value_to_string(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::Null => None,
+        serde_json::Value::String(s) => Some(s.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+/// Accepts either a bare array of task objects or a full project export document.
+fn parse_json_rows(body: &[u8], mapping: &HashMap<String, String>) -> Result<Vec<RawRow>, String> {
+    let document: serde_json::Value =
+        serde_json::from_slice(body).map_err(|e| format!("Invalid JSON: {}", e))?;
+
+    let items = match document {
+        serde_json::Value::Array(items) => items,
+        serde_json::Value::Object(mut obj) => match obj.remove("tasks") {
+            Some(serde_json::Value::Array(items)) => items,
+            _ => return Err("JSON must be an array of tasks or an object with a `tasks` array".to_string()),
+        },
+        _ => return Err("JSON must be an array of tasks or an object with a `tasks` array".to_string()),
+    };
+
+    let mut rows = Vec::new();
+    for (index, item) in items.into_iter().enumerate() {
+        let row = index + 1;
+        let obj = match item {
+            serde_json::Value::Object(obj) => obj,
+            _ => return Err(format!("Task {} is not a JSON object", row)),
+        };
+
+        let mut fields = HashMap::new();
+        let mut subtasks = Vec::new();
+        for (key, value) in obj {
+            if key == "subtasks" {
+                for subtask in value.as_array().into_iter().flatten() {
+                    match subtask {
+                        serde_json::Value::String(title) => subtasks.push(ExportedSubtask {
+                            title: title.clone(),
+                            is_completed: false,
+                        }),
+                        other => {
+                            let parsed: ExportedSubtask = serde_json::from_value(other.clone())
+                                .map_err(|e| format!("Invalid subtask in task {}: {}", row, e))?;
+                            subtasks.push(parsed);
+                        }
+                    }
+                }
+                continue;
+            }
+            if let (Some(field), Some(value)) = (resolve_field(&key, mapping), json_value_to_string(&value)) {
+                fields.insert(field, value);
+            }
+        }
+
+        rows.push(RawRow { row, fields, subtasks });
+    }
+
+    Ok(rows)
+}
+
+fn parse_due_date(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(value) {
+        return Some(dt.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .map(|dt| dt.and_utc())
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_lowercase().as_str() {
+        "true" | "yes" | "y" | "1" | "x" | "done" => Some(true),
+        "false" | "no" | "n" | "0" | "" => Some(false),
+        _ => None,
+    }
+}
+
+fn row_error(row: usize, field: Option<&str>, message: impl Into<String>) -> ImportRowError {
+    ImportRowError {
+        row,
+        field: field.map(|f| f.to_string()),
+        message: message.into(),
+    }
+}
+
+/// Validates every row against `CreateTaskRequest` rules, resolving assignees by email
+/// among the organization's members and parents either within the file or among the
+/// project's existing tasks.
+pub async fn validate_rows(
+    pool: &PgPool,
+    organization_id: Uuid,
+    project_id: Uuid,
+    rows: Vec<RawRow>,
+) -> Result<ValidatedImport, sqlx::Error> {
+    let mut tasks: Vec<ImportedTask> = Vec::new();
+    let mut task_rows: Vec<usize> = Vec::new();
+    let mut errors = Vec::new();
+    let mut skipped = 0;
+    let mut references: HashMap<String, usize> = HashMap::new();
+    let mut pending_subtasks: Vec<(usize, String, ExportedSubtask)> = Vec::new();
+    let mut assignees: HashMap<String, Option<Uuid>> = HashMap::new();
+
+    for raw in rows {
+        let record_type = raw.get("record_type").unwrap_or("task").to_lowercase();
+        match record_type.as_str() {
+            "task" => {}
+            "subtask" => {
+                let title = raw.get("title");
+                let parent = raw.get("parent_id");
+                let completed = parse_bool(raw.get("is_completed").unwrap_or(""));
+                match (title, parent, completed) {
+                    (None, _, _) => errors.push(row_error(raw.row, Some("title"), "Subtask title is required")),
+                    (_, None, _) => errors.push(row_error(raw.row, Some("parent_id"), "Subtask needs a parent_id")),
+                    (_, _, None) => errors.push(row_error(raw.row, Some("is_completed"), "Expected true or false")),
+                    (Some(title), Some(parent), Some(is_completed)) => pending_subtasks.push((
+                        raw.row,
+                        parent.to_string(),
+                        ExportedSubtask {
+                            title: title.to_string(),
+                            is_completed,
+                        },
+                    )),
+                }
+                continue;
+            }
+            // History is exported for archival only; it needs an author and is not re-imported
+            "history" => {
+                skipped += 1;
+                continue;
+            }
+            other => {
+                errors.push(row_error(raw.row, Some("record_type"), format!("Unknown record type: {}", other)));
+                continue;
+            }
+        }
+
+        let mut row_errors = Vec::new();
+
+        let title = match raw.get("title") {
+            Some(title) if title.chars().count() <= 255 => title.to_string(),
+            Some(_) => {
+                row_errors.push(row_error(raw.row, Some("title"), "Title must be at most 255 characters"));
+                String::new()
+            }
+            None => {
+                row_errors.push(row_error(raw.row, Some("title"), "Title is required"));
+                String::new()
+            }
+        };
+
+        let priority = match raw.get("priority").map(|p| p.parse::<TaskPriority>()) {
+            Some(Ok(p)) => Some(p),
+            Some(Err(e)) => {
+                row_errors.push(row_error(raw.row, Some("priority"), e));
+                None
+            }
+            None => None,
+        };
+
+        let status = match raw.get("status").map(|s| s.parse::<TaskStatus>()) {
+            Some(Ok(s)) => Some(s),
+            Some(Err(e)) => {
+                row_errors.push(row_error(raw.row, Some("status"), e));
+                None
+            }
+            None => None,
+        };
+
+        let progress_percent = match raw.get("progress_percent").map(|p| p.parse::<i32>()) {
+            Some(Ok(p)) if (0..=100).contains(&p) => Some(p),
+            Some(_) => {
+                row_errors.push(row_error(raw.row, Some("progress_percent"), "Progress must be between 0 and 100"));
+                None
+            }
+            None => None,
+        };
+
+        let estimate_minutes = match raw.get("estimate_minutes").map(|m| m.parse::<i32>()) {
+            Some(Ok(m)) if m >= 0 => Some(m),
+            Some(_) => {
+                row_errors.push(row_error(raw.row, Some("estimate_minutes"), "Estimate must be a whole number of minutes"));
+                None
+            }
+            None => None,
+        };
+
+        let due_date = match raw.get("due_date") {
+            Some(value) => match parse_due_date(value) {
+                Some(d) => Some(d),
+                None => {
+                    row_errors.push(row_error(
+                        raw.row,
+                        Some("due_date"),
+                        format!("Invalid date `{}`; use YYYY-MM-DD or RFC 3339", value),
+                    ));
+                    None
+                }
+            },
+            None => None,
+        };
+
+        let assignee_id = match raw.get("assignee_email") {
+            Some(email) => {
+                let key = email.to_lowercase();
+                if !assignees.contains_key(&key) {
+                    let member = match find_user_by_email(pool, email).await? {
+                        Some(user) if is_organization_member(pool, organization_id, user.id).await? => Some(user.id),
+                        _ => None,
+                    };
+                    assignees.insert(key.clone(), member);
+                }
+                match assignees[&key] {
+                    Some(id) => Some(id),
+                    None => {
+                        row_errors.push(row_error(raw.row, Some("assignee_email"), format!("No user with email {}", email)));
+                        None
+                    }
+                }
+            }
+            None => None,
+        };
+
+        let reference = raw.get("id").map(|r| r.to_string());
+        if let Some(reference) = &reference {
+            if references.contains_key(reference) {
+                row_errors.push(row_error(raw.row, Some("id"), format!("Duplicate id `{}`", reference)));
+            }
+        }
+
+        if !row_errors.is_empty() {
+            errors.extend(row_errors);
+            continue;
+        }
+
+        if let Some(reference) = &reference {
+            references.insert(reference.clone(), tasks.len());
+        }
+        task_rows.push(raw.row);
+        tasks.push(ImportedTask {
+            reference,
+            parent_ref: raw.get("parent_id").map(|p| p.to_string()),
+            task: CreateTaskRequest {
+                project_id,
+                parent_task_id: None,
+                title,
+                description: raw.get("description").map(|d| d.to_string()),
+                priority,
+                assignee_id,
+                due_date,
+                estimate_minutes,
+            },
+            status,
+            progress_percent,
+            subtasks: raw.subtasks,
+        });
+    }
+
+    for (row, parent, subtask) in pending_subtasks {
+        match references.get(&parent) {
+            Some(&index) => tasks[index].subtasks.push(subtask),
+            None => errors.push(row_error(row, Some("parent_id"), format!("No task with id `{}` in this file", parent))),
+        }
+    }
+
+    // Parents outside the file must be existing tasks of the same project
+    let mut invalid = HashSet::new();
+    for (index, task) in tasks.iter_mut().enumerate() {
+        let Some(parent) = task.parent_ref.clone() else { continue };
+        if references.contains_key(&parent) {
+            continue;
+        }
+        let existing = match Uuid::parse_str(&parent) {
+            Ok(id) => find_task_by_id(pool, organization_id, id).await?.filter(|t| t.project_id == project_id),
+            Err(_) => None,
+        };
+        match existing {
+            Some(parent_task) => {
+                task.task.parent_task_id = Some(parent_task.id);
+                task.parent_ref = None;
+            }
+            None => {
+                errors.push(row_error(task_rows[index], Some("parent_id"), format!("Unknown parent task `{}`", parent)));
+                invalid.insert(index);
+            }
+        }
+    }
+
+    // Reject parent cycles within the file
+    for (index, task) in tasks.iter().enumerate() {
+        let mut seen = HashSet::from([index]);
+        let mut current = task.parent_ref.as_ref().and_then(|p| references.get(p));
+        while let Some(&parent_index) = current {
+            if !seen.insert(parent_index) {
+                errors.push(row_error(task_rows[index], Some("parent_id"), "Parent references form a cycle"));
+                invalid.insert(index);
+                break;
+            }
+            current = tasks[parent_index].parent_ref.as_ref().and_then(|p| references.get(p));
+        }
+    }
+
+    if !invalid.is_empty() {
+        tasks = tasks
+            .into_iter()
+            .enumerate()
+            .filter(|(index, _)| !invalid.contains(index))
+            .map(|(_, task)| task)
+            .collect();
+    }
+    errors.sort_by_key(|e| e.row);
+
+    Ok(ValidatedImport { tasks, errors, skipped })
+}
@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+use uuid::Uuid;
+
+use crate::models::{Subtask, Task, TemplateTask, TemplateTaskInput};
+
+/// Rebuilds the nested task tree from a template's stored rows.
+pub fn template_task_tree(rows: &[TemplateTask]) -> Vec<TemplateTaskInput> {
+    let mut children: HashMap<Option<Uuid>, Vec<&TemplateTask>> = HashMap::new();
+    for row in rows {
+        children.entry(row.parent_id).or_default().push(row);
+    }
+
+    fn build(parent: Option<Uuid>, children: &HashMap<Option<Uuid>, Vec<&TemplateTask>>) -> Vec<TemplateTaskInput> {
+        children
+            .get(&parent)
+            .map(|rows| {
+                rows.iter()
+                    .map(|row| TemplateTaskInput {
+                        title: row.title.clone(),
+                        description: row.description.clone(),
+                        priority: row.priority.parse().ok(),
+                        estimate_minutes: row.estimate_minutes,
+                        due_offset_days: row.due_offset_days,
+                        assignee_role: row.assignee_role.as_deref().and_then(|r| r.parse().ok()),
+                        subtasks: row.subtasks.clone(),
+                        children: build(Some(row.id), children),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    build(None, &children)
+}
+
+/// Captures a project's task tree as template tasks. Due dates become day
+/// offsets from `start_date`, and assignees become their role in the project's
+/// team (`roles`, keyed by user id); assignees outside the team are dropped.
+pub fn template_tasks_from_project(
+    tasks: &[Task],
+    subtasks: &[Subtask],
+    roles: &HashMap<Uuid, String>,
+    start_date: NaiveDate,
+) -> Vec<TemplateTaskInput> {
+    let mut children: HashMap<Option<Uuid>, Vec<&Task>> = HashMap::new();
+    let mut ordered: Vec<&Task> = tasks.iter().collect();
+    ordered.sort_by_key(|t| t.created_at);
+    for task in ordered {
+        // A parent outside the captured set makes the task a root
+        let parent = task.parent_task_id.filter(|id| tasks.iter().any(|t| t.id == *id));
+        children.entry(parent).or_default().push(task);
+    }
+
+    let mut subtask_titles: HashMap<Uuid, Vec<String>> = HashMap::new();
+    for subtask in subtasks {
+        subtask_titles
+            .entry(subtask.parent_task_id)
+            .or_default()
+            .push(subtask.title.clone());
+    }
+
+    struct Context<'a> {
+        children: HashMap<Option<Uuid>, Vec<&'a Task>>,
+        subtask_titles: HashMap<Uuid, Vec<String>>,
+        roles: &'a HashMap<Uuid, String>,
+        start_date: NaiveDate,
+    }
+
+    fn build(parent: Option<Uuid>, ctx: &Context<'_>) -> Vec<TemplateTaskInput> {
+        ctx.children
+            .get(&parent)
+            .map(|tasks| {
+                tasks
+                    .iter()
+                    .map(|task| TemplateTaskInput {
+                        title: task.title.clone(),
+                        description: task.description.clone(),
+                        priority: task.priority.parse().ok(),
+                        estimate_minutes: task.estimate_minutes,
+                        due_offset_days: task
+                            .due_date
+                            .map(|due| (due.date_naive() - ctx.start_date).num_days() as i32),
+                        assignee_role: task
+                            .assignee_id
+                            .and_then(|id| ctx.roles.get(&id))
+                            .and_then(|role| role.parse().ok()),
+                        subtasks: ctx.subtask_titles.get(&task.id).cloned().unwrap_or_default(),
+                        children: build(Some(task.id), ctx),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    let ctx = Context {
+        children,
+        subtask_titles,
+        roles,
+        start_date,
+    };
+    build(None, &ctx)
+}
@@ -0,0 +1,47 @@
+use sqlx::PgPool;
+
+use crate::{
+    db::{list_sprint_task_outcomes, sprint_member_loads},
+    models::{Sprint, SprintReport, SprintTotals},
+};
+
+/// Committed vs. completed for a sprint, with spillover once it has closed.
+pub async fn build_sprint_report(pool: &PgPool, sprint: Sprint) -> Result<SprintReport, sqlx::Error> {
+    let tasks = list_sprint_task_outcomes(pool, sprint.id).await?;
+    let members = sprint_member_loads(pool, &sprint).await?;
+
+    let mut committed = SprintTotals::default();
+    let mut added = SprintTotals::default();
+    let mut completed = SprintTotals::default();
+    let mut removed = SprintTotals::default();
+    let mut spillover = SprintTotals::default();
+    let mut remaining = SprintTotals::default();
+
+    for task in &tasks {
+        if task.added_after_start {
+            added.add(task);
+        } else {
+            committed.add(task);
+        }
+
+        match task.outcome.as_str() {
+            "completed" => completed.add(task),
+            "removed" => removed.add(task),
+            "spilled" => spillover.add(task),
+            _ if task.status == "done" => completed.add(task),
+            _ => remaining.add(task),
+        }
+    }
+
+    Ok(SprintReport {
+        sprint,
+        committed,
+        added,
+        completed,
+        removed,
+        spillover,
+        remaining,
+        members,
+        tasks,
+    })
+}
@@ -0,0 +1,34 @@
+use crate::models::Timesheet;
+
+pub const TIMESHEET_CSV_COLUMNS: [&str; 9] = [
+    "date",
+    "user_email",
+    "user_name",
+    "project_name",
+    "task_id",
+    "task_title",
+    "estimate_minutes",
+    "minutes",
+    "hours",
+];
+
+pub fn timesheet_to_csv(timesheet: &Timesheet) -> Result<Vec<u8>, csv::Error> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(TIMESHEET_CSV_COLUMNS)?;
+
+    for row in &timesheet.rows {
+        writer.write_record([
+            &row.date.to_string(),
+            &row.user_email,
+            &row.user_name,
+            &row.project_name,
+            &row.task_id.to_string(),
+            &row.task_title,
+            &row.estimate_minutes.map(|m| m.to_string()).unwrap_or_default(),
+            &row.minutes.to_string(),
+            &format!("{:.2}", row.minutes as f64 / 60.0),
+        ])?;
+    }
+
+    writer.into_inner().map_err(|e| e.into_error().into())
+}
@@ -0,0 +1,57 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+use crate::{
+    db::{across_organizations, purge_trash_before},
+    models::TrashPurgeSummary,
+    services::{enqueue_once_at, BackgroundJob, JobContext},
+    storage::{purge_blobs, BlobStore},
+};
+
+/// Permanently removes every organization's trash older than `retention_days`,
+/// including attachment blobs.
+pub async fn purge_expired_trash(
+    pool: &PgPool,
+    store: &dyn BlobStore,
+    retention_days: i64,
+) -> Result<TrashPurgeSummary, sqlx::Error> {
+    let cutoff = Utc::now() - chrono::Duration::days(retention_days);
+    let (summary, blob_keys) = across_organizations(purge_trash_before(pool, cutoff)).await?;
+    purge_blobs(store, &blob_keys).await;
+
+    Ok(summary)
+}
+
+/// Runs [`purge_expired_trash`], then queues itself again to run after
+/// `trash.purge_interval_minutes`, while the trash purge feature is on.
+#[derive(Serialize, Deserialize)]
+pub struct PurgeTrash;
+
+#[async_trait]
+impl BackgroundJob for PurgeTrash {
+    const KIND: &'static str = "purge_trash";
+    const KEEP_COMPLETED: bool = false;
+
+    async fn run(&self, ctx: &JobContext) -> anyhow::Result<()> {
+        if !ctx.config.features.trash_purge {
+            return Ok(());
+        }
+
+        let summary = purge_expired_trash(&ctx.pool, ctx.blob_store.as_ref(), ctx.config.trash_retention_days).await?;
+        if summary.teams + summary.projects + summary.tasks > 0 {
+            log::info!(
+                "Purged trash: {} teams, {} projects, {} tasks, {} attachment blobs",
+                summary.teams,
+                summary.projects,
+                summary.tasks,
+                summary.blobs
+            );
+        }
+
+        let next_run = Utc::now() + chrono::Duration::minutes(ctx.config.trash_purge_interval_minutes as i64);
+        enqueue_once_at(&ctx.pool, &PurgeTrash, next_run).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,76 @@
+use std::sync::Arc;
+
+use actix_web::web::Bytes;
+use async_trait::async_trait;
+
+use crate::config::StorageConfig;
+
+pub mod local;
+pub mod s3;
+
+pub use local::*;
+pub use s3::*;
+
+#[derive(Debug, thiserror::Error)]
+pub enum StorageError {
+    #[error("object not found: {0}")]
+    NotFound(String),
+    #[error("invalid storage key: {0}")]
+    InvalidKey(String),
+    #[error("storage I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("storage backend error: {0}")]
+    Backend(String),
+}
+
+/// Where attachment contents are kept. Keys are `/`-separated relative paths.
+#[async_trait]
+pub trait BlobStore: Send + Sync {
+    async fn put(&self, key: &str, content_type: &str, data: Bytes) -> Result<(), StorageError>;
+    async fn get(&self, key: &str) -> Result<Bytes, StorageError>;
+    /// Deleting a missing object is not an error.
+    async fn delete(&self, key: &str) -> Result<(), StorageError>;
+}
+
+/// Rejects keys that could escape the store's root.
+pub fn validate_key(key: &str) -> Result<(), StorageError> {
+    let bad_segment = key
+        .split('/')
+        .any(|segment| segment.is_empty() || segment == "." || segment == "..");
+    if key.is_empty() || key.starts_with('/') || key.contains('\\') || bad_segment {
+        return Err(StorageError::InvalidKey(key.to_string()));
+    }
+    Ok(())
+}
+
+/// Removes blobs whose attachment rows were deleted by a cascading delete.
+pub async fn purge_blobs(store: &dyn BlobStore, keys: &[String]) {
+    for key in keys {
+        if let Err(e) = store.delete(key).await {
+            log::warn!("Failed to delete blob {}: {}", key, e);
+        }
+    }
+}
+
+pub fn blob_store_from_config(config: &StorageConfig) -> Result<Arc<dyn BlobStore>, String> {
+    match config.backend.as_str() {
+        "local" => Ok(Arc::new(LocalBlobStore::new(&config.local_path))),
+        "s3" => {
+            let endpoint = config.s3_endpoint.clone().ok_or("S3_ENDPOINT must be set")?;
+            let bucket = config.s3_bucket.clone().ok_or("S3_BUCKET must be set")?;
+            let access_key = config.s3_access_key_id.clone().ok_or("S3_ACCESS_KEY_ID must be set")?;
+            let secret_key = config
+                .s3_secret_access_key
+                .clone()
+                .ok_or("S3_SECRET_ACCESS_KEY must be set")?;
+            Ok(Arc::new(S3BlobStore::new(
+                &endpoint,
+                &bucket,
+                &config.s3_region,
+                &access_key,
+                &secret_key,
+            )?))
+        }
+        other => Err(format!("Unknown STORAGE_BACKEND: {}", other)),
+    }
+}
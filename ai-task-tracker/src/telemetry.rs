@@ -0,0 +1,67 @@
+use std::io::IsTerminal;
+
+use opentelemetry::{trace::TracerProvider as _, KeyValue};
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::{runtime, trace::TracerProvider, Resource};
+use tracing_subscriber::{fmt::format::FmtSpan, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
+
+use crate::config::{LogConfig, LogFormat};
+
+/// Keeps span export running until [`Telemetry::shutdown`].
+pub struct Telemetry {
+    provider: Option<TracerProvider>,
+}
+
+impl Telemetry {
+    /// Sends the spans not exported yet. The provider blocks until its batch
+    /// exporter, a task on this runtime, is done, so it waits on another thread.
+    pub async fn shutdown(self) {
+        let Some(provider) = self.provider else { return };
+        match actix_web::rt::task::spawn_blocking(move || provider.shutdown()).await {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => eprintln!("Failed to flush exported spans: {}", e),
+            Err(e) => eprintln!("Failed to flush exported spans: {}", e),
+        }
+    }
+}
+
+/// Installs the global subscriber: text or JSON lines on stdout, filtered by
+/// `RUST_LOG` or else the configured level, plus OTLP export when an endpoint
+/// is configured. `log` records from dependencies are routed through it too.
+/// A line is written as each span closes, so every request (and, at `debug`,
+/// every repository call) is logged with its duration.
+pub fn init_telemetry(config: &LogConfig) -> Result<Telemetry, opentelemetry::trace::TraceError> {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(&config.level));
+    let output = match config.format {
+        LogFormat::Text => tracing_subscriber::fmt::layer()
+            .with_ansi(std::io::stdout().is_terminal())
+            .with_span_events(FmtSpan::CLOSE)
+            .boxed(),
+        LogFormat::Json => tracing_subscriber::fmt::layer()
+            .json()
+            .with_span_events(FmtSpan::CLOSE)
+            .boxed(),
+    };
+
+    let provider = match &config.otlp_endpoint {
+        Some(endpoint) => {
+            let exporter = SpanExporter::builder()
+                .with_http()
+                .with_endpoint(format!("{}/v1/traces", endpoint.trim_end_matches('/')))
+                .build()?;
+            Some(
+                TracerProvider::builder()
+                    .with_batch_exporter(exporter, runtime::Tokio)
+                    .with_resource(Resource::new([KeyValue::new("service.name", config.service_name.clone())]))
+                    .build(),
+            )
+        }
+        None => None,
+    };
+    let export = provider
+        .as_ref()
+        .map(|provider| tracing_opentelemetry::layer().with_tracer(provider.tracer("ai-task-tracker")));
+
+    tracing_subscriber::registry().with(filter).with(output).with(export).init();
+    Ok(Telemetry { provider })
+}
@@ -1,25 +1,50 @@
-use chrono::{Duration, Utc};
+use chrono::{DateTime, Duration, Utc};
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::config::JwtConfig;
+use crate::models::MfaPending;
+
+/// Minutes a user has to enter their second factor after the password step
+const MFA_TOKEN_EXPIRATION_MINUTES: i64 = 5;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Claims {
     pub sub: String, // user id
     pub email: String,
     pub role: String,
     pub exp: i64, // expiration timestamp
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iss: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aud: Option<String>,
+    /// Set for personal access tokens; sessions from a login are unrestricted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scopes: Option<Vec<String>>,
+    /// Set on the token issued between the password and second-factor steps of
+    /// a login; `AuthMiddleware` does not accept it as a session
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mfa_pending: Option<MfaPending>,
+    /// The admin acting as this user, on tokens from `/api/admin/users/{id}/impersonate`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub impersonator: Option<String>,
+    /// The organization the session works in. Tokens without one work in the
+    /// first organization the user joined; `AuthMiddleware` always sets it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub org_id: Option<Uuid>,
 }
 
+/// A session for the user, in `org_id` or else their first organization.
 pub fn create_jwt(
     user_id: Uuid,
     email: &str,
     role: &str,
-    secret: &str,
-    expiration_hours: i64,
+    org_id: Option<Uuid>,
+    jwt: &JwtConfig,
 ) -> Result<String, jsonwebtoken::errors::Error> {
     let expiration = Utc::now()
-        .checked_add_signed(Duration::hours(expiration_hours))
+        .checked_add_signed(Duration::hours(jwt.expiration_hours))
         .expect("valid timestamp")
         .timestamp();
 
@@ -28,20 +53,93 @@ pub fn create_jwt(
         email: email.to_string(),
         role: role.to_string(),
         exp: expiration,
+        iss: jwt.issuer.clone(),
+        aud: jwt.audience.clone(),
+        scopes: None,
+        mfa_pending: None,
+        impersonator: None,
+        org_id,
+    };
+
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(jwt.secret.as_bytes()),
+    )
+}
+
+/// A short-lived token for finishing a login that needs a second factor.
+pub fn create_mfa_token(
+    user_id: Uuid,
+    email: &str,
+    role: &str,
+    pending: MfaPending,
+    jwt: &JwtConfig,
+) -> Result<String, jsonwebtoken::errors::Error> {
+    let claims = Claims {
+        sub: user_id.to_string(),
+        email: email.to_string(),
+        role: role.to_string(),
+        exp: (Utc::now() + Duration::minutes(MFA_TOKEN_EXPIRATION_MINUTES)).timestamp(),
+        iss: jwt.issuer.clone(),
+        aud: jwt.audience.clone(),
+        scopes: None,
+        mfa_pending: Some(pending),
+        impersonator: None,
+        org_id: None,
     };
 
     encode(
         &Header::default(),
         &claims,
-        &EncodingKey::from_secret(secret.as_bytes()),
+        &EncodingKey::from_secret(jwt.secret.as_bytes()),
     )
 }
 
-pub fn verify_jwt(token: &str, secret: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
+/// A session for `user_id` in `org_id`, held by the admin `impersonator_id` and
+/// valid until `expires_at`.
+pub fn create_impersonation_jwt(
+    user_id: Uuid,
+    email: &str,
+    role: &str,
+    impersonator_id: Uuid,
+    org_id: Uuid,
+    expires_at: DateTime<Utc>,
+    jwt: &JwtConfig,
+) -> Result<String, jsonwebtoken::errors::Error> {
+    let claims = Claims {
+        sub: user_id.to_string(),
+        email: email.to_string(),
+        role: role.to_string(),
+        exp: expires_at.timestamp(),
+        iss: jwt.issuer.clone(),
+        aud: jwt.audience.clone(),
+        scopes: None,
+        mfa_pending: None,
+        impersonator: Some(impersonator_id.to_string()),
+        org_id: Some(org_id),
+    };
+
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(jwt.secret.as_bytes()),
+    )
+}
+
+pub fn verify_jwt(token: &str, jwt: &JwtConfig) -> Result<Claims, jsonwebtoken::errors::Error> {
+    let mut validation = Validation::default();
+    if let Some(issuer) = &jwt.issuer {
+        validation.set_issuer(&[issuer]);
+    }
+    if let Some(audience) = &jwt.audience {
+        validation.set_audience(&[audience]);
+    }
+
     let token_data = decode::<Claims>(
         token,
-        &DecodingKey::from_secret(secret.as_bytes()),
-        &Validation::default(),
+        &DecodingKey::from_secret(jwt.secret.as_bytes()),
+        &validation,
     )?;
 
     Ok(token_data.claims)
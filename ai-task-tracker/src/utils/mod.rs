@@ -1,5 +1,9 @@
 pub mod password;
 pub mod jwt;
+pub mod token;
+pub mod totp;
 
 pub use password::*;
 pub use jwt::*;
+pub use token::*;
+pub use totp::*;
@@ -1,7 +1,8 @@
-use bcrypt::{hash, verify, DEFAULT_COST};
+use bcrypt::{hash, verify};
 
-pub fn hash_password(password: &str) -> Result<String, bcrypt::BcryptError> {
-    hash(password, DEFAULT_COST)
+/// Hashes with the given bcrypt work factor (`Config::bcrypt_cost`).
+pub fn hash_password(password: &str, cost: u32) -> Result<String, bcrypt::BcryptError> {
+    hash(password, cost)
 }
 
 pub fn verify_password(password: &str, hash: &str) -> Result<bool, bcrypt::BcryptError> {
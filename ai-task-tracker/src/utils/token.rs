@@ -0,0 +1,26 @@
+use rand::{distributions::Alphanumeric, Rng};
+use sha2::{Digest, Sha256};
+
+/// Personal access tokens start with this, which is how `AuthMiddleware` tells them from JWTs.
+pub const API_TOKEN_PREFIX: &str = "pat_";
+const API_TOKEN_SECRET_LENGTH: usize = 40;
+
+/// Generates a random URL-safe alphanumeric secret of the given length.
+pub fn generate_token(len: usize) -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(len)
+        .map(char::from)
+        .collect()
+}
+
+/// Generates a new personal access token, `pat_` followed by a random secret.
+pub fn generate_api_token() -> String {
+    format!("{}{}", API_TOKEN_PREFIX, generate_token(API_TOKEN_SECRET_LENGTH))
+}
+
+/// The hex SHA-256 digest under which a token is stored. Tokens are long random
+/// secrets, so a fast hash is enough.
+pub fn hash_api_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
@@ -0,0 +1,233 @@
+mod common;
+
+use actix_web::{
+    http::{Method, StatusCode},
+    test,
+};
+use serde_json::json;
+
+use ai_task_tracker::{build_app, utils::verify_jwt};
+use common::{
+    delete, get, id_of, post, promote_to_admin, promote_to_platform_admin, put, register_user, send, test_jwt_config,
+    TestContext, TEST_PASSWORD,
+};
+
+#[actix_web::test]
+async fn role_changes_apply_to_open_sessions() {
+    let Some(ctx) = TestContext::new().await else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+    let admin = register_user(&app, "admin@example.com").await;
+    let admin_token = promote_to_admin(&ctx, &admin).await;
+    let dev = register_user(&app, "dev@example.com").await;
+
+    let (status, _) = get(&app, "/api/admin/impersonations", &dev.token).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let role_uri = format!("/api/admin/users/{}/role", dev.id);
+    let (status, _) = put(&app, &role_uri, &dev.token, json!({"role": "admin"})).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, user) = put(&app, &role_uri, &admin_token, json!({"role": "admin"})).await;
+    assert_eq!(status, StatusCode::OK, "{}", user);
+    assert_eq!(user["role"], "admin");
+
+    // The token issued at registration still says member
+    let (status, _) = get(&app, "/api/admin/impersonations", &dev.token).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = put(&app, &role_uri, &admin_token, json!({"role": "owner"})).await;
+    assert!(status.is_client_error());
+    let own_uri = format!("/api/admin/users/{}/role", admin.id);
+    let (status, _) = put(&app, &own_uri, &admin_token, json!({"role": "member"})).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn deactivated_users_are_locked_out_until_reactivated() {
+    let Some(ctx) = TestContext::new().await else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+    let admin = register_user(&app, "admin@example.com").await;
+    let admin_token = promote_to_admin(&ctx, &admin).await;
+    let dev = register_user(&app, "dev@example.com").await;
+    let (_, created) = post(&app, "/api/tokens", &dev.token, json!({"name": "ci", "scopes": ["read:tasks"]})).await;
+    let api_token = created["token"].as_str().expect("api token").to_string();
+    let (_, calendar) = get(&app, "/api/users/me/calendar", &dev.token).await;
+    let feed_uri = calendar["url"].as_str().expect("feed url").to_string();
+
+    let (status, user) = post(&app, &format!("/api/admin/users/{}/deactivate", dev.id), &admin_token, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(user["deactivated_at"].is_string());
+
+    let (status, _) = get(&app, "/api/users/me", &dev.token).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = get(&app, "/api/tasks", &api_token).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let login = json!({"email": "dev@example.com", "password": TEST_PASSWORD});
+    let (status, body) = send(&app, Method::POST, "/api/auth/login", None, Some(login.clone())).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["error"], "Account is deactivated");
+    let (status, _) = send(&app, Method::GET, &feed_uri, None, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = post(&app, &format!("/api/admin/users/{}/deactivate", admin.id), &admin_token, json!({})).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, user) = post(&app, &format!("/api/admin/users/{}/reactivate", dev.id), &admin_token, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(user["deactivated_at"].is_null());
+    let (status, _) = get(&app, "/api/users/me", &dev.token).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = get(&app, "/api/tasks", &api_token).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(&app, Method::POST, "/api/auth/login", None, Some(login)).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(&app, Method::GET, &feed_uri, None, None).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn a_forced_password_reset_blocks_everything_else() {
+    let Some(ctx) = TestContext::new().await else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+    let admin = register_user(&app, "admin@example.com").await;
+    let admin_token = promote_to_admin(&ctx, &admin).await;
+    let dev = register_user(&app, "dev@example.com").await;
+
+    let reset_uri = format!("/api/admin/users/{}/require-password-reset", dev.id);
+    let (status, user) = post(&app, &reset_uri, &admin_token, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(user["password_reset_required"], true);
+
+    let (status, body) = get(&app, "/api/projects", &dev.token).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body, "Password reset required");
+    let (status, _) = get(&app, "/api/users/me", &dev.token).await;
+    assert_eq!(status, StatusCode::OK);
+    // Only their own password
+    let (status, _) = put(&app, &format!("/api/users/{}", admin.id), &dev.token, json!({"password": "hijacked"})).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let user_uri = format!("/api/users/{}", dev.id);
+    let (status, user) = put(&app, &user_uri, &dev.token, json!({"password": "a-new-password"})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(user["password_reset_required"], false);
+    let (status, _) = get(&app, "/api/projects", &dev.token).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn impersonation_is_audited_and_limited() {
+    let Some(ctx) = TestContext::new().await else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+    let admin = register_user(&app, "admin@example.com").await;
+    let admin_token = promote_to_admin(&ctx, &admin).await;
+    let dev = register_user(&app, "dev@example.com").await;
+    let (_, project) = post(&app, "/api/projects", &dev.token, json!({"name": "Launch"})).await;
+
+    let impersonate_uri = format!("/api/admin/users/{}/impersonate", dev.id);
+    let (status, _) = post(&app, &impersonate_uri, &admin_token, json!({"reason": "  "})).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = post(&app, &impersonate_uri, &dev.token, json!({"reason": "SUP-1"})).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let own_uri = format!("/api/admin/users/{}/impersonate", admin.id);
+    let (status, _) = post(&app, &own_uri, &admin_token, json!({"reason": "SUP-1"})).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = post(&app, &impersonate_uri, &admin_token, json!({"reason": "SUP-1 missing project"})).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+    assert_eq!(body["user"]["id"], dev.id.to_string());
+    assert_eq!(body["impersonation"]["admin_id"], admin.id.to_string());
+    let token = body["token"].as_str().expect("token").to_string();
+
+    // The session sees what the user sees
+    let (status, me) = get(&app, "/api/users/me", &token).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(me["email"], "dev@example.com");
+    let (status, _) = get(&app, &format!("/api/projects/{}", project["id"].as_str().unwrap()), &token).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // but cannot take over the account
+    let (status, _) = put(&app, &format!("/api/users/{}", dev.id), &token, json!({"password": "hijacked"})).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = post(&app, "/api/tokens", &token, json!({"name": "backdoor", "scopes": ["admin"]})).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, trail) = get(&app, &format!("/api/admin/impersonations?user_id={}", dev.id), &admin_token).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(trail.as_array().map(Vec::len), Some(1));
+    assert_eq!(trail[0]["reason"], "SUP-1 missing project");
+    let (status, _) = get(&app, "/api/admin/impersonations", &dev.token).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[actix_web::test]
+async fn admins_only_manage_users_of_their_organization() {
+    let Some(ctx) = TestContext::new().await else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+    let admin = register_user(&app, "admin@example.com").await;
+    let admin_token = promote_to_admin(&ctx, &admin).await;
+    let dev = register_user(&app, "dev@example.com").await;
+
+    let (_, acme) = post(&app, "/api/admin/organizations", &admin_token, json!({"name": "Acme", "slug": "acme"})).await;
+    let switch_uri = format!("/api/organizations/{}/switch", id_of(&acme));
+    let (_, session) = post(&app, &switch_uri, &admin_token, json!({})).await;
+    let acme_token = session["token"].as_str().expect("token").to_string();
+
+    // dev is not in Acme, so an admin working there cannot touch them
+    let role_uri = format!("/api/admin/users/{}/role", dev.id);
+    let (status, _) = put(&app, &role_uri, &acme_token, json!({"role": "admin"})).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    for action in ["deactivate", "reactivate", "require-password-reset"] {
+        let (status, _) = post(&app, &format!("/api/admin/users/{}/{}", dev.id, action), &acme_token, json!({})).await;
+        assert_eq!(status, StatusCode::NOT_FOUND, "{}", action);
+    }
+    let impersonate_uri = format!("/api/admin/users/{}/impersonate", dev.id);
+    let (status, _) = post(&app, &impersonate_uri, &acme_token, json!({"reason": "SUP-2"})).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (_, user) = get(&app, "/api/users/me", &dev.token).await;
+    assert_eq!(user["role"], "member");
+    assert!(user["deactivated_at"].is_null());
+
+    // Impersonating from the default organization stays there
+    let (status, body) = post(&app, &impersonate_uri, &admin_token, json!({"reason": "SUP-2"})).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+    let claims = verify_jwt(body["token"].as_str().expect("token"), &test_jwt_config()).expect("claims");
+    let (_, organizations) = get(&app, "/api/organizations", &dev.token).await;
+    assert_eq!(claims.org_id.map(|id| id.to_string()), organizations[0]["id"].as_str().map(str::to_string));
+    let (_, trail) = get(&app, "/api/admin/impersonations", &acme_token).await;
+    assert_eq!(trail.as_array().map(Vec::len), Some(0));
+    let (_, trail) = get(&app, "/api/admin/impersonations", &admin_token).await;
+    assert_eq!(trail.as_array().map(Vec::len), Some(1));
+}
+
+#[actix_web::test]
+async fn admins_do_not_reach_other_organizations_tokens_or_shared_settings() {
+    let Some(ctx) = TestContext::new().await else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+    let admin = register_user(&app, "admin@example.com").await;
+    let admin_token = promote_to_admin(&ctx, &admin).await;
+    let platform = register_user(&app, "platform@example.com").await;
+    let platform_token = promote_to_platform_admin(&ctx, &platform).await;
+    let dev = register_user(&app, "dev@example.com").await;
+
+    let (_, acme) = post(&app, "/api/admin/organizations", &admin_token, json!({"name": "Acme", "slug": "acme"})).await;
+    let switch_uri = format!("/api/organizations/{}/switch", id_of(&acme));
+    let (_, session) = post(&app, &switch_uri, &admin_token, json!({})).await;
+    let acme_token = session["token"].as_str().expect("token").to_string();
+
+    // dev's token was issued in the default organization
+    let (_, created) = post(&app, "/api/tokens", &dev.token, json!({"name": "ci", "scopes": ["read:tasks"]})).await;
+    let token_uri = format!("/api/tokens/{}", id_of(&created));
+    let (status, _) = delete(&app, &token_uri, &acme_token).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = delete(&app, &token_uri, &admin_token).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    // The two-factor policy applies to every organization
+    let policy = json!({"required_roles": ["admin"]});
+    let (status, _) = put(&app, "/api/mfa/policy", &admin_token, policy.clone()).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = get(&app, "/api/mfa/policy", &admin_token).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, updated) = put(&app, "/api/mfa/policy", &platform_token, policy).await;
+    assert_eq!((status, updated["required_roles"].clone()), (StatusCode::OK, json!(["admin"])));
+}
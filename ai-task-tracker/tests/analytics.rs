@@ -0,0 +1,128 @@
+mod common;
+
+use actix_web::{http::StatusCode, test};
+use chrono::{DateTime, Datelike, Duration, Utc};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use ai_task_tracker::{build_app, db};
+use common::{get, id_of, post, put, register_user, TestContext};
+
+/// Moves a task's creation, or one of its status changes, back in time.
+async fn backdate(ctx: &TestContext, task_id: Uuid, status: &str, at: DateTime<Utc>) {
+    let activity = sqlx::query("UPDATE task_activity SET created_at = $3 WHERE task_id = $1 AND new_value = $2")
+        .bind(task_id)
+        .bind(status)
+        .bind(at);
+    db::across_organizations(activity.execute(ctx.pool())).await.unwrap();
+    if status == "todo" {
+        let task = sqlx::query("UPDATE tasks SET created_at = $2 WHERE id = $1").bind(task_id).bind(at);
+        db::across_organizations(task.execute(ctx.pool())).await.unwrap();
+    }
+}
+
+/// `(key, field)` pairs of a bucketed series, for comparing whole charts at once.
+fn series<'a>(rows: &'a Value, key: &str, field: &str) -> Vec<(&'a str, i64)> {
+    rows.as_array()
+        .unwrap()
+        .iter()
+        .map(|row| (row[key].as_str().unwrap(), row[field].as_i64().unwrap()))
+        .collect()
+}
+
+#[actix_web::test]
+async fn analytics_are_computed_from_task_activity() {
+    let Some(ctx) = TestContext::new().await else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+    let dev = register_user(&app, "dev@example.com").await;
+    let outsider = register_user(&app, "outsider@example.com").await;
+    let move_outsider = sqlx::query(
+        "WITH others AS (INSERT INTO organizations (name, slug) VALUES ('Others', 'others') RETURNING id)
+         UPDATE organization_members SET organization_id = (SELECT id FROM others) WHERE user_id = $1",
+    )
+    .bind(outsider.id);
+    db::across_organizations(move_outsider.execute(ctx.pool())).await.unwrap();
+    let (_, project) = post(&app, "/api/projects", &dev.token, json!({"name": "Launch"})).await;
+    let project_id = id_of(&project);
+
+    let now = Utc::now();
+    let mut tasks = Vec::new();
+    for (title, priority, assignee, due) in [
+        ("Overdue", "high", Some(dev.id), Some(now - Duration::days(1))),
+        ("Due soon", "urgent", Some(dev.id), Some(now + Duration::days(3))),
+        ("Unassigned", "low", None, None),
+        ("Shipped", "medium", Some(dev.id), None),
+    ] {
+        let body = json!({
+            "project_id": project_id, "title": title, "priority": priority, "assignee_id": assignee, "due_date": due,
+        });
+        let (status, task) = post(&app, "/api/tasks", &dev.token, body).await;
+        assert_eq!(status, StatusCode::CREATED, "{}", task);
+        tasks.push(id_of(&task));
+    }
+
+    // "Shipped" was created on a Sunday, started on the Monday and done 48 hours later
+    let shipped = tasks[3];
+    let uri = format!("/api/tasks/{}", shipped);
+    for status in ["in_progress", "done"] {
+        let (status, task) = put(&app, &uri, &dev.token, json!({"status": status})).await;
+        assert_eq!(status, StatusCode::OK, "{}", task);
+    }
+    let today = now.date_naive();
+    let monday = today - Duration::days(today.weekday().num_days_from_monday() as i64 + 21);
+    let started = monday.and_hms_opt(9, 0, 0).unwrap().and_utc();
+    backdate(&ctx, shipped, "todo", started - Duration::days(1)).await;
+    backdate(&ctx, shipped, "in_progress", started).await;
+    backdate(&ctx, shipped, "done", started + Duration::hours(48)).await;
+
+    let uri = format!("/api/analytics/workload?project_id={}", project_id);
+    let (status, workload) = get(&app, &uri, &dev.token).await;
+    assert_eq!(status, StatusCode::OK, "{}", workload);
+    let load: Vec<(Value, i64, i64, i64, i64)> = workload
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| {
+            let count = |priority: &str| r[priority].as_i64().unwrap();
+            (r["assignee_id"].clone(), count("low"), count("high"), count("urgent"), count("total"))
+        })
+        .collect();
+    assert_eq!(load, vec![(json!(dev.id), 0, 1, 1, 2), (Value::Null, 1, 0, 0, 1)]);
+
+    let (_, overdue) = get(&app, &format!("/api/analytics/overdue?project_id={}", project_id), &dev.token).await;
+    assert_eq!(overdue["total_overdue"], 1, "{}", overdue);
+    let by_assignee = &overdue["by_assignee"];
+    assert_eq!(by_assignee.as_array().unwrap().len(), 1, "{}", overdue);
+    assert_eq!((&by_assignee[0]["overdue"], &by_assignee[0]["due_this_week"]), (&json!(1), &json!(1)));
+
+    let (last_week, week) = ((monday - Duration::days(7)).to_string(), monday.to_string());
+    let range = format!("project_id={}&from={}&to={}", project_id, last_week, monday + Duration::days(6));
+    let (_, throughput) = get(&app, &format!("/api/analytics/throughput?{}", range), &dev.token).await;
+    assert_eq!(series(&throughput, "week_start", "completed"), vec![(last_week.as_str(), 0), (week.as_str(), 1)]);
+
+    let (_, cycle_time) = get(&app, &format!("/api/analytics/cycle-time?{}", range), &dev.token).await;
+    assert_eq!(series(&cycle_time, "week_start", "completed"), vec![(last_week.as_str(), 0), (week.as_str(), 1)]);
+    assert!(cycle_time[0]["avg_hours"].is_null());
+    assert_eq!((&cycle_time[1]["avg_hours"], &cycle_time[1]["median_hours"]), (&json!(48.0), &json!(48.0)));
+
+    // Tasks created today are outside the burndown window; "Shipped" is in scope from Sunday on
+    let (from, to) = (monday - Duration::days(2), monday + Duration::days(2));
+    let uri = format!("/api/analytics/burndown/{}?from={}&to={}", project_id, from, to);
+    let (status, burndown) = get(&app, &uri, &dev.token).await;
+    assert_eq!(status, StatusCode::OK, "{}", burndown);
+    let days: Vec<String> = (0..5).map(|d| (from + Duration::days(d)).to_string()).collect();
+    let remaining: Vec<(&str, i64)> = days.iter().map(String::as_str).zip([0, 1, 1, 1, 0]).collect();
+    assert_eq!(series(&burndown, "date", "remaining"), remaining);
+    assert_eq!(burndown[4]["completed"], 1);
+
+    let uri = format!("/api/analytics/throughput?from={}&to={}", today, monday);
+    let (status, _) = get(&app, &uri, &dev.token).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "an inverted range was accepted");
+
+    // Another organization's figures stay out of the caller's charts
+    let (_, workload) = get(&app, "/api/analytics/workload", &outsider.token).await;
+    assert_eq!(workload, json!([]));
+    let uri = format!("/api/analytics/burndown/{}?from={}&to={}", project_id, from, to);
+    let (_, burndown) = get(&app, &uri, &outsider.token).await;
+    assert!(burndown.as_array().unwrap().iter().all(|point| point["total"] == 0), "{}", burndown);
+}
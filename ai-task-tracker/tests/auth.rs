@@ -0,0 +1,117 @@
+mod common;
+
+use actix_web::{
+    http::{Method, StatusCode},
+    test,
+};
+use serde_json::json;
+
+use ai_task_tracker::{build_app, utils::totp_at};
+use common::{get, post, register_user, send, TestContext, TEST_PASSWORD};
+
+#[actix_web::test]
+async fn register_returns_a_working_token() {
+    let Some(ctx) = TestContext::new().await else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+
+    let user = register_user(&app, "ada@example.com").await;
+
+    let (status, me) = get(&app, "/api/users/me", &user.token).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(me["email"], "ada@example.com");
+    assert_eq!(me["role"], "member");
+    assert!(me.get("password_hash").is_none(), "password hash leaked: {}", me);
+}
+
+#[actix_web::test]
+async fn registering_an_email_twice_fails() {
+    let Some(ctx) = TestContext::new().await else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+
+    register_user(&app, "ada@example.com").await;
+    let body = json!({"email": "ada@example.com", "password": TEST_PASSWORD, "full_name": "Ada"});
+    let (status, _) = send(&app, Method::POST, "/api/auth/register", None, Some(body)).await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn login_checks_the_password() {
+    let Some(ctx) = TestContext::new().await else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+    register_user(&app, "ada@example.com").await;
+
+    let wrong = json!({"email": "ada@example.com", "password": "not-the-password"});
+    let (status, _) = send(&app, Method::POST, "/api/auth/login", None, Some(wrong)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let right = json!({"email": "ada@example.com", "password": TEST_PASSWORD});
+    let (status, body) = send(&app, Method::POST, "/api/auth/login", None, Some(right)).await;
+    assert_eq!(status, StatusCode::OK);
+    let token = body["token"].as_str().expect("token");
+
+    let (status, _) = get(&app, "/api/users/me", token).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn protected_routes_need_a_valid_token() {
+    let Some(ctx) = TestContext::new().await else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+
+    let (status, _) = send(&app, Method::GET, "/api/users/me", None, None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, _) = get(&app, "/api/users/me", "not-a-jwt").await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[actix_web::test]
+async fn two_factor_login_takes_each_code_once() {
+    let Some(ctx) = TestContext::new().await else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+    let user = register_user(&app, "ada@example.com").await;
+
+    let (status, enrollment) = post(&app, "/api/mfa/totp", &user.token, json!({})).await;
+    assert_eq!(status, StatusCode::OK, "{}", enrollment);
+    let secret = enrollment["secret"].as_str().expect("secret");
+    let now = chrono::Utc::now().timestamp();
+    let code = totp_at(secret, now).unwrap();
+    let (status, confirmed) = post(&app, "/api/mfa/totp/confirm", &user.token, json!({"code": code})).await;
+    assert_eq!(status, StatusCode::OK, "{}", confirmed);
+    let recovery_code = confirmed["recovery_codes"][0].as_str().expect("recovery code").to_string();
+
+    let app = &app;
+    let login = move || async move {
+        let body = json!({"email": "ada@example.com", "password": TEST_PASSWORD});
+        let (status, challenge) = send(&app, Method::POST, "/api/auth/login", None, Some(body)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(challenge["mfa_required"], "verify");
+        assert!(challenge.get("token").is_none(), "session issued before the second factor");
+        challenge["mfa_token"].as_str().expect("mfa token").to_string()
+    };
+    let verify = move |mfa_token: String, code: String| async move {
+        let body = json!({"mfa_token": mfa_token, "code": code});
+        send(&app, Method::POST, "/api/auth/mfa/verify", None, Some(body)).await
+    };
+
+    // The step used to confirm enrollment is spent; the next one, within the allowed drift, is not
+    let (status, _) = verify(login().await, code).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let next_code = totp_at(secret, now + 30).unwrap();
+    let (status, session) = verify(login().await, next_code.clone()).await;
+    assert_eq!(status, StatusCode::OK, "{}", session);
+    let (status, _) = get(&app, "/api/users/me", session["token"].as_str().expect("token")).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = verify(login().await, next_code).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED, "a code was accepted twice");
+
+    let (status, _) = verify(login().await, recovery_code.to_uppercase()).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = verify(login().await, recovery_code).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED, "a recovery code was accepted twice");
+
+    // The session token cannot stand in for the login challenge
+    let (status, _) = verify(user.token.clone(), totp_at(secret, now - 30).unwrap()).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
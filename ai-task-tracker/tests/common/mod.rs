@@ -0,0 +1,319 @@
+//! Shared harness for the HTTP integration tests. Each test gets its own
+//! database, created on the server named by `TEST_DATABASE_URL` and dropped
+//! afterwards; without that variable the tests are skipped.
+
+// Each test binary uses a different subset of the helpers
+#![allow(dead_code)]
+
+use std::{str::FromStr, sync::Arc, time::Duration};
+
+use actix_http::Request;
+use actix_web::{
+    body::MessageBody,
+    dev::{Service, ServiceResponse},
+    http::{Method, StatusCode},
+    test,
+};
+use serde_json::{json, Value};
+use sqlx::{
+    postgres::{PgConnectOptions, PgPoolOptions},
+    ConnectOptions, Connection, Executor, PgPool,
+};
+use uuid::Uuid;
+
+use ai_task_tracker::{
+    config::{Config, DatabaseConfig, FeatureConfig, JobsConfig, JwtConfig, LogConfig, LogFormat, StorageConfig},
+    db::{self, Repositories},
+    models::CreateUserRequest,
+    services::Metrics,
+    storage::LocalBlobStore,
+    utils::create_jwt,
+    AppState,
+};
+
+pub const TEST_JWT_SECRET: &str = "integration-test-secret";
+pub const TEST_PASSWORD: &str = "password123";
+
+/// A migrated database and the application state pointing at it.
+pub struct TestContext {
+    pub state: AppState,
+    server: PgConnectOptions,
+    database: String,
+    _storage_dir: tempfile::TempDir,
+}
+
+impl TestContext {
+    /// Creates and migrates a fresh database, or returns `None` (after saying
+    /// so) when `TEST_DATABASE_URL` is not set.
+    pub async fn new() -> Option<Self> {
+        let Ok(url) = std::env::var("TEST_DATABASE_URL") else {
+            eprintln!("TEST_DATABASE_URL is not set; skipping");
+            return None;
+        };
+        let server = PgConnectOptions::from_str(&url).expect("TEST_DATABASE_URL is a valid Postgres URL");
+        let database = format!("tracker_test_{}", Uuid::new_v4().simple());
+
+        let mut conn = server.connect().await.expect("connect to the test server");
+        conn.execute(format!(r#"CREATE DATABASE "{}""#, database).as_str())
+            .await
+            .expect("create the test database");
+        conn.close().await.ok();
+
+        let pool = db::pool_options()
+            .max_connections(5)
+            .connect_with(server.clone().database(&database))
+            .await
+            .expect("connect to the test database");
+        db::run_migrations(&pool).await.expect("migrate the test database");
+
+        let storage_dir = tempfile::tempdir().expect("create attachment directory");
+        let state = test_state(pool.clone(), Repositories::postgres(pool), url, &storage_dir);
+
+        Some(TestContext {
+            state,
+            server,
+            database,
+            _storage_dir: storage_dir,
+        })
+    }
+
+    pub fn pool(&self) -> &PgPool {
+        &self.state.pool
+    }
+}
+
+pub fn test_jwt_config() -> JwtConfig {
+    JwtConfig {
+        secret: TEST_JWT_SECRET.to_string(),
+        expiration_hours: 1,
+        issuer: None,
+        audience: None,
+    }
+}
+
+/// Application state whose task, project, team and user repositories live in
+/// memory, so it needs no database. Handlers that still query the pool
+/// directly, such as project trash, import and export or the calendar feed,
+/// fail with a server error.
+pub struct MemoryContext {
+    pub state: AppState,
+    /// The organization `user` adds everyone to
+    pub org_id: Uuid,
+    _storage_dir: tempfile::TempDir,
+}
+
+impl MemoryContext {
+    pub fn new() -> Self {
+        // The pool never connects as long as nothing uses it
+        let url = "postgres://unused@127.0.0.1/unused".to_string();
+        let pool = PgPoolOptions::new().connect_lazy(&url).expect("lazy pool");
+        let storage_dir = tempfile::tempdir().expect("create attachment directory");
+        let state = test_state(pool, Repositories::in_memory(), url, &storage_dir);
+
+        MemoryContext {
+            state,
+            org_id: Uuid::new_v4(),
+            _storage_dir: storage_dir,
+        }
+    }
+
+    /// Adds a user straight to the repository and signs a session token for them.
+    pub async fn user(&self, email: &str) -> TestUser {
+        let req = CreateUserRequest {
+            email: email.to_string(),
+            password: TEST_PASSWORD.to_string(),
+            full_name: email.to_string(),
+        };
+        let users = &self.state.repositories.users;
+        let user = users.create_user(&req, "not-a-real-hash", self.org_id).await.expect("create user");
+
+        TestUser {
+            id: user.id,
+            email: email.to_string(),
+            token: create_jwt(user.id, email, &user.role, None, &test_jwt_config()).expect("session token"),
+        }
+    }
+}
+
+fn test_state(
+    pool: PgPool,
+    repositories: Repositories,
+    database_url: String,
+    storage_dir: &tempfile::TempDir,
+) -> AppState {
+    let config = Config {
+        host: "127.0.0.1".to_string(),
+        port: 0,
+        database: DatabaseConfig {
+            url: database_url,
+            max_connections: 5,
+            min_connections: 0,
+            acquire_timeout: Duration::from_secs(30),
+            idle_timeout: None,
+        },
+        jwt: test_jwt_config(),
+        bcrypt_cost: 4,
+        cors_allowed_origins: vec!["*".to_string()],
+        log: LogConfig {
+            format: LogFormat::Text,
+            level: "info".to_string(),
+            otlp_endpoint: None,
+            service_name: "ai-task-tracker".to_string(),
+        },
+        features: FeatureConfig {
+            registration: true,
+            api_docs: true,
+            trash_purge: false,
+        },
+        storage: StorageConfig {
+            backend: "local".to_string(),
+            local_path: storage_dir.path().to_string_lossy().into_owned(),
+            s3_endpoint: None,
+            s3_bucket: None,
+            s3_region: "us-east-1".to_string(),
+            s3_access_key_id: None,
+            s3_secret_access_key: None,
+            max_attachment_bytes: 1024 * 1024,
+        },
+        trash_retention_days: 30,
+        trash_purge_interval_minutes: 60,
+        jobs: JobsConfig {
+            workers: 0,
+            poll_interval: Duration::from_secs(1),
+        },
+        oidc: None,
+        imap: None,
+    };
+
+    AppState {
+        pool,
+        repositories,
+        blob_store: Arc::new(LocalBlobStore::new(&config.storage.local_path)),
+        config,
+        oidc_client: None,
+        metrics: Arc::new(Metrics::new()),
+    }
+}
+
+impl Drop for TestContext {
+    fn drop(&mut self) {
+        // Drop runs inside the test's runtime, so the cleanup gets one of its own
+        let server = self.server.clone();
+        let database = std::mem::take(&mut self.database);
+        std::thread::spawn(move || {
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .expect("build cleanup runtime")
+                .block_on(async {
+                    if let Ok(mut conn) = server.connect().await {
+                        let drop = format!(r#"DROP DATABASE IF EXISTS "{}" WITH (FORCE)"#, database);
+                        conn.execute(drop.as_str()).await.ok();
+                    }
+                });
+        })
+        .join()
+        .ok();
+    }
+}
+
+/// The initialized application, as returned by `test::init_service`.
+pub trait TestService<B>: Service<Request, Response = ServiceResponse<B>, Error = actix_web::Error> {}
+
+impl<S, B> TestService<B> for S where S: Service<Request, Response = ServiceResponse<B>, Error = actix_web::Error> {}
+
+/// Sends a request and returns the status with the JSON body (`Null` when empty).
+pub async fn send<B: MessageBody>(
+    app: &impl TestService<B>,
+    method: Method,
+    uri: &str,
+    token: Option<&str>,
+    body: Option<Value>,
+) -> (StatusCode, Value) {
+    let mut req = test::TestRequest::default().method(method).uri(uri);
+    if let Some(token) = token {
+        req = req.insert_header(("Authorization", format!("Bearer {}", token)));
+    }
+    if let Some(body) = body {
+        req = req.set_json(body);
+    }
+
+    // Middleware rejections (such as a missing token) come back as errors
+    let (status, bytes) = match test::try_call_service(app, req.to_request()).await {
+        Ok(response) => (response.status(), test::read_body(response).await),
+        Err(e) => {
+            let response = e.error_response();
+            let status = response.status();
+            (status, actix_web::body::to_bytes(response.into_body()).await.unwrap_or_default())
+        }
+    };
+    let body = if bytes.is_empty() {
+        Value::Null
+    } else {
+        serde_json::from_slice(&bytes).unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&bytes).into_owned()))
+    };
+    (status, body)
+}
+
+pub async fn get<B: MessageBody>(app: &impl TestService<B>, uri: &str, token: &str) -> (StatusCode, Value) {
+    send(app, Method::GET, uri, Some(token), None).await
+}
+
+pub async fn post<B: MessageBody>(app: &impl TestService<B>, uri: &str, token: &str, body: Value) -> (StatusCode, Value) {
+    send(app, Method::POST, uri, Some(token), Some(body)).await
+}
+
+pub async fn put<B: MessageBody>(app: &impl TestService<B>, uri: &str, token: &str, body: Value) -> (StatusCode, Value) {
+    send(app, Method::PUT, uri, Some(token), Some(body)).await
+}
+
+pub async fn delete<B: MessageBody>(app: &impl TestService<B>, uri: &str, token: &str) -> (StatusCode, Value) {
+    send(app, Method::DELETE, uri, Some(token), None).await
+}
+
+/// A registered user and a session token for them.
+pub struct TestUser {
+    pub id: Uuid,
+    pub email: String,
+    pub token: String,
+}
+
+/// Registers a user with `TEST_PASSWORD` through the API.
+pub async fn register_user<B: MessageBody>(app: &impl TestService<B>, email: &str) -> TestUser {
+    let body = json!({"email": email, "password": TEST_PASSWORD, "full_name": email});
+    let (status, body) = send(app, Method::POST, "/api/auth/register", None, Some(body)).await;
+    assert_eq!(status, StatusCode::CREATED, "register {}: {}", email, body);
+
+    TestUser {
+        id: body["user"]["id"].as_str().and_then(|id| id.parse().ok()).expect("user id"),
+        email: email.to_string(),
+        token: body["token"].as_str().expect("token").to_string(),
+    }
+}
+
+/// Makes the user an admin and returns a token carrying the new role.
+pub async fn promote_to_admin(ctx: &TestContext, user: &TestUser) -> String {
+    sqlx::query("UPDATE users SET role = 'admin' WHERE id = $1")
+        .bind(user.id)
+        .execute(ctx.pool())
+        .await
+        .expect("promote user");
+    create_jwt(user.id, &user.email, "admin", None, &test_jwt_config()).expect("admin token")
+}
+
+/// Promotes `user` to an admin who also looks after the job queue and the
+/// two-factor policy.
+pub async fn promote_to_platform_admin(ctx: &TestContext, user: &TestUser) -> String {
+    let token = promote_to_admin(ctx, user).await;
+    sqlx::query("UPDATE users SET is_platform_admin = TRUE WHERE id = $1")
+        .bind(user.id)
+        .execute(ctx.pool())
+        .await
+        .expect("promote user");
+    token
+}
+
+/// Parses the `id` of a created resource.
+pub fn id_of(body: &Value) -> Uuid {
+    body["id"].as_str().and_then(|id| id.parse().ok()).unwrap_or_else(|| panic!("no id in {}", body))
+}
@@ -0,0 +1,292 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use actix_web::{http::StatusCode, test};
+use serde_json::json;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+
+use ai_task_tracker::{
+    build_app,
+    config::ImapConfig,
+    db,
+    services::{
+        enqueue_once_at, parse_email, poll_mailbox, strip_quoted_reply, JobContext, JobRegistry, JobRunner,
+        PollMailbox,
+    },
+};
+use common::{get, id_of, post, register_user, TestContext};
+
+struct StoredMessage {
+    uid: u32,
+    raw: Vec<u8>,
+    seen: bool,
+}
+
+type Mailbox = Arc<Mutex<Vec<StoredMessage>>>;
+
+/// A local IMAP server with one mailbox, speaking just the commands the poller
+/// sends. Returns the settings to reach it and its messages.
+async fn start_imap_server(mailbox: &'static str, messages: Vec<String>) -> (ImapConfig, Mailbox) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let messages: Vec<StoredMessage> = (1..)
+        .zip(messages)
+        .map(|(uid, raw)| StoredMessage { uid, raw: raw.replace('\n', "\r\n").into_bytes(), seen: false })
+        .collect();
+    let store = Arc::new(Mutex::new(messages));
+
+    let served = store.clone();
+    actix_web::rt::spawn(async move {
+        loop {
+            let (socket, _) = listener.accept().await.unwrap();
+            let (reader, mut writer) = socket.into_split();
+            let mut lines = BufReader::new(reader).lines();
+            writer.write_all(b"* OK test server ready\r\n").await.unwrap();
+
+            while let Ok(Some(line)) = lines.next_line().await {
+                let (tag, command) = line.split_once(' ').unwrap();
+                let mut reply = Vec::new();
+                let status = match command {
+                    "LOGIN \"tasks@example.com\" \"p\\\"ss\"" => "OK logged in",
+                    c if c.starts_with("LOGIN") => "NO wrong password",
+                    c if c == format!("SELECT \"{}\"", mailbox) => "OK [READ-WRITE] selected",
+                    c if c.starts_with("SELECT") => "NO no such mailbox",
+                    "UID SEARCH UNSEEN" => {
+                        let store = served.lock().unwrap();
+                        let uids: Vec<String> = store.iter().filter(|m| !m.seen).map(|m| m.uid.to_string()).collect();
+                        reply.extend(format!("* SEARCH {}\r\n", uids.join(" ")).into_bytes());
+                        "OK search done"
+                    }
+                    c if c.starts_with("UID FETCH") => {
+                        let uid: u32 = c.split(' ').nth(2).unwrap().parse().unwrap();
+                        let store = served.lock().unwrap();
+                        let message = store.iter().find(|m| m.uid == uid).unwrap();
+                        let size = message.raw.len();
+                        // Servers may report flag changes on any message before answering
+                        reply.extend(format!("* {} FETCH (FLAGS (\\Seen))\r\n", uid).into_bytes());
+                        reply.extend(format!("* {} FETCH (UID {} BODY[] {{{}}}\r\n", uid, uid, size).into_bytes());
+                        reply.extend(&message.raw);
+                        reply.extend(b")\r\n");
+                        "OK fetch done"
+                    }
+                    c if c.starts_with("UID STORE") && c.ends_with("+FLAGS.SILENT (\\Seen)") => {
+                        let uid: u32 = c.split(' ').nth(2).unwrap().parse().unwrap();
+                        served.lock().unwrap().iter_mut().find(|m| m.uid == uid).unwrap().seen = true;
+                        "OK store done"
+                    }
+                    "LOGOUT" => {
+                        reply.extend(b"* BYE logging out\r\n");
+                        "OK logout done"
+                    }
+                    _ => "BAD unknown command",
+                };
+                reply.extend(format!("{} {}\r\n", tag, status).into_bytes());
+                writer.write_all(&reply).await.unwrap();
+            }
+        }
+    });
+
+    let config = ImapConfig {
+        host: "127.0.0.1".to_string(),
+        port,
+        tls: false,
+        username: "tasks@example.com".to_string(),
+        password: "p\"ss".to_string(),
+        mailbox: mailbox.to_string(),
+        poll_interval: Duration::from_secs(60),
+        max_message_bytes: 1024 * 1024,
+    };
+    (config, store)
+}
+
+const NEW_REQUEST: &str = "From: Ana Lima <ana@customer.example>
+To: tasks@example.com
+Subject: Printer on floor 3 is jammed
+Message-ID: <request-1@customer.example>
+Date: Mon, 19 Oct 2026 09:00:00 +0000
+Content-Type: text/plain; charset=utf-8
+
+It jams on every double-sided job.
+";
+
+const HTML_REPLY: &str = "From: Ana Lima <ana@customer.example>
+To: tasks@example.com
+Subject: Re: Broken login page
+Message-ID: <reply-1@customer.example>
+In-Reply-To: <origin@customer.example>
+References: <origin@customer.example>
+Date: Mon, 19 Oct 2026 10:00:00 +0000
+Content-Type: text/html; charset=utf-8
+
+<html><body><p>It happens in <b>Firefox</b> too.</p>
+<div>On Sun, 18 Oct 2026, Support &lt;tasks@example.com&gt; wrote:</div>
+<blockquote><p>Which browser are you using?</p></blockquote>
+</body></html>
+";
+
+const PLAIN_REPLY: &str = "From: ana@customer.example
+Subject: Re: Broken login page
+Message-ID: <reply-2@customer.example>
+In-Reply-To: <reply-1@customer.example>
+Date: Mon, 19 Oct 2026 11:00:00 +0000
+Content-Type: multipart/alternative; boundary=\"b1\"
+
+--b1
+Content-Type: text/plain; charset=utf-8
+
+And in Safari.
+
+> It happens in Firefox too.
+--b1
+Content-Type: text/html; charset=utf-8
+
+<p>And in Safari.</p>
+--b1--
+";
+
+/// Replies on the thread of `PLAIN_REPLY` from `sender`, who may not thread onto its task.
+fn reply_from(sender: &str, id: &str) -> String {
+    let headers = format!("From: {}\nSubject: Re: Broken login page\nMessage-ID: <{}>\n", sender, id);
+    format!("{}In-Reply-To: <reply-2@customer.example>\n\nSee http://x.example\n", headers)
+}
+
+#[actix_web::test]
+async fn the_mailbox_is_polled_and_replies_thread_onto_their_task() {
+    let Some(ctx) = TestContext::new().await else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+    let dev = register_user(&app, "dev@example.com").await;
+    register_user(&app, "ana@customer.example").await;
+    let rival = register_user(&app, "rival@example.com").await;
+    let move_rival = sqlx::query(
+        "WITH rivals AS (INSERT INTO organizations (name, slug) VALUES ('Rivals', 'rivals') RETURNING id)
+         UPDATE organization_members SET organization_id = (SELECT id FROM rivals) WHERE user_id = $1",
+    )
+    .bind(rival.id);
+    db::across_organizations(move_rival.execute(ctx.pool())).await.unwrap();
+    let (_, project) = post(&app, "/api/projects", &dev.token, json!({"name": "Support"})).await;
+    let body = json!({"project_id": id_of(&project), "title": "Login"});
+    let (_, task) = post(&app, "/api/tasks", &dev.token, body).await;
+    let task_id = id_of(&task);
+    let seed = sqlx::query("UPDATE tasks SET source_email_id = 'origin@customer.example' WHERE id = $1")
+        .bind(task_id)
+        .execute(ctx.pool());
+    db::across_organizations(seed).await.unwrap();
+
+    let messages = vec![
+        HTML_REPLY.to_string(),
+        NEW_REQUEST.to_string(),
+        // Delivered twice, e.g. to two addresses that both end up in the mailbox
+        NEW_REQUEST.to_string(),
+        PLAIN_REPLY.to_string(),
+        "Subject: no sender\n\nJust text\n".to_string(),
+        // Knowing a thread's Message-IDs is not enough to post on its task
+        reply_from("mallory@elsewhere.example", "outsider@elsewhere.example"),
+        reply_from("Rival@Example.com", "rival@example.com"),
+    ];
+    let (config, mailbox) = start_imap_server("Support Requests", messages).await;
+
+    let stored = poll_mailbox(ctx.pool(), &config, &ctx.state.metrics).await.unwrap();
+    assert_eq!(stored, 5);
+    assert_eq!(ctx.state.metrics.emails_processed.get(), 7);
+    assert!(mailbox.lock().unwrap().iter().all(|m| m.seen));
+    assert_eq!(poll_mailbox(ctx.pool(), &config, &ctx.state.metrics).await.unwrap(), 0);
+
+    let (status, emails) = get(&app, &format!("/api/tasks/{}/emails", task_id), &dev.token).await;
+    assert_eq!(status, StatusCode::OK, "{}", emails);
+    let emails = emails.as_array().unwrap();
+    assert_eq!(emails.len(), 2);
+    assert_eq!(emails[0]["message_id"], "reply-1@customer.example");
+    assert_eq!(emails[0]["sender"], "ana@customer.example");
+    assert_eq!(emails[0]["body_text"], "It happens in **Firefox** too.");
+    assert_eq!(emails[0]["processed"], true);
+    assert_eq!(emails[0]["organization_id"], task["organization_id"]);
+    assert_eq!(emails[1]["in_reply_to"], "reply-1@customer.example");
+    assert_eq!(emails[1]["body_text"], "And in Safari.");
+
+    for message_id in ["request-1@customer.example", "outsider@elsewhere.example", "rival@example.com"] {
+        let request = sqlx::query_as("SELECT processed, task_id, organization_id FROM email_logs WHERE message_id = $1")
+            .bind(message_id)
+            .fetch_one(ctx.pool());
+        let stored: (bool, Option<uuid::Uuid>, Option<uuid::Uuid>) = db::across_organizations(request).await.unwrap();
+        assert_eq!(stored, (false, None, None), "{}", message_id);
+    }
+
+    let wrong_mailbox = ImapConfig { mailbox: "INBOX".to_string(), ..config };
+    let error = poll_mailbox(ctx.pool(), &wrong_mailbox, &ctx.state.metrics).await.unwrap_err();
+    assert!(error.to_string().contains("refused SELECT"), "{}", error);
+}
+
+#[actix_web::test]
+async fn messages_over_the_size_limit_are_skipped() {
+    let Some(ctx) = TestContext::new().await else { return };
+    let huge = format!("{}\n{}\n", NEW_REQUEST.replace("request-1", "huge-1"), "x".repeat(4096));
+    let (config, mailbox) = start_imap_server("INBOX", vec![huge, NEW_REQUEST.to_string()]).await;
+    let config = ImapConfig { max_message_bytes: 2048, ..config };
+
+    assert_eq!(poll_mailbox(ctx.pool(), &config, &ctx.state.metrics).await.unwrap(), 1);
+    assert_eq!(ctx.state.metrics.emails_processed.get(), 2);
+    assert!(mailbox.lock().unwrap().iter().all(|m| m.seen));
+
+    let stored = sqlx::query_scalar::<_, String>("SELECT message_id FROM email_logs").fetch_all(ctx.pool());
+    assert_eq!(db::across_organizations(stored).await.unwrap(), vec!["request-1@customer.example"]);
+}
+
+#[actix_web::test]
+async fn the_poll_job_stores_what_it_can_and_queues_the_next_poll() {
+    let Some(ctx) = TestContext::new().await else { return };
+    let reject = "CREATE FUNCTION reject_email() RETURNS trigger AS $$
+        BEGIN RAISE EXCEPTION 'disk full'; END $$ LANGUAGE plpgsql;
+        CREATE TRIGGER reject_email BEFORE INSERT ON email_logs
+        FOR EACH ROW WHEN (NEW.message_id = 'failing-1@customer.example') EXECUTE FUNCTION reject_email()";
+    sqlx::raw_sql(reject).execute(ctx.pool()).await.unwrap();
+
+    let failing = NEW_REQUEST.replace("request-1", "failing-1");
+    let (imap, mailbox) = start_imap_server("INBOX", vec![failing, NEW_REQUEST.to_string()]).await;
+    let mut config = ctx.state.config.clone();
+    config.imap = Some(imap);
+    let context = JobContext {
+        pool: ctx.pool().clone(),
+        config,
+        blob_store: ctx.state.blob_store.clone(),
+        metrics: ctx.state.metrics.clone(),
+    };
+    let runner = JobRunner::new(context, JobRegistry::new().register::<PollMailbox>());
+
+    let now = chrono::Utc::now();
+    enqueue_once_at(ctx.pool(), &PollMailbox, now).await.unwrap();
+    assert!(runner.run_next().await.unwrap());
+    let seen: Vec<bool> = mailbox.lock().unwrap().iter().map(|m| m.seen).collect();
+    assert_eq!(seen, vec![false, true], "a failed message is read again next time");
+
+    let stored = sqlx::query_scalar::<_, String>("SELECT message_id FROM email_logs").fetch_all(ctx.pool());
+    assert_eq!(db::across_organizations(stored).await.unwrap(), vec!["request-1@customer.example"]);
+
+    let queued = db::list_jobs(ctx.pool(), "pending", Some("poll_mailbox"), 10).await.unwrap();
+    assert_eq!(queued.len(), 1);
+    assert!(queued[0].run_at - now >= chrono::Duration::seconds(60));
+}
+
+#[actix_web::test]
+async fn emails_are_parsed_into_their_parts() {
+    let email = parse_email(NEW_REQUEST.as_bytes()).unwrap();
+    assert_eq!(email.sender, "ana@customer.example");
+    assert_eq!(email.subject.as_deref(), Some("Printer on floor 3 is jammed"));
+    assert_eq!(email.body_text.as_deref(), Some("It jams on every double-sided job."));
+    assert_eq!(email.message_id.as_deref(), Some("request-1@customer.example"));
+    assert_eq!(email.received_at.to_rfc3339(), "2026-10-19T09:00:00+00:00");
+    assert!(email.thread_ids().is_empty());
+
+    let reply = parse_email(PLAIN_REPLY.as_bytes()).unwrap();
+    assert_eq!(reply.body_text.as_deref(), Some("And in Safari."));
+    assert_eq!(reply.thread_ids(), vec!["reply-1@customer.example"]);
+
+    assert!(parse_email(b"Subject: no sender\r\n\r\nJust text\r\n").is_none());
+
+    let outlook = "Sounds good.\r\n\r\nFrom: Support <tasks@example.com>\r\nSent: Monday\r\nSubject: Re: Login\r\n";
+    assert_eq!(strip_quoted_reply(outlook), "Sounds good.");
+    let gmail = "Fixed now\n\nOn Mon, Oct 19, 2026 at 9:00 AM Ana Lima <\nana@customer.example> wrote:\n> Still broken";
+    assert_eq!(strip_quoted_reply(gmail), "Fixed now");
+}
@@ -0,0 +1,59 @@
+mod common;
+
+use actix_web::{
+    http::{Method, StatusCode},
+    test,
+};
+use serde_json::json;
+
+use ai_task_tracker::build_app;
+use common::{get, id_of, post, register_user, send, TestContext};
+
+#[actix_web::test]
+async fn probes_report_a_migrated_database_as_ready() {
+    let Some(ctx) = TestContext::new().await else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+
+    let (status, body) = send(&app, Method::GET, "/healthz", None, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "ok");
+
+    let (status, report) = send(&app, Method::GET, "/readyz", None, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(report["ready"], true);
+    assert_eq!(report["pending_migrations"], json!([]));
+
+    sqlx::query("DELETE FROM _sqlx_migrations WHERE version = (SELECT MAX(version) FROM _sqlx_migrations)")
+        .execute(ctx.pool())
+        .await
+        .unwrap();
+    let (status, report) = send(&app, Method::GET, "/readyz", None, None).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(report["database"], true);
+    assert_eq!(report["pending_migrations"].as_array().map(Vec::len), Some(1));
+}
+
+#[actix_web::test]
+async fn metrics_count_requests_by_route_and_created_tasks() {
+    let Some(ctx) = TestContext::new().await else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+    let user = register_user(&app, "ada@example.com").await;
+    let (_, project) = post(&app, "/api/projects", &user.token, json!({"name": "Launch"})).await;
+    let body = json!({"project_id": id_of(&project), "title": "Write docs"});
+    let (status, task) = post(&app, "/api/tasks", &user.token, body).await;
+    assert_eq!(status, StatusCode::CREATED);
+    get(&app, &format!("/api/tasks/{}", id_of(&task)), &user.token).await;
+
+    let (status, text) = send(&app, Method::GET, "/metrics", None, None).await;
+    assert_eq!(status, StatusCode::OK);
+    let text = text.as_str().unwrap_or_default();
+    assert!(text.contains("tasks_created_total 1"), "{}", text);
+    assert!(text.contains("emails_processed_total 0"), "{}", text);
+    assert!(
+        text.contains(r#"http_requests_total{method="GET",route="/api/tasks/{id}",status="200"} 1"#),
+        "{}",
+        text
+    );
+    assert!(text.contains("http_request_duration_seconds_bucket"), "{}", text);
+    assert!(text.contains("db_pool_max_connections 5"), "{}", text);
+}
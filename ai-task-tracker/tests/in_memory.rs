@@ -0,0 +1,182 @@
+//! Handler tests against the in-memory repositories; these run without Postgres.
+
+mod common;
+
+use actix_web::{
+    http::{Method, StatusCode},
+    test,
+};
+use serde_json::json;
+
+use ai_task_tracker::build_app;
+use common::{delete, get, id_of, post, put, send, MemoryContext, TEST_PASSWORD};
+
+#[actix_web::test]
+async fn tasks_are_managed_without_a_database() {
+    let ctx = MemoryContext::new();
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+    let user = ctx.user("dev@example.com").await;
+    let (_, project) = post(&app, "/api/projects", &user.token, json!({"name": "Launch"})).await;
+    let project_id = id_of(&project);
+
+    let body = json!({"project_id": project_id, "title": "Write docs", "assignee_id": user.id});
+    let (status, task) = post(&app, "/api/tasks", &user.token, body).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(task["priority"], "medium");
+    let task_uri = format!("/api/tasks/{}", id_of(&task));
+
+    let (status, task) = put(&app, &task_uri, &user.token, json!({"status": "done"})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(task["status"], "done");
+    assert_eq!(task["title"], "Write docs");
+
+    let (_, tasks) = get(&app, "/api/tasks?status=done", &user.token).await;
+    assert_eq!(tasks.as_array().map(Vec::len), Some(1));
+    let (_, tasks) = get(&app, "/api/tasks?status=todo", &user.token).await;
+    assert_eq!(tasks.as_array().map(Vec::len), Some(0));
+
+    let progress = json!({"progress_percent": 101});
+    let (status, _) = put(&app, &format!("{}/progress", task_uri), &user.token, progress).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Deleting the project takes its tasks with it
+    let (status, _) = delete(&app, &format!("/api/projects/{}", project_id), &user.token).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = get(&app, &task_uri, &user.token).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn task_details_are_served_without_a_database() {
+    let ctx = MemoryContext::new();
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+    let user = ctx.user("dev@example.com").await;
+    let (_, project) = post(&app, "/api/projects", &user.token, json!({"name": "Launch"})).await;
+    let body = json!({"project_id": id_of(&project), "title": "Ship"});
+    let (_, task) = post(&app, "/api/tasks", &user.token, body).await;
+    let task_uri = format!("/api/tasks/{}", id_of(&task));
+
+    let (status, subtask) = post(&app, &format!("{}/subtasks", task_uri), &user.token, json!({"title": "Tag"})).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let subtask_uri = format!("/api/subtasks/{}", id_of(&subtask));
+    let (status, subtask) = put(&app, &subtask_uri, &user.token, json!({"is_completed": true})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!((&subtask["title"], &subtask["is_completed"]), (&json!("Tag"), &json!(true)));
+
+    let note = json!({"comment": "Halfway", "completion_percentage": 50});
+    let (status, _) = post(&app, &format!("{}/history", task_uri), &user.token, note).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (_, history) = get(&app, &format!("{}/history", task_uri), &user.token).await;
+    assert_eq!(history[0]["comment"], "Halfway");
+    assert_eq!(history[0]["user_email"], "dev@example.com");
+
+    put(&app, &task_uri, &user.token, json!({"status": "in_progress"})).await;
+    let (_, activity) = get(&app, &format!("{}/activity", task_uri), &user.token).await;
+    let actions: Vec<&str> = activity.as_array().unwrap().iter().map(|a| a["action"].as_str().unwrap()).collect();
+    assert_eq!(actions, vec!["status_changed", "created"]);
+    assert_eq!(activity[0]["new_value"], "in_progress");
+    let (status, emails) = get(&app, &format!("{}/emails", task_uri), &user.token).await;
+    assert_eq!((status, emails), (StatusCode::OK, json!([])));
+
+    // A task in the trash takes its subtasks out of reach
+    delete(&app, &task_uri, &user.token).await;
+    let (status, _) = put(&app, &subtask_uri, &user.token, json!({"title": "Retag"})).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = get(&app, &format!("{}/subtasks", task_uri), &user.token).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn team_roles_are_enforced_without_a_database() {
+    let ctx = MemoryContext::new();
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+    let owner = ctx.user("owner@example.com").await;
+    let dev = ctx.user("dev@example.com").await;
+    let (status, team) = post(&app, "/api/teams", &owner.token, json!({"name": "Platform"})).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let members_uri = format!("/api/teams/{}/members", id_of(&team));
+
+    let (status, member) = post(&app, &members_uri, &owner.token, json!({"email": "dev@example.com"})).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(member["user_id"], dev.id.to_string());
+
+    let owner_uri = format!("{}/{}", members_uri, owner.id);
+    let (status, _) = put(&app, &owner_uri, &dev.token, json!({"role": "member"})).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (_, members) = get(&app, &members_uri, &owner.token).await;
+    assert_eq!(members.as_array().map(Vec::len), Some(2));
+    assert_eq!(members[0]["team_role"], "owner");
+    assert_eq!(members[1]["email"], "dev@example.com");
+}
+
+#[actix_web::test]
+async fn team_invitations_and_trash_are_served_without_a_database() {
+    let ctx = MemoryContext::new();
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+    let owner = ctx.user("owner@example.com").await;
+    let (_, team) = post(&app, "/api/teams", &owner.token, json!({"name": "Platform"})).await;
+    let team_uri = format!("/api/teams/{}", id_of(&team));
+
+    let invite = json!({"email": "new@example.com", "role": "viewer"});
+    let (status, invitation) = post(&app, &format!("{}/members", team_uri), &owner.token, invite.clone()).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    let (status, _) = post(&app, &format!("{}/members", team_uri), &owner.token, invite).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let invitations_uri = format!("{}/invitations", team_uri);
+    let (_, invitations) = get(&app, &invitations_uri, &owner.token).await;
+    assert_eq!(invitations.as_array().map(Vec::len), Some(1));
+    let invitation_uri = format!("{}/{}", invitations_uri, id_of(&invitation));
+    let (status, _) = delete(&app, &invitation_uri, &owner.token).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (_, invitations) = get(&app, &invitations_uri, &owner.token).await;
+    assert_eq!(invitations, json!([]));
+
+    // A project deleted on its own stays in the trash when the team is restored
+    let body = json!({"name": "Launch", "team_id": id_of(&team)});
+    let (_, project) = post(&app, "/api/projects", &owner.token, body).await;
+    delete(&app, &format!("/api/projects/{}", id_of(&project)), &owner.token).await;
+    let (status, _) = delete(&app, &team_uri, &owner.token).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, trash) = get(&app, &format!("{}/trash", team_uri), &owner.token).await;
+    assert_eq!(status, StatusCode::OK);
+    let kinds: Vec<&str> = trash.as_array().unwrap().iter().map(|i| i["kind"].as_str().unwrap()).collect();
+    assert_eq!(kinds, vec!["team", "project"]);
+    assert_eq!(trash[1]["parent_deleted"], true);
+    assert_eq!(trash[1]["deleted_by_name"], "owner@example.com");
+
+    let (status, _) = post(&app, &format!("{}/restore", team_uri), &owner.token, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, trash) = get(&app, &format!("{}/trash", team_uri), &owner.token).await;
+    assert_eq!(trash.as_array().map(Vec::len), Some(1));
+    assert_eq!((&trash[0]["id"], &trash[0]["parent_deleted"]), (&project["id"], &json!(false)));
+}
+
+#[actix_web::test]
+async fn users_are_served_from_the_repository() {
+    let ctx = MemoryContext::new();
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+    let user = ctx.user("ada@example.com").await;
+
+    let user_uri = format!("/api/users/{}", user.id);
+    let (status, updated) = put(&app, &user_uri, &user.token, json!({"full_name": "Ada"})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(updated["full_name"], "Ada");
+
+    let (status, me) = get(&app, "/api/users/me", &user.token).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(me["full_name"], "Ada");
+    assert_eq!(me["email"], "ada@example.com");
+}
+
+#[actix_web::test]
+async fn registration_can_be_switched_off() {
+    let mut ctx = MemoryContext::new();
+    ctx.state.config.features.registration = false;
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+
+    let body = json!({"email": "ada@example.com", "password": TEST_PASSWORD, "full_name": "Ada"});
+    let (status, error) = send(&app, Method::POST, "/api/auth/register", None, Some(body)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(error["error"], "Registration is disabled");
+}
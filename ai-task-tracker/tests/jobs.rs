@@ -0,0 +1,190 @@
+mod common;
+
+use actix_web::{http::{Method, StatusCode}, test};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use uuid::Uuid;
+
+use ai_task_tracker::{
+    build_app,
+    db::{self, claim_next_job, complete_job, fail_job, heartbeat_job, list_jobs},
+    services::{enqueue, enqueue_once_at, BackgroundJob, JobContext, JobRegistry, JobRunner, PurgeTrash},
+};
+use common::{delete, get, id_of, post, promote_to_admin, promote_to_platform_admin, register_user, send, TestContext};
+
+#[derive(Serialize, Deserialize)]
+struct RenameProject {
+    project_id: Uuid,
+    name: String,
+}
+
+#[async_trait]
+impl BackgroundJob for RenameProject {
+    const KIND: &'static str = "rename_project";
+
+    async fn run(&self, ctx: &JobContext) -> anyhow::Result<()> {
+        sqlx::query("UPDATE projects SET name = $1 WHERE id = $2")
+            .bind(&self.name)
+            .bind(self.project_id)
+            .execute(&ctx.pool)
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SendDigest;
+
+#[async_trait]
+impl BackgroundJob for SendDigest {
+    const KIND: &'static str = "send_digest";
+    const MAX_ATTEMPTS: i32 = 2;
+
+    async fn run(&self, _ctx: &JobContext) -> anyhow::Result<()> {
+        anyhow::bail!("mail server unavailable")
+    }
+}
+
+fn runner(ctx: &TestContext) -> JobRunner {
+    let context = JobContext {
+        pool: ctx.pool().clone(),
+        config: ctx.state.config.clone(),
+        blob_store: ctx.state.blob_store.clone(),
+        metrics: ctx.state.metrics.clone(),
+    };
+    JobRunner::new(context, JobRegistry::new().register::<RenameProject>().register::<SendDigest>())
+}
+
+#[actix_web::test]
+async fn queued_jobs_run_once() {
+    let Some(ctx) = TestContext::new().await else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+    let user = register_user(&app, "ada@example.com").await;
+    let (_, project) = post(&app, "/api/projects", &user.token, json!({"name": "Launch"})).await;
+    let project_uri = format!("/api/projects/{}", id_of(&project));
+
+    let job = RenameProject { project_id: id_of(&project), name: "Relaunch".to_string() };
+    enqueue(ctx.pool(), &job).await.unwrap();
+    let runner = runner(&ctx);
+    assert!(runner.run_next().await.unwrap());
+    assert!(!runner.run_next().await.unwrap());
+
+    let (_, project) = get(&app, &project_uri, &user.token).await;
+    assert_eq!(project["name"], "Relaunch");
+}
+
+#[actix_web::test]
+async fn only_the_worker_holding_the_lock_records_the_outcome() {
+    let Some(ctx) = TestContext::new().await else { return };
+    let job = enqueue(ctx.pool(), &SendDigest).await.unwrap();
+    let (stalled, current) = (Uuid::new_v4(), Uuid::new_v4());
+
+    let claimed = claim_next_job(ctx.pool(), chrono::Utc::now(), stalled).await.unwrap().unwrap();
+    assert_eq!(claimed.locked_by, Some(stalled));
+    assert!(claim_next_job(ctx.pool(), chrono::Utc::now() - chrono::Duration::minutes(15), current)
+        .await
+        .unwrap()
+        .is_none());
+
+    // Once its lock is stale, another worker takes the job over
+    let taken = claim_next_job(ctx.pool(), chrono::Utc::now(), current).await.unwrap().unwrap();
+    assert_eq!((taken.id, taken.attempts), (job.id, 2));
+    assert!(!heartbeat_job(ctx.pool(), job.id, stalled).await.unwrap());
+    assert!(!complete_job(ctx.pool(), job.id, stalled).await.unwrap());
+    assert!(!fail_job(ctx.pool(), job.id, stalled, "late", None).await.unwrap());
+
+    assert!(heartbeat_job(ctx.pool(), job.id, current).await.unwrap());
+    assert!(complete_job(ctx.pool(), job.id, current).await.unwrap());
+    assert!(!complete_job(ctx.pool(), job.id, current).await.unwrap());
+}
+
+#[actix_web::test]
+async fn the_trash_purge_runs_as_a_job_that_queues_its_next_run() {
+    let Some(ctx) = TestContext::new().await else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+    let user = register_user(&app, "ada@example.com").await;
+    let (_, project) = post(&app, "/api/projects", &user.token, json!({"name": "Old"})).await;
+    let project_uri = format!("/api/projects/{}", id_of(&project));
+    let (status, _) = delete(&app, &project_uri, &user.token).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let age = sqlx::query("UPDATE projects SET deleted_at = NOW() - INTERVAL '31 days' WHERE id = $1")
+        .bind(id_of(&project))
+        .execute(ctx.pool());
+    db::across_organizations(age).await.unwrap();
+
+    let mut config = ctx.state.config.clone();
+    config.features.trash_purge = true;
+    let context = JobContext {
+        pool: ctx.pool().clone(),
+        config,
+        blob_store: ctx.state.blob_store.clone(),
+        metrics: ctx.state.metrics.clone(),
+    };
+    let runner = JobRunner::new(context, JobRegistry::new().register::<PurgeTrash>());
+
+    let now = chrono::Utc::now();
+    assert!(enqueue_once_at(ctx.pool(), &PurgeTrash, now).await.unwrap().is_some());
+    assert!(enqueue_once_at(ctx.pool(), &PurgeTrash, now).await.unwrap().is_none(), "queued twice");
+    assert!(runner.run_next().await.unwrap());
+    assert!(!runner.run_next().await.unwrap(), "next run is not due yet");
+
+    let remaining = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM projects WHERE id = $1")
+        .bind(id_of(&project))
+        .fetch_one(ctx.pool());
+    assert_eq!(db::across_organizations(remaining).await.unwrap(), 0);
+
+    let queued = list_jobs(ctx.pool(), "pending", Some("purge_trash"), 10).await.unwrap();
+    assert_eq!(queued.len(), 1);
+    let completed = list_jobs(ctx.pool(), "completed", Some("purge_trash"), 10).await.unwrap();
+    assert!(completed.is_empty(), "finished runs pile up in the queue");
+    let wait = queued[0].run_at - now;
+    assert!(wait > chrono::Duration::minutes(59) && wait < chrono::Duration::minutes(61), "{}", wait);
+}
+
+#[actix_web::test]
+async fn failing_jobs_are_retried_then_listed_as_dead() {
+    let Some(ctx) = TestContext::new().await else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+    let user = register_user(&app, "ada@example.com").await;
+    let admin_token = promote_to_platform_admin(&ctx, &user).await;
+    let job = enqueue(ctx.pool(), &SendDigest).await.unwrap();
+    let job_uri = format!("/api/admin/jobs/{}", job.id);
+    let runner = runner(&ctx);
+
+    // The first failure schedules a retry with backoff
+    assert!(runner.run_next().await.unwrap());
+    let (_, retrying) = get(&app, &job_uri, &admin_token).await;
+    assert_eq!(retrying["status"], "pending");
+    assert_eq!(retrying["attempts"], 1);
+    assert_eq!(retrying["last_error"], "mail server unavailable");
+    assert!(!runner.run_next().await.unwrap(), "retry ran before its backoff");
+
+    sqlx::query("UPDATE jobs SET run_at = NOW() WHERE id = $1").bind(job.id).execute(ctx.pool()).await.unwrap();
+    assert!(runner.run_next().await.unwrap());
+
+    let (status, dead) = get(&app, "/api/admin/jobs", &admin_token).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(dead.as_array().map(Vec::len), Some(1));
+    assert_eq!(dead[0]["kind"], "send_digest");
+    assert_eq!(dead[0]["attempts"], 2);
+
+    // Promotion applies to the user's earlier session too, so ask as someone else.
+    // The queue is shared by every organization, so an organization's admin cannot see it either.
+    let member = register_user(&app, "bob@example.com").await;
+    let (status, _) = get(&app, "/api/admin/jobs", &member.token).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let org_admin = register_user(&app, "carol@example.com").await;
+    let org_admin_token = promote_to_admin(&ctx, &org_admin).await;
+    for (method, uri) in [(Method::GET, "/api/admin/jobs".to_string()), (Method::POST, format!("{}/retry", job_uri))] {
+        let (status, _) = send(&app, method, &uri, Some(&org_admin_token), None).await;
+        assert_eq!(status, StatusCode::FORBIDDEN, "{}", uri);
+    }
+
+    let (status, retried) = post(&app, &format!("{}/retry", job_uri), &admin_token, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(retried["status"], "pending");
+    assert_eq!(retried["attempts"], 0);
+    let (status, _) = post(&app, &format!("{}/retry", job_uri), &admin_token, json!({})).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,99 @@
+mod common;
+
+use actix_web::{http::StatusCode, test};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use ai_task_tracker::build_app;
+use common::{delete, get, id_of, post, register_user, TestContext};
+
+/// Task titles in a list response, sorted.
+fn titles(tasks: &Value) -> Vec<&str> {
+    let mut titles: Vec<&str> = tasks.as_array().unwrap().iter().map(|t| t["title"].as_str().unwrap()).collect();
+    titles.sort();
+    titles
+}
+
+#[actix_web::test]
+async fn labels_stay_in_their_project_and_filter_tasks() {
+    let Some(ctx) = TestContext::new().await else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+    let dev = register_user(&app, "dev@example.com").await;
+    let (_, project) = post(&app, "/api/projects", &dev.token, json!({"name": "Web"})).await;
+    let (_, elsewhere) = post(&app, "/api/projects", &dev.token, json!({"name": "Mobile"})).await;
+    let project_id = id_of(&project);
+
+    let mut labels = Vec::new();
+    for (name, project) in [("bug", project_id), ("ui", project_id), ("crash", id_of(&elsewhere))] {
+        let body = json!({"project_id": project, "name": name, "color": "#FF0000"});
+        let (status, label) = post(&app, "/api/labels", &dev.token, body).await;
+        assert_eq!(status, StatusCode::CREATED, "{}", label);
+        assert_eq!(label["color"], "#ff0000");
+        labels.push(id_of(&label));
+    }
+    let (bug, ui, crash) = (labels[0], labels[1], labels[2]);
+
+    let body = json!({"project_id": project_id, "name": "bug"});
+    let (status, _) = post(&app, "/api/labels", &dev.token, body).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let body = json!({"project_id": project_id, "name": "docs", "color": "red"});
+    let (status, _) = post(&app, "/api/labels", &dev.token, body).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let mut tasks = Vec::new();
+    for (title, labels) in [("Both", vec![bug, ui]), ("Bug only", vec![bug]), ("Neither", vec![])] {
+        let (_, task) = post(&app, "/api/tasks", &dev.token, json!({"project_id": project_id, "title": title})).await;
+        for label in labels {
+            let uri = format!("/api/tasks/{}/labels", id_of(&task));
+            let (status, body) = post(&app, &uri, &dev.token, json!({"label_id": label})).await;
+            assert_eq!(status, StatusCode::CREATED, "{}", body);
+        }
+        tasks.push(id_of(&task));
+    }
+    let uri = format!("/api/tasks/{}/labels", tasks[0]);
+    let (status, _) = post(&app, &uri, &dev.token, json!({"label_id": bug})).await;
+    assert_eq!(status, StatusCode::OK, "adding a label twice is not an error");
+    let (status, _) = post(&app, &uri, &dev.token, json!({"label_id": crash})).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "a label from another project was added");
+
+    let list = |query: String| {
+        let app = &app;
+        let token = dev.token.clone();
+        async move {
+            let (status, tasks) = get(app, &format!("/api/tasks?project_id={}&{}", project_id, query), &token).await;
+            assert_eq!(status, StatusCode::OK, "{}", tasks);
+            tasks
+        }
+    };
+    assert_eq!(titles(&list(format!("labels={},{}", bug, ui)).await), vec!["Both"]);
+    assert_eq!(titles(&list(format!("labels={},{}&label_match=any", bug, ui)).await), vec!["Both", "Bug only"]);
+    let (status, _) = get(&app, "/api/tasks?labels=bug", &dev.token).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = delete(&app, &format!("{}/{}", uri, ui), &dev.token).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = delete(&app, &format!("{}/{}", uri, ui), &dev.token).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (_, activity) = get(&app, &format!("/api/tasks/{}/activity", tasks[0]), &dev.token).await;
+    let mut events: Vec<(&str, &str)> = activity
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|a| a["action"].as_str().unwrap().starts_with("label_"))
+        .map(|a| {
+            let name = a["new_value"].as_str().or(a["old_value"].as_str()).unwrap();
+            (a["action"].as_str().unwrap(), name)
+        })
+        .collect();
+    events.sort();
+    assert_eq!(events, vec![("label_added", "bug"), ("label_added", "ui"), ("label_removed", "ui")]);
+
+    // Deleting a label takes it off its tasks
+    let (status, _) = delete(&app, &format!("/api/labels/{}", bug), &dev.token).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (_, remaining) = get(&app, &uri, &dev.token).await;
+    assert_eq!(remaining, json!([]));
+    let (status, _) = get(&app, &format!("/api/labels/{}", Uuid::new_v4()), &dev.token).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
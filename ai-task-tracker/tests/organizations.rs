@@ -0,0 +1,243 @@
+mod common;
+
+use actix_web::{body::MessageBody, http::StatusCode, test};
+use serde_json::json;
+use sqlx::{Connection, Executor};
+use uuid::Uuid;
+
+use ai_task_tracker::build_app;
+use common::{delete, get, id_of, post, promote_to_admin, put, register_user, TestContext, TestService, TestUser};
+
+/// Invites `user` to the organization and has them accept.
+async fn join<B: MessageBody>(app: &impl TestService<B>, admin_token: &str, organization_id: Uuid, user: &TestUser) {
+    let uri = format!("/api/admin/organizations/{}/invitations", organization_id);
+    let (status, invitation) = post(app, &uri, admin_token, json!({"user_id": user.id})).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", invitation);
+    let uri = format!("/api/organizations/invitations/{}/accept", id_of(&invitation));
+    let (status, accepted) = post(app, &uri, &user.token, json!({})).await;
+    assert_eq!(status, StatusCode::OK, "{}", accepted);
+}
+
+#[actix_web::test]
+async fn organizations_cannot_see_each_other() {
+    let Some(ctx) = TestContext::new().await else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+    let admin = register_user(&app, "admin@example.com").await;
+    let admin_token = promote_to_admin(&ctx, &admin).await;
+    let dev = register_user(&app, "dev@example.com").await;
+    let outsider = register_user(&app, "outsider@example.com").await;
+
+    let organizations_uri = "/api/admin/organizations";
+    let acme = json!({"name": "Acme", "slug": "acme"});
+    let (status, _) = post(&app, organizations_uri, &dev.token, acme.clone()).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = post(&app, organizations_uri, &admin_token, json!({"name": "Acme", "slug": "Not A Slug"})).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, created) = post(&app, organizations_uri, &admin_token, acme.clone()).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", created);
+    let (status, _) = post(&app, organizations_uri, &admin_token, acme).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let acme = created;
+    join(&app, &admin_token, id_of(&acme), &dev).await;
+
+    let (_, organizations) = get(&app, "/api/organizations", &dev.token).await;
+    assert_eq!(organizations.as_array().map(Vec::len), Some(2));
+    let switch_uri = format!("/api/organizations/{}/switch", id_of(&acme));
+    let (status, _) = post(&app, &switch_uri, &outsider.token, json!({})).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, session) = post(&app, &switch_uri, &dev.token, json!({})).await;
+    assert_eq!(status, StatusCode::OK, "{}", session);
+    assert_eq!(session["organization"]["slug"], "acme");
+    let acme_token = session["token"].as_str().expect("token").to_string();
+
+    let (_, secret) = post(&app, "/api/projects", &acme_token, json!({"name": "Secret"})).await;
+    let (_, task) = post(&app, "/api/tasks", &acme_token, json!({"project_id": id_of(&secret), "title": "Plan"})).await;
+    let (_, public) = post(&app, "/api/projects", &outsider.token, json!({"name": "Public"})).await;
+
+    let (_, projects) = get(&app, "/api/projects", &acme_token).await;
+    assert_eq!(projects.as_array().map(Vec::len), Some(1));
+    assert_eq!(projects[0]["name"], "Secret");
+    let (_, projects) = get(&app, "/api/projects", &dev.token).await;
+    assert_eq!(projects.as_array().map(Vec::len), Some(1));
+    assert_eq!(projects[0]["name"], "Public");
+
+    let (status, _) = get(&app, &format!("/api/projects/{}", id_of(&secret)), &outsider.token).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = get(&app, &format!("/api/tasks/{}", id_of(&task)), &outsider.token).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = get(&app, &format!("/api/projects/{}", id_of(&public)), &acme_token).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let peek = json!({"project_id": id_of(&secret), "title": "Peek"});
+    let (status, _) = post(&app, "/api/tasks", &outsider.token, peek).await;
+    assert!(status.is_client_error());
+
+    let (_, users) = get(&app, "/api/users", &acme_token).await;
+    let emails: Vec<_> = users.as_array().expect("users").iter().map(|u| u["email"].clone()).collect();
+    assert_eq!(emails.len(), 2);
+    assert!(!emails.contains(&json!("outsider@example.com")));
+}
+
+#[actix_web::test]
+async fn leaving_an_organization_ends_its_sessions() {
+    let Some(ctx) = TestContext::new().await else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+    let admin = register_user(&app, "admin@example.com").await;
+    let admin_token = promote_to_admin(&ctx, &admin).await;
+    let dev = register_user(&app, "dev@example.com").await;
+
+    let (_, acme) = post(&app, "/api/admin/organizations", &admin_token, json!({"name": "Acme", "slug": "acme"})).await;
+    join(&app, &admin_token, id_of(&acme), &dev).await;
+    let members_uri = format!("/api/admin/organizations/{}/members", id_of(&acme));
+    let (_, session) = post(&app, &format!("/api/organizations/{}/switch", id_of(&acme)), &dev.token, json!({})).await;
+    let acme_token = session["token"].as_str().expect("token").to_string();
+    let (status, _) = get(&app, "/api/projects", &acme_token).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let member_uri = format!("{}/{}", members_uri, dev.id);
+    let (status, _) = delete(&app, &member_uri, &admin_token).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = delete(&app, &member_uri, &admin_token).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = get(&app, "/api/projects", &acme_token).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = get(&app, "/api/projects", &dev.token).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+/// The policies hold even for a query that forgets its organization filter,
+/// as long as the connection is not a superuser's. A connection that names no
+/// organization sees none, unless it asks for all of them.
+#[actix_web::test]
+async fn row_level_security_hides_other_organizations() {
+    let Some(ctx) = TestContext::new().await else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+    let admin = register_user(&app, "admin@example.com").await;
+    let admin_token = promote_to_admin(&ctx, &admin).await;
+    let (_, acme) = post(&app, "/api/admin/organizations", &admin_token, json!({"name": "Acme", "slug": "acme"})).await;
+    let switch_uri = format!("/api/organizations/{}/switch", id_of(&acme));
+    let (_, session) = post(&app, &switch_uri, &admin_token, json!({})).await;
+    let acme_token = session["token"].as_str().expect("token").to_string();
+    for (token, name) in [(&admin.token, "Default"), (&acme_token, "Acme")] {
+        let (_, project) = post(&app, "/api/projects", token, json!({"name": name})).await;
+        let (_, task) = post(&app, "/api/tasks", token, json!({"project_id": id_of(&project), "title": name})).await;
+        post(&app, &format!("/api/tasks/{}/comments", id_of(&task)), token, json!({"body": name})).await;
+    }
+
+    let role = format!("tracker_rls_{}", Uuid::new_v4().simple());
+    let mut conn = ctx.pool().acquire().await.expect("connection");
+    conn.execute(format!("CREATE ROLE {} NOLOGIN", role).as_str()).await.expect("create role");
+    conn.execute(format!("GRANT SELECT ON projects, tasks, comments TO {}", role).as_str()).await.expect("grant");
+
+    let mut tx = conn.begin().await.expect("transaction");
+    tx.execute(format!("SET LOCAL ROLE {}", role).as_str()).await.expect("set role");
+    let mut seen = Vec::new();
+    let scopes = [(id_of(&acme).to_string(), ""), (String::new(), ""), (String::new(), "on")];
+    for (organization, all_organizations) in scopes {
+        sqlx::query("SELECT set_config('app.organization_id', $1, true), set_config('app.all_organizations', $2, true)")
+            .bind(organization)
+            .bind(all_organizations)
+            .execute(&mut *tx)
+            .await
+            .expect("set organization");
+        let projects: Vec<String> =
+            sqlx::query_scalar("SELECT name FROM projects ORDER BY name").fetch_all(&mut *tx).await.expect("query");
+        let comments: Vec<String> =
+            sqlx::query_scalar("SELECT body FROM comments ORDER BY body").fetch_all(&mut *tx).await.expect("query");
+        seen.push((projects, comments));
+    }
+    tx.rollback().await.expect("rollback");
+
+    conn.execute(format!("REVOKE ALL ON projects, tasks, comments FROM {}", role).as_str()).await.expect("revoke");
+    conn.execute(format!("DROP ROLE {}", role).as_str()).await.expect("drop role");
+    let acme = vec!["Acme".to_string()];
+    let both = vec!["Acme".to_string(), "Default".to_string()];
+    assert_eq!(seen, vec![(acme.clone(), acme), (vec![], vec![]), (both.clone(), both)]);
+}
+
+#[actix_web::test]
+async fn admins_only_manage_organizations_they_belong_to() {
+    let Some(ctx) = TestContext::new().await else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+    let admin = register_user(&app, "admin@example.com").await;
+    let admin_token = promote_to_admin(&ctx, &admin).await;
+    let other = register_user(&app, "other-admin@example.com").await;
+    let other_token = promote_to_admin(&ctx, &other).await;
+    let dev = register_user(&app, "dev@example.com").await;
+
+    let (_, acme) = post(&app, "/api/admin/organizations", &admin_token, json!({"name": "Acme", "slug": "acme"})).await;
+    let (_, organizations) = get(&app, "/api/admin/organizations", &other_token).await;
+    let slugs: Vec<_> = organizations.as_array().expect("organizations").iter().map(|o| o["slug"].clone()).collect();
+    assert_eq!(slugs, vec![json!("default")]);
+
+    let members_uri = format!("/api/admin/organizations/{}/members", id_of(&acme));
+    let (status, _) = get(&app, &members_uri, &other_token).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let invitations_uri = format!("/api/admin/organizations/{}/invitations", id_of(&acme));
+    let (status, _) = post(&app, &invitations_uri, &other_token, json!({"user_id": dev.id})).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = post(&app, &invitations_uri, &other_token, json!({"user_id": other.id})).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = delete(&app, &format!("{}/{}", members_uri, admin.id), &other_token).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, members) = get(&app, &members_uri, &admin_token).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(members.as_array().map(Vec::len), Some(1));
+}
+
+#[actix_web::test]
+async fn users_join_other_organizations_only_by_accepting() {
+    let Some(ctx) = TestContext::new().await else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+    let admin = register_user(&app, "admin@example.com").await;
+    let admin_token = promote_to_admin(&ctx, &admin).await;
+    let dev = register_user(&app, "dev@example.com").await;
+    let outsider = register_user(&app, "outsider@example.com").await;
+    let (_, acme) = post(&app, "/api/admin/organizations", &admin_token, json!({"name": "Acme", "slug": "acme"})).await;
+    let switch_uri = format!("/api/organizations/{}/switch", id_of(&acme));
+
+    let invitations_uri = format!("/api/admin/organizations/{}/invitations", id_of(&acme));
+    let (status, invitation) = post(&app, &invitations_uri, &admin_token, json!({"user_id": dev.id})).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", invitation);
+    let (status, _) = post(&app, &invitations_uri, &admin_token, json!({"user_id": dev.id})).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let (status, _) = post(&app, &invitations_uri, &admin_token, json!({"user_id": admin.id})).await;
+    assert_eq!(status, StatusCode::CONFLICT, "a member was invited");
+    let (status, _) = post(&app, &invitations_uri, &admin_token, json!({"user_id": Uuid::new_v4()})).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // Until dev accepts, they are not in Acme
+    let (status, _) = post(&app, &switch_uri, &dev.token, json!({})).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (_, pending) = get(&app, "/api/organizations/invitations", &dev.token).await;
+    assert_eq!(pending.as_array().map(Vec::len), Some(1));
+    let accept_uri = format!("/api/organizations/invitations/{}/accept", id_of(&invitation));
+    let (status, _) = post(&app, &accept_uri, &outsider.token, json!({})).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = post(&app, &accept_uri, &dev.token, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = post(&app, &accept_uri, &dev.token, json!({})).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let (status, _) = post(&app, &switch_uri, &dev.token, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, invitation) = post(&app, &invitations_uri, &admin_token, json!({"user_id": outsider.id})).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", invitation);
+    let decline_uri = format!("/api/organizations/invitations/{}/decline", id_of(&invitation));
+    let (status, declined) = post(&app, &decline_uri, &outsider.token, json!({})).await;
+    assert_eq!((status, &declined["status"]), (StatusCode::OK, &json!("declined")));
+    let (status, _) = post(&app, &switch_uri, &outsider.token, json!({})).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // dev's account is shared with the default organization, so Acme's admin cannot change it
+    let (_, session) = post(&app, &switch_uri, &admin_token, json!({})).await;
+    let acme_token = session["token"].as_str().expect("token").to_string();
+    let role_uri = format!("/api/admin/users/{}/role", dev.id);
+    let (status, _) = put(&app, &role_uri, &acme_token, json!({"role": "admin"})).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    for action in ["deactivate", "reactivate", "require-password-reset"] {
+        let (status, _) = post(&app, &format!("/api/admin/users/{}/{}", dev.id, action), &acme_token, json!({})).await;
+        assert_eq!(status, StatusCode::FORBIDDEN, "{}", action);
+    }
+}
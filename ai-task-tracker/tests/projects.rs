@@ -0,0 +1,159 @@
+mod common;
+
+use actix_web::{body::MessageBody, http::StatusCode, test};
+use serde_json::{json, Value};
+
+use ai_task_tracker::build_app;
+use common::{delete, get, id_of, post, put, register_user, TestContext, TestService};
+
+#[actix_web::test]
+async fn projects_are_created_updated_and_listed_by_team() {
+    let Some(ctx) = TestContext::new().await else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+    let user = register_user(&app, "pm@example.com").await;
+    let (_, team) = post(&app, "/api/teams", &user.token, json!({"name": "Platform"})).await;
+    let team_id = id_of(&team);
+
+    let (status, project) = post(
+        &app,
+        "/api/projects",
+        &user.token,
+        json!({"name": "Launch", "description": "Ship it", "team_id": team_id}),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(project["team_id"], team_id.to_string());
+    let project_uri = format!("/api/projects/{}", id_of(&project));
+
+    let (status, updated) = put(&app, &project_uri, &user.token, json!({"name": "Launch v2"})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(updated["name"], "Launch v2");
+    assert_eq!(updated["description"], "Ship it");
+
+    post(&app, "/api/projects", &user.token, json!({"name": "Side project"})).await;
+
+    let (_, all) = get(&app, "/api/projects", &user.token).await;
+    assert_eq!(all.as_array().map(Vec::len), Some(2));
+    let (_, team_projects) = get(&app, &format!("/api/projects?team_id={}", team_id), &user.token).await;
+    assert_eq!(team_projects.as_array().map(Vec::len), Some(1));
+    assert_eq!(team_projects[0]["name"], "Launch v2");
+}
+
+#[actix_web::test]
+async fn unknown_projects_are_not_found() {
+    let Some(ctx) = TestContext::new().await else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+    let user = register_user(&app, "pm@example.com").await;
+
+    let (status, body) = get(&app, &format!("/api/projects/{}", uuid::Uuid::new_v4()), &user.token).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert!(body["error"].is_string());
+}
+
+#[actix_web::test]
+async fn deleted_projects_go_to_the_trash() {
+    let Some(ctx) = TestContext::new().await else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+    let user = register_user(&app, "pm@example.com").await;
+    let (_, project) = post(&app, "/api/projects", &user.token, json!({"name": "Launch"})).await;
+    let project_uri = format!("/api/projects/{}", id_of(&project));
+
+    let (status, _) = delete(&app, &project_uri, &user.token).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = get(&app, &project_uri, &user.token).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (_, trash) = get(&app, &format!("{}/trash", project_uri), &user.token).await;
+    assert_eq!(trash[0]["kind"], "project");
+
+    let (status, _) = post(&app, &format!("{}/restore", project_uri), &user.token, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = get(&app, &project_uri, &user.token).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+async fn import_csv<B: MessageBody>(
+    app: &impl TestService<B>,
+    uri: &str,
+    token: &str,
+    csv: &str,
+) -> (StatusCode, Value) {
+    let req = test::TestRequest::post()
+        .uri(uri)
+        .insert_header(("Authorization", format!("Bearer {}", token)))
+        .insert_header(("Content-Type", "text/csv"))
+        .set_payload(csv.to_string())
+        .to_request();
+    let response = test::call_service(app, req).await;
+    (response.status(), test::read_body_json(response).await)
+}
+
+/// `(row, field)` of each error in an import report.
+fn error_fields(report: &Value) -> Vec<(u64, &str)> {
+    let errors = report["errors"].as_array().expect("errors");
+    errors.iter().map(|e| (e["row"].as_u64().unwrap(), e["field"].as_str().unwrap_or_default())).collect()
+}
+
+#[actix_web::test]
+async fn imports_report_every_invalid_row_and_create_nothing_until_fixed() {
+    let Some(ctx) = TestContext::new().await else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+    let user = register_user(&app, "pm@example.com").await;
+    let (_, project) = post(&app, "/api/projects", &user.token, json!({"name": "Migration"})).await;
+    let import_uri = format!("/api/projects/{}/import?map.title=Headline", id_of(&project));
+    let tasks_uri = format!("/api/tasks?project_id={}", id_of(&project));
+
+    // The header is line 1, so the first row is line 2
+    let invalid = "Headline,ref,parent,priority,assignee,due,record_type,is_completed
+Plan,A,B,high,,,task,
+Build,B,A,low,,,task,
+,C,,high,,,task,
+Test,D,,critical,,,task,
+Ship,E,,,nobody@example.com,,task,
+Review,F,,,,next friday,task,
+Again,A,,,,,task,
+Check boxes,,,,,,subtask,yes
+Orphan,G,Z,,,,task,
+Notes,,,,,,history,
+";
+    let (status, report) = import_csv(&app, &format!("{}&dry_run=true", import_uri), &user.token, invalid).await;
+    assert_eq!(status, StatusCode::OK, "{}", report);
+    assert_eq!(
+        error_fields(&report),
+        vec![
+            (2, "parent_id"),
+            (3, "parent_id"),
+            (4, "title"),
+            (5, "priority"),
+            (6, "assignee_email"),
+            (7, "due_date"),
+            (8, "id"),
+            (9, "parent_id"),
+            (10, "parent_id"),
+        ]
+    );
+    assert_eq!(report["errors"][0]["message"], "Parent references form a cycle");
+    assert_eq!((report["total_rows"].as_u64(), report["skipped_rows"].as_u64()), (Some(10), Some(1)));
+
+    let (status, report) = import_csv(&app, &import_uri, &user.token, invalid).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(report["created_tasks"], 0);
+    let (_, tasks) = get(&app, &tasks_uri, &user.token).await;
+    assert_eq!(tasks.as_array().map(Vec::len), Some(0));
+
+    let valid = "Headline,ref,parent,assignee,due,record_type,is_completed
+Launch,L,,pm@example.com,2026-11-01,task,
+Write docs,W,L,,,task,
+Proofread,,W,,,subtask,yes
+";
+    let (status, report) = import_csv(&app, &import_uri, &user.token, valid).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", report);
+    assert_eq!((report["created_tasks"].as_u64(), report["created_subtasks"].as_u64()), (Some(2), Some(1)));
+
+    let (_, tasks) = get(&app, &tasks_uri, &user.token).await;
+    let tasks = tasks.as_array().expect("tasks");
+    let launch = tasks.iter().find(|t| t["title"] == "Launch").expect("Launch");
+    let docs = tasks.iter().find(|t| t["title"] == "Write docs").expect("Write docs");
+    assert_eq!(launch["assignee_id"], user.id.to_string());
+    assert_eq!(launch["due_date"], "2026-11-01T00:00:00Z");
+    assert_eq!(docs["parent_task_id"], launch["id"]);
+}
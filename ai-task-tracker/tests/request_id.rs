@@ -0,0 +1,29 @@
+mod common;
+
+use actix_web::{http::StatusCode, test};
+
+use ai_task_tracker::build_app;
+use common::MemoryContext;
+
+#[actix_web::test]
+async fn request_ids_are_echoed_or_generated() {
+    let ctx = MemoryContext::new();
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+
+    let req = test::TestRequest::get().uri("/healthz").insert_header(("X-Request-Id", "abc-123"));
+    let res = test::call_service(&app, req.to_request()).await;
+    assert_eq!(res.headers().get("x-request-id").unwrap(), "abc-123");
+
+    // Unusable ids are replaced rather than echoed
+    let req = test::TestRequest::get().uri("/healthz").insert_header(("X-Request-Id", "has spaces"));
+    let res = test::call_service(&app, req.to_request()).await;
+    let generated = res.headers().get("x-request-id").unwrap().to_str().unwrap();
+    assert!(uuid::Uuid::parse_str(generated).is_ok(), "{}", generated);
+
+    // Rejections by the auth middleware carry one too
+    let req = test::TestRequest::get().uri("/api/tasks").insert_header(("X-Request-Id", "def-456"));
+    let err = test::try_call_service(&app, req.to_request()).await.err().expect("unauthorized");
+    let res = err.error_response();
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(res.headers().get("x-request-id").unwrap(), "def-456");
+}
@@ -0,0 +1,42 @@
+mod common;
+
+use actix_web::{http::StatusCode, test};
+use serde_json::json;
+
+use ai_task_tracker::build_app;
+use common::{delete, get, id_of, post, register_user, TestContext};
+
+#[actix_web::test]
+async fn closing_a_sprint_leaves_trashed_tasks_behind() {
+    let Some(ctx) = TestContext::new().await else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+    let dev = register_user(&app, "dev@example.com").await;
+    let (_, project) = post(&app, "/api/projects", &dev.token, json!({"name": "Launch"})).await;
+    let project_id = id_of(&project);
+
+    let mut sprints = Vec::new();
+    for (name, start, end) in [("Sprint 1", "2026-10-05", "2026-10-16"), ("Sprint 2", "2026-10-19", "2026-10-30")] {
+        let body = json!({"project_id": project_id, "name": name, "start_date": start, "end_date": end});
+        let (status, sprint) = post(&app, "/api/sprints", &dev.token, body).await;
+        assert_eq!(status, StatusCode::CREATED, "{}", sprint);
+        sprints.push(id_of(&sprint));
+    }
+    let mut tasks = Vec::new();
+    for title in ["Kept", "Trashed"] {
+        let (_, task) = post(&app, "/api/tasks", &dev.token, json!({"project_id": project_id, "title": title})).await;
+        tasks.push(id_of(&task));
+    }
+    let uri = format!("/api/sprints/{}", sprints[0]);
+    post(&app, &format!("{}/tasks", uri), &dev.token, json!({"task_ids": tasks})).await;
+    post(&app, &format!("{}/start", uri), &dev.token, json!({})).await;
+    let (status, _) = delete(&app, &format!("/api/tasks/{}", tasks[1]), &dev.token).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, closed) = post(&app, &format!("{}/close", uri), &dev.token, json!({"move_to_next": true})).await;
+    assert_eq!(status, StatusCode::OK, "{}", closed);
+    assert_eq!(closed["moved_task_ids"], json!([tasks[0]]));
+
+    let (_, next) = get(&app, &format!("/api/sprints/{}/tasks", sprints[1]), &dev.token).await;
+    let titles: Vec<&str> = next.as_array().unwrap().iter().map(|t| t["title"].as_str().unwrap()).collect();
+    assert_eq!(titles, vec!["Kept"]);
+}
@@ -0,0 +1,181 @@
+mod common;
+
+use actix_web::{http::StatusCode, test};
+use serde_json::json;
+use uuid::Uuid;
+
+use ai_task_tracker::build_app;
+use common::{delete, get, id_of, post, put, register_user, TestContext, TestService, TestUser};
+
+/// A multipart body with one file part per `(file name, contents)`.
+fn multipart_body(files: &[(&str, &str)]) -> String {
+    let mut body = String::new();
+    for (name, contents) in files {
+        body.push_str(&format!(
+            "--boundary\r\nContent-Disposition: form-data; name=\"file\"; filename=\"{}\"\r\n\r\n{}\r\n",
+            name, contents
+        ));
+    }
+    body + "--boundary--\r\n"
+}
+
+async fn create_project<B: actix_web::body::MessageBody>(app: &impl TestService<B>, user: &TestUser) -> Uuid {
+    let (status, project) = post(app, "/api/projects", &user.token, json!({"name": "Launch"})).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", project);
+    id_of(&project)
+}
+
+#[actix_web::test]
+async fn tasks_are_created_updated_and_filtered() {
+    let Some(ctx) = TestContext::new().await else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+    let user = register_user(&app, "dev@example.com").await;
+    let project_id = create_project(&app, &user).await;
+    let other_project_id = create_project(&app, &user).await;
+
+    let (status, task) = post(
+        &app,
+        "/api/tasks",
+        &user.token,
+        json!({"project_id": project_id, "title": "Write docs", "priority": "high", "assignee_id": user.id}),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(task["status"], "todo");
+    assert_eq!(task["priority"], "high");
+    let task_uri = format!("/api/tasks/{}", id_of(&task));
+
+    let (status, task) = put(&app, &task_uri, &user.token, json!({"status": "in_progress"})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(task["status"], "in_progress");
+    assert_eq!(task["title"], "Write docs");
+
+    post(&app, "/api/tasks", &user.token, json!({"project_id": other_project_id, "title": "Elsewhere"})).await;
+
+    let (_, tasks) = get(&app, &format!("/api/tasks?project_id={}", project_id), &user.token).await;
+    assert_eq!(tasks.as_array().map(Vec::len), Some(1));
+    let (_, tasks) = get(&app, "/api/tasks?status=in_progress", &user.token).await;
+    assert_eq!(tasks.as_array().map(Vec::len), Some(1));
+    let (_, tasks) = get(&app, &format!("/api/tasks?assignee_id={}", user.id), &user.token).await;
+    assert_eq!(tasks[0]["title"], "Write docs");
+}
+
+#[actix_web::test]
+async fn tasks_need_an_existing_project() {
+    let Some(ctx) = TestContext::new().await else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+    let user = register_user(&app, "dev@example.com").await;
+
+    let body = json!({"project_id": Uuid::new_v4(), "title": "Orphan"});
+    let (status, _) = post(&app, "/api/tasks", &user.token, body).await;
+    assert!(status.is_client_error(), "unexpected {}", status);
+}
+
+#[actix_web::test]
+async fn subtasks_are_tracked_per_task() {
+    let Some(ctx) = TestContext::new().await else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+    let user = register_user(&app, "dev@example.com").await;
+    let project_id = create_project(&app, &user).await;
+    let (_, task) = post(&app, "/api/tasks", &user.token, json!({"project_id": project_id, "title": "Release"})).await;
+    let subtasks_uri = format!("/api/tasks/{}/subtasks", id_of(&task));
+
+    let (status, subtask) = post(&app, &subtasks_uri, &user.token, json!({"title": "Tag the build"})).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(subtask["is_completed"], false);
+    post(&app, &subtasks_uri, &user.token, json!({"title": "Announce"})).await;
+
+    let subtask_uri = format!("/api/subtasks/{}", id_of(&subtask));
+    let (status, subtask) = put(&app, &subtask_uri, &user.token, json!({"is_completed": true})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(subtask["is_completed"], true);
+
+    let (status, _) = delete(&app, &subtask_uri, &user.token).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (_, subtasks) = get(&app, &subtasks_uri, &user.token).await;
+    assert_eq!(subtasks.as_array().map(Vec::len), Some(1));
+    assert_eq!(subtasks[0]["title"], "Announce");
+}
+
+#[actix_web::test]
+async fn history_entries_record_who_reported_progress() {
+    let Some(ctx) = TestContext::new().await else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+    let user = register_user(&app, "dev@example.com").await;
+    let project_id = create_project(&app, &user).await;
+    let (_, task) = post(&app, "/api/tasks", &user.token, json!({"project_id": project_id, "title": "Release"})).await;
+    let history_uri = format!("/api/tasks/{}/history", id_of(&task));
+
+    let (status, entry) = post(
+        &app,
+        &history_uri,
+        &user.token,
+        json!({"comment": "Halfway there", "completion_percentage": 50}),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(entry["user_id"], user.id.to_string());
+
+    let (status, history) = get(&app, &history_uri, &user.token).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(history.as_array().map(Vec::len), Some(1));
+    assert_eq!(history[0]["comment"], "Halfway there");
+    assert_eq!(history[0]["completion_percentage"], 50);
+    assert_eq!(history[0]["user_email"], "dev@example.com");
+}
+
+#[actix_web::test]
+async fn deleted_tasks_can_be_restored() {
+    let Some(ctx) = TestContext::new().await else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+    let user = register_user(&app, "dev@example.com").await;
+    let project_id = create_project(&app, &user).await;
+    let (_, task) = post(&app, "/api/tasks", &user.token, json!({"project_id": project_id, "title": "Release"})).await;
+    let task_uri = format!("/api/tasks/{}", id_of(&task));
+
+    let (status, _) = delete(&app, &task_uri, &user.token).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = get(&app, &task_uri, &user.token).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (_, tasks) = get(&app, &format!("/api/tasks?project_id={}", project_id), &user.token).await;
+    assert_eq!(tasks.as_array().map(Vec::len), Some(0));
+
+    let (status, _) = post(&app, &format!("{}/restore", task_uri), &user.token, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = get(&app, &task_uri, &user.token).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn uploads_with_an_oversized_file_store_none_of_their_files() {
+    let Some(mut ctx) = TestContext::new().await else { return };
+    ctx.state.config.storage.max_attachment_bytes = 16;
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+    let user = register_user(&app, "dev@example.com").await;
+    let project_id = create_project(&app, &user).await;
+    let (_, task) = post(&app, "/api/tasks", &user.token, json!({"project_id": project_id, "title": "Ship"})).await;
+    let attachments_uri = format!("/api/tasks/{}/attachments", id_of(&task));
+
+    let upload = |body: String| {
+        let req = test::TestRequest::post()
+            .uri(&attachments_uri)
+            .insert_header(("Authorization", format!("Bearer {}", user.token)))
+            .insert_header(("Content-Type", "multipart/form-data; boundary=boundary"))
+            .set_payload(body)
+            .to_request();
+        test::call_service(&app, req)
+    };
+
+    let response = upload(multipart_body(&[("notes.txt", "small"), ("dump.txt", &"x".repeat(32))])).await;
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    let (_, attachments) = get(&app, &attachments_uri, &user.token).await;
+    assert_eq!(attachments, json!([]), "the file before the oversized one was kept");
+    let blobs = std::fs::read_dir(&ctx.state.config.storage.local_path).unwrap().count();
+    assert_eq!(blobs, 0, "a blob was left behind");
+
+    let response = upload(multipart_body(&[("notes.txt", "small"), ("todo.txt", "also small")])).await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let (_, attachments) = get(&app, &attachments_uri, &user.token).await;
+    assert_eq!(attachments.as_array().map(Vec::len), Some(2));
+}
@@ -0,0 +1,136 @@
+mod common;
+
+use actix_web::{http::StatusCode, test};
+use serde_json::json;
+
+use ai_task_tracker::build_app;
+use common::{delete, get, id_of, post, promote_to_admin, put, register_user, TestContext};
+
+#[actix_web::test]
+async fn creator_becomes_the_owner() {
+    let Some(ctx) = TestContext::new().await else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+    let owner = register_user(&app, "owner@example.com").await;
+
+    let (status, team) = post(&app, "/api/teams", &owner.token, json!({"name": "Platform"})).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(team["name"], "Platform");
+
+    let (status, members) = get(&app, &format!("/api/teams/{}/members", id_of(&team)), &owner.token).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(members[0]["user_id"], owner.id.to_string());
+    assert_eq!(members[0]["team_role"], "owner");
+}
+
+#[actix_web::test]
+async fn owners_add_members_and_invite_unknown_emails() {
+    let Some(ctx) = TestContext::new().await else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+    let owner = register_user(&app, "owner@example.com").await;
+    register_user(&app, "dev@example.com").await;
+    let (_, team) = post(&app, "/api/teams", &owner.token, json!({"name": "Platform"})).await;
+    let members_uri = format!("/api/teams/{}/members", id_of(&team));
+
+    let (status, member) = post(&app, &members_uri, &owner.token, json!({"email": "dev@example.com"})).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(member["role"], "member");
+
+    let invite = json!({"email": "new@example.com"});
+    let (status, invitation) = post(&app, &members_uri, &owner.token, invite.clone()).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    assert_eq!(invitation["status"], "pending");
+
+    let (status, _) = post(&app, &members_uri, &owner.token, invite).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    // The invitation is claimed when the invitee registers
+    let newcomer = register_user(&app, "new@example.com").await;
+    let (_, invitations) = get(&app, "/api/invitations", &newcomer.token).await;
+    assert_eq!(invitations.as_array().map(Vec::len), Some(1));
+}
+
+#[actix_web::test]
+async fn members_cannot_manage_the_team() {
+    let Some(ctx) = TestContext::new().await else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+    let owner = register_user(&app, "owner@example.com").await;
+    let dev = register_user(&app, "dev@example.com").await;
+    let (_, team) = post(&app, "/api/teams", &owner.token, json!({"name": "Platform"})).await;
+    let members_uri = format!("/api/teams/{}/members", id_of(&team));
+    post(&app, &members_uri, &owner.token, json!({"user_id": dev.id})).await;
+
+    let (status, _) = post(&app, &members_uri, &dev.token, json!({"email": "other@example.com"})).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let owner_uri = format!("{}/{}", members_uri, owner.id);
+    let (status, _) = put(&app, &owner_uri, &dev.token, json!({"role": "member"})).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[actix_web::test]
+async fn admins_manage_any_team() {
+    let Some(ctx) = TestContext::new().await else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+    let owner = register_user(&app, "owner@example.com").await;
+    let dev = register_user(&app, "dev@example.com").await;
+    let admin = register_user(&app, "admin@example.com").await;
+    let admin_token = promote_to_admin(&ctx, &admin).await;
+    let (_, team) = post(&app, "/api/teams", &owner.token, json!({"name": "Platform"})).await;
+
+    let members_uri = format!("/api/teams/{}/members", id_of(&team));
+    let (status, _) = post(&app, &members_uri, &admin_token, json!({"user_id": dev.id, "role": "maintainer"})).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (_, members) = get(&app, &members_uri, &owner.token).await;
+    assert_eq!(members.as_array().map(Vec::len), Some(2));
+}
+
+#[actix_web::test]
+async fn deleted_teams_can_be_restored() {
+    let Some(ctx) = TestContext::new().await else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+    let owner = register_user(&app, "owner@example.com").await;
+    let (_, team) = post(&app, "/api/teams", &owner.token, json!({"name": "Platform"})).await;
+    let team_uri = format!("/api/teams/{}", id_of(&team));
+
+    let (status, _) = delete(&app, &team_uri, &owner.token).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = get(&app, &team_uri, &owner.token).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, restored) = post(&app, &format!("{}/restore", team_uri), &owner.token, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(restored["name"], "Platform");
+    let (status, _) = get(&app, &team_uri, &owner.token).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn team_roles_gate_editing_and_deleting_the_team() {
+    let Some(ctx) = TestContext::new().await else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+    let owner = register_user(&app, "owner@example.com").await;
+    let maintainer = register_user(&app, "maintainer@example.com").await;
+    let viewer = register_user(&app, "viewer@example.com").await;
+    let (_, team) = post(&app, "/api/teams", &owner.token, json!({"name": "Platform"})).await;
+    let team_uri = format!("/api/teams/{}", id_of(&team));
+    let members_uri = format!("{}/members", team_uri);
+    for (user, role) in [(&maintainer, "maintainer"), (&viewer, "viewer")] {
+        let (status, _) = post(&app, &members_uri, &owner.token, json!({"user_id": user.id, "role": role})).await;
+        assert_eq!(status, StatusCode::CREATED);
+    }
+    let (status, _) = post(&app, &members_uri, &owner.token, json!({"user_id": viewer.id})).await;
+    assert_eq!(status, StatusCode::CONFLICT, "a member was added twice");
+
+    let (status, _) = put(&app, &team_uri, &viewer.token, json!({"name": "Renamed"})).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, renamed) = put(&app, &team_uri, &maintainer.token, json!({"name": "Core"})).await;
+    assert_eq!((status, &renamed["name"]), (StatusCode::OK, &json!("Core")));
+
+    for user in [&viewer, &maintainer] {
+        let (status, _) = delete(&app, &team_uri, &user.token).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+    let (status, _) = delete(&app, &team_uri, &owner.token).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use actix_web::{http::StatusCode, test};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use ai_task_tracker::build_app;
+use common::{get, id_of, post, register_user, TestContext};
+
+/// The onboarding tree: two roots, one with a child task and subtasks.
+fn onboarding_tasks() -> Value {
+    json!([
+        {
+            "title": "Kickoff",
+            "due_offset_days": 0,
+            "assignee_role": "owner",
+            "subtasks": ["Agenda", "Invite"],
+            "children": [{"title": "Contract", "due_offset_days": 3, "estimate_minutes": 60}],
+        },
+        {"title": "Handover", "due_offset_days": 30},
+    ])
+}
+
+#[actix_web::test]
+async fn instantiating_a_template_creates_its_whole_tree_or_nothing() {
+    let Some(ctx) = TestContext::new().await else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+    let dev = register_user(&app, "dev@example.com").await;
+
+    let body = json!({"name": "Onboarding", "tasks": [{"title": "  "}]});
+    let (status, _) = post(&app, "/api/templates", &dev.token, body).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "a task without a title was saved");
+    let body = json!({"name": "Onboarding", "tasks": onboarding_tasks()});
+    let (status, template) = post(&app, "/api/templates", &dev.token, body).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", template);
+    let uri = format!("/api/templates/{}/instantiate", id_of(&template));
+
+    // An unknown assignee fails the whole instantiation, project included
+    let body = json!({"name": "Broken", "assignees": {"owner": Uuid::new_v4()}});
+    let (status, _) = post(&app, &uri, &dev.token, body).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (_, projects) = get(&app, "/api/projects", &dev.token).await;
+    assert!(projects.as_array().unwrap().iter().all(|p| p["name"] != "Broken"), "{}", projects);
+
+    let body = json!({"name": "Acme onboarding", "start_date": "2026-11-02", "assignees": {"owner": dev.id}});
+    let (status, result) = post(&app, &uri, &dev.token, body).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", result);
+    assert_eq!((result["created_tasks"].as_u64(), result["created_subtasks"].as_u64()), (Some(3), Some(2)));
+    assert_eq!(result["project"]["name"], "Acme onboarding");
+
+    let project_id = id_of(&result["project"]);
+    let (_, tasks) = get(&app, &format!("/api/tasks?project_id={}", project_id), &dev.token).await;
+    let task = |title: &str| tasks.as_array().unwrap().iter().find(|t| t["title"] == title).unwrap().clone();
+    let (kickoff, contract, handover) = (task("Kickoff"), task("Contract"), task("Handover"));
+    assert_eq!(contract["parent_task_id"], kickoff["id"]);
+    assert!(handover["parent_task_id"].is_null());
+    assert_eq!(kickoff["assignee_id"], json!(dev.id));
+    assert!(contract["assignee_id"].is_null());
+    assert_eq!(contract["estimate_minutes"], 60);
+    for (task, due) in [(&kickoff, "2026-11-02"), (&contract, "2026-11-05"), (&handover, "2026-12-02")] {
+        assert!(task["due_date"].as_str().unwrap().starts_with(due), "{}", task);
+    }
+    let (_, subtasks) = get(&app, &format!("/api/tasks/{}/subtasks", id_of(&kickoff)), &dev.token).await;
+    let mut titles: Vec<&str> = subtasks.as_array().unwrap().iter().map(|s| s["title"].as_str().unwrap()).collect();
+    titles.sort();
+    assert_eq!(titles, vec!["Agenda", "Invite"]);
+
+    // Saving the project back as a template captures the same tree
+    let body = json!({"name": "Acme copy", "start_date": "2026-11-02"});
+    let (status, copy) = post(&app, &format!("/api/templates/from-project/{}", project_id), &dev.token, body).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", copy);
+    let (_, copy) = get(&app, &format!("/api/templates/{}", id_of(&copy)), &dev.token).await;
+    let tree = &copy["tasks"];
+    assert_eq!((tree[0]["title"].as_str(), tree[1]["title"].as_str()), (Some("Kickoff"), Some("Handover")));
+    assert_eq!(tree[0]["children"][0]["title"], "Contract");
+    assert_eq!(tree[0]["children"][0]["due_offset_days"], 3);
+    assert_eq!(tree[1]["due_offset_days"], 30);
+}
@@ -0,0 +1,90 @@
+mod common;
+
+use actix_web::{http::StatusCode, test};
+use chrono::{Duration, Utc};
+use futures_util::future::join;
+use serde_json::json;
+
+use ai_task_tracker::build_app;
+use common::{get, id_of, post, register_user, TestContext};
+
+#[actix_web::test]
+async fn each_user_runs_one_timer_at_a_time() {
+    let Some(ctx) = TestContext::new().await else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+    let dev = register_user(&app, "dev@example.com").await;
+    let (_, project) = post(&app, "/api/projects", &dev.token, json!({"name": "Billing"})).await;
+    let body = json!({"project_id": id_of(&project), "title": "Invoice run", "estimate_minutes": 120});
+    let (_, task) = post(&app, "/api/tasks", &dev.token, body).await;
+    let timer = json!({"task_id": id_of(&task)});
+
+    // Two starts racing each other still leave a single running timer
+    let ((first, a), (second, b)) = join(
+        post(&app, "/api/time-entries/timer", &dev.token, timer.clone()),
+        post(&app, "/api/time-entries/timer", &dev.token, timer.clone()),
+    )
+    .await;
+    let mut statuses = [first, second];
+    statuses.sort();
+    assert_eq!(statuses, [StatusCode::CREATED, StatusCode::CONFLICT], "{} / {}", a, b);
+    let (running, conflict) = if first == StatusCode::CREATED { (a, b) } else { (b, a) };
+    assert_eq!(id_of(&conflict["running"]), id_of(&running));
+
+    let (status, current) = get(&app, "/api/time-entries/timer", &dev.token).await;
+    assert_eq!((status, id_of(&current)), (StatusCode::OK, id_of(&running)));
+
+    let (status, stopped) = post(&app, "/api/time-entries/timer/stop", &dev.token, json!({})).await;
+    assert_eq!(status, StatusCode::OK, "{}", stopped);
+    assert!(stopped["ended_at"].is_string());
+    let (status, _) = post(&app, "/api/time-entries/timer/stop", &dev.token, json!({})).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // Once stopped, a new timer can start
+    let (status, restarted) = post(&app, "/api/time-entries/timer", &dev.token, timer).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", restarted);
+    assert_ne!(id_of(&restarted), id_of(&running));
+}
+
+#[actix_web::test]
+async fn timesheets_total_finished_work_and_export_csv() {
+    let Some(ctx) = TestContext::new().await else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+    let dev = register_user(&app, "dev@example.com").await;
+    let other = register_user(&app, "other@example.com").await;
+    let (_, project) = post(&app, "/api/projects", &dev.token, json!({"name": "Billing"})).await;
+    let project_id = id_of(&project);
+    let body = json!({"project_id": project_id, "title": "Invoice run"});
+    let (_, task) = post(&app, "/api/tasks", &dev.token, body).await;
+
+    let started_at = Utc::now() - Duration::hours(3);
+    let body = json!({"task_id": id_of(&task), "started_at": started_at, "duration_minutes": 90});
+    let (status, entry) = post(&app, "/api/time-entries", &dev.token, body).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", entry);
+    assert_eq!(entry["duration_minutes"], 90);
+
+    let body = json!({"task_id": id_of(&task), "started_at": started_at, "ended_at": Utc::now() + Duration::hours(1)});
+    let (status, _) = post(&app, "/api/time-entries", &dev.token, body).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "an entry ending in the future was logged");
+
+    // A running timer is not counted until it stops
+    post(&app, "/api/time-entries/timer", &dev.token, json!({"task_id": id_of(&task)})).await;
+    let (status, timesheet) = get(&app, &format!("/api/timesheets/projects/{}", project_id), &dev.token).await;
+    assert_eq!(status, StatusCode::OK, "{}", timesheet);
+    assert_eq!(timesheet["total_minutes"], 90);
+
+    let uri = format!("/api/timesheets/users/{}?format=csv", dev.id);
+    let req = test::TestRequest::get()
+        .uri(&uri)
+        .insert_header(("Authorization", format!("Bearer {}", dev.token)))
+        .to_request();
+    let response = test::call_service(&app, req).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers().get("content-type").unwrap(), "text/csv; charset=utf-8");
+    let csv = String::from_utf8(test::read_body(response).await.to_vec()).unwrap();
+    let minutes: i64 = csv.lines().skip(1).map(|line| line.split(',').nth(7).unwrap().parse::<i64>().unwrap()).sum();
+    assert_eq!(minutes, 90, "{}", csv);
+    assert!(csv.contains("Invoice run"));
+
+    let (status, _) = get(&app, &format!("/api/timesheets/users/{}", dev.id), &other.token).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
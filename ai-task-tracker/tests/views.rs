@@ -0,0 +1,144 @@
+mod common;
+
+use actix_web::{http::StatusCode, test};
+use chrono::{Duration, Utc};
+use serde_json::{json, Value};
+
+use ai_task_tracker::{build_app, models::MY_TASKS_VIEW_ID};
+use common::{delete, get, id_of, post, put, register_user, TestContext};
+
+/// The titles of a view's tasks, group by group.
+fn titles(result: &Value) -> Vec<Vec<String>> {
+    result["groups"]
+        .as_array()
+        .expect("groups")
+        .iter()
+        .map(|group| {
+            let tasks = group["tasks"].as_array().expect("tasks");
+            tasks.iter().map(|t| t["title"].as_str().unwrap_or_default().to_string()).collect()
+        })
+        .collect()
+}
+
+#[actix_web::test]
+async fn my_tasks_is_a_built_in_view() {
+    let Some(ctx) = TestContext::new().await else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+    let dev = register_user(&app, "dev@example.com").await;
+    let other = register_user(&app, "other@example.com").await;
+    let (_, project) = post(&app, "/api/projects", &dev.token, json!({"name": "Launch"})).await;
+    let project_id = id_of(&project);
+
+    let soon = (Utc::now() + Duration::days(1)).to_rfc3339();
+    let later = (Utc::now() + Duration::days(20)).to_rfc3339();
+    for (title, due, assignee) in [("Later", &later, dev.id), ("Soon", &soon, dev.id), ("Theirs", &soon, other.id)] {
+        let body = json!({"project_id": project_id, "title": title, "due_date": due, "assignee_id": assignee});
+        post(&app, "/api/tasks", &dev.token, body).await;
+    }
+    let body = json!({"project_id": project_id, "title": "Finished", "assignee_id": dev.id});
+    let (_, finished) = post(&app, "/api/tasks", &dev.token, body).await;
+    put(&app, &format!("/api/tasks/{}", id_of(&finished)), &dev.token, json!({"status": "done"})).await;
+
+    let (status, views) = get(&app, "/api/views", &dev.token).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(views[0]["id"], MY_TASKS_VIEW_ID.to_string());
+    assert_eq!(views[0]["name"], "My Tasks");
+
+    let view_uri = format!("/api/views/{}", MY_TASKS_VIEW_ID);
+    let (status, result) = get(&app, &format!("{}/tasks", view_uri), &dev.token).await;
+    assert_eq!(status, StatusCode::OK, "{}", result);
+    assert_eq!(result["groups"][0]["key"], "todo");
+    assert_eq!(result["groups"][1]["key"], "done");
+    assert_eq!(titles(&result), vec![vec!["Soon", "Later"], vec!["Finished"]]);
+
+    let (_, result) = get(&app, &format!("{}/tasks", view_uri), &other.token).await;
+    assert_eq!(titles(&result), vec![vec!["Theirs"]]);
+
+    let (status, _) = put(&app, &view_uri, &dev.token, json!({"name": "Mine"})).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = delete(&app, &view_uri, &dev.token).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[actix_web::test]
+async fn saved_views_are_personal_or_shared_with_a_team() {
+    let Some(ctx) = TestContext::new().await else { return };
+    let app = test::init_service(build_app(ctx.state.clone())).await;
+    let lead = register_user(&app, "lead@example.com").await;
+    let dev = register_user(&app, "dev@example.com").await;
+    let outsider = register_user(&app, "outsider@example.com").await;
+    let (_, team) = post(&app, "/api/teams", &lead.token, json!({"name": "Platform"})).await;
+    let team_id = id_of(&team);
+    post(&app, &format!("/api/teams/{}/members", team_id), &lead.token, json!({"user_id": dev.id})).await;
+    let (_, project) = post(&app, "/api/projects", &lead.token, json!({"name": "Launch", "team_id": team_id})).await;
+    let project_id = id_of(&project);
+
+    let soon = (Utc::now() + Duration::days(2)).to_rfc3339();
+    let later = (Utc::now() + Duration::days(30)).to_rfc3339();
+    let tasks = [
+        ("Fire", "urgent", Some(&soon), dev.id),
+        ("Someday fire", "urgent", Some(&later), dev.id),
+        ("Chore", "low", Some(&soon), dev.id),
+        ("Lead's fire", "urgent", Some(&soon), lead.id),
+        ("Undated fire", "urgent", None, dev.id),
+    ];
+    for (title, priority, due, assignee) in tasks {
+        let body = json!({
+            "project_id": project_id, "title": title, "priority": priority, "due_date": due, "assignee_id": assignee
+        });
+        let (status, task) = post(&app, "/api/tasks", &lead.token, body).await;
+        assert_eq!(status, StatusCode::CREATED, "{}", task);
+    }
+
+    let urgent = json!({
+        "name": "My urgent tasks this week",
+        "filter": {"assigned_to_me": true, "priority": "urgent", "due_within_days": 7},
+    });
+    let (status, view) = post(&app, "/api/views", &dev.token, urgent).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", view);
+    assert_eq!(view["user_id"], dev.id.to_string());
+    let (_, result) = get(&app, &format!("/api/views/{}/tasks", id_of(&view)), &dev.token).await;
+    assert_eq!(titles(&result), vec![vec!["Fire"]]);
+    let (status, _) = get(&app, &format!("/api/views/{}", id_of(&view)), &lead.token).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = post(&app, "/api/views", &outsider.token, json!({"name": "Snoop", "team_id": team_id})).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = post(&app, "/api/views", &dev.token, json!({"name": " "})).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let board = json!({
+        "name": "Urgent by assignee",
+        "team_id": team_id,
+        "filter": {"project_id": project_id, "priority": "urgent"},
+        "sort_by": "title",
+        "sort_order": "asc",
+        "group_by": "assignee",
+    });
+    let (status, board) = post(&app, "/api/views", &lead.token, board).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", board);
+    let board_uri = format!("/api/views/{}", id_of(&board));
+
+    let (status, result) = get(&app, &format!("{}/tasks", board_uri), &dev.token).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(result["groups"][0]["key"], dev.id.to_string());
+    assert_eq!(titles(&result), vec![vec!["Fire", "Someday fire", "Undated fire"], vec!["Lead's fire"]]);
+    let (status, _) = get(&app, &format!("{}/tasks", board_uri), &outsider.token).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (_, views) = get(&app, "/api/views", &dev.token).await;
+    assert_eq!(views.as_array().map(Vec::len), Some(3));
+
+    // Members can use a team view, but only its creator and maintainers change it
+    let rename = json!({"name": "Renamed", "filter": {"project_id": project_id}});
+    let (status, _) = put(&app, &board_uri, &dev.token, rename.clone()).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, renamed) = put(&app, &board_uri, &lead.token, rename).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(renamed["name"], "Renamed");
+    assert!(renamed["group_by"].is_null());
+    let (status, _) = delete(&app, &board_uri, &lead.token).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = get(&app, &board_uri, &dev.token).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}